@@ -1,6 +1,6 @@
- use shared::ipc::{Command, IpcError, Response};
+ use shared::ipc::{Command, Event, IpcError, Response};
  use std::path::PathBuf;
- use tokio::io::{AsyncReadExt, AsyncWriteExt};
+ use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
  use tokio::net::UnixStream;
  use tokio::time::{timeout, Duration};
  use tracing::warn;
@@ -29,22 +29,36 @@ impl DaemonClient {
         }
     }
 
-    pub async fn send_command(&self, cmd: Command) -> Result<Response, IpcError> {
-        // Connect with timeout
-        let mut stream = match timeout(SOCKET_TIMEOUT, UnixStream::connect(&self.socket_path)).await {
-            Ok(Ok(stream)) => stream,
+    async fn connect(&self) -> Result<UnixStream, IpcError> {
+        match timeout(SOCKET_TIMEOUT, UnixStream::connect(&self.socket_path)).await {
+            Ok(Ok(stream)) => Ok(stream),
             Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Err(IpcError::ConnectionRefused);
+                Err(IpcError::ConnectionRefused)
             }
             Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
-                return Err(IpcError::ConnectionRefused);
+                Err(IpcError::ConnectionRefused)
             }
-            Ok(Err(e)) => return Err(e.into()),
+            Ok(Err(e)) => Err(e.into()),
             Err(_) => {
                 warn!("Connection timeout: failed to connect to daemon at {} within {:?}", self.socket_path.display(), SOCKET_TIMEOUT);
-                return Err(IpcError::Timeout);
+                Err(IpcError::Timeout)
             }
-        };
+        }
+    }
+
+    pub async fn send_command(&self, cmd: Command) -> Result<Response, IpcError> {
+        self.send_command_with_timeout(cmd, SOCKET_TIMEOUT).await
+    }
+
+    /// Send a command that may take longer than `SOCKET_TIMEOUT` to answer,
+    /// such as transcribing a file.
+    pub async fn send_command_with_timeout(
+        &self,
+        cmd: Command,
+        response_timeout: Duration,
+    ) -> Result<Response, IpcError> {
+        // Connect with timeout
+        let mut stream = self.connect().await?;
 
         // Serialize command
         let command_json = serde_json::to_vec(&cmd)?;
@@ -55,23 +69,70 @@ impl DaemonClient {
             return Err(IpcError::Timeout);
         }
 
-        // Read with timeout
-        let mut buffer = vec![0u8; 1024];
-        let n = match timeout(SOCKET_TIMEOUT, stream.read(&mut buffer)).await {
-            Ok(Ok(n)) => n,
+        // Read with timeout; the daemon closes the connection after the
+        // response, which can be longer than one read (e.g. a batch report)
+        let mut buffer = Vec::new();
+        match timeout(response_timeout, stream.read_to_end(&mut buffer)).await {
+            Ok(Ok(_)) => {}
             Ok(Err(e)) => return Err(e.into()),
             Err(_) => {
-                warn!("Read timeout: failed to receive response from daemon within {:?}", SOCKET_TIMEOUT);
+                warn!("Read timeout: failed to receive response from daemon within {:?}", response_timeout);
                 return Err(IpcError::Timeout);
             }
-        };
-
-        buffer.truncate(n);
+        }
 
         let response: Response = serde_json::from_slice(&buffer)?;
 
         Ok(response)
     }
+
+    /// Open an event stream. The daemon acknowledges with a response line and
+    /// then pushes one JSON event per line until the connection is closed.
+    pub async fn subscribe(&self) -> Result<EventStream, IpcError> {
+        let mut stream = self.connect().await?;
+
+        let command_json = serde_json::to_vec(&Command::Subscribe)?;
+        if timeout(SOCKET_TIMEOUT, stream.write_all(&command_json)).await.is_err() {
+            warn!("Write timeout: failed to send subscription to daemon within {:?}", SOCKET_TIMEOUT);
+            return Err(IpcError::Timeout);
+        }
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        match timeout(SOCKET_TIMEOUT, reader.read_line(&mut line)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => {
+                warn!("Read timeout: daemon did not acknowledge subscription within {:?}", SOCKET_TIMEOUT);
+                return Err(IpcError::Timeout);
+            }
+        }
+
+        match serde_json::from_str::<Response>(&line)? {
+            Response::Error(msg) => Err(IpcError::Rejected(msg)),
+            _ => Ok(EventStream {
+                reader,
+                line: String::new(),
+            }),
+        }
+    }
+}
+
+/// Events received from a subscription.
+pub struct EventStream {
+    reader: BufReader<UnixStream>,
+    line: String,
+}
+
+impl EventStream {
+    /// Wait for the next event. Returns `Ok(None)` once the daemon closes the stream.
+    pub async fn next_event(&mut self) -> Result<Option<Event>, IpcError> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line).await? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&self.line)?))
+    }
 }
 
 #[cfg(test)]
@@ -129,6 +190,8 @@ mod tests {
                     is_running: true,
                     is_active: false,
                     language: "en".to_string(),
+                    audio_level: None,
+                    detected_language: None,
                 }),
                 _ => Response::Error("unknown".to_string()),
             };
@@ -168,6 +231,8 @@ mod tests {
                 is_running: true,
                 is_active: false,
                 language: "en".to_string(),
+                audio_level: None,
+                detected_language: None,
             });
 
             let response_json = serde_json::to_vec(&response).unwrap();
@@ -300,4 +365,71 @@ mod tests {
 
         std::fs::remove_file(test_socket).ok();
     }
+
+    #[tokio::test]
+    async fn test_subscribe_receives_events() {
+        use shared::ipc::AudioLevel;
+
+        let test_socket = "/tmp/test_ndict_subscribe.sock";
+        std::fs::remove_file(test_socket).ok();
+
+        let listener = UnixListener::bind(test_socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut buffer = vec![0u8; 1024];
+            let n = stream.read(&mut buffer).await.unwrap();
+            let command: Command = serde_json::from_slice(&buffer[..n]).unwrap();
+            assert_eq!(command, Command::Subscribe);
+
+            let mut payload = serde_json::to_vec(&Response::Ok).unwrap();
+            payload.push(b'\n');
+            let event = Event::AudioLevel(AudioLevel { rms: 0.1, peak: 0.2 });
+            payload.extend(serde_json::to_vec(&event).unwrap());
+            payload.push(b'\n');
+            stream.write_all(&payload).await.unwrap();
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = DaemonClient {
+            socket_path: PathBuf::from(test_socket),
+        };
+
+        let mut events = client.subscribe().await.unwrap();
+        let event = events.next_event().await.unwrap();
+        assert_eq!(event, Some(Event::AudioLevel(AudioLevel { rms: 0.1, peak: 0.2 })));
+        assert_eq!(events.next_event().await.unwrap(), None);
+
+        std::fs::remove_file(test_socket).ok();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_rejected() {
+        let test_socket = "/tmp/test_ndict_subscribe_rejected.sock";
+        std::fs::remove_file(test_socket).ok();
+
+        let listener = UnixListener::bind(test_socket).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut buffer = vec![0u8; 1024];
+            let _n = stream.read(&mut buffer).await.unwrap();
+
+            let mut payload = serde_json::to_vec(&Response::Error("nope".to_string())).unwrap();
+            payload.push(b'\n');
+            stream.write_all(&payload).await.unwrap();
+        });
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = DaemonClient {
+            socket_path: PathBuf::from(test_socket),
+        };
+
+        let result = client.subscribe().await;
+        assert!(matches!(result, Err(IpcError::Rejected(msg)) if msg == "nope"));
+
+        std::fs::remove_file(test_socket).ok();
+    }
 }
@@ -3,7 +3,7 @@ mod client;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use client::DaemonClient;
-use shared::ipc::{Command, Response};
+use shared::ipc::{Command, Response, TranscribedFile};
 
 #[derive(Parser)]
 #[command(name = "ndict")]
@@ -26,13 +26,61 @@ enum Commands {
     MComplete,
     MCompleteRaw,
     MStop,
+    /// Set the spoken language, e.g. "en", or "auto" to detect it
+    Language {
+        code: String,
+        /// Only for the next utterance, e.g. one phrase in another language
+        #[arg(long)]
+        once: bool,
+    },
+    /// Set the Whisper task: "transcribe" or "translate" (to English)
+    Task { task: String },
+    /// Switch to a named model from whisper.models ("default" for model_url),
+    /// or list the installed models when no name is given
+    Model { name: Option<String> },
+    /// Stream daemon events (audio levels, ...) as JSON lines
+    Events,
+    /// Write this session's transcriptions to a file
+    Export {
+        /// "text" or "markdown"
+        #[arg(short, long, default_value = "text")]
+        format: String,
+        /// Output file; defaults to a file under ~/.local/share/ndict/exports
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Type the text held back while paused (output.buffer_while_paused)
+    Flush,
+    /// Transcribe the last utterance again with whisper.retry.model
+    Retry,
+    /// Transcribe a WAV file, e.g. into subtitles, or every WAV file in a
+    /// directory or matching a quoted pattern such as 'talks/*.wav'
+    Transcribe {
+        file: std::path::PathBuf,
+        /// "text", "srt", "vtt" or "json"
+        #[arg(short, long, default_value = "text")]
+        format: String,
+        /// Output file, or directory for several inputs; defaults to the
+        /// input file with the format's extension
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
 }
 
+/// How long `ndict transcribe` waits for the daemon to finish its files.
+const TRANSCRIBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     let client = DaemonClient::new();
 
+    // The daemon runs in another directory
+    let absolute = |path: std::path::PathBuf| -> Result<String> {
+        Ok(std::path::absolute(path)?.display().to_string())
+    };
+    let mut response_timeout = None;
+
     let command = match cli.command {
         Commands::Start => Command::Start,
         Commands::Stop => Command::Stop,
@@ -45,9 +93,37 @@ async fn main() -> Result<()> {
         Commands::MComplete => Command::MComplete,
         Commands::MCompleteRaw => Command::MCompleteRaw,
         Commands::MStop => Command::MStop,
+        Commands::Language { code, once: false } => Command::SetLanguage(code),
+        Commands::Language { code, once: true } => Command::SetNextLanguage(code),
+        Commands::Task { task } => Command::SetTask(task),
+        Commands::Model { name: Some(name) } => Command::SetModel(name),
+        Commands::Model { name: None } => Command::ListModels,
+        Commands::Events => return stream_events(&client).await,
+        Commands::Flush => Command::Flush,
+        Commands::Retry => Command::Retry,
+        Commands::Export { format, output } => Command::Export {
+            format,
+            path: output.map(absolute).transpose()?,
+        },
+        Commands::Transcribe {
+            file,
+            format,
+            output,
+        } => {
+            response_timeout = Some(TRANSCRIBE_TIMEOUT);
+            Command::TranscribeFile {
+                path: absolute(file)?,
+                format,
+                output: output.map(absolute).transpose()?,
+            }
+        }
     };
 
-    match client.send_command(command).await {
+    let response = match response_timeout {
+        Some(response_timeout) => client.send_command_with_timeout(command, response_timeout).await,
+        None => client.send_command(command).await,
+    };
+    match response {
         Ok(Response::Ok) => {
             println!("Success");
         }
@@ -56,6 +132,36 @@ async fn main() -> Result<()> {
             println!("  Running: {}", info.is_running);
             println!("  Active: {}", info.is_active);
             println!("  Language: {}", info.language);
+            if let Some(detected) = info.detected_language {
+                println!("  Detected language: {}", detected);
+            }
+            if let Some(level) = info.audio_level {
+                println!(
+                    "  Audio level: rms {:.1} dBFS, peak {:.1} dBFS",
+                    level.rms_dbfs(),
+                    level.peak_dbfs()
+                );
+            }
+        }
+        Ok(Response::Exported(path)) => {
+            println!("Written to {}", path);
+        }
+        Ok(Response::Models(models)) => {
+            if models.is_empty() {
+                println!("No models installed yet");
+            }
+            for model in models {
+                println!("{}", model.path);
+                println!("  Size: {} bytes", model.size_bytes);
+                println!("  SHA-256: {}", model.sha256);
+                println!("  Last verified: {}", model.last_verified);
+            }
+        }
+        Ok(Response::Transcribed(files)) => {
+            print_transcription_report(&files);
+            if files.iter().any(|file| file.error.is_some()) {
+                std::process::exit(1);
+            }
         }
         Ok(Response::Error(msg)) => {
             eprintln!("Error: {}", msg);
@@ -69,3 +175,42 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// One line per file, then a summary for several files.
+fn print_transcription_report(files: &[TranscribedFile]) {
+    for file in files {
+        match (&file.output, &file.error) {
+            (Some(output), _) => println!("{} -> {}", file.input, output),
+            (None, Some(error)) => eprintln!("{}: {}", file.input, error),
+            (None, None) => eprintln!("{}: not transcribed", file.input),
+        }
+    }
+    if files.len() > 1 {
+        let failed = files.iter().filter(|file| file.error.is_some()).count();
+        let audio_ms: u64 = files.iter().map(|file| file.audio_ms).sum();
+        println!(
+            "{} transcribed, {} failed, {}m{:02}s of audio",
+            files.len() - failed,
+            failed,
+            audio_ms / 60_000,
+            audio_ms / 1000 % 60
+        );
+    }
+}
+
+/// Print each daemon event as one JSON line until the daemon closes the stream.
+async fn stream_events(client: &DaemonClient) -> Result<()> {
+    let mut events = match client.subscribe().await {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("Failed to subscribe to ndictd: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    while let Some(event) = events.next_event().await? {
+        println!("{}", serde_json::to_string(&event)?);
+    }
+
+    Ok(())
+}
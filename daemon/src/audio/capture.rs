@@ -1,10 +1,18 @@
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, SampleFormat, Stream, StreamConfig};
+use cpal::{BufferSize, Device, SampleFormat, Stream, StreamConfig, SupportedBufferSize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
+use crate::audio::rebatch::FrameRebatcher;
+use crate::audio::source::AudioSource;
+use crate::config::DeviceProfile;
+
+/// Frame size used until `with_frame_size` is called; matches the default
+/// `audio.chunk_size`.
+const DEFAULT_FRAME_SIZE: usize = 512;
+
 pub struct AudioCapture {
     device: Option<Device>,
     stream: Option<Box<Stream>>,
@@ -12,6 +20,19 @@ pub struct AudioCapture {
     is_running: Arc<AtomicBool>,
     sample_rate: u32,
     channels: u16,
+    frame_size: usize,
+    input_channel: Option<u16>,
+    device_gain: f32,
+    buffer_frames: Option<u32>,
+}
+
+/// Per-stream state used by the capture callback: how to fold the device's
+/// interleaved channels into mono, plus the frame rebatcher.
+struct CapturePipeline {
+    channels: usize,
+    input_channel: Option<usize>,
+    gain: f32,
+    rebatcher: FrameRebatcher,
 }
 
 impl AudioCapture {
@@ -25,6 +46,42 @@ impl AudioCapture {
             .default_input_device()
             .ok_or_else(|| anyhow::anyhow!("No default input device found"))?;
 
+        Self::from_device(device, sample_rate, channels)
+    }
+
+    /// Open the input device whose name matches `device_name`.
+    ///
+    /// An exact match wins over a case-insensitive substring match. `"default"`
+    /// or an empty name selects the host's default input device.
+    pub fn new_with_device(sample_rate: u32, channels: u16, device_name: &str) -> Result<Self> {
+        if device_name.is_empty() || device_name == "default" {
+            return Self::new_with_channels(sample_rate, channels);
+        }
+
+        let device = find_input_device(device_name)?
+            .ok_or_else(|| anyhow::anyhow!("Input device '{}' not found", device_name))?;
+        Self::from_device(device, sample_rate, channels)
+    }
+
+    /// Open the desktop's monitor (loopback) device so playback audio can be
+    /// transcribed: `device_name` when one is given, else a capture device
+    /// with "monitor" in its name. `None` when there is no such device.
+    pub fn new_loopback(
+        sample_rate: u32,
+        channels: u16,
+        device_name: &str,
+    ) -> Result<Option<Self>> {
+        if !device_name.is_empty() && device_name != "default" {
+            return Self::new_with_device(sample_rate, channels, device_name).map(Some);
+        }
+
+        match find_input_device("monitor")? {
+            Some(device) => Self::from_device(device, sample_rate, channels).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn from_device(device: Device, sample_rate: u32, channels: u16) -> Result<Self> {
         tracing::info!("Audio capture initialized with sample rate: {}Hz, channels: {}", sample_rate, channels);
         tracing::info!("Using input device: {}", device.name()?);
 
@@ -35,10 +92,61 @@ impl AudioCapture {
             is_running: Arc::new(AtomicBool::new(false)),
             sample_rate,
             channels,
+            frame_size: DEFAULT_FRAME_SIZE,
+            input_channel: None,
+            device_gain: 1.0,
+            buffer_frames: None,
         })
     }
 
-    pub fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+    /// Ask the driver for callbacks of `buffer_frames` frames instead of its
+    /// default. Smaller buffers lower latency at the cost of more wakeups.
+    pub fn with_buffer_frames(mut self, buffer_frames: Option<u32>) -> Self {
+        self.buffer_frames = buffer_frames;
+        self
+    }
+
+    /// Apply a `[audio.device_profiles]` entry: capture only `profile.channel`
+    /// (1-based) of a multichannel device and scale it by `profile.gain`.
+    pub fn with_profile(mut self, profile: &DeviceProfile) -> Result<Self> {
+        if profile.channel == Some(0) {
+            return Err(anyhow::anyhow!("Device profile channels are numbered from 1"));
+        }
+        self.input_channel = profile.channel.map(|c| c - 1);
+        self.device_gain = profile.gain;
+        Ok(self)
+    }
+
+    /// Broadcast audio in frames of exactly `frame_size` samples, regardless
+    /// of the buffer size the driver uses for its callbacks.
+    pub fn with_frame_size(mut self, frame_size: usize) -> Self {
+        self.frame_size = frame_size.max(1);
+        self
+    }
+
+    fn process_audio_chunk(
+        data: &[f32],
+        pipeline: &mut CapturePipeline,
+        audio_tx: Option<&broadcast::Sender<Vec<f32>>>,
+        is_running: &Arc<AtomicBool>,
+    ) {
+        if is_running.load(Ordering::Acquire) {
+            if let Some(sender) = audio_tx {
+                let mono = to_mono(data, pipeline.channels, pipeline.input_channel, pipeline.gain);
+                pipeline.rebatcher.push(&mono, |frame| {
+                    let _ = sender.send(frame);
+                });
+            }
+        }
+    }
+}
+
+impl AudioSource for AudioCapture {
+    fn name(&self) -> &str {
+        "microphone"
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
         self.audio_tx = Some(Arc::new(audio_tx));
         self.is_running.store(true, Ordering::Release);
 
@@ -55,13 +163,20 @@ impl AudioCapture {
 
         let supported_configs = device.supported_input_configs()?;
         let mut config: Option<StreamConfig> = None;
+        let mut buffer_range = SupportedBufferSize::Unknown;
 
         for supported in supported_configs {
             tracing::debug!("Supported config: {:?}", supported);
-            if supported.channels() == self.channels
+            // A selected channel only needs a layout wide enough to contain it
+            let channels_ok = match self.input_channel {
+                Some(channel) => supported.channels() > channel,
+                None => supported.channels() == self.channels,
+            };
+            if channels_ok
                 && supported.min_sample_rate().0 <= self.sample_rate
                 && supported.max_sample_rate().0 >= self.sample_rate
             {
+                buffer_range = *supported.buffer_size();
                 config = Some(
                     supported
                         .with_sample_rate(cpal::SampleRate(self.sample_rate))
@@ -71,9 +186,22 @@ impl AudioCapture {
             }
         }
 
-        let final_config =
+        let mut final_config =
             config.ok_or_else(|| anyhow::anyhow!("No suitable audio configuration found"))?;
 
+        if let Some(requested) = self.buffer_frames {
+            let frames = clamp_buffer_frames(requested, &buffer_range);
+            if frames != requested {
+                tracing::warn!(
+                    "Buffer size {} frames not supported by device, using {}",
+                    requested,
+                    frames
+                );
+            }
+            tracing::info!("Requesting capture buffer of {} frames", frames);
+            final_config.buffer_size = BufferSize::Fixed(frames);
+        }
+
         let audio_tx = self.audio_tx.as_ref().map(Arc::clone);
         let is_running = Arc::clone(&self.is_running);
 
@@ -86,12 +214,31 @@ impl AudioCapture {
             .map(|c| c.sample_format())
             .unwrap_or(SampleFormat::F32);
 
+        if let Some(channel) = self.input_channel {
+            tracing::info!(
+                "Capturing channel {} of {}",
+                channel + 1,
+                final_config.channels
+            );
+        }
+        let mut pipeline = CapturePipeline {
+            channels: final_config.channels as usize,
+            input_channel: self.input_channel.map(usize::from),
+            gain: self.device_gain,
+            rebatcher: FrameRebatcher::new(self.frame_size),
+        };
+
         let stream: Box<Stream> = match sample_format {
             SampleFormat::F32 => {
                 let stream = device.build_input_stream(
                     &final_config,
                     move |data: &[f32], _: &_| {
-                        Self::process_audio_chunk(data, audio_tx.as_deref(), &is_running);
+                        Self::process_audio_chunk(
+                            data,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
                     },
                     error_callback,
                     None,
@@ -102,9 +249,13 @@ impl AudioCapture {
                 let stream = device.build_input_stream(
                     &final_config,
                     move |data: &[i16], _: &_| {
-                        let converted: Vec<f32> =
-                            data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-                        Self::process_audio_chunk(&converted, audio_tx.as_deref(), &is_running);
+                        let converted: Vec<f32> = data.iter().map(|&s| i16_to_f32(s)).collect();
+                        Self::process_audio_chunk(
+                            &converted,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
                     },
                     error_callback,
                     None,
@@ -115,11 +266,64 @@ impl AudioCapture {
                 let stream = device.build_input_stream(
                     &final_config,
                     move |data: &[u16], _: &_| {
-                        let converted: Vec<f32> = data
-                            .iter()
-                            .map(|&s| (s as i16 as f32) / i16::MAX as f32)
-                            .collect();
-                        Self::process_audio_chunk(&converted, audio_tx.as_deref(), &is_running);
+                        let converted: Vec<f32> = data.iter().map(|&s| u16_to_f32(s)).collect();
+                        Self::process_audio_chunk(
+                            &converted,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
+                    },
+                    error_callback,
+                    None,
+                )?;
+                Box::new(stream)
+            }
+            SampleFormat::I32 => {
+                let stream = device.build_input_stream(
+                    &final_config,
+                    move |data: &[i32], _: &_| {
+                        let converted: Vec<f32> = data.iter().map(|&s| i32_to_f32(s)).collect();
+                        Self::process_audio_chunk(
+                            &converted,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
+                    },
+                    error_callback,
+                    None,
+                )?;
+                Box::new(stream)
+            }
+            SampleFormat::U8 => {
+                let stream = device.build_input_stream(
+                    &final_config,
+                    move |data: &[u8], _: &_| {
+                        let converted: Vec<f32> = data.iter().map(|&s| u8_to_f32(s)).collect();
+                        Self::process_audio_chunk(
+                            &converted,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
+                    },
+                    error_callback,
+                    None,
+                )?;
+                Box::new(stream)
+            }
+            SampleFormat::F64 => {
+                let stream = device.build_input_stream(
+                    &final_config,
+                    move |data: &[f64], _: &_| {
+                        let converted: Vec<f32> = data.iter().map(|&s| f64_to_f32(s)).collect();
+                        Self::process_audio_chunk(
+                            &converted,
+                            &mut pipeline,
+                            audio_tx.as_deref(),
+                            &is_running,
+                        );
                     },
                     error_callback,
                     None,
@@ -138,19 +342,7 @@ impl AudioCapture {
         Ok(())
     }
 
-    fn process_audio_chunk(
-        data: &[f32],
-        audio_tx: Option<&broadcast::Sender<Vec<f32>>>,
-        is_running: &Arc<AtomicBool>,
-    ) {
-        if is_running.load(Ordering::Acquire) {
-            if let Some(sender) = audio_tx {
-                let _ = sender.send(data.to_vec());
-            }
-        }
-    }
-
-    pub async fn stop(&mut self) -> anyhow::Result<()> {
+    fn stop(&mut self) -> Result<()> {
         self.is_running.store(false, Ordering::Release);
         if let Some(stream) = self.stream.take() {
             drop(stream);
@@ -163,3 +355,162 @@ impl AudioCapture {
 }
 
 unsafe impl Send for AudioCapture {}
+
+/// Fit a requested buffer size into the range the device reports, if any.
+fn clamp_buffer_frames(requested: u32, supported: &SupportedBufferSize) -> u32 {
+    match supported {
+        SupportedBufferSize::Range { min, max } => requested.clamp(*min, *max),
+        SupportedBufferSize::Unknown => requested,
+    }
+}
+
+/// Fold interleaved `channels`-wide audio into mono, either by picking
+/// `input_channel` or by averaging all channels, and apply `gain`.
+fn to_mono(data: &[f32], channels: usize, input_channel: Option<usize>, gain: f32) -> Vec<f32> {
+    if channels <= 1 {
+        return data.iter().map(|&s| s * gain).collect();
+    }
+
+    data.chunks_exact(channels)
+        .map(|frame| {
+            let sample = match input_channel {
+                Some(channel) => frame[channel.min(channels - 1)],
+                None => frame.iter().sum::<f32>() / channels as f32,
+            };
+            sample * gain
+        })
+        .collect()
+}
+
+fn i16_to_f32(sample: i16) -> f32 {
+    sample as f32 / i16::MAX as f32
+}
+
+fn u16_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / 32768.0
+}
+
+fn i32_to_f32(sample: i32) -> f32 {
+    (sample as f64 / i32::MAX as f64) as f32
+}
+
+fn u8_to_f32(sample: u8) -> f32 {
+    (sample as f32 - 128.0) / 128.0
+}
+
+fn f64_to_f32(sample: f64) -> f32 {
+    sample as f32
+}
+
+/// Find the default host's input device matching `wanted`.
+fn find_input_device(wanted: &str) -> Result<Option<Device>> {
+    let host = cpal::default_host();
+    let devices: Vec<Device> = host.input_devices()?.collect();
+    let names: Vec<String> = devices
+        .iter()
+        .map(|d| d.name().unwrap_or_default())
+        .collect();
+
+    Ok(match_device_name(&names, wanted).map(|index| devices[index].clone()))
+}
+
+/// Pick the index of the device name that best matches `wanted`: an exact
+/// match first, then the first case-insensitive substring match.
+pub fn match_device_name(names: &[String], wanted: &str) -> Option<usize> {
+    if let Some(index) = names.iter().position(|name| name == wanted) {
+        return Some(index);
+    }
+
+    let wanted = wanted.to_lowercase();
+    names
+        .iter()
+        .position(|name| name.to_lowercase().contains(&wanted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_clamp_buffer_frames() {
+        let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+        assert_eq!(clamp_buffer_frames(128, &range), 128);
+        assert_eq!(clamp_buffer_frames(16, &range), 64);
+        assert_eq!(clamp_buffer_frames(8192, &range), 4096);
+        assert_eq!(clamp_buffer_frames(16, &SupportedBufferSize::Unknown), 16);
+    }
+
+    #[test]
+    fn test_to_mono_passes_mono_through_with_gain() {
+        assert_eq!(to_mono(&[0.1, -0.2], 1, None, 2.0), vec![0.2, -0.4]);
+    }
+
+    #[test]
+    fn test_to_mono_averages_channels() {
+        let interleaved = [0.2, 0.4, -0.5, 0.5];
+        assert_eq!(to_mono(&interleaved, 2, None, 1.0), vec![0.3, 0.0]);
+    }
+
+    #[test]
+    fn test_to_mono_selects_channel() {
+        // Four-channel interface, take the third channel
+        let interleaved = [0.0, 0.1, 0.3, 0.0, 0.0, 0.1, 0.5, 0.0];
+        assert_eq!(to_mono(&interleaved, 4, Some(2), 2.0), vec![0.6, 1.0]);
+    }
+
+    #[test]
+    fn test_i16_to_f32() {
+        assert_eq!(i16_to_f32(0), 0.0);
+        assert_eq!(i16_to_f32(i16::MAX), 1.0);
+        assert!(i16_to_f32(i16::MIN) <= -1.0);
+    }
+
+    #[test]
+    fn test_u16_to_f32_is_centered() {
+        assert_eq!(u16_to_f32(32768), 0.0);
+        assert_eq!(u16_to_f32(0), -1.0);
+        assert!((u16_to_f32(u16::MAX) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_i32_to_f32() {
+        assert_eq!(i32_to_f32(0), 0.0);
+        assert_eq!(i32_to_f32(i32::MAX), 1.0);
+        assert!((i32_to_f32(i32::MAX / 2) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_u8_to_f32_is_centered() {
+        assert_eq!(u8_to_f32(128), 0.0);
+        assert_eq!(u8_to_f32(0), -1.0);
+        assert!((u8_to_f32(255) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_f64_to_f32() {
+        assert_eq!(f64_to_f32(0.25), 0.25);
+        assert_eq!(f64_to_f32(-1.0), -1.0);
+    }
+
+    #[test]
+    fn test_match_device_name_prefers_exact_match() {
+        let devices = names(&["USB Headset Monitor", "USB Headset"]);
+        assert_eq!(match_device_name(&devices, "USB Headset"), Some(1));
+    }
+
+    #[test]
+    fn test_match_device_name_substring_is_case_insensitive() {
+        let devices = names(&["default", "Monitor of Built-in Audio"]);
+        assert_eq!(match_device_name(&devices, "monitor"), Some(1));
+    }
+
+    #[test]
+    fn test_match_device_name_no_match() {
+        let devices = names(&["default", "pulse"]);
+        assert_eq!(match_device_name(&devices, "jack"), None);
+    }
+}
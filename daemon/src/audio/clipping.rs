@@ -0,0 +1,152 @@
+use shared::ipc::Event;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Absolute sample value (after gain) counted as clipped.
+const CLIP_THRESHOLD: f32 = 0.99;
+
+/// Fraction of clipped samples within one second that counts as sustained
+/// clipping rather than the odd transient.
+const CLIP_RATIO: f32 = 0.001;
+
+/// Minimum time between two warnings.
+const WARNING_COOLDOWN_SECS: u32 = 30;
+
+/// Watches the capture stream for sustained clipping.
+///
+/// Audio is judged in one-second windows with `gain` applied, since that is
+/// what Whisper eventually receives. Timing is counted in samples.
+pub struct ClippingDetector {
+    gain: f32,
+    window_samples: usize,
+    cooldown_samples: usize,
+    seen: usize,
+    clipped: usize,
+    since_warning: Option<usize>,
+}
+
+impl ClippingDetector {
+    pub fn new(sample_rate: u32, gain: f32) -> Self {
+        let window_samples = (sample_rate as usize).max(1);
+        Self {
+            gain,
+            window_samples,
+            cooldown_samples: window_samples * WARNING_COOLDOWN_SECS as usize,
+            seen: 0,
+            clipped: 0,
+            since_warning: None,
+        }
+    }
+
+    /// Feed a chunk; returns the clipped fraction of the window when a
+    /// warning should be raised.
+    pub fn push(&mut self, samples: &[f32]) -> Option<f32> {
+        self.clipped += samples
+            .iter()
+            .filter(|&&s| (s * self.gain).abs() >= CLIP_THRESHOLD)
+            .count();
+        self.seen += samples.len();
+        if let Some(since) = self.since_warning.as_mut() {
+            *since += samples.len();
+        }
+
+        if self.seen < self.window_samples {
+            return None;
+        }
+
+        let ratio = self.clipped as f32 / self.seen as f32;
+        self.seen = 0;
+        self.clipped = 0;
+
+        let cooled_down = self
+            .since_warning
+            .is_none_or(|since| since >= self.cooldown_samples);
+        if ratio >= CLIP_RATIO && cooled_down {
+            self.since_warning = Some(0);
+            Some(ratio)
+        } else {
+            None
+        }
+    }
+}
+
+/// Log and publish `Event::Warning` when the input clips. Ends when the
+/// audio channel closes.
+pub fn spawn_clipping_monitor(
+    mut audio_rx: broadcast::Receiver<Vec<f32>>,
+    sample_rate: u32,
+    gain: f32,
+    event_tx: broadcast::Sender<Event>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut detector = ClippingDetector::new(sample_rate, gain);
+
+        loop {
+            match audio_rx.recv().await {
+                Ok(samples) => {
+                    if let Some(ratio) = detector.push(&samples) {
+                        let message = format!(
+                            "Input is clipping ({:.1}% of samples); lower audio.gain (currently {:.2}) or the input volume",
+                            ratio * 100.0,
+                            gain
+                        );
+                        tracing::warn!("{}", message);
+                        // No subscribers is not an error
+                        let _ = event_tx.send(Event::Warning(message));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_audio_does_not_warn() {
+        let mut detector = ClippingDetector::new(100, 1.0);
+        assert_eq!(detector.push(&[0.5; 100]), None);
+        assert_eq!(detector.push(&[0.5; 100]), None);
+    }
+
+    #[test]
+    fn test_sustained_clipping_warns_once_per_cooldown() {
+        let mut detector = ClippingDetector::new(100, 1.0);
+        assert!(detector.push(&[1.0; 100]).is_some());
+
+        // Still clipping, but within the cooldown
+        assert_eq!(detector.push(&[1.0; 100]), None);
+
+        for _ in 0..WARNING_COOLDOWN_SECS {
+            detector.push(&[0.0; 100]);
+        }
+        assert!(detector.push(&[1.0; 100]).is_some());
+    }
+
+    #[test]
+    fn test_gain_is_applied_before_checking() {
+        let mut detector = ClippingDetector::new(100, 4.0);
+        assert_eq!(detector.push(&[0.3; 100]), Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_monitor_publishes_warning() {
+        let (audio_tx, audio_rx) = broadcast::channel(8);
+        let (event_tx, mut event_rx) = broadcast::channel(8);
+
+        let handle = spawn_clipping_monitor(audio_rx, 100, 1.0, event_tx);
+        audio_tx.send(vec![1.0; 100]).unwrap();
+
+        match event_rx.recv().await.unwrap() {
+            Event::Warning(message) => assert!(message.contains("clipping")),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        drop(audio_tx);
+        handle.await.unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::audio::resample::resample;
+use crate::audio::source::AudioSource;
+
+/// Plays a WAV file into the audio pipeline in real time.
+///
+/// Useful for reproducing a dictation session without a microphone. Once the
+/// file has been played, silence is delivered until the source is stopped so
+/// the VAD can close the final utterance.
+pub struct FileSource {
+    path: PathBuf,
+    samples: Arc<Vec<f32>>,
+    sample_rate: u32,
+    chunk_size: usize,
+    is_running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl FileSource {
+    pub fn new(path: impl AsRef<Path>, sample_rate: u32, chunk_size: usize) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let samples = read_wav_mono(&path, sample_rate)?;
+
+        tracing::info!(
+            "File audio source initialized: {} ({} samples at {}Hz)",
+            path.display(),
+            samples.len(),
+            sample_rate
+        );
+
+        Ok(Self {
+            path,
+            samples: Arc::new(samples),
+            sample_rate,
+            chunk_size: chunk_size.max(1),
+            is_running: Arc::new(AtomicBool::new(false)),
+            worker: None,
+        })
+    }
+}
+
+impl AudioSource for FileSource {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+        self.is_running.store(true, Ordering::Release);
+
+        let samples = Arc::clone(&self.samples);
+        let is_running = Arc::clone(&self.is_running);
+        let chunk_size = self.chunk_size;
+        let chunk_duration =
+            Duration::from_secs_f64(chunk_size as f64 / self.sample_rate as f64);
+
+        let worker = std::thread::Builder::new()
+            .name("ndict-file-source".to_string())
+            .spawn(move || {
+                let mut offset = 0;
+                while is_running.load(Ordering::Acquire) {
+                    let chunk = if offset < samples.len() {
+                        let end = (offset + chunk_size).min(samples.len());
+                        let chunk = samples[offset..end].to_vec();
+                        offset = end;
+                        if offset == samples.len() {
+                            tracing::info!("File audio source reached end of file");
+                        }
+                        chunk
+                    } else {
+                        vec![0.0; chunk_size]
+                    };
+
+                    let _ = audio_tx.send(chunk);
+                    std::thread::sleep(chunk_duration);
+                }
+            })?;
+
+        self.worker = Some(worker);
+        tracing::info!("File audio source started: {}", self.path.display());
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.is_running.store(false, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        tracing::info!("File audio source stopped");
+        Ok(())
+    }
+}
+
+/// Read a WAV file and downmix it to mono f32 samples.
+///
+/// The file must already be at `expected_sample_rate`.
+pub fn read_wav_mono(path: &Path, expected_sample_rate: u32) -> Result<Vec<f32>> {
+    let (samples, sample_rate) = read_wav(path)?;
+    if sample_rate != expected_sample_rate {
+        return Err(anyhow::anyhow!(
+            "WAV file {} is {}Hz, expected {}Hz",
+            path.display(),
+            sample_rate,
+            expected_sample_rate
+        ));
+    }
+    Ok(samples)
+}
+
+/// Read a WAV file at any sample rate, downmixed to mono and resampled to
+/// `sample_rate`.
+pub fn read_wav_resampled(path: &Path, sample_rate: u32) -> Result<Vec<f32>> {
+    let (samples, file_rate) = read_wav(path)?;
+    Ok(resample(&samples, file_rate, sample_rate))
+}
+
+/// Mono samples of a WAV file and its sample rate.
+fn read_wav(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open WAV file {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let channels = spec.channels.max(1) as usize;
+    let samples = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    Ok((samples, spec.sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, sample_rate: u32, channels: u16, samples: &[i16]) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_read_wav_mono_downmixes_stereo() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stereo.wav");
+        write_wav(&path, 16000, 2, &[16384, 0, -16384, -16384]);
+
+        let samples = read_wav_mono(&path, 16000).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert!((samples[0] - 0.25).abs() < 1e-4);
+        assert!((samples[1] + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_read_wav_mono_rejects_sample_rate_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("48k.wav");
+        write_wav(&path, 48000, 1, &[0; 16]);
+
+        let result = read_wav_mono(&path, 16000);
+        assert!(result.is_err());
+        assert!(result.err().unwrap().to_string().contains("48000Hz"));
+    }
+
+    #[test]
+    fn test_read_wav_resampled_converts_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("48k.wav");
+        write_wav(&path, 48000, 1, &[0; 48]);
+
+        assert_eq!(read_wav_resampled(&path, 16000).unwrap().len(), 16);
+    }
+
+    #[tokio::test]
+    async fn test_file_source_delivers_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mono.wav");
+        write_wav(&path, 16000, 1, &[8192; 64]);
+
+        let mut source = FileSource::new(&path, 16000, 32).unwrap();
+        let (tx, mut rx) = broadcast::channel(16);
+        source.start(tx).unwrap();
+
+        let chunk = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk.len(), 32);
+        assert!((chunk[0] - 0.25).abs() < 1e-4);
+
+        source.stop().unwrap();
+    }
+}
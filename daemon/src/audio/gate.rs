@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Mutes the input for a short while so audible feedback cues (start/stop
+/// sounds played by the daemon or by the script that triggered it) are not
+/// picked up by the VAD and transcribed.
+///
+/// Muted samples are replaced with silence rather than dropped, so the VAD's
+/// sense of time is unaffected.
+pub struct InputGate {
+    sample_rate: u32,
+    remaining: AtomicUsize,
+}
+
+impl InputGate {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            remaining: AtomicUsize::new(0),
+        }
+    }
+
+    /// Mute the next `duration_ms` of audio. Overlapping calls extend the
+    /// gate rather than shortening it.
+    pub fn engage(&self, duration_ms: u32) {
+        let samples = (self.sample_rate as u64 * duration_ms as u64 / 1000) as usize;
+        self.remaining.fetch_max(samples, Ordering::AcqRel);
+    }
+
+    /// Silence the start of `samples` while the gate is engaged.
+    pub fn apply(&self, samples: &mut [f32]) {
+        let mut remaining = self.remaining.load(Ordering::Acquire);
+        loop {
+            if remaining == 0 {
+                return;
+            }
+            let muted = remaining.min(samples.len());
+            match self.remaining.compare_exchange(
+                remaining,
+                remaining - muted,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    samples[..muted].fill(0.0);
+                    return;
+                }
+                Err(current) => remaining = current,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_gate_passes_audio() {
+        let gate = InputGate::new(1000);
+        let mut samples = vec![0.5; 4];
+        gate.apply(&mut samples);
+        assert_eq!(samples, vec![0.5; 4]);
+    }
+
+    #[test]
+    fn test_gate_mutes_for_duration() {
+        // 5ms at 1kHz is 5 samples
+        let gate = InputGate::new(1000);
+        gate.engage(5);
+
+        let mut first = vec![0.5; 3];
+        gate.apply(&mut first);
+        assert_eq!(first, vec![0.0; 3]);
+
+        let mut second = vec![0.5; 4];
+        gate.apply(&mut second);
+        assert_eq!(second, vec![0.0, 0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_engage_does_not_shorten_gate() {
+        let gate = InputGate::new(1000);
+        gate.engage(10);
+        gate.engage(2);
+
+        let mut samples = vec![0.5; 10];
+        gate.apply(&mut samples);
+        assert_eq!(samples, vec![0.0; 10]);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+use crate::audio::rebatch::FrameRebatcher;
+use crate::audio::source::AudioSource;
+
+/// JACK client name; the input port shows up as `ndict:input`.
+pub const CLIENT_NAME: &str = "ndict";
+const PORT_NAME: &str = "input";
+
+/// Captures from a JACK input port so any source can be routed into
+/// dictation from the user's patchbay.
+///
+/// The port is left unconnected; wiring it up is up to the user. The JACK
+/// server must run at `audio.sample_rate` since no resampling is done.
+pub struct JackSource {
+    sample_rate: u32,
+    frame_size: usize,
+    client: Option<jack::AsyncClient<(), CaptureHandler>>,
+}
+
+struct CaptureHandler {
+    port: jack::Port<jack::AudioIn>,
+    rebatcher: FrameRebatcher,
+    audio_tx: broadcast::Sender<Vec<f32>>,
+}
+
+impl jack::ProcessHandler for CaptureHandler {
+    fn process(&mut self, _: &jack::Client, scope: &jack::ProcessScope) -> jack::Control {
+        let audio_tx = &self.audio_tx;
+        self.rebatcher.push(self.port.as_slice(scope), |frame| {
+            let _ = audio_tx.send(frame);
+        });
+        jack::Control::Continue
+    }
+}
+
+impl JackSource {
+    pub fn new(sample_rate: u32, frame_size: usize) -> Result<Self> {
+        tracing::info!("JACK audio source initialized");
+        Ok(Self {
+            sample_rate,
+            frame_size,
+            client: None,
+        })
+    }
+}
+
+impl AudioSource for JackSource {
+    fn name(&self) -> &str {
+        "jack"
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+        let (client, _status) =
+            jack::Client::new(CLIENT_NAME, jack::ClientOptions::NO_START_SERVER)
+                .map_err(|e| anyhow::anyhow!("Failed to connect to the JACK server: {}", e))?;
+
+        if client.sample_rate() != self.sample_rate {
+            return Err(anyhow::anyhow!(
+                "JACK server runs at {}Hz but audio.sample_rate is {}Hz",
+                client.sample_rate(),
+                self.sample_rate
+            ));
+        }
+
+        let port = client.register_port(PORT_NAME, jack::AudioIn::default())?;
+        let handler = CaptureHandler {
+            port,
+            rebatcher: FrameRebatcher::new(self.frame_size),
+            audio_tx,
+        };
+
+        let client = client.activate_async((), handler)?;
+        tracing::info!(
+            "JACK audio source started on port {}:{}",
+            CLIENT_NAME,
+            PORT_NAME
+        );
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        if let Some(client) = self.client.take() {
+            client.deactivate()?;
+        }
+        tracing::info!("JACK audio source stopped");
+        Ok(())
+    }
+}
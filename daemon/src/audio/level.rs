@@ -0,0 +1,123 @@
+use shared::ipc::{AudioLevel, Event};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+/// Accumulates captured chunks and reports one level per fixed window.
+///
+/// Windows are counted in samples rather than wall-clock time, so the report
+/// rate follows the audio regardless of how the backend sizes its callbacks.
+pub struct LevelMeter {
+    window_samples: usize,
+    sum_squares: f64,
+    peak: f32,
+    count: usize,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32, interval_ms: u32) -> Self {
+        let window_samples = (sample_rate as u64 * interval_ms as u64 / 1000).max(1) as usize;
+        Self {
+            window_samples,
+            sum_squares: 0.0,
+            peak: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Feed a chunk; returns a level each time a full window has been seen.
+    pub fn push(&mut self, samples: &[f32]) -> Option<AudioLevel> {
+        for &sample in samples {
+            self.sum_squares += (sample * sample) as f64;
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.count += samples.len();
+
+        if self.count < self.window_samples {
+            return None;
+        }
+
+        let level = AudioLevel {
+            rms: (self.sum_squares / self.count as f64).sqrt() as f32,
+            peak: self.peak,
+        };
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.count = 0;
+        Some(level)
+    }
+}
+
+/// Track the input level of the capture stream, keeping `current_level`
+/// up to date and publishing `Event::AudioLevel` to subscribers. Ends when
+/// the audio channel closes.
+pub fn spawn_level_meter(
+    mut audio_rx: broadcast::Receiver<Vec<f32>>,
+    sample_rate: u32,
+    interval_ms: u32,
+    current_level: Arc<Mutex<Option<AudioLevel>>>,
+    event_tx: broadcast::Sender<Event>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut meter = LevelMeter::new(sample_rate, interval_ms);
+
+        loop {
+            match audio_rx.recv().await {
+                Ok(samples) => {
+                    if let Some(level) = meter.push(&samples) {
+                        *current_level.lock().await = Some(level);
+                        // No subscribers is not an error
+                        let _ = event_tx.send(Event::AudioLevel(level));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+
+        *current_level.lock().await = None;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_meter_reports_once_per_window() {
+        // 10ms at 1kHz is a 10-sample window
+        let mut meter = LevelMeter::new(1000, 10);
+        assert_eq!(meter.push(&[0.5; 6]), None);
+
+        let level = meter.push(&[0.5; 6]).unwrap();
+        assert!((level.rms - 0.5).abs() < 1e-6);
+        assert_eq!(level.peak, 0.5);
+
+        // The window restarts after reporting
+        assert_eq!(meter.push(&[0.1; 5]), None);
+    }
+
+    #[test]
+    fn test_level_meter_resets_peak_between_windows() {
+        let mut meter = LevelMeter::new(1000, 1);
+        assert_eq!(meter.push(&[0.9]).unwrap().peak, 0.9);
+        assert_eq!(meter.push(&[0.2]).unwrap().peak, 0.2);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_level_meter_publishes_and_clears() {
+        let (audio_tx, audio_rx) = broadcast::channel(8);
+        let (event_tx, mut event_rx) = broadcast::channel(8);
+        let current = Arc::new(Mutex::new(None));
+
+        let handle = spawn_level_meter(audio_rx, 1000, 10, Arc::clone(&current), event_tx);
+        audio_tx.send(vec![0.25; 10]).unwrap();
+
+        let event = event_rx.recv().await.unwrap();
+        assert_eq!(event, Event::AudioLevel(AudioLevel { rms: 0.25, peak: 0.25 }));
+
+        drop(audio_tx);
+        handle.await.unwrap();
+        assert!(current.lock().await.is_none());
+    }
+}
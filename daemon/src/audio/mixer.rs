@@ -0,0 +1,223 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use tokio::sync::broadcast;
+
+use crate::audio::source::AudioSource;
+
+/// Capacity of the channel between each input and the mixer thread.
+const INPUT_CHANNEL_CAPACITY: usize = 64;
+
+/// Number of frames one input may run ahead before the others are treated as
+/// silent, so a stalled device cannot hold back the mix forever.
+const MAX_BACKLOG_FRAMES: usize = 4;
+
+/// Sums several mono inputs into one stream, frame by frame.
+///
+/// A frame is emitted once every input has contributed `frame_size` samples.
+/// If one input falls more than `MAX_BACKLOG_FRAMES` behind, the frame is
+/// emitted anyway with the missing samples treated as silence.
+pub struct FrameMixer {
+    frame_size: usize,
+    queues: Vec<VecDeque<f32>>,
+}
+
+impl FrameMixer {
+    pub fn new(inputs: usize, frame_size: usize) -> Self {
+        Self {
+            frame_size: frame_size.max(1),
+            queues: vec![VecDeque::new(); inputs],
+        }
+    }
+
+    pub fn push(&mut self, input: usize, samples: &[f32]) {
+        if let Some(queue) = self.queues.get_mut(input) {
+            queue.extend(samples);
+        }
+    }
+
+    /// Mix the next frame if enough audio has arrived.
+    pub fn pop_frame(&mut self) -> Option<Vec<f32>> {
+        let longest = self.queues.iter().map(VecDeque::len).max().unwrap_or(0);
+        let shortest = self.queues.iter().map(VecDeque::len).min().unwrap_or(0);
+        if shortest < self.frame_size && longest < self.frame_size * MAX_BACKLOG_FRAMES {
+            return None;
+        }
+
+        let mut frame = vec![0.0f32; self.frame_size];
+        for queue in &mut self.queues {
+            let take = self.frame_size.min(queue.len());
+            for (mixed, sample) in frame.iter_mut().zip(queue.drain(..take)) {
+                *mixed += sample;
+            }
+        }
+        for sample in &mut frame {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+        Some(frame)
+    }
+}
+
+/// Captures from several input devices at once and mixes them into a single
+/// stream, for setups with more than one microphone.
+pub struct MixedSource {
+    sources: Vec<Box<dyn AudioSource>>,
+    frame_size: usize,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl MixedSource {
+    pub fn new(sources: Vec<Box<dyn AudioSource>>, frame_size: usize) -> Result<Self> {
+        if sources.is_empty() {
+            return Err(anyhow::anyhow!("Mixed audio source needs at least one input"));
+        }
+        tracing::info!("Mixing {} input devices", sources.len());
+        Ok(Self {
+            sources,
+            frame_size,
+            workers: Vec::new(),
+        })
+    }
+}
+
+impl AudioSource for MixedSource {
+    fn name(&self) -> &str {
+        "mixed"
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+        let (chunk_tx, chunk_rx) = mpsc::channel::<(usize, Vec<f32>)>();
+
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            let (input_tx, mut input_rx) = broadcast::channel(INPUT_CHANNEL_CAPACITY);
+            source.start(input_tx)?;
+
+            let chunk_tx = chunk_tx.clone();
+            let worker = std::thread::Builder::new()
+                .name(format!("ndict-mix-input-{}", index))
+                .spawn(move || loop {
+                    match input_rx.blocking_recv() {
+                        Ok(samples) => {
+                            if chunk_tx.send((index, samples)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Mixer input {} lagged, dropped {} chunks", index, n);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                })?;
+            self.workers.push(worker);
+        }
+        drop(chunk_tx);
+
+        let mut mixer = FrameMixer::new(self.sources.len(), self.frame_size);
+        let worker = std::thread::Builder::new()
+            .name("ndict-mixer".to_string())
+            .spawn(move || {
+                // Ends once every input forwarder has shut down
+                while let Ok((index, samples)) = chunk_rx.recv() {
+                    mixer.push(index, &samples);
+                    while let Some(frame) = mixer.pop_frame() {
+                        let _ = audio_tx.send(frame);
+                    }
+                }
+            })?;
+        self.workers.push(worker);
+
+        tracing::info!("Mixed audio source started");
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for source in &mut self.sources {
+            if let Err(e) = source.stop() {
+                tracing::error!("Failed to stop mixer input '{}': {}", source.name(), e);
+                result = Err(e);
+            }
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        tracing::info!("Mixed audio source stopped");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixer_waits_for_all_inputs() {
+        let mut mixer = FrameMixer::new(2, 2);
+        mixer.push(0, &[0.1, 0.2]);
+        assert_eq!(mixer.pop_frame(), None);
+
+        mixer.push(1, &[0.3, 0.4, 0.5]);
+        let frame = mixer.pop_frame().unwrap();
+        assert!((frame[0] - 0.4).abs() < 1e-6);
+        assert!((frame[1] - 0.6).abs() < 1e-6);
+        assert_eq!(mixer.pop_frame(), None);
+    }
+
+    #[test]
+    fn test_mixer_clamps_sum() {
+        let mut mixer = FrameMixer::new(2, 1);
+        mixer.push(0, &[0.8]);
+        mixer.push(1, &[0.7]);
+        assert_eq!(mixer.pop_frame(), Some(vec![1.0]));
+    }
+
+    #[test]
+    fn test_mixer_does_not_stall_on_silent_input() {
+        let mut mixer = FrameMixer::new(2, 2);
+        mixer.push(0, &[0.5; 2 * MAX_BACKLOG_FRAMES]);
+
+        assert_eq!(mixer.pop_frame(), Some(vec![0.5, 0.5]));
+        // Back under the backlog limit, so wait for the other input again
+        assert_eq!(mixer.pop_frame(), None);
+    }
+
+    struct ConstantSource {
+        value: f32,
+    }
+
+    impl AudioSource for ConstantSource {
+        fn name(&self) -> &str {
+            "constant"
+        }
+
+        fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+            audio_tx.send(vec![self.value; 4])?;
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mixed_source_mixes_inputs() {
+        let sources: Vec<Box<dyn AudioSource>> = vec![
+            Box::new(ConstantSource { value: 0.25 }),
+            Box::new(ConstantSource { value: 0.5 }),
+        ];
+        let mut mixed = MixedSource::new(sources, 4).unwrap();
+        let (tx, mut rx) = broadcast::channel(8);
+
+        mixed.start(tx).unwrap();
+        let frame = rx.blocking_recv().unwrap();
+        assert_eq!(frame, vec![0.75; 4]);
+        mixed.stop().unwrap();
+    }
+
+    #[test]
+    fn test_mixed_source_requires_inputs() {
+        assert!(MixedSource::new(Vec::new(), 512).is_err());
+    }
+}
@@ -1 +1,16 @@
 pub mod capture;
+pub mod clipping;
+pub mod file;
+pub mod gate;
+#[cfg(feature = "jack")]
+pub mod jack_source;
+pub mod level;
+pub mod mixer;
+pub mod network;
+pub mod noise_gate;
+pub mod process;
+pub mod rebatch;
+pub mod recorder;
+pub mod resample;
+pub mod source;
+pub mod watchdog;
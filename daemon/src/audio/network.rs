@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::audio::process::read_samples;
+use crate::audio::source::AudioSource;
+
+/// How often the worker checks for Stop while waiting for a sender or data.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receives audio streamed over TCP: raw little-endian f32 mono samples at
+/// `audio.sample_rate`, e.g. from
+/// `parec --format=float32le --rate=16000 --channels=1 --raw | nc host 7373`
+/// on another machine. One sender at a time; when it disconnects the next
+/// one is accepted.
+pub struct NetworkSource {
+    listener: Arc<TcpListener>,
+    chunk_size: usize,
+    is_running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl NetworkSource {
+    pub fn new(address: &str, chunk_size: usize) -> Result<Self> {
+        let listener = TcpListener::bind(address)
+            .with_context(|| format!("Failed to listen for audio on {}", address))?;
+        listener.set_nonblocking(true)?;
+        let source = Self {
+            listener: Arc::new(listener),
+            chunk_size: chunk_size.max(1),
+            is_running: Arc::new(AtomicBool::new(false)),
+            worker: None,
+        };
+        tracing::info!("Network audio source listening on {}", source.local_addr()?);
+        Ok(source)
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+impl AudioSource for NetworkSource {
+    fn name(&self) -> &str {
+        "network"
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+        self.is_running.store(true, Ordering::Release);
+
+        let listener = Arc::clone(&self.listener);
+        let is_running = Arc::clone(&self.is_running);
+        let chunk_size = self.chunk_size;
+        let worker = std::thread::Builder::new()
+            .name("ndict-network-source".to_string())
+            .spawn(move || {
+                while is_running.load(Ordering::Acquire) {
+                    let (stream, peer) = match listener.accept() {
+                        Ok(connection) => connection,
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(POLL_INTERVAL);
+                            continue;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to accept audio connection: {}", e);
+                            std::thread::sleep(POLL_INTERVAL);
+                            continue;
+                        }
+                    };
+                    tracing::info!("Receiving audio from {}", peer);
+                    let receive = stream
+                        .set_nonblocking(false)
+                        .and_then(|()| stream.set_read_timeout(Some(POLL_INTERVAL)))
+                        .and_then(|()| {
+                            read_samples(&stream, chunk_size, &is_running, |chunk| {
+                                let _ = audio_tx.send(chunk);
+                            })
+                        });
+                    match receive {
+                        Ok(()) => tracing::info!("Audio sender {} disconnected", peer),
+                        Err(e) => tracing::warn!("Receiving audio from {} failed: {}", peer, e),
+                    }
+                }
+            })?;
+
+        self.worker = Some(worker);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.is_running.store(false, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        tracing::info!("Network audio source stopped");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    #[tokio::test]
+    async fn test_network_source_receives_samples() {
+        let mut source = NetworkSource::new("127.0.0.1:0", 2).unwrap();
+        let (audio_tx, mut audio_rx) = broadcast::channel(8);
+        source.start(audio_tx).unwrap();
+
+        let mut sender = TcpStream::connect(source.local_addr().unwrap()).unwrap();
+        let bytes: Vec<u8> = [0.5f32, -0.25]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        sender.write_all(&bytes).unwrap();
+
+        let chunk = tokio::time::timeout(Duration::from_secs(2), audio_rx.recv())
+            .await
+            .expect("no audio received")
+            .unwrap();
+        assert_eq!(chunk, vec![0.5, -0.25]);
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn test_network_source_reports_bad_address() {
+        let error = NetworkSource::new("not an address", 512).err().unwrap();
+        assert!(error.to_string().contains("not an address"));
+    }
+}
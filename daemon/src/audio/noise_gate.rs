@@ -0,0 +1,126 @@
+use shared::ipc::dbfs_to_amplitude;
+
+use crate::config::AudioConfig;
+
+/// Downward expander that attenuates audio whose envelope stays below a
+/// threshold, cleaning up low-level hiss before the VAD and Whisper see it.
+///
+/// The gain opens over `attack` and closes over `release`, so word onsets
+/// come through quickly and quiet word endings are not chopped off.
+pub struct NoiseGate {
+    threshold: f32,
+    floor_gain: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+    gain: f32,
+}
+
+impl NoiseGate {
+    /// # Arguments
+    /// * `threshold_db` - Envelope level in dBFS below which audio is attenuated
+    /// * `range_db` - Attenuation applied while closed, e.g. 30 for -30dB
+    /// * `attack_ms` / `release_ms` - Time to open / close the gate
+    pub fn new(
+        sample_rate: u32,
+        threshold_db: f32,
+        range_db: f32,
+        attack_ms: f32,
+        release_ms: f32,
+    ) -> Self {
+        Self {
+            threshold: dbfs_to_amplitude(threshold_db),
+            floor_gain: dbfs_to_amplitude(-range_db.abs()),
+            attack_coeff: smoothing_coeff(sample_rate, attack_ms),
+            release_coeff: smoothing_coeff(sample_rate, release_ms),
+            envelope: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    /// The gate configured by `audio.noise_gate*`, if enabled.
+    pub fn from_config(config: &AudioConfig) -> Option<Self> {
+        config.noise_gate.then(|| {
+            Self::new(
+                config.sample_rate,
+                config.noise_gate_threshold_db,
+                config.noise_gate_range_db,
+                config.noise_gate_attack_ms,
+                config.noise_gate_release_ms,
+            )
+        })
+    }
+
+    pub fn apply(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let level = sample.abs();
+            // Peak envelope: jump up immediately, decay with the release time
+            self.envelope = if level > self.envelope {
+                level
+            } else {
+                level + (self.envelope - level) * self.release_coeff
+            };
+
+            let (target, coeff) = if self.envelope >= self.threshold {
+                (1.0, self.attack_coeff)
+            } else {
+                (self.floor_gain, self.release_coeff)
+            };
+            self.gain = target + (self.gain - target) * coeff;
+            *sample *= self.gain;
+        }
+    }
+}
+
+/// One-pole smoothing coefficient reaching ~63% of a step in `time_ms`.
+fn smoothing_coeff(sample_rate: u32, time_ms: f32) -> f32 {
+    let samples = sample_rate as f32 * time_ms / 1000.0;
+    if samples <= 1.0 {
+        return 0.0;
+    }
+    (-1.0 / samples).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate() -> NoiseGate {
+        // Instant attack, 1ms release at 16kHz
+        NoiseGate::new(16000, -40.0, 40.0, 0.0, 1.0)
+    }
+
+    #[test]
+    fn test_hiss_is_attenuated() {
+        let mut gate = gate();
+        let mut hiss = vec![0.001f32; 1600];
+        gate.apply(&mut hiss);
+        // Settles at the -40dB floor
+        assert!(hiss[1599] < 0.001 * 0.02);
+    }
+
+    #[test]
+    fn test_speech_passes_through() {
+        let mut gate = gate();
+        let mut speech = vec![0.2f32; 160];
+        gate.apply(&mut speech);
+        assert!(speech.iter().all(|&s| (s - 0.2).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_release_closes_gradually() {
+        let mut gate = NoiseGate::new(16000, -40.0, 40.0, 0.0, 50.0);
+        gate.apply(&mut [0.2f32; 160]);
+        let mut tail = vec![0.001f32; 160];
+        gate.apply(&mut tail);
+        // 10ms into a 50ms release the gate is still mostly open
+        assert!(tail[159] > 0.0005);
+    }
+
+    #[test]
+    fn test_smoothing_coeff() {
+        assert_eq!(smoothing_coeff(16000, 0.0), 0.0);
+        let coeff = smoothing_coeff(16000, 10.0);
+        assert!(coeff > 0.99 && coeff < 1.0);
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use std::io::{ErrorKind, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tokio::sync::broadcast;
+
+use crate::audio::source::AudioSource;
+
+/// Records audio with an external program writing raw little-endian f32
+/// mono samples to its stdout, such as `parec` for a PulseAudio/PipeWire
+/// monitor source or `pw-record` for a PipeWire node. What to record is
+/// passed on the program's command line only, so the daemon's own
+/// environment and later captures are unaffected.
+pub struct ProcessSource {
+    name: &'static str,
+    program: String,
+    args: Vec<String>,
+    chunk_size: usize,
+    is_running: Arc<AtomicBool>,
+    child: Option<Child>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ProcessSource {
+    pub fn new(name: &'static str, program: &str, args: Vec<String>, chunk_size: usize) -> Self {
+        Self {
+            name,
+            program: program.to_string(),
+            args,
+            chunk_size: chunk_size.max(1),
+            is_running: Arc::new(AtomicBool::new(false)),
+            child: None,
+            worker: None,
+        }
+    }
+
+    /// Record the PulseAudio/PipeWire source `device`, e.g.
+    /// "@DEFAULT_MONITOR@", with `parec`.
+    pub fn parec(device: &str, sample_rate: u32, chunk_size: usize) -> Self {
+        Self::new(
+            "loopback",
+            "parec",
+            vec![
+                format!("--device={}", device),
+                "--format=float32le".to_string(),
+                format!("--rate={}", sample_rate),
+                "--channels=1".to_string(),
+                "--raw".to_string(),
+            ],
+            chunk_size,
+        )
+    }
+
+    /// Record the PipeWire node `target` with `pw-record`, or the default
+    /// input for "" or "default".
+    pub fn pw_record(target: &str, sample_rate: u32, chunk_size: usize) -> Self {
+        let mut args = Vec::new();
+        if !target.is_empty() && target != "default" {
+            args.extend(["--target".to_string(), target.to_string()]);
+        }
+        args.extend([
+            "--format=f32".to_string(),
+            format!("--rate={}", sample_rate),
+            "--channels=1".to_string(),
+            "--raw".to_string(),
+            "-".to_string(),
+        ]);
+        Self::new("pipewire", "pw-record", args, chunk_size)
+    }
+}
+
+impl AudioSource for ProcessSource {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.program))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("No output from {}", self.program))?;
+        self.is_running.store(true, Ordering::Release);
+
+        let is_running = Arc::clone(&self.is_running);
+        let chunk_size = self.chunk_size;
+        let program = self.program.clone();
+        let worker = std::thread::Builder::new()
+            .name("ndict-process-source".to_string())
+            .spawn(move || {
+                let result = read_samples(stdout, chunk_size, &is_running, |chunk| {
+                    let _ = audio_tx.send(chunk);
+                });
+                match result {
+                    Err(e) => tracing::warn!("Reading audio from {} failed: {}", program, e),
+                    Ok(()) if is_running.load(Ordering::Acquire) => {
+                        tracing::warn!("{} stopped delivering audio", program)
+                    }
+                    Ok(()) => {}
+                }
+            })?;
+
+        self.child = Some(child);
+        self.worker = Some(worker);
+        tracing::info!(
+            "Recording audio with {} {}",
+            self.program,
+            self.args.join(" ")
+        );
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.is_running.store(false, Ordering::Release);
+        // Ending the program closes its output, which ends the worker
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        tracing::info!("{} audio source stopped", self.name);
+        Ok(())
+    }
+}
+
+impl Drop for ProcessSource {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Read little-endian f32 samples from `reader`, handing them to `deliver`
+/// in chunks of `chunk_size`, until it ends or `is_running` is cleared.
+/// Read timeouts only make it check `is_running` again.
+pub fn read_samples(
+    mut reader: impl Read,
+    chunk_size: usize,
+    is_running: &AtomicBool,
+    mut deliver: impl FnMut(Vec<f32>),
+) -> std::io::Result<()> {
+    let mut bytes = vec![0u8; chunk_size.max(1) * 4];
+    let mut filled = 0;
+    while is_running.load(Ordering::Acquire) {
+        let read = match reader.read(&mut bytes[filled..]) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        filled += read;
+        if filled == bytes.len() {
+            deliver(
+                bytes
+                    .chunks_exact(4)
+                    .map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]))
+                    .collect(),
+            );
+            filled = 0;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_bytes(samples: &[f32]) -> Vec<u8> {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_read_samples_in_chunks() {
+        let bytes = to_bytes(&[0.5, -0.5, 0.25, 1.0, 0.75]);
+        let mut chunks = Vec::new();
+        let running = AtomicBool::new(true);
+        read_samples(&bytes[..], 2, &running, |chunk| chunks.push(chunk)).unwrap();
+        // The incomplete last chunk is dropped
+        assert_eq!(chunks, vec![vec![0.5, -0.5], vec![0.25, 1.0]]);
+    }
+
+    #[test]
+    fn test_recorder_arguments() {
+        let source = ProcessSource::parec("@DEFAULT_MONITOR@", 16000, 512);
+        assert_eq!(source.program, "parec");
+        assert!(source
+            .args
+            .contains(&"--device=@DEFAULT_MONITOR@".to_string()));
+        assert!(source.args.contains(&"--rate=16000".to_string()));
+
+        let default = ProcessSource::pw_record("default", 16000, 512);
+        assert_eq!(default.name(), "pipewire");
+        assert!(!default.args.contains(&"--target".to_string()));
+        let node = ProcessSource::pw_record("alsa_input.usb-mic", 16000, 512);
+        assert_eq!(node.args[..2], ["--target", "alsa_input.usb-mic"]);
+    }
+
+    #[tokio::test]
+    async fn test_process_source_delivers_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audio.raw");
+        std::fs::write(&path, to_bytes(&[0.1, 0.2, 0.3, 0.4])).unwrap();
+
+        let mut source =
+            ProcessSource::new("test", "cat", vec![path.to_string_lossy().into_owned()], 2);
+        let (audio_tx, mut audio_rx) = broadcast::channel(8);
+        source.start(audio_tx).unwrap();
+        assert_eq!(audio_rx.recv().await.unwrap(), vec![0.1, 0.2]);
+        assert_eq!(audio_rx.recv().await.unwrap(), vec![0.3, 0.4]);
+        source.stop().unwrap();
+    }
+
+    #[test]
+    fn test_missing_program_fails_to_start() {
+        let mut source = ProcessSource::new("test", "/nonexistent/recorder", Vec::new(), 2);
+        let (audio_tx, _audio_rx) = broadcast::channel(8);
+        let error = source.start(audio_tx).unwrap_err();
+        assert!(error.to_string().contains("/nonexistent/recorder"));
+    }
+}
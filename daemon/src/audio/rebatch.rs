@@ -0,0 +1,76 @@
+/// Re-slices arbitrarily sized capture callbacks into fixed-size frames.
+///
+/// Backends hand over whatever buffer size the driver picked, which makes
+/// anything counting chunks (the VAD in particular) behave differently per
+/// device. Samples are held back until a full frame is available, so every
+/// emitted frame has exactly `frame_size` samples.
+pub struct FrameRebatcher {
+    frame_size: usize,
+    pending: Vec<f32>,
+}
+
+impl FrameRebatcher {
+    pub fn new(frame_size: usize) -> Self {
+        let frame_size = frame_size.max(1);
+        Self {
+            frame_size,
+            pending: Vec::with_capacity(frame_size),
+        }
+    }
+
+    /// Append `samples`, calling `emit` once for every completed frame.
+    pub fn push(&mut self, mut samples: &[f32], mut emit: impl FnMut(Vec<f32>)) {
+        while !samples.is_empty() {
+            let needed = self.frame_size - self.pending.len();
+            let take = needed.min(samples.len());
+            self.pending.extend_from_slice(&samples[..take]);
+            samples = &samples[take..];
+
+            if self.pending.len() == self.frame_size {
+                let frame =
+                    std::mem::replace(&mut self.pending, Vec::with_capacity(self.frame_size));
+                emit(frame);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_all(rebatcher: &mut FrameRebatcher, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut frames = Vec::new();
+        rebatcher.push(samples, |frame| frames.push(frame));
+        frames
+    }
+
+    #[test]
+    fn test_small_callbacks_are_joined() {
+        let mut rebatcher = FrameRebatcher::new(4);
+        assert!(push_all(&mut rebatcher, &[1.0, 2.0, 3.0]).is_empty());
+
+        let frames = push_all(&mut rebatcher, &[4.0, 5.0]);
+        assert_eq!(frames, vec![vec![1.0, 2.0, 3.0, 4.0]]);
+        assert_eq!(rebatcher.pending, vec![5.0]);
+    }
+
+    #[test]
+    fn test_large_callback_is_split() {
+        let mut rebatcher = FrameRebatcher::new(3);
+        let samples: Vec<f32> = (0..10).map(|i| i as f32).collect();
+
+        let frames = push_all(&mut rebatcher, &samples);
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|f| f.len() == 3));
+        assert_eq!(frames[2], vec![6.0, 7.0, 8.0]);
+        assert_eq!(rebatcher.pending, vec![9.0]);
+    }
+
+    #[test]
+    fn test_zero_frame_size_is_clamped() {
+        let mut rebatcher = FrameRebatcher::new(0);
+        assert_eq!(rebatcher.frame_size, 1);
+        assert_eq!(push_all(&mut rebatcher, &[0.5, 0.25]).len(), 2);
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::config::DebugConfig;
+
+/// Number of seconds between WAV header updates, so a crash still leaves a
+/// playable file behind.
+const FLUSH_INTERVAL_SECS: u32 = 1;
+
+/// Directory debug recordings are written to: `debug.recording_dir` or
+/// `$XDG_STATE_HOME/ndict/recordings`.
+pub fn recording_dir(config: &DebugConfig) -> PathBuf {
+    match &config.recording_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("ndict")
+            .join("recordings"),
+    }
+}
+
+/// Build a timestamped file name such as `segment-20260114-103000.123.wav`.
+pub fn timestamped_path(dir: &Path, prefix: &str) -> PathBuf {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    dir.join(format!("{}-{}.wav", prefix, timestamp))
+}
+
+fn wav_spec(sample_rate: u32) -> hound::WavSpec {
+    hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    }
+}
+
+fn create_writer(path: &Path, sample_rate: u32) -> Result<hound::WavWriter<BufWriter<File>>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    hound::WavWriter::create(path, wav_spec(sample_rate))
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))
+}
+
+/// Write a complete mono f32 WAV file.
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let mut writer = create_writer(path, sample_rate)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Save an utterance handed to Whisper when `debug.record_segments` is on.
+/// Failures are logged and otherwise ignored.
+pub fn save_segment(config: &DebugConfig, samples: &[f32], sample_rate: u32) {
+    if !config.record_segments {
+        return;
+    }
+
+    let path = timestamped_path(&recording_dir(config), "segment");
+    match write_wav(&path, samples, sample_rate) {
+        Ok(()) => tracing::debug!("Saved speech segment to {}", path.display()),
+        Err(e) => tracing::warn!("Failed to save speech segment: {}", e),
+    }
+}
+
+/// Tee the raw capture stream into a session WAV file until the audio
+/// channel closes.
+pub fn spawn_session_recorder(
+    config: &DebugConfig,
+    mut audio_rx: broadcast::Receiver<Vec<f32>>,
+    sample_rate: u32,
+) -> Result<JoinHandle<()>> {
+    let path = timestamped_path(&recording_dir(config), "session");
+    let mut writer = create_writer(&path, sample_rate)?;
+    tracing::info!("Recording raw audio to {}", path.display());
+
+    Ok(tokio::spawn(async move {
+        let flush_every = (sample_rate * FLUSH_INTERVAL_SECS) as usize;
+        let mut since_flush = 0;
+
+        loop {
+            match audio_rx.recv().await {
+                Ok(samples) => {
+                    for &sample in &samples {
+                        if let Err(e) = writer.write_sample(sample) {
+                            tracing::error!("Failed to write debug recording: {}", e);
+                            return;
+                        }
+                    }
+                    since_flush += samples.len();
+                    if since_flush >= flush_every {
+                        since_flush = 0;
+                        let _ = writer.flush();
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Debug recorder lagged, dropped {} audio chunks", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+
+        match writer.finalize() {
+            Ok(()) => tracing::info!("Debug recording saved to {}", path.display()),
+            Err(e) => tracing::error!("Failed to finalize debug recording: {}", e),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_in(dir: &Path) -> DebugConfig {
+        DebugConfig {
+            record_audio: true,
+            record_segments: true,
+            recording_dir: Some(dir.to_string_lossy().to_string()),
+        }
+    }
+
+    #[test]
+    fn test_recording_dir_uses_override() {
+        let config = config_in(Path::new("/tmp/ndict-recordings"));
+        assert_eq!(recording_dir(&config), PathBuf::from("/tmp/ndict-recordings"));
+    }
+
+    #[test]
+    fn test_recording_dir_default_is_under_ndict() {
+        let config = DebugConfig::default();
+        assert!(recording_dir(&config).ends_with("ndict/recordings"));
+    }
+
+    #[test]
+    fn test_write_wav_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.wav");
+        write_wav(&path, &[0.0, 0.5, -0.25], 16000).unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        let samples: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, vec![0.0, 0.5, -0.25]);
+    }
+
+    #[test]
+    fn test_save_segment_respects_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config_in(dir.path());
+        config.record_segments = false;
+        save_segment(&config, &[0.1; 16], 16000);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        config.record_segments = true;
+        save_segment(&config, &[0.1; 16], 16000);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_recorder_finalizes_on_close() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config_in(dir.path());
+        let (tx, rx) = broadcast::channel(8);
+
+        let handle = spawn_session_recorder(&config, rx, 16000).unwrap();
+        tx.send(vec![0.25; 32]).unwrap();
+        drop(tx);
+        handle.await.unwrap();
+
+        let entry = std::fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap();
+        let reader = hound::WavReader::open(entry.path()).unwrap();
+        assert_eq!(reader.len(), 32);
+    }
+}
@@ -0,0 +1,58 @@
+/// Sample rate whisper.cpp expects its input at.
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Convert `samples` from `from_rate` to `to_rate` by linear interpolation.
+///
+/// Good enough for speech recognition, which only needs the band below
+/// 8kHz; audio already at `to_rate` is returned unchanged.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let output_len = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let current = samples[index];
+            let next = samples.get(index + 1).copied().unwrap_or(current);
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+/// Duration of `samples` samples at `sample_rate`, in milliseconds.
+pub fn duration_ms(samples: usize, sample_rate: u32) -> u64 {
+    samples as u64 * 1000 / sample_rate.max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_same_rate_is_unchanged() {
+        let samples = vec![0.1, -0.2, 0.3];
+        assert_eq!(resample(&samples, 16000, 16000), samples);
+    }
+
+    #[test]
+    fn test_resample_down_and_up() {
+        let samples: Vec<f32> = (0..48).map(|i| i as f32).collect();
+        let down = resample(&samples, 48000, 16000);
+        assert_eq!(down.len(), 16);
+        assert_eq!(down[..3], [0.0, 3.0, 6.0]);
+
+        let up = resample(&[0.0, 1.0], 8000, 16000);
+        assert_eq!(up, vec![0.0, 0.5, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_duration_ms() {
+        assert_eq!(duration_ms(16000, 16000), 1000);
+        assert_eq!(duration_ms(4800, 48000), 100);
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+use crate::audio::capture::AudioCapture;
+use crate::audio::file::FileSource;
+use crate::audio::mixer::MixedSource;
+use crate::audio::network::NetworkSource;
+use crate::audio::process::ProcessSource;
+use crate::config::AudioConfig;
+
+/// An input backend that produces mono f32 audio chunks.
+///
+/// Implementations push chunks into the broadcast channel handed to `start`
+/// until `stop` is called. The daemon only talks to sources through this
+/// trait, so adding a backend means implementing it and registering a name
+/// in `create_audio_source`.
+pub trait AudioSource: Send {
+    /// Short human readable name used in logs.
+    fn name(&self) -> &str;
+
+    /// Begin delivering audio chunks to `audio_tx`.
+    fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()>;
+
+    /// Stop delivering audio and release the underlying device or file.
+    fn stop(&mut self) -> Result<()>;
+}
+
+/// Build the audio source selected by `audio.source`.
+///
+/// # Arguments
+/// * `config` - Audio configuration section
+///
+/// # Returns
+/// * `Ok(Box<dyn AudioSource>)` - Source ready to be started
+/// * `Err` - Unknown source name or the source failed to initialize
+pub fn create_audio_source(config: &AudioConfig) -> Result<Box<dyn AudioSource>> {
+    match config.source.as_str() {
+        "microphone" | "mic" | "cpal" if !config.devices.is_empty() => {
+            let inputs = config
+                .devices
+                .iter()
+                .map(|device| -> Result<Box<dyn AudioSource>> {
+                    Ok(Box::new(open_capture(config, device)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Box::new(MixedSource::new(inputs, config.chunk_size as usize)?))
+        }
+        "microphone" | "mic" | "cpal" => Ok(Box::new(open_capture(config, &config.device)?)),
+        "loopback" | "monitor" => open_loopback(config),
+        "file" => {
+            let path = config.file_path.as_ref().ok_or_else(|| {
+                anyhow::anyhow!("audio.source = \"file\" requires audio.file_path to be set")
+            })?;
+            Ok(Box::new(FileSource::new(
+                path,
+                config.sample_rate,
+                config.chunk_size as usize,
+            )?))
+        }
+        "pipewire" => Ok(Box::new(ProcessSource::pw_record(
+            &config.device,
+            config.sample_rate,
+            config.chunk_size as usize,
+        ))),
+        "network" => {
+            let address = config.network_address.as_ref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "audio.source = \"network\" requires audio.network_address to be set"
+                )
+            })?;
+            Ok(Box::new(NetworkSource::new(address, config.chunk_size as usize)?))
+        }
+        #[cfg(feature = "jack")]
+        "jack" => Ok(Box::new(crate::audio::jack_source::JackSource::new(
+            config.sample_rate,
+            config.chunk_size as usize,
+        )?)),
+        #[cfg(not(feature = "jack"))]
+        "jack" => Err(anyhow::anyhow!(
+            "audio.source = \"jack\" requires ndictd to be built with `--features jack`"
+        )),
+        other => Err(anyhow::anyhow!(
+            "Unknown audio source '{}'. Expected one of: microphone, loopback, file, pipewire, network, jack",
+            other
+        )),
+    }
+}
+
+/// Open the desktop's monitor source. Without a monitor capture device,
+/// `audio.loopback_source` is recorded through PulseAudio/PipeWire with
+/// `parec`.
+fn open_loopback(config: &AudioConfig) -> Result<Box<dyn AudioSource>> {
+    if let Some(capture) =
+        AudioCapture::new_loopback(config.sample_rate, config.channels, &config.device)?
+    {
+        return Ok(Box::new(with_capture_settings(config, &config.device, capture)?));
+    }
+    let source = config.loopback_source.as_deref().unwrap_or("@DEFAULT_MONITOR@");
+    tracing::info!("No monitor capture device, recording {} with parec", source);
+    Ok(Box::new(ProcessSource::parec(
+        source,
+        config.sample_rate,
+        config.chunk_size as usize,
+    )))
+}
+
+fn open_capture(config: &AudioConfig, device_name: &str) -> Result<AudioCapture> {
+    let capture = AudioCapture::new_with_device(config.sample_rate, config.channels, device_name)?;
+    with_capture_settings(config, device_name, capture)
+}
+
+/// Apply the frame and buffer sizes and any `audio.device_profiles` entry for
+/// `device_name` to a freshly opened capture.
+fn with_capture_settings(
+    config: &AudioConfig,
+    device_name: &str,
+    capture: AudioCapture,
+) -> Result<AudioCapture> {
+    let capture = capture
+        .with_frame_size(config.chunk_size as usize)
+        .with_buffer_frames(config.effective_buffer_frames());
+    match config.device_profiles.get(device_name) {
+        Some(profile) => {
+            tracing::info!(
+                "Using device profile for '{}': gain={:.2}, channel={:?}",
+                device_name,
+                profile.gain,
+                profile.channel
+            );
+            capture.with_profile(profile)
+        }
+        None => Ok(capture),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_unknown_source_fails() {
+        let config = AudioConfig {
+            source: "carrier-pigeon".to_string(),
+            ..AudioConfig::default()
+        };
+        let result = create_audio_source(&config);
+        assert!(result.is_err());
+        assert!(result.err().unwrap().to_string().contains("Unknown audio source"));
+    }
+
+    #[cfg(not(feature = "jack"))]
+    #[test]
+    fn test_create_jack_source_requires_feature() {
+        let config = AudioConfig {
+            source: "jack".to_string(),
+            ..AudioConfig::default()
+        };
+        let result = create_audio_source(&config);
+        assert!(result.err().unwrap().to_string().contains("--features jack"));
+    }
+
+    #[test]
+    fn test_create_network_source_requires_address() {
+        let config = AudioConfig {
+            source: "network".to_string(),
+            ..AudioConfig::default()
+        };
+        let result = create_audio_source(&config);
+        assert!(result.err().unwrap().to_string().contains("audio.network_address"));
+    }
+
+    #[test]
+    fn test_create_pipewire_source() {
+        let config = AudioConfig {
+            source: "pipewire".to_string(),
+            ..AudioConfig::default()
+        };
+        assert_eq!(create_audio_source(&config).unwrap().name(), "pipewire");
+    }
+
+    #[test]
+    fn test_create_file_source_requires_path() {
+        let config = AudioConfig {
+            source: "file".to_string(),
+            file_path: None,
+            ..AudioConfig::default()
+        };
+        let result = create_audio_source(&config);
+        assert!(result.is_err());
+        assert!(result.err().unwrap().to_string().contains("audio.file_path"));
+    }
+}
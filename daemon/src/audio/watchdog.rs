@@ -0,0 +1,197 @@
+use anyhow::Result;
+use shared::ipc::Event;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::audio::source::AudioSource;
+
+/// Restart the audio source when no audio arrives for `timeout`.
+///
+/// Driver hiccups or a suspend/resume cycle can leave a stream open but
+/// silent. The watchdog replaces the source in `audio_source` with a fresh
+/// one from `create_source`, started on the same `audio_tx` so existing
+/// receivers keep working, and publishes `Event::AudioRestarted`. The old
+/// source is dropped first, so whatever it holds, such as a listening port,
+/// is free for the new one.
+///
+/// The task holds a clone of `audio_tx`; abort it when the source is stopped
+/// so the channel can close.
+pub fn spawn_watchdog<F>(
+    audio_tx: broadcast::Sender<Vec<f32>>,
+    audio_source: Arc<Mutex<Option<Box<dyn AudioSource>>>>,
+    create_source: F,
+    timeout: Duration,
+    event_tx: broadcast::Sender<Event>,
+) -> JoinHandle<()>
+where
+    F: Fn() -> Result<Box<dyn AudioSource>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut audio_rx = audio_tx.subscribe();
+        // The last restart failed, so there is no source to replace
+        let mut failed_restart = false;
+
+        loop {
+            match tokio::time::timeout(timeout, audio_rx.recv()).await {
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(broadcast::error::RecvError::Closed)) => break,
+                Err(_) => {}
+            }
+
+            let mut guard = audio_source.lock().await;
+            match guard.take() {
+                Some(mut old_source) => {
+                    tracing::warn!(
+                        "No audio from '{}' for {:?}, restarting the audio stream",
+                        old_source.name(),
+                        timeout
+                    );
+                    if let Err(e) = old_source.stop() {
+                        tracing::warn!("Failed to stop stalled audio source: {}", e);
+                    }
+                }
+                None if failed_restart => {}
+                // Source was stopped while we were waiting
+                None => break,
+            }
+
+            let restarted = create_source().and_then(|mut source| {
+                source.start(audio_tx.clone())?;
+                Ok(source)
+            });
+            match restarted {
+                Ok(source) => {
+                    tracing::info!("Audio source '{}' restarted", source.name());
+                    *guard = Some(source);
+                    failed_restart = false;
+                    // No subscribers is not an error
+                    let _ = event_tx.send(Event::AudioRestarted);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to restart audio source, retrying: {}", e);
+                    failed_restart = true;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::network::NetworkSource;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sends one chunk on start and then goes quiet.
+    struct StallingSource;
+
+    impl AudioSource for StallingSource {
+        fn name(&self) -> &str {
+            "stalling"
+        }
+
+        fn start(&mut self, audio_tx: broadcast::Sender<Vec<f32>>) -> Result<()> {
+            let _ = audio_tx.send(vec![0.0; 4]);
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_restarts_stalled_source() {
+        let (audio_tx, mut audio_rx) = broadcast::channel(8);
+        let (event_tx, mut event_rx) = broadcast::channel(8);
+        let audio_source: Arc<Mutex<Option<Box<dyn AudioSource>>>> =
+            Arc::new(Mutex::new(Some(Box::new(StallingSource))));
+        let restarts = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&restarts);
+        let handle = spawn_watchdog(
+            audio_tx,
+            Arc::clone(&audio_source),
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(StallingSource) as Box<dyn AudioSource>)
+            },
+            Duration::from_millis(20),
+            event_tx,
+        );
+
+        assert_eq!(event_rx.recv().await.unwrap(), Event::AudioRestarted);
+        // The restarted source delivers on the original channel
+        assert_eq!(audio_rx.recv().await.unwrap(), vec![0.0; 4]);
+        assert!(restarts.load(Ordering::SeqCst) >= 1);
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_restarts_idle_network_source() {
+        let (audio_tx, mut audio_rx) = broadcast::channel(8);
+        let (event_tx, mut event_rx) = broadcast::channel(8);
+        let mut source = NetworkSource::new("127.0.0.1:0", 2).unwrap();
+        let address = source.local_addr().unwrap();
+        source.start(audio_tx.clone()).unwrap();
+        let audio_source: Arc<Mutex<Option<Box<dyn AudioSource>>>> =
+            Arc::new(Mutex::new(Some(Box::new(source))));
+
+        // The new source listens on the address the old one held
+        let handle = spawn_watchdog(
+            audio_tx,
+            Arc::clone(&audio_source),
+            move || {
+                Ok(Box::new(NetworkSource::new(&address.to_string(), 2)?) as Box<dyn AudioSource>)
+            },
+            // Long enough for the sender below to connect before the next restart
+            Duration::from_millis(500),
+            event_tx,
+        );
+
+        let restarted = tokio::time::timeout(Duration::from_secs(2), event_rx.recv())
+            .await
+            .expect("network source was not restarted");
+        assert_eq!(restarted.unwrap(), Event::AudioRestarted);
+
+        let mut sender = TcpStream::connect(address).unwrap();
+        let bytes: Vec<u8> = [0.5f32, -0.25]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+        sender.write_all(&bytes).unwrap();
+        let chunk = tokio::time::timeout(Duration::from_secs(2), audio_rx.recv())
+            .await
+            .expect("no audio received")
+            .unwrap();
+        assert_eq!(chunk, vec![0.5, -0.25]);
+
+        handle.abort();
+        let source = audio_source.lock().await.take();
+        if let Some(mut source) = source {
+            source.stop().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_exits_when_source_is_gone() {
+        let (audio_tx, _audio_rx) = broadcast::channel::<Vec<f32>>(8);
+        let (event_tx, _event_rx) = broadcast::channel(8);
+        let audio_source: Arc<Mutex<Option<Box<dyn AudioSource>>>> = Arc::new(Mutex::new(None));
+
+        let handle = spawn_watchdog(
+            audio_tx,
+            audio_source,
+            || Err(anyhow::anyhow!("should not be called")),
+            Duration::from_millis(10),
+            event_tx,
+        );
+
+        handle.await.unwrap();
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::transcription::models;
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Config {
     #[serde(default)]
@@ -15,6 +18,8 @@ pub struct Config {
     #[serde(default)]
     pub streaming: StreamingConfig,
     #[serde(default)]
+    pub stt: SttConfig,
+    #[serde(default)]
     pub buffer: BufferConfig,
     #[serde(default)]
     pub output: OutputConfig,
@@ -24,12 +29,26 @@ pub struct Config {
     pub timeouts: TimeoutsConfig,
     #[serde(default)]
     pub llm: LlmConfig,
+    #[serde(default)]
+    pub cloud: CloudConfig,
+    #[serde(default)]
+    pub debug: DebugConfig,
+    #[serde(default)]
+    pub wake: WakeConfig,
+    #[serde(default)]
+    pub download: DownloadConfig,
+    #[serde(default)]
+    pub transcribe: TranscribeConfig,
+    #[serde(default)]
+    pub post_processing: PostProcessingConfig,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct AudioConfig {
     #[serde(default)]
     pub device: String,
+    #[serde(default)]
+    pub devices: Vec<String>,
     #[serde(default = "default_sample_rate")]
     pub sample_rate: u32,
     #[serde(default = "default_chunk_size")]
@@ -38,11 +57,81 @@ pub struct AudioConfig {
     pub gain: f32,
     #[serde(default = "default_channels")]
     pub channels: u16,
+    #[serde(default = "default_audio_source")]
+    pub source: String,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    #[serde(default)]
+    pub loopback_source: Option<String>,
+    /// Address to receive streamed audio on when `source = "network"`.
+    #[serde(default)]
+    pub network_address: Option<String>,
+    #[serde(default = "default_level_interval_ms")]
+    pub level_interval_ms: u32,
+    #[serde(default = "default_clipping_warnings")]
+    pub clipping_warnings: bool,
+    #[serde(default)]
+    pub device_profiles: HashMap<String, DeviceProfile>,
+    #[serde(default)]
+    pub cue_gate_ms: u32,
+    #[serde(default)]
+    pub buffer_frames: Option<u32>,
+    #[serde(default)]
+    pub low_latency: bool,
+    #[serde(default)]
+    pub noise_gate: bool,
+    #[serde(default = "default_noise_gate_threshold_db")]
+    pub noise_gate_threshold_db: f32,
+    #[serde(default = "default_noise_gate_range_db")]
+    pub noise_gate_range_db: f32,
+    #[serde(default = "default_noise_gate_attack_ms")]
+    pub noise_gate_attack_ms: f32,
+    #[serde(default = "default_noise_gate_release_ms")]
+    pub noise_gate_release_ms: f32,
+}
+
+/// Capture buffer size requested by the `audio.low_latency` preset (8ms at
+/// 16kHz).
+pub const LOW_LATENCY_BUFFER_FRAMES: u32 = 128;
+
+impl AudioConfig {
+    /// Buffer size to request from the driver: `buffer_frames` if set, the
+    /// low-latency preset if enabled, otherwise the driver default (`None`).
+    pub fn effective_buffer_frames(&self) -> Option<u32> {
+        self.buffer_frames
+            .or(self.low_latency.then_some(LOW_LATENCY_BUFFER_FRAMES))
+    }
+}
+
+/// Capture settings for one input device, keyed by the name used in
+/// `audio.device` / `audio.devices`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct DeviceProfile {
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    /// 1-based channel to capture from a multichannel device; all channels
+    /// are averaged when unset.
+    #[serde(default)]
+    pub channel: Option<u16>,
+}
+
+impl Default for DeviceProfile {
+    fn default() -> Self {
+        Self {
+            gain: default_gain(),
+            channel: None,
+        }
+    }
 }
 
 fn default_sample_rate() -> u32 {
     16000
 }
+
+fn default_clipping_warnings() -> bool {
+    true
+}
+
 fn default_chunk_size() -> u32 {
     512
 }
@@ -52,6 +141,24 @@ fn default_gain() -> f32 {
 fn default_channels() -> u16 {
     1
 }
+fn default_audio_source() -> String {
+    "microphone".to_string()
+}
+fn default_level_interval_ms() -> u32 {
+    50
+}
+fn default_noise_gate_threshold_db() -> f32 {
+    -50.0
+}
+fn default_noise_gate_range_db() -> f32 {
+    20.0
+}
+fn default_noise_gate_attack_ms() -> f32 {
+    2.0
+}
+fn default_noise_gate_release_ms() -> f32 {
+    150.0
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct VadConfig {
@@ -59,10 +166,121 @@ pub struct VadConfig {
     pub threshold_start: f32,
     #[serde(default = "default_threshold_stop")]
     pub threshold_stop: f32,
+    /// Start threshold in dBFS; overrides `threshold_start` when set.
+    #[serde(default)]
+    pub threshold_start_db: Option<f32>,
+    /// Stop threshold in dBFS; overrides `threshold_stop` when set.
+    #[serde(default)]
+    pub threshold_stop_db: Option<f32>,
     #[serde(default = "default_min_speech_duration")]
     pub min_speech_duration_ms: u32,
     #[serde(default = "default_min_silence_duration")]
     pub min_silence_duration_ms: u32,
+    #[serde(default = "default_trim_silence")]
+    pub trim_silence: bool,
+    #[serde(default = "default_trim_margin")]
+    pub trim_margin_ms: u32,
+    #[serde(default = "default_pre_speech_padding")]
+    pub pre_speech_padding_ms: u32,
+    /// Length of the frames the detector analyzes, independent of the
+    /// capture chunk size; 0 analyzes chunks as they arrive.
+    #[serde(default = "default_vad_frame_ms")]
+    pub frame_ms: u32,
+    #[serde(default = "default_hangover_frames")]
+    pub speech_start_frames: u32,
+    #[serde(default = "default_hangover_frames")]
+    pub speech_stop_frames: u32,
+    /// Ambient audio to measure on `Start` before listening for speech; 0
+    /// skips calibration.
+    #[serde(default)]
+    pub calibration_ms: u32,
+    /// Emit intermediate segments after this much continuous speech; 0 waits
+    /// for the speaker to pause.
+    #[serde(default)]
+    pub max_segment_ms: u32,
+    /// Shorten the silence timeout to `min_endpoint_ms` when speech ends
+    /// abruptly and stretch it to `max_endpoint_ms` during hesitations.
+    #[serde(default)]
+    pub smart_endpointing: bool,
+    #[serde(default = "default_min_endpoint_ms")]
+    pub min_endpoint_ms: u32,
+    #[serde(default = "default_max_endpoint_ms")]
+    pub max_endpoint_ms: u32,
+    #[serde(default = "default_vad_backend")]
+    pub backend: String,
+    #[serde(default)]
+    pub silero_model_path: Option<String>,
+    #[serde(default = "default_silero_threshold")]
+    pub silero_threshold: f32,
+    #[serde(default)]
+    pub adaptive_thresholds: bool,
+    #[serde(default = "default_noise_start_ratio")]
+    pub noise_start_ratio: f32,
+    #[serde(default = "default_noise_stop_ratio")]
+    pub noise_stop_ratio: f32,
+    #[serde(default)]
+    pub spectral_features: bool,
+    #[serde(default = "default_min_spectral_flatness")]
+    pub min_spectral_flatness: f32,
+    #[serde(default = "default_min_zero_crossing_rate")]
+    pub min_zero_crossing_rate: f32,
+}
+
+impl VadConfig {
+    /// Linear RMS start/stop thresholds, taking the dBFS variants into
+    /// account when they are set.
+    pub fn effective_thresholds(&self) -> (f32, f32) {
+        let start = self
+            .threshold_start_db
+            .map(shared::ipc::dbfs_to_amplitude)
+            .unwrap_or(self.threshold_start);
+        let stop = self
+            .threshold_stop_db
+            .map(shared::ipc::dbfs_to_amplitude)
+            .unwrap_or(self.threshold_stop);
+        (start, stop)
+    }
+}
+
+fn default_vad_backend() -> String {
+    "energy".to_string()
+}
+fn default_silero_threshold() -> f32 {
+    0.5
+}
+fn default_noise_start_ratio() -> f32 {
+    3.0
+}
+fn default_noise_stop_ratio() -> f32 {
+    2.0
+}
+fn default_min_spectral_flatness() -> f32 {
+    0.05
+}
+fn default_min_zero_crossing_rate() -> f32 {
+    0.02
+}
+
+fn default_trim_silence() -> bool {
+    true
+}
+fn default_trim_margin() -> u32 {
+    200
+}
+fn default_pre_speech_padding() -> u32 {
+    200
+}
+fn default_min_endpoint_ms() -> u32 {
+    400
+}
+fn default_max_endpoint_ms() -> u32 {
+    1500
+}
+fn default_vad_frame_ms() -> u32 {
+    30
+}
+fn default_hangover_frames() -> u32 {
+    2
 }
 
 fn default_min_speech_duration() -> u32 {
@@ -82,34 +300,507 @@ fn default_threshold_stop() -> f32 {
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct WhisperConfig {
+    /// Model file to load for the default model, wherever it is, instead of
+    /// searching the usual directories for the file named in `model_url`.
     #[serde(default)]
     pub model_path: Option<String>,
+    /// Registry alias such as "base.en", or "auto" to pick one for the
+    /// machine; takes precedence over `model_url`.
+    #[serde(default)]
+    pub model: Option<String>,
     #[serde(default = "default_model_url")]
     pub model_url: String,
+    /// Expected SHA-256 of the model file. Downloads without one are checked
+    /// against the checksum HuggingFace publishes, when there is one.
     #[serde(default)]
     pub model_checksum: Option<String>,
     #[serde(default = "default_language")]
     pub language: String,
-    #[serde(default = "default_n_thread")]
+    /// With `language = "auto"`, keep the first language detected in a
+    /// session instead of detecting it again for every segment.
+    #[serde(default)]
+    pub detect_language_once: bool,
+    /// "transcribe" in the spoken language or "translate" it to English.
+    #[serde(default = "default_task")]
+    pub task: String,
+    /// Whisper CPU threads; "auto" (stored as 0) picks from the core count.
+    #[serde(default = "default_n_thread", deserialize_with = "deserialize_thread_count")]
     pub n_thread: u32,
+    /// Utterances transcribed at once, each on its own Whisper state sharing
+    /// one loaded model. Each worker runs `n_thread` threads, so keep
+    /// `workers * n_thread` within the core count.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
     #[serde(default = "default_backend")]
     pub backend: String,
+    /// Index of the GPU to use with a GPU backend, for machines with several.
+    #[serde(default)]
+    pub gpu_device: i32,
     #[serde(default = "default_streaming_mode")]
     pub streaming_mode: bool,
     #[serde(default = "default_min_audio_samples")]
     pub min_audio_samples: usize,
     #[serde(default = "default_sampling_strategy")]
     pub sampling_strategy: String,
+    /// Segments Whisper rates as more likely than this to contain no speech
+    /// are dropped; 1.0 keeps everything.
+    #[serde(default = "default_no_speech_threshold")]
+    pub no_speech_threshold: f32,
+    /// Results whose mean token probability is below this are handled per
+    /// `low_confidence_action`; 0.0 accepts everything.
+    #[serde(default)]
+    pub min_confidence: f32,
+    /// "drop" low-confidence results or "flag" them and type them anyway.
+    #[serde(default = "default_low_confidence_action")]
+    pub low_confidence_action: String,
+    /// Text given to Whisper as prior context, e.g. to set style or topic.
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+    /// Names and jargon Whisper should spell correctly.
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    /// File with one additional vocabulary term per line.
+    #[serde(default)]
+    pub vocabulary_file: Option<String>,
+    /// Extra models by name (URL or registry alias), switchable at runtime
+    /// with `SetModel`. The configured model is always available as
+    /// "default", and registry aliases can be selected directly.
+    #[serde(default)]
+    pub models: HashMap<String, String>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
+    /// Mark speaker changes with ">>" in transcripts, using tinydiarize.
+    /// Needs a `-tdrz` model and only applies when writing a transcript.
+    #[serde(default)]
+    pub diarize: bool,
+    /// Align each word to the audio with whisper.cpp's DTW, giving
+    /// frame-accurate word timings instead of per-segment ones. Works with
+    /// the official models only and costs some decoding time.
+    #[serde(default)]
+    pub dtw_timestamps: bool,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub grammar: GrammarConfig,
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+}
+
+/// Splitting of long utterances under `[whisper.chunking]`: the pieces are
+/// transcribed in parallel on the `workers` and joined in order.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ChunkingConfig {
+    /// Split utterances at least this long; 0 disables chunking.
+    #[serde(default = "default_chunking_min_length_ms")]
+    pub min_length_ms: u64,
+    /// Longest chunk; cuts are made at the quietest point before this.
+    #[serde(default = "default_chunk_ms")]
+    pub chunk_ms: u64,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            min_length_ms: default_chunking_min_length_ms(),
+            chunk_ms: default_chunk_ms(),
+        }
+    }
+}
+
+/// Constrained decoding under `[whisper.grammar]`, e.g. for dictating editor
+/// commands: Whisper is steered to say exactly one of the listed phrases.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct GrammarConfig {
+    /// Phrases Whisper may produce; empty leaves decoding unconstrained.
+    #[serde(default)]
+    pub phrases: Vec<String>,
+    /// File with one additional phrase per line.
+    #[serde(default)]
+    pub phrases_file: Option<String>,
+    /// Log-probability penalty for tokens outside the grammar. Higher
+    /// values hold Whisper to the phrases more strictly.
+    #[serde(default = "default_grammar_penalty")]
+    pub penalty: f32,
+}
+
+impl Default for GrammarConfig {
+    fn default() -> Self {
+        Self {
+            phrases: Vec::new(),
+            phrases_file: None,
+            penalty: default_grammar_penalty(),
+        }
+    }
+}
+
+/// Second pass settings under `[whisper.retry]`: utterances are transcribed
+/// again with a larger model and differing results reported as
+/// `Event::Corrected`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
+pub struct RetryConfig {
+    /// Model to retry with, named as for `SetModel`; unset disables retries.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Retry utterances scored below this without being asked; 0.0 only
+    /// retries on the `Retry` command.
+    #[serde(default)]
+    pub below_confidence: f32,
+}
+
+/// Decoder settings under `[whisper.decoding]`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct DecodingConfig {
+    /// Hypotheses kept per step with `sampling_strategy = "beam"`.
+    #[serde(default = "default_beam_size")]
+    pub beam_size: i32,
+    /// Beam search patience; above 1.0 explores longer before stopping.
+    #[serde(default = "default_patience")]
+    pub patience: f32,
+    /// Candidates sampled per step with `sampling_strategy = "greedy"`.
+    #[serde(default = "default_best_of")]
+    pub best_of: i32,
+    /// Sampling temperature of the first decoding attempt.
+    #[serde(default)]
+    pub temperature: f32,
+    /// Added to the temperature for each retry after a failed attempt;
+    /// 0.0 disables the fallback.
+    #[serde(default = "default_temperature_increment")]
+    pub temperature_increment: f32,
+    /// Attempts whose token entropy falls below this are treated as
+    /// repetition loops and retried, like OpenAI's compression ratio check.
+    #[serde(default = "default_entropy_threshold")]
+    pub entropy_threshold: f32,
+    /// Attempts whose average token log-probability is below this are
+    /// retried, and combined with `no_speech_threshold` mark silence.
+    #[serde(default = "default_logprob_threshold")]
+    pub logprob_threshold: f32,
+    /// Stop Whisper from starting a segment with a blank token.
+    #[serde(default = "default_suppress_blank")]
+    pub suppress_blank: bool,
+    /// Keep Whisper from emitting non-speech tokens such as "[Music]" or
+    /// "(laughs)"; bracketed text is then no longer stripped afterwards.
+    #[serde(default)]
+    pub suppress_non_speech: bool,
+}
+
+impl Default for DecodingConfig {
+    fn default() -> Self {
+        Self {
+            beam_size: default_beam_size(),
+            patience: default_patience(),
+            best_of: default_best_of(),
+            temperature: 0.0,
+            temperature_increment: default_temperature_increment(),
+            entropy_threshold: default_entropy_threshold(),
+            logprob_threshold: default_logprob_threshold(),
+            suppress_blank: default_suppress_blank(),
+            suppress_non_speech: false,
+        }
+    }
+}
+
+fn default_beam_size() -> i32 {
+    5
+}
+
+fn default_patience() -> f32 {
+    1.0
+}
+
+fn default_best_of() -> i32 {
+    1
+}
+
+fn default_temperature_increment() -> f32 {
+    0.2
+}
+
+fn default_entropy_threshold() -> f32 {
+    2.4
+}
+
+fn default_logprob_threshold() -> f32 {
+    -1.0
+}
+
+fn default_suppress_blank() -> bool {
+    true
+}
+
+fn default_grammar_penalty() -> f32 {
+    100.0
+}
+
+fn default_chunking_min_length_ms() -> u64 {
+    60000
+}
+
+fn default_chunk_ms() -> u64 {
+    30000
+}
+
+/// `whisper.n_thread` value that sizes the thread pool from the core count.
+pub const AUTO_THREADS: u32 = 0;
+
+/// Upper bound for automatic thread counts; whisper.cpp gains little past it.
+const MAX_AUTO_THREADS: usize = 8;
+
+/// Name under which `whisper.model_url` is selected with `SetModel`.
+pub const DEFAULT_MODEL: &str = "default";
+
+impl WhisperConfig {
+    /// URL or alias the model called `name` refers to.
+    fn model_reference<'a>(&'a self, name: &'a str) -> &'a str {
+        match name {
+            DEFAULT_MODEL => match self.model.as_deref() {
+                Some(models::AUTO_MODEL) => self.auto_model().alias,
+                Some(alias) => alias,
+                None => &self.model_url,
+            },
+            other => self.models.get(other).map_or(other, String::as_str),
+        }
+    }
+
+    /// Model `model = "auto"` stands for on this machine.
+    fn auto_model(&self) -> &'static models::ModelInfo {
+        let available_mib = models::available_memory_mib().unwrap_or(0);
+        let gpu = self.backend != "cpu";
+        let english_only = self.language == "en" && self.task == "transcribe";
+        models::auto_select(available_mib, gpu, english_only)
+    }
+
+    /// Download URL of the model called `name`: the configured model for
+    /// "default", an entry of `models`, or a registry alias.
+    pub fn model_url_for(&self, name: &str) -> Option<String> {
+        models::resolve_url(self.model_reference(name))
+    }
+
+    /// File the model called `name` must be loaded from, when `model_path`
+    /// pins it; otherwise the file is looked up from its URL.
+    pub fn model_path_for(&self, name: &str) -> Option<PathBuf> {
+        match name {
+            DEFAULT_MODEL => self.model_path.as_ref().map(PathBuf::from),
+            _ => None,
+        }
+    }
+
+    /// Registry entry of the model called `name`, if it is an alias.
+    pub fn model_info_for(&self, name: &str) -> Option<&'static models::ModelInfo> {
+        models::lookup(self.model_reference(name))
+    }
+
+    /// Expected SHA-256 of the model called `name`. `model_checksum` only
+    /// applies to the default model; aliases use the registry's value.
+    pub fn model_checksum_for(&self, name: &str) -> Option<String> {
+        if name == DEFAULT_MODEL && self.model_checksum.is_some() {
+            return self.model_checksum.clone();
+        }
+        self.model_info_for(name)
+            .and_then(|model| model.sha256)
+            .map(str::to_string)
+    }
+
+    /// Number of threads to hand Whisper, resolving "auto".
+    pub fn effective_threads(&self) -> i32 {
+        if self.n_thread != AUTO_THREADS {
+            return self.n_thread as i32;
+        }
+        std::thread::available_parallelism()
+            .map_or(1, |cores| cores.get().min(MAX_AUTO_THREADS)) as i32
+    }
+}
+
+fn deserialize_thread_count<'de, D>(deserializer: D) -> std::result::Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ThreadCount {
+        Count(u32),
+        Named(String),
+    }
+
+    match ThreadCount::deserialize(deserializer)? {
+        ThreadCount::Count(count) => Ok(count),
+        ThreadCount::Named(name) if name == "auto" => Ok(AUTO_THREADS),
+        ThreadCount::Named(name) => Err(serde::de::Error::custom(format!(
+            "invalid n_thread '{}', expected a number or \"auto\"",
+            name
+        ))),
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct StreamingConfig {
+    /// Decode the unfinished window every `step_ms` of new audio; fixed
+    /// windows only, i.e. without `vad_segmentation`.
     #[serde(default = "default_streaming_step_ms")]
     pub step_ms: u32,
+    /// Type the unfinished window at each step as a draft and correct it
+    /// with backspaces as Whisper revises it, instead of appending the
+    /// words each step adds.
+    #[serde(default = "default_streaming_correct_drafts")]
+    pub correct_drafts: bool,
     #[serde(default = "default_streaming_length_ms")]
     pub length_ms: u32,
     #[serde(default = "default_streaming_keep_ms")]
     pub keep_ms: u32,
+    /// Cut windows where the `[vad]` detector hears a pause, capped at
+    /// `length_ms`, instead of every `length_ms` with `keep_ms` overlap.
+    #[serde(default = "default_streaming_vad_segmentation")]
+    pub vad_segmentation: bool,
+    /// Decode the window being spoken every `partial_ms` and publish its
+    /// text as `Event::Partial` for live previews; 0 turns this off. Only
+    /// final text is typed.
+    #[serde(default)]
+    pub partial_ms: u32,
+    /// Add the punctuation and capitalization Whisper leaves out of
+    /// single streaming windows before post-processing.
+    #[serde(default)]
+    pub restore_punctuation: bool,
+    /// Name of a `whisper.models` entry to stream with, e.g. a small model
+    /// for fast drafts; unset uses `whisper.model_url`.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Which speech-to-text engine transcribes locally.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct SttConfig {
+    /// "whisper", "vosk", "server" or "ctranslate2".
+    #[serde(default = "default_stt_backend")]
+    pub backend: String,
+    #[serde(default)]
+    pub vosk: VoskConfig,
+    #[serde(default)]
+    pub server: WhisperServerConfig,
+    #[serde(default)]
+    pub ctranslate2: CTranslate2Config,
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_stt_backend(),
+            vosk: VoskConfig::default(),
+            server: WhisperServerConfig::default(),
+            ctranslate2: CTranslate2Config::default(),
+        }
+    }
+}
+
+fn default_stt_backend() -> String {
+    "whisper".to_string()
+}
+
+/// Settings for `stt.backend = "vosk"`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct VoskConfig {
+    /// The libvosk shared library, loaded when the backend starts.
+    #[serde(default = "default_vosk_library")]
+    pub library: String,
+    /// Model directory for each language code.
+    #[serde(default)]
+    pub models: HashMap<String, String>,
+}
+
+impl Default for VoskConfig {
+    fn default() -> Self {
+        Self {
+            library: default_vosk_library(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+fn default_vosk_library() -> String {
+    "libvosk.so".to_string()
+}
+
+/// Settings for `stt.backend = "ctranslate2"`, Whisper through the
+/// faster-whisper runtime.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct CTranslate2Config {
+    /// Converted model directory, e.g. a download of
+    /// `Systran/faster-whisper-small`.
+    #[serde(default)]
+    pub model_dir: Option<String>,
+    /// "cpu" or "cuda".
+    #[serde(default = "default_ctranslate2_device")]
+    pub device: String,
+    /// Weight precision, e.g. "int8", "float16" or "default" to keep the
+    /// model's own.
+    #[serde(default = "default_ctranslate2_compute_type")]
+    pub compute_type: String,
+    #[serde(default = "default_ctranslate2_beam_size")]
+    pub beam_size: usize,
+}
+
+impl Default for CTranslate2Config {
+    fn default() -> Self {
+        Self {
+            model_dir: None,
+            device: default_ctranslate2_device(),
+            compute_type: default_ctranslate2_compute_type(),
+            beam_size: default_ctranslate2_beam_size(),
+        }
+    }
+}
+
+fn default_ctranslate2_device() -> String {
+    "cpu".to_string()
+}
+
+fn default_ctranslate2_compute_type() -> String {
+    "int8".to_string()
+}
+
+fn default_ctranslate2_beam_size() -> usize {
+    5
+}
+
+/// Settings for `stt.backend = "server"`, a whisper.cpp `server` reached
+/// over HTTP.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct WhisperServerConfig {
+    #[serde(default = "default_whisper_server_url")]
+    pub url: String,
+    #[serde(default = "default_whisper_server_timeout")]
+    pub timeout_seconds: u64,
+    /// Wait before the first reconnection attempt; doubles after each
+    /// failure up to `max_retry_seconds`.
+    #[serde(default = "default_whisper_server_retry")]
+    pub retry_seconds: u64,
+    #[serde(default = "default_whisper_server_max_retry")]
+    pub max_retry_seconds: u64,
+}
+
+impl Default for WhisperServerConfig {
+    fn default() -> Self {
+        Self {
+            url: default_whisper_server_url(),
+            timeout_seconds: default_whisper_server_timeout(),
+            retry_seconds: default_whisper_server_retry(),
+            max_retry_seconds: default_whisper_server_max_retry(),
+        }
+    }
+}
+
+fn default_whisper_server_url() -> String {
+    "http://127.0.0.1:8080".to_string()
+}
+
+fn default_whisper_server_timeout() -> u64 {
+    20
+}
+
+fn default_whisper_server_retry() -> u64 {
+    2
+}
+
+fn default_whisper_server_max_retry() -> u64 {
+    60
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -134,10 +825,18 @@ fn default_language() -> String {
     "en".to_string()
 }
 
+fn default_task() -> String {
+    "transcribe".to_string()
+}
+
 fn default_n_thread() -> u32 {
     4
 }
 
+fn default_workers() -> usize {
+    1
+}
+
 fn default_backend() -> String {
     "cpu".to_string()
 }
@@ -154,6 +853,14 @@ fn default_sampling_strategy() -> String {
     "greedy".to_string()
 }
 
+fn default_no_speech_threshold() -> f32 {
+    0.6
+}
+
+fn default_low_confidence_action() -> String {
+    "drop".to_string()
+}
+
 fn default_streaming_step_ms() -> u32 {
     3000
 }
@@ -166,6 +873,14 @@ fn default_streaming_keep_ms() -> u32 {
     500
 }
 
+fn default_streaming_correct_drafts() -> bool {
+    true
+}
+
+fn default_streaming_vad_segmentation() -> bool {
+    true
+}
+
 fn default_broadcast_capacity() -> usize {
     100
 }
@@ -176,14 +891,156 @@ fn default_log_level() -> String {
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct OutputConfig {
+    /// Keyboard emulation: "wayland" (virtual keyboard protocol), "x11"
+    /// (XTEST through xdotool) or "auto" for the running session.
+    #[serde(default = "default_output_backend")]
+    pub backend: String,
+    /// "instant" types key events, "delayed" types them with a pause after
+    /// each character, "paste" pastes through the clipboard.
     #[serde(default = "default_typing_mode")]
     pub typing_mode: String,
+    /// Pause after each character in "delayed" mode.
+    #[serde(default = "default_key_delay_ms")]
+    pub key_delay_ms: u64,
+    /// Each pause varies randomly by up to this much.
+    #[serde(default = "default_key_jitter_ms")]
+    pub key_jitter_ms: u64,
+    /// Pastes in "paste" mode: "ctrl+v" or "shift+insert".
+    #[serde(default = "default_paste_shortcut")]
+    pub paste_shortcut: String,
+    /// Milliseconds to wait after pasting before the previous clipboard
+    /// contents are put back.
+    #[serde(default = "default_paste_restore_ms")]
+    pub paste_restore_ms: u64,
+    #[serde(default)]
+    pub transcript_file: Option<String>,
+    /// Write transcriptions as line-delimited JSON to "stdout" or a file
+    /// or named pipe instead of typing them.
+    #[serde(default)]
+    pub json_output: Option<String>,
+    /// Append every final transcription, with time and language, to a
+    /// file per day in `history_dir`.
+    #[serde(default)]
+    pub history: bool,
+    /// Defaults to `~/.local/share/ndict/history`.
+    #[serde(default)]
+    pub history_dir: Option<String>,
+    /// Keep transcribing on Pause but hold the text back, typing it on
+    /// Resume or `Flush`.
+    #[serde(default)]
+    pub buffer_while_paused: bool,
+    /// Treat "scratch that", "delete last word" and "undo" as editing
+    /// commands for the typed text instead of typing them.
+    #[serde(default = "default_voice_editing")]
+    pub voice_editing: bool,
+    /// Typed between consecutive utterances: "space", "newline" or "none".
+    /// Transcript files get a line per utterance regardless.
+    #[serde(default = "default_separator")]
+    pub separator: String,
+    /// Type a space after every utterance.
+    #[serde(default)]
+    pub trailing_space: bool,
+    /// Key pressed after every final utterance, e.g. "enter" to send chat
+    /// messages or "ctrl+enter"; unset presses nothing.
+    #[serde(default)]
+    pub submit_key: Option<String>,
+    /// `[[output.snippets]]` spoken macros.
+    #[serde(default)]
+    pub snippets: Vec<Snippet>,
+    /// The output can type any Unicode character, as wrtype and the
+    /// transcript file can. Turn off for applications that drop non-ASCII
+    /// input; emoji and smart quotes are then not produced.
+    #[serde(default = "default_unicode_output")]
+    pub unicode: bool,
+    #[serde(default)]
+    pub sinks: SinksConfig,
+}
+
+/// Where final text goes, under `[output.sinks]`; any number can be on.
+/// Unset flags follow the rest of `[output]`, so setting `transcript_file`
+/// alone still writes the transcript instead of typing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct SinksConfig {
+    /// Type with the virtual keyboard. On unless text goes to a transcript,
+    /// JSON output or IBus.
+    #[serde(default)]
+    pub keyboard: Option<bool>,
+    /// Append to `output.transcript_file`. On when that is set.
+    #[serde(default)]
+    pub transcript: Option<bool>,
+    /// Write `output.json_output`, or stdout when that is unset. On when
+    /// it is set.
+    #[serde(default)]
+    pub json: Option<bool>,
+    /// Commit text through IBus as the "ndict" input method, which has to
+    /// be selected while dictating. Off by default.
+    #[serde(default)]
+    pub ibus: bool,
+    /// Publish transcriptions to `ndict events` subscribers.
+    #[serde(default = "default_events_sink")]
+    pub events: bool,
+}
+
+impl Default for SinksConfig {
+    fn default() -> Self {
+        Self {
+            keyboard: None,
+            transcript: None,
+            json: None,
+            ibus: false,
+            events: default_events_sink(),
+        }
+    }
+}
+
+fn default_events_sink() -> bool {
+    true
 }
 
 fn default_typing_mode() -> String {
     "instant".to_string()
 }
 
+fn default_output_backend() -> String {
+    "auto".to_string()
+}
+
+fn default_key_delay_ms() -> u64 {
+    20
+}
+
+fn default_key_jitter_ms() -> u64 {
+    10
+}
+
+fn default_paste_shortcut() -> String {
+    "ctrl+v".to_string()
+}
+
+fn default_paste_restore_ms() -> u64 {
+    300
+}
+
+fn default_voice_editing() -> bool {
+    true
+}
+
+/// Text typed in place of a spoken trigger phrase, e.g. a signature.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Snippet {
+    pub trigger: String,
+    /// May span several lines.
+    pub text: String,
+}
+
+fn default_separator() -> String {
+    "space".to_string()
+}
+
+fn default_unicode_output() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub struct RateLimitConfig {
     #[serde(default = "default_commands_per_second")]
@@ -218,80 +1075,449 @@ pub struct TimeoutsConfig {
     pub socket_operation_timeout_seconds: u64,
     #[serde(default = "default_model_download_timeout")]
     pub model_download_timeout_seconds: u64,
+    #[serde(default = "default_audio_watchdog_timeout")]
+    pub audio_watchdog_seconds: u64,
+    /// Unload models once the daemon has been stopped this long, reloading
+    /// them on the next Start; 0 keeps them loaded.
+    #[serde(default)]
+    pub model_idle_unload_seconds: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            whisper_timeout_seconds: default_whisper_timeout(),
+            keyboard_timeout_seconds: default_keyboard_timeout(),
+            socket_connect_timeout_seconds: default_socket_connect_timeout(),
+            socket_operation_timeout_seconds: default_socket_operation_timeout(),
+            model_download_timeout_seconds: default_model_download_timeout(),
+            audio_watchdog_seconds: default_audio_watchdog_timeout(),
+            model_idle_unload_seconds: 0,
+        }
+    }
+}
+
+fn default_whisper_timeout() -> u64 {
+    30
+}
+
+fn default_keyboard_timeout() -> u64 {
+    5
+}
+
+fn default_socket_connect_timeout() -> u64 {
+    5
+}
+
+fn default_socket_operation_timeout() -> u64 {
+    10
+}
+
+fn default_model_download_timeout() -> u64 {
+    300
+}
+
+fn default_audio_watchdog_timeout() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct LlmConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_llm_api_url")]
+    pub api_url: String,
+    #[serde(default = "default_llm_model")]
+    pub model: String,
+    #[serde(default = "default_llm_system_prompt")]
+    pub system_prompt: String,
+    #[serde(default = "default_llm_timeout")]
+    pub timeout_seconds: u64,
+    /// What the LLM corrects: "processed" text after `post_processing`, or
+    /// the "raw" transcript, with post-processing applied to its answer.
+    #[serde(default = "default_llm_input")]
+    pub input: String,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: default_llm_api_url(),
+            model: default_llm_model(),
+            system_prompt: default_llm_system_prompt(),
+            timeout_seconds: default_llm_timeout(),
+            input: default_llm_input(),
+        }
+    }
+}
+
+fn default_llm_api_url() -> String {
+    "http://localhost:11434".to_string()
+}
+
+fn default_llm_model() -> String {
+    "qwen2.5:0.5b".to_string()
+}
+
+fn default_llm_system_prompt() -> String {
+    "Clean up the following raw transcription. Fix grammar and punctuation. Remove filler words (um, uh, er, like, you know, I mean). Remove rambling and false starts. Make it coherent and natural. Preserve the original meaning. Output ONLY valid JSON: {\"cleaned_text\": \"...\"}".to_string()
+}
+
+fn default_llm_timeout() -> u64 {
+    10
+}
+
+fn default_llm_input() -> String {
+    "processed".to_string()
+}
+
+/// Transcription by an OpenAI-compatible `/audio/transcriptions` service.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct CloudConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_cloud_base_url")]
+    pub base_url: String,
+    #[serde(default = "default_cloud_model")]
+    pub model: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Environment variable holding the API key when `api_key` is unset.
+    #[serde(default = "default_cloud_api_key_env")]
+    pub api_key_env: String,
+    #[serde(default = "default_cloud_timeout")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_cloud_fallback_to_local")]
+    pub fallback_to_local: bool,
+}
+
+impl CloudConfig {
+    /// Whether the local Whisper model has to be loaded: always without the
+    /// cloud service, and as its fallback when one is allowed.
+    pub fn needs_local_engine(&self) -> bool {
+        !self.enabled || self.fallback_to_local
+    }
+
+    /// `api_key`, or the value of `api_key_env`.
+    pub fn resolve_api_key(&self) -> Option<String> {
+        self.api_key
+            .clone()
+            .or_else(|| std::env::var(&self.api_key_env).ok())
+            .filter(|key| !key.is_empty())
+    }
+}
+
+impl Default for CloudConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: default_cloud_base_url(),
+            model: default_cloud_model(),
+            api_key: None,
+            api_key_env: default_cloud_api_key_env(),
+            timeout_seconds: default_cloud_timeout(),
+            fallback_to_local: default_cloud_fallback_to_local(),
+        }
+    }
+}
+
+fn default_cloud_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_cloud_model() -> String {
+    "whisper-1".to_string()
+}
+
+fn default_cloud_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+fn default_cloud_timeout() -> u64 {
+    10
+}
+
+fn default_cloud_fallback_to_local() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
+pub struct DebugConfig {
+    #[serde(default)]
+    pub record_audio: bool,
+    #[serde(default)]
+    pub record_segments: bool,
+    #[serde(default)]
+    pub recording_dir: Option<String>,
+}
+
+/// How models are fetched, under `[download]`, for networks where
+/// HuggingFace is slow, blocked or only reachable through a proxy.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
+pub struct DownloadConfig {
+    /// Base URL used instead of https://huggingface.co in model URLs, e.g.
+    /// "https://hf-mirror.com".
+    #[serde(default)]
+    pub mirror: Option<String>,
+    /// Proxy for model downloads: "http://", "https://" or "socks5://" URL.
+    /// Unset uses the HTTP_PROXY/HTTPS_PROXY environment variables.
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Rules applied to dictated text after the built-in post-processing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct PostProcessingConfig {
+    /// Decimal separator, date format and quotation marks of the
+    /// `numbers` and `punctuation` stages, e.g. "en-GB" or "de". "auto"
+    /// follows the dictation language.
+    #[serde(default = "default_post_processing_locale")]
+    pub locale: String,
+    /// Type the locale's quotation marks instead of straight quotes.
+    /// Needs `output.unicode`.
+    #[serde(default)]
+    pub smart_quotes: bool,
+    /// Turn ":rocket:" and "thumbs up emoji" into emoji. Needs
+    /// `output.unicode`.
+    #[serde(default)]
+    pub emoji: bool,
+    /// Stages run on dictated text, in order; see
+    /// `post_processor::STAGE_NAMES`. Leaving a stage out turns it off.
+    #[serde(default = "default_post_processing_stages")]
+    pub stages: Vec<String>,
+    /// Which bracketed text the `brackets` stage strips.
+    #[serde(default)]
+    pub brackets: BracketsConfig,
+    /// Collapse repeated words and phrases in text Whisper looped on.
+    #[serde(default)]
+    pub dedup_repeats: bool,
+    /// Repeats are only collapsed in text whose compression ratio is above
+    /// this; Whisper's own loop check uses 2.4.
+    #[serde(default = "default_dedup_compression_ratio")]
+    pub dedup_compression_ratio: f32,
+    /// Drop fillers like "um" and "you know".
+    #[serde(default)]
+    pub remove_fillers: bool,
+    /// Filler lists by language code, replacing the built-in list for
+    /// that language.
+    #[serde(default)]
+    pub fillers: HashMap<String, Vec<String>>,
+    /// Write spelled-out numbers as digits, "twenty three" as "23", for
+    /// English and Spanish dictation.
+    #[serde(default)]
+    pub numbers: bool,
+    /// File of `spoken form = written form` word replacements, reloaded
+    /// when it changes.
+    #[serde(default)]
+    pub dictionary_file: Option<String>,
+    /// Capitalize sentence starts, "I" and `proper_nouns`.
+    #[serde(default)]
+    pub capitalize: bool,
+    /// Words always written with this casing when `capitalize` is on, e.g.
+    /// "GitHub".
+    #[serde(default)]
+    pub proper_nouns: Vec<String>,
+    /// `[[post_processing.replace]]` entries, applied in order after the
+    /// dictionary.
+    #[serde(default)]
+    pub replace: Vec<ReplaceRule>,
+}
+
+impl Default for PostProcessingConfig {
+    fn default() -> Self {
+        Self {
+            locale: default_post_processing_locale(),
+            smart_quotes: false,
+            emoji: false,
+            stages: default_post_processing_stages(),
+            brackets: BracketsConfig::default(),
+            dedup_repeats: false,
+            dedup_compression_ratio: default_dedup_compression_ratio(),
+            remove_fillers: false,
+            fillers: HashMap::new(),
+            numbers: false,
+            dictionary_file: None,
+            capitalize: false,
+            proper_nouns: Vec::new(),
+            replace: Vec::new(),
+        }
+    }
+}
+
+fn default_post_processing_locale() -> String {
+    "auto".to_string()
+}
+
+fn default_post_processing_stages() -> Vec<String> {
+    crate::transcription::post_processor::STAGE_NAMES
+        .iter()
+        .map(|stage| stage.to_string())
+        .collect()
+}
+
+fn default_dedup_compression_ratio() -> f32 {
+    2.4
+}
+
+/// Bracket stripping under `[post_processing.brackets]`. Each bracket type
+/// is "all", "annotations" (known Whisper annotations like "(laughs)") or
+/// "none".
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct BracketsConfig {
+    #[serde(default = "default_bracket_mode")]
+    pub square: String,
+    #[serde(default = "default_bracket_mode")]
+    pub round: String,
+    #[serde(default = "default_bracket_mode")]
+    pub curly: String,
+    /// Stripped in "annotations" mode besides the built-in list, e.g.
+    /// "dog barking".
+    #[serde(default)]
+    pub annotations: Vec<String>,
 }
 
-impl Default for TimeoutsConfig {
+impl Default for BracketsConfig {
     fn default() -> Self {
         Self {
-            whisper_timeout_seconds: default_whisper_timeout(),
-            keyboard_timeout_seconds: default_keyboard_timeout(),
-            socket_connect_timeout_seconds: default_socket_connect_timeout(),
-            socket_operation_timeout_seconds: default_socket_operation_timeout(),
-            model_download_timeout_seconds: default_model_download_timeout(),
+            square: default_bracket_mode(),
+            round: default_bracket_mode(),
+            curly: default_bracket_mode(),
+            annotations: Vec::new(),
         }
     }
 }
 
-fn default_whisper_timeout() -> u64 {
-    30
+fn default_bracket_mode() -> String {
+    "annotations".to_string()
 }
 
-fn default_keyboard_timeout() -> u64 {
-    5
+/// Replace every match of a regex, e.g. to fix a word Whisper keeps
+/// mishearing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ReplaceRule {
+    pub pattern: String,
+    /// May refer to capture groups as `$1` or `${name}`.
+    pub replacement: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
 }
 
-fn default_socket_connect_timeout() -> u64 {
-    5
+/// `ndict transcribe`, under `[transcribe]`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct TranscribeConfig {
+    /// Files of a directory or pattern transcribed at the same time. Their
+    /// chunks share the `whisper.workers` workers.
+    #[serde(default = "default_parallel_files")]
+    pub parallel_files: usize,
 }
 
-fn default_socket_operation_timeout() -> u64 {
-    10
+impl Default for TranscribeConfig {
+    fn default() -> Self {
+        Self {
+            parallel_files: default_parallel_files(),
+        }
+    }
 }
 
-fn default_model_download_timeout() -> u64 {
-    300
+fn default_parallel_files() -> usize {
+    2
 }
 
+/// Always-listening mode where a spoken phrase turns dictation on and off.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
-pub struct LlmConfig {
+pub struct WakeConfig {
     #[serde(default)]
     pub enabled: bool,
-    #[serde(default = "default_llm_api_url")]
-    pub api_url: String,
-    #[serde(default = "default_llm_model")]
-    pub model: String,
-    #[serde(default = "default_llm_system_prompt")]
-    pub system_prompt: String,
-    #[serde(default = "default_llm_timeout")]
-    pub timeout_seconds: u64,
+    #[serde(default = "default_wake_phrase")]
+    pub wake_phrase: String,
+    #[serde(default = "default_stop_phrase")]
+    pub stop_phrase: String,
+    /// What spots the wake phrase while dictation is off: "whisper", the
+    /// model at `model_url`, or "vosk", a keyword spotter on the small Vosk
+    /// model at `vosk_model`.
+    #[serde(default = "default_wake_spotter")]
+    pub spotter: String,
+    /// Directory of the Vosk model for `spotter = "vosk"`, e.g. a download
+    /// of vosk-model-small-en-us-0.15.
+    #[serde(default)]
+    pub vosk_model: Option<String>,
+    /// Small Whisper model used by `spotter = "whisper"`.
+    #[serde(default = "default_wake_model_url")]
+    pub model_url: String,
+    /// Segments longer than this cannot be the wake phrase and are skipped
+    /// without running the model.
+    #[serde(default = "default_max_phrase_ms")]
+    pub max_phrase_ms: u32,
 }
 
-impl Default for LlmConfig {
+impl Default for WakeConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            api_url: default_llm_api_url(),
-            model: default_llm_model(),
-            system_prompt: default_llm_system_prompt(),
-            timeout_seconds: default_llm_timeout(),
+            wake_phrase: default_wake_phrase(),
+            stop_phrase: default_stop_phrase(),
+            spotter: default_wake_spotter(),
+            vosk_model: None,
+            model_url: default_wake_model_url(),
+            max_phrase_ms: default_max_phrase_ms(),
         }
     }
 }
 
-fn default_llm_api_url() -> String {
-    "http://localhost:11434".to_string()
+fn default_wake_phrase() -> String {
+    "hey ndict".to_string()
 }
 
-fn default_llm_model() -> String {
-    "qwen2.5:0.5b".to_string()
+fn default_stop_phrase() -> String {
+    "stop dictation".to_string()
 }
 
-fn default_llm_system_prompt() -> String {
-    "Clean up the following raw transcription. Fix grammar and punctuation. Remove filler words (um, uh, er, like, you know, I mean). Remove rambling and false starts. Make it coherent and natural. Preserve the original meaning. Output ONLY valid JSON: {\"cleaned_text\": \"...\"}".to_string()
+fn default_wake_spotter() -> String {
+    "whisper".to_string()
 }
 
-fn default_llm_timeout() -> u64 {
-    10
+fn default_wake_model_url() -> String {
+    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin".to_string()
+}
+
+fn default_max_phrase_ms() -> u32 {
+    2500
+}
+
+impl Config {
+    /// Whether final text goes to a transcript file: always for loopback
+    /// capture, otherwise as `output.sinks.transcript` says.
+    pub fn writes_transcript(&self) -> bool {
+        self.audio.source == "loopback"
+            || self
+                .output
+                .sinks
+                .transcript
+                .unwrap_or(self.output.transcript_file.is_some())
+    }
+
+    /// Where final text is written as JSON lines, if anywhere.
+    pub fn json_target(&self) -> Option<&str> {
+        let output = &self.output;
+        output
+            .sinks
+            .json
+            .unwrap_or(output.json_output.is_some())
+            .then(|| output.json_output.as_deref().unwrap_or("stdout"))
+    }
+
+    /// Whether final text is typed with the virtual keyboard.
+    pub fn types_text(&self) -> bool {
+        self.output
+            .sinks
+            .keyboard
+            .unwrap_or(
+                !self.writes_transcript()
+                    && self.json_target().is_none()
+                    && !self.output.sinks.ibus,
+            )
+    }
 }
 
 impl Default for Config {
@@ -300,40 +1526,129 @@ impl Default for Config {
             log_level: default_log_level(),
             audio: AudioConfig {
                 device: "default".to_string(),
+                devices: Vec::new(),
                 sample_rate: 16000,
                 chunk_size: 512,
                 gain: 1.0,
                 channels: 1,
+                source: "microphone".to_string(),
+                file_path: None,
+                loopback_source: None,
+                network_address: None,
+                level_interval_ms: 50,
+                clipping_warnings: true,
+                device_profiles: HashMap::new(),
+                cue_gate_ms: 0,
+                buffer_frames: None,
+                low_latency: false,
+                noise_gate: false,
+                noise_gate_threshold_db: -50.0,
+                noise_gate_range_db: 20.0,
+                noise_gate_attack_ms: 2.0,
+                noise_gate_release_ms: 150.0,
             },
             vad: VadConfig {
                 threshold_start: 0.02,
                 threshold_stop: 0.01,
+                threshold_start_db: None,
+                threshold_stop_db: None,
                 min_speech_duration_ms: 250,
                 min_silence_duration_ms: 1000,
+                trim_silence: true,
+                trim_margin_ms: 200,
+                pre_speech_padding_ms: 200,
+                frame_ms: 30,
+                speech_start_frames: 2,
+                speech_stop_frames: 2,
+                calibration_ms: 0,
+                max_segment_ms: 0,
+                smart_endpointing: false,
+                min_endpoint_ms: 400,
+                max_endpoint_ms: 1500,
+                backend: "energy".to_string(),
+                silero_model_path: None,
+                silero_threshold: 0.5,
+                adaptive_thresholds: false,
+                noise_start_ratio: 3.0,
+                noise_stop_ratio: 2.0,
+                spectral_features: false,
+                min_spectral_flatness: 0.05,
+                min_zero_crossing_rate: 0.02,
             },
             whisper: WhisperConfig {
                 model_path: None,
+                model: None,
                 model_url:
                     "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin"
                         .to_string(),
                 model_checksum: None,
                 language: "en".to_string(),
+                detect_language_once: false,
+                task: "transcribe".to_string(),
                 n_thread: 4,
+                workers: 1,
                 backend: "cpu".to_string(),
+                gpu_device: 0,
                 streaming_mode: false,
                 min_audio_samples: 18000,
                 sampling_strategy: "greedy".to_string(),
+                no_speech_threshold: 0.6,
+                min_confidence: 0.0,
+                low_confidence_action: "drop".to_string(),
+                initial_prompt: None,
+                vocabulary: Vec::new(),
+                vocabulary_file: None,
+                models: HashMap::new(),
+                decoding: DecodingConfig {
+                    beam_size: 5,
+                    patience: 1.0,
+                    best_of: 1,
+                    temperature: 0.0,
+                    temperature_increment: 0.2,
+                    entropy_threshold: 2.4,
+                    logprob_threshold: -1.0,
+                    suppress_blank: true,
+                    suppress_non_speech: false,
+                },
+                diarize: false,
+                dtw_timestamps: false,
+                retry: RetryConfig::default(),
+                grammar: GrammarConfig::default(),
+                chunking: ChunkingConfig::default(),
             },
             streaming: StreamingConfig {
                 step_ms: 3000,
+                correct_drafts: true,
                 length_ms: 10000,
                 keep_ms: 500,
+                vad_segmentation: true,
+                partial_ms: 0,
+                restore_punctuation: false,
+                model: None,
             },
+            stt: SttConfig::default(),
             buffer: BufferConfig {
                 broadcast_capacity: 100,
             },
             output: OutputConfig {
+                backend: "auto".to_string(),
                 typing_mode: "instant".to_string(),
+                key_delay_ms: 20,
+                key_jitter_ms: 10,
+                paste_shortcut: "ctrl+v".to_string(),
+                paste_restore_ms: 300,
+                transcript_file: None,
+                json_output: None,
+                history: false,
+                history_dir: None,
+                buffer_while_paused: false,
+                voice_editing: true,
+                separator: "space".to_string(),
+                trailing_space: false,
+                submit_key: None,
+                snippets: Vec::new(),
+                unicode: true,
+                sinks: SinksConfig::default(),
             },
             rate_limit: RateLimitConfig {
                 commands_per_second: 10,
@@ -346,6 +1661,8 @@ impl Default for Config {
                 socket_connect_timeout_seconds: 5,
                 socket_operation_timeout_seconds: 10,
                 model_download_timeout_seconds: 300,
+                audio_watchdog_seconds: 5,
+                model_idle_unload_seconds: 0,
             },
             llm: LlmConfig {
                 enabled: false,
@@ -353,7 +1670,28 @@ impl Default for Config {
             model: default_llm_model(),
             system_prompt: default_llm_system_prompt(),
                 timeout_seconds: 10,
+                input: "processed".to_string(),
             },
+            cloud: CloudConfig::default(),
+            debug: DebugConfig {
+                record_audio: false,
+                record_segments: false,
+                recording_dir: None,
+            },
+            wake: WakeConfig {
+                enabled: false,
+                wake_phrase: "hey ndict".to_string(),
+                stop_phrase: "stop dictation".to_string(),
+                spotter: "whisper".to_string(),
+                vosk_model: None,
+                model_url: default_wake_model_url(),
+                max_phrase_ms: 2500,
+            },
+            download: DownloadConfig::default(),
+            transcribe: TranscribeConfig {
+                parallel_files: 2,
+            },
+            post_processing: PostProcessingConfig::default(),
         }
     }
 }
@@ -393,15 +1731,51 @@ mod tests {
         let config = Config::default();
 
         assert_eq!(config.audio.device, "default");
+        assert!(config.audio.devices.is_empty());
         assert_eq!(config.audio.sample_rate, 16000);
         assert_eq!(config.audio.chunk_size, 512);
         assert_eq!(config.audio.gain, 1.0);
         assert_eq!(config.audio.channels, 1);
+        assert_eq!(config.audio.source, "microphone");
+        assert_eq!(config.audio.file_path, None);
+        assert_eq!(config.audio.network_address, None);
+        assert_eq!(config.audio.level_interval_ms, 50);
+        assert!(config.audio.clipping_warnings);
+        assert_eq!(config.audio.cue_gate_ms, 0);
+        assert_eq!(config.audio.buffer_frames, None);
+        assert!(!config.audio.low_latency);
+        assert!(!config.audio.noise_gate);
+        assert_eq!(config.audio.noise_gate_threshold_db, -50.0);
+        assert_eq!(config.audio.noise_gate_range_db, 20.0);
+        assert_eq!(config.audio.noise_gate_attack_ms, 2.0);
+        assert_eq!(config.audio.noise_gate_release_ms, 150.0);
 
         assert_eq!(config.vad.threshold_start, 0.02);
         assert_eq!(config.vad.threshold_stop, 0.01);
+        assert_eq!(config.vad.threshold_start_db, None);
+        assert_eq!(config.vad.threshold_stop_db, None);
         assert_eq!(config.vad.min_speech_duration_ms, 250);
         assert_eq!(config.vad.min_silence_duration_ms, 1000);
+        assert!(config.vad.trim_silence);
+        assert_eq!(config.vad.trim_margin_ms, 200);
+        assert_eq!(config.vad.pre_speech_padding_ms, 200);
+        assert_eq!(config.vad.frame_ms, 30);
+        assert_eq!(config.vad.speech_start_frames, 2);
+        assert_eq!(config.vad.speech_stop_frames, 2);
+        assert_eq!(config.vad.calibration_ms, 0);
+        assert_eq!(config.vad.max_segment_ms, 0);
+        assert!(!config.vad.smart_endpointing);
+        assert_eq!(config.vad.min_endpoint_ms, 400);
+        assert_eq!(config.vad.max_endpoint_ms, 1500);
+        assert_eq!(config.vad.backend, "energy");
+        assert_eq!(config.vad.silero_model_path, None);
+        assert_eq!(config.vad.silero_threshold, 0.5);
+        assert!(!config.vad.adaptive_thresholds);
+        assert_eq!(config.vad.noise_start_ratio, 3.0);
+        assert_eq!(config.vad.noise_stop_ratio, 2.0);
+        assert!(!config.vad.spectral_features);
+        assert_eq!(config.vad.min_spectral_flatness, 0.05);
+        assert_eq!(config.vad.min_zero_crossing_rate, 0.02);
 
         assert_eq!(
             config.whisper.model_url,
@@ -410,18 +1784,73 @@ mod tests {
         assert_eq!(config.whisper.model_checksum, None);
         assert_eq!(config.whisper.language, "en");
         assert_eq!(config.whisper.backend, "cpu");
+        assert_eq!(config.whisper.gpu_device, 0);
         assert_eq!(config.whisper.n_thread, 4);
+        assert_eq!(config.whisper.workers, 1);
         assert_eq!(config.whisper.streaming_mode, false);
         assert_eq!(config.whisper.min_audio_samples, 18000);
         assert_eq!(config.whisper.sampling_strategy, "greedy");
+        assert_eq!(config.whisper.no_speech_threshold, 0.6);
+        assert!(!config.whisper.detect_language_once);
+        assert_eq!(config.whisper.task, "transcribe");
+        assert_eq!(config.whisper.min_confidence, 0.0);
+        assert_eq!(config.whisper.low_confidence_action, "drop");
+        assert_eq!(config.whisper.initial_prompt, None);
+        assert!(config.whisper.vocabulary.is_empty());
+        assert_eq!(config.whisper.vocabulary_file, None);
+        assert!(!config.whisper.diarize);
+        assert!(!config.whisper.dtw_timestamps);
+        assert_eq!(config.whisper.retry.model, None);
+        assert_eq!(config.whisper.retry.below_confidence, 0.0);
+        assert!(config.whisper.grammar.phrases.is_empty());
+        assert_eq!(config.whisper.grammar.phrases_file, None);
+        assert_eq!(config.whisper.grammar.penalty, 100.0);
+        assert_eq!(config.whisper.chunking.min_length_ms, 60000);
+        assert_eq!(config.whisper.chunking.chunk_ms, 30000);
+        assert_eq!(config.whisper.model, None);
+        assert_eq!(config.whisper.decoding.beam_size, 5);
+        assert_eq!(config.whisper.decoding.patience, 1.0);
+        assert_eq!(config.whisper.decoding.best_of, 1);
+        assert_eq!(config.whisper.decoding.temperature, 0.0);
+        assert_eq!(config.whisper.decoding.temperature_increment, 0.2);
+        assert_eq!(config.whisper.decoding.entropy_threshold, 2.4);
+        assert_eq!(config.whisper.decoding.logprob_threshold, -1.0);
+        assert!(config.whisper.decoding.suppress_blank);
+        assert!(!config.whisper.decoding.suppress_non_speech);
+        assert!(config.whisper.models.is_empty());
+        assert_eq!(config.streaming.model, None);
 
         assert_eq!(config.streaming.step_ms, 3000);
+        assert!(config.streaming.correct_drafts);
         assert_eq!(config.streaming.length_ms, 10000);
         assert_eq!(config.streaming.keep_ms, 500);
+        assert!(config.streaming.vad_segmentation);
+        assert_eq!(config.streaming.partial_ms, 0);
+        assert!(!config.streaming.restore_punctuation);
 
         assert_eq!(config.buffer.broadcast_capacity, 100);
 
         assert_eq!(config.output.typing_mode, "instant");
+        assert_eq!(config.output.backend, "auto");
+        assert_eq!(config.output.key_delay_ms, 20);
+        assert_eq!(config.output.key_jitter_ms, 10);
+        assert_eq!(config.output.paste_shortcut, "ctrl+v");
+        assert_eq!(config.output.paste_restore_ms, 300);
+        assert_eq!(config.output.json_output, None);
+        assert_eq!(config.output.sinks.keyboard, None);
+        assert!(!config.output.sinks.ibus);
+        assert!(config.output.sinks.events);
+        assert!(config.types_text());
+        assert_eq!(config.output.transcript_file, None);
+        assert!(!config.output.history);
+        assert_eq!(config.output.history_dir, None);
+        assert!(!config.output.buffer_while_paused);
+        assert!(config.output.voice_editing);
+        assert!(config.output.unicode);
+        assert_eq!(config.output.separator, "space");
+        assert!(!config.output.trailing_space);
+        assert_eq!(config.output.submit_key, None);
+        assert!(config.output.snippets.is_empty());
 
         assert_eq!(config.rate_limit.commands_per_second, 10);
         assert_eq!(config.rate_limit.burst_capacity, 20);
@@ -432,6 +1861,8 @@ mod tests {
         assert_eq!(config.timeouts.socket_connect_timeout_seconds, 5);
         assert_eq!(config.timeouts.socket_operation_timeout_seconds, 10);
         assert_eq!(config.timeouts.model_download_timeout_seconds, 300);
+        assert_eq!(config.timeouts.audio_watchdog_seconds, 5);
+        assert_eq!(config.timeouts.model_idle_unload_seconds, 0);
     }
 
     #[test]
@@ -448,6 +1879,7 @@ mod tests {
         assert!(toml_str.contains("[rate_limit]"));
         assert!(toml_str.contains("[timeouts]"));
         assert!(toml_str.contains("[llm]"));
+        assert!(toml_str.contains("[debug]"));
     }
 
     #[test]
@@ -460,10 +1892,13 @@ mod tests {
         assert_eq!(config.vad, parsed.vad);
         assert_eq!(config.whisper, parsed.whisper);
         assert_eq!(config.streaming, parsed.streaming);
+        assert_eq!(config.stt, parsed.stt);
         assert_eq!(config.buffer, parsed.buffer);
         assert_eq!(config.output, parsed.output);
         assert_eq!(config.timeouts, parsed.timeouts);
         assert_eq!(config.llm, parsed.llm);
+        assert_eq!(config.cloud, parsed.cloud);
+        assert_eq!(config.debug, parsed.debug);
     }
 
     #[test]
@@ -506,6 +1941,53 @@ mod tests {
         assert_eq!(config.output.typing_mode, "delayed");
     }
 
+    #[test]
+    fn test_effective_buffer_frames() {
+        let mut audio = AudioConfig::default();
+        assert_eq!(audio.effective_buffer_frames(), None);
+
+        audio.low_latency = true;
+        assert_eq!(audio.effective_buffer_frames(), Some(LOW_LATENCY_BUFFER_FRAMES));
+
+        // An explicit size wins over the preset
+        audio.buffer_frames = Some(512);
+        assert_eq!(audio.effective_buffer_frames(), Some(512));
+    }
+
+    #[test]
+    fn test_effective_thresholds_prefer_dbfs() {
+        let mut vad = Config::default().vad;
+        assert_eq!(vad.effective_thresholds(), (0.02, 0.01));
+
+        vad.threshold_start_db = Some(-20.0);
+        let (start, stop) = vad.effective_thresholds();
+        assert!((start - 0.1).abs() < 1e-6);
+        assert_eq!(stop, 0.01);
+
+        vad.threshold_stop_db = Some(-40.0);
+        assert!((vad.effective_thresholds().1 - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_device_profiles_parse() {
+        let toml_str = r#"
+            [audio]
+            device = "Scarlett 18i8"
+
+            [audio.device_profiles."Scarlett 18i8"]
+            channel = 3
+            gain = 2.0
+
+            [audio.device_profiles."USB Headset"]
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let profile = &config.audio.device_profiles["Scarlett 18i8"];
+        assert_eq!(profile.channel, Some(3));
+        assert_eq!(profile.gain, 2.0);
+        assert_eq!(config.audio.device_profiles["USB Headset"], DeviceProfile::default());
+    }
+
     #[test]
     fn test_config_with_missing_fields_uses_defaults() {
         let toml_str = r#"
@@ -587,6 +2069,12 @@ mod tests {
             config.whisper.model_path,
             Some("/custom/path/model.bin".to_string())
         );
+        assert_eq!(
+            config.whisper.model_path_for(DEFAULT_MODEL),
+            Some(PathBuf::from("/custom/path/model.bin"))
+        );
+        // Other models are still found from their URL
+        assert_eq!(config.whisper.model_path_for("small"), None);
     }
 
     #[test]
@@ -729,6 +2217,60 @@ mod tests {
         assert_eq!(default_channels(), 1);
     }
 
+    #[test]
+    fn test_config_with_loopback_source() {
+        let toml_str = r#"
+            [audio]
+            source = "loopback"
+            loopback_source = "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor"
+
+            [output]
+            transcript_file = "/tmp/call.txt"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.audio.source, "loopback");
+        assert_eq!(
+            config.audio.loopback_source,
+            Some("alsa_output.pci-0000_00_1f.3.analog-stereo.monitor".to_string())
+        );
+        assert_eq!(config.output.transcript_file, Some("/tmp/call.txt".to_string()));
+    }
+
+    #[test]
+    fn test_default_level_interval_ms() {
+        assert_eq!(default_level_interval_ms(), 50);
+    }
+
+    #[test]
+    fn test_default_audio_source() {
+        assert_eq!(default_audio_source(), "microphone");
+    }
+
+    #[test]
+    fn test_config_with_file_audio_source() {
+        let toml_str = r#"
+            [audio]
+            source = "file"
+            file_path = "/tmp/session.wav"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.audio.source, "file");
+        assert_eq!(config.audio.file_path, Some("/tmp/session.wav".to_string()));
+        assert_eq!(config.audio.sample_rate, 16000);
+    }
+
+    #[test]
+    fn test_config_with_network_audio_source() {
+        let toml_str = r#"
+            [audio]
+            source = "network"
+            network_address = "0.0.0.0:7373"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.audio.source, "network");
+        assert_eq!(config.audio.network_address.as_deref(), Some("0.0.0.0:7373"));
+    }
+
     #[test]
     fn test_default_broadcast_capacity() {
         assert_eq!(default_broadcast_capacity(), 100);
@@ -777,6 +2319,94 @@ mod tests {
         assert_eq!(config.whisper.sampling_strategy, "beam");
     }
 
+    #[test]
+    fn test_config_n_thread_accepts_auto() {
+        let config: Config = toml::from_str("[whisper]\nn_thread = 6").unwrap();
+        assert_eq!(config.whisper.n_thread, 6);
+        assert_eq!(config.whisper.effective_threads(), 6);
+
+        let config: Config = toml::from_str("[whisper]\nn_thread = \"auto\"").unwrap();
+        assert_eq!(config.whisper.n_thread, AUTO_THREADS);
+        let threads = config.whisper.effective_threads();
+        assert!((1..=MAX_AUTO_THREADS as i32).contains(&threads));
+
+        let config: Config = toml::from_str("[whisper]\nlanguage = \"en\"").unwrap();
+        assert_eq!(config.whisper.n_thread, 4);
+
+        assert!(toml::from_str::<Config>("[whisper]\nn_thread = \"many\"").is_err());
+    }
+
+    #[test]
+    fn test_config_named_models() {
+        let toml_str = r#"
+            [whisper]
+            model_url = "http://example.com/base.bin"
+
+            [whisper.models]
+            tiny = "http://example.com/tiny.bin"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.whisper.model_url_for(DEFAULT_MODEL).as_deref(),
+            Some("http://example.com/base.bin")
+        );
+        assert_eq!(
+            config.whisper.model_url_for("tiny").as_deref(),
+            Some("http://example.com/tiny.bin")
+        );
+        assert_eq!(config.whisper.model_url_for("large"), None);
+        // Registry aliases work without a [whisper.models] entry
+        assert!(config.whisper.model_url_for("small.en").unwrap().ends_with("/ggml-small.en.bin"));
+    }
+
+    #[test]
+    fn test_config_auto_model_resolves_to_registry() {
+        let config: Config = toml::from_str("[whisper]\nmodel = \"auto\"").unwrap();
+        let url = config.whisper.model_url_for(DEFAULT_MODEL).unwrap();
+        assert!(url.starts_with("https://huggingface.co/ggerganov/whisper.cpp/"));
+        assert!(config.whisper.model_info_for(DEFAULT_MODEL).is_some());
+    }
+
+    #[test]
+    fn test_config_model_alias_overrides_url() {
+        let toml_str = r#"
+            [whisper]
+            model = "base.en"
+            model_url = "http://example.com/base.bin"
+            model_checksum = "abc123"
+
+            [whisper.models]
+            drafts = "tiny.en"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config
+            .whisper
+            .model_url_for(DEFAULT_MODEL)
+            .unwrap()
+            .ends_with("/ggml-base.en.bin"));
+        assert!(config.whisper.model_url_for("drafts").unwrap().ends_with("/ggml-tiny.en.bin"));
+        assert_eq!(config.whisper.model_checksum_for(DEFAULT_MODEL).as_deref(), Some("abc123"));
+        assert_eq!(config.whisper.model_checksum_for("drafts"), None);
+    }
+
+    #[test]
+    fn test_config_decoding_section() {
+        let toml_str = r#"
+            [whisper]
+            sampling_strategy = "beam"
+
+            [whisper.decoding]
+            beam_size = 8
+            temperature_increment = 0.0
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.whisper.decoding.beam_size, 8);
+        assert_eq!(config.whisper.decoding.temperature_increment, 0.0);
+        assert_eq!(config.whisper.decoding.entropy_threshold, 2.4);
+        assert_eq!(config.whisper.decoding.patience, 1.0);
+        assert_eq!(config.whisper.decoding.best_of, 1);
+    }
+
     #[test]
     fn test_config_backwards_compatibility_buffer() {
         let toml_str = r#"
@@ -808,6 +2438,105 @@ mod tests {
         assert_eq!(config.llm.model, "qwen2.5:0.5b");
         assert_eq!(config.llm.timeout_seconds, 10);
         assert!(config.llm.system_prompt.contains("Output ONLY valid JSON"));
+        assert_eq!(config.llm.input, "processed");
+    }
+
+    #[test]
+    fn test_default_stt_config() {
+        let config = Config::default();
+        assert_eq!(config.stt.backend, "whisper");
+        assert_eq!(config.stt.vosk.library, "libvosk.so");
+        assert!(config.stt.vosk.models.is_empty());
+        assert_eq!(config.stt.server.url, "http://127.0.0.1:8080");
+        assert_eq!(config.stt.server.timeout_seconds, 20);
+        assert_eq!(config.stt.server.retry_seconds, 2);
+        assert_eq!(config.stt.server.max_retry_seconds, 60);
+        assert_eq!(config.stt.ctranslate2.model_dir, None);
+        assert_eq!(config.stt.ctranslate2.device, "cpu");
+        assert_eq!(config.stt.ctranslate2.compute_type, "int8");
+        assert_eq!(config.stt.ctranslate2.beam_size, 5);
+    }
+
+    #[test]
+    fn test_stt_vosk_config_parsing() {
+        let toml_str = r#"
+            [stt]
+            backend = "vosk"
+
+            [stt.vosk.models]
+            en = "/models/vosk-model-small-en-us-0.15"
+            de = "/models/vosk-model-small-de-0.15"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.stt.backend, "vosk");
+        assert_eq!(config.stt.vosk.library, "libvosk.so");
+        assert_eq!(config.stt.vosk.models.len(), 2);
+        assert_eq!(config.stt.vosk.models["de"], "/models/vosk-model-small-de-0.15");
+    }
+
+    #[test]
+    fn test_stt_ctranslate2_config_parsing() {
+        let toml_str = r#"
+            [stt]
+            backend = "ctranslate2"
+
+            [stt.ctranslate2]
+            model_dir = "/models/faster-whisper-small"
+            device = "cuda"
+            compute_type = "float16"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.stt.backend, "ctranslate2");
+        assert_eq!(
+            config.stt.ctranslate2.model_dir.as_deref(),
+            Some("/models/faster-whisper-small")
+        );
+        assert_eq!(config.stt.ctranslate2.device, "cuda");
+        assert_eq!(config.stt.ctranslate2.compute_type, "float16");
+        assert_eq!(config.stt.ctranslate2.beam_size, 5);
+    }
+
+    #[test]
+    fn test_default_cloud_config() {
+        let config = Config::default();
+        assert!(!config.cloud.enabled);
+        assert_eq!(config.cloud.base_url, "https://api.openai.com/v1");
+        assert_eq!(config.cloud.model, "whisper-1");
+        assert_eq!(config.cloud.api_key, None);
+        assert_eq!(config.cloud.api_key_env, "OPENAI_API_KEY");
+        assert_eq!(config.cloud.timeout_seconds, 10);
+        assert!(config.cloud.fallback_to_local);
+        assert!(config.cloud.needs_local_engine());
+    }
+
+    #[test]
+    fn test_cloud_config_parsing() {
+        let toml_str = r#"
+            [cloud]
+            enabled = true
+            base_url = "http://192.168.1.10:8000/v1"
+            api_key = "sk-test"
+            fallback_to_local = false
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.cloud.enabled);
+        assert_eq!(config.cloud.base_url, "http://192.168.1.10:8000/v1");
+        assert_eq!(config.cloud.model, "whisper-1");
+        assert_eq!(config.cloud.resolve_api_key().as_deref(), Some("sk-test"));
+        assert!(!config.cloud.needs_local_engine());
+    }
+
+    #[test]
+    fn test_cloud_api_key_from_environment() {
+        let cloud = CloudConfig {
+            api_key_env: "NDICT_TEST_CLOUD_KEY".to_string(),
+            ..CloudConfig::default()
+        };
+        std::env::remove_var("NDICT_TEST_CLOUD_KEY");
+        assert_eq!(cloud.resolve_api_key(), None);
+        std::env::set_var("NDICT_TEST_CLOUD_KEY", "sk-env");
+        assert_eq!(cloud.resolve_api_key().as_deref(), Some("sk-env"));
+        std::env::remove_var("NDICT_TEST_CLOUD_KEY");
     }
 
     #[test]
@@ -869,4 +2598,206 @@ mod tests {
     fn test_default_llm_timeout() {
         assert_eq!(default_llm_timeout(), 10);
     }
+
+    #[test]
+    fn test_default_debug_config() {
+        let config = Config::default();
+        assert!(!config.debug.record_audio);
+        assert!(!config.debug.record_segments);
+        assert_eq!(config.debug.recording_dir, None);
+    }
+
+    #[test]
+    fn test_debug_config_with_custom_values() {
+        let toml_str = r#"
+            [debug]
+            record_audio = true
+            record_segments = true
+            recording_dir = "/tmp/ndict-debug"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.debug.record_audio);
+        assert!(config.debug.record_segments);
+        assert_eq!(config.debug.recording_dir, Some("/tmp/ndict-debug".to_string()));
+    }
+
+    #[test]
+    fn test_default_wake_config() {
+        let config = Config::default();
+        assert!(!config.wake.enabled);
+        assert_eq!(config.wake.wake_phrase, "hey ndict");
+        assert_eq!(config.wake.stop_phrase, "stop dictation");
+        assert_eq!(config.wake.spotter, "whisper");
+        assert_eq!(config.wake.vosk_model, None);
+        assert!(config.wake.model_url.ends_with("ggml-tiny.en.bin"));
+        assert_eq!(config.wake.max_phrase_ms, 2500);
+        assert_eq!(config.download.mirror, None);
+        assert_eq!(config.download.proxy, None);
+        assert_eq!(config.transcribe.parallel_files, 2);
+        assert_eq!(TranscribeConfig::default(), config.transcribe);
+        assert!(config.post_processing.replace.is_empty());
+        assert!(!config.post_processing.numbers);
+        assert_eq!(config.post_processing.locale, "auto");
+        assert!(!config.post_processing.smart_quotes);
+        assert!(!config.post_processing.emoji);
+        assert_eq!(
+            config.post_processing.stages,
+            vec![
+                "brackets",
+                "dedup",
+                "fillers",
+                "numbers",
+                "dictionary",
+                "emoji",
+                "punctuation",
+                "casing",
+                "replace"
+            ]
+        );
+        assert!(!config.post_processing.dedup_repeats);
+        assert_eq!(config.post_processing.dedup_compression_ratio, 2.4);
+        assert_eq!(config.post_processing.brackets.round, "annotations");
+        assert!(config.post_processing.brackets.annotations.is_empty());
+        assert!(!config.post_processing.remove_fillers);
+        assert!(config.post_processing.fillers.is_empty());
+        assert_eq!(config.post_processing.dictionary_file, None);
+        assert!(!config.post_processing.capitalize);
+        assert!(config.post_processing.proper_nouns.is_empty());
+        assert_eq!(WakeConfig::default(), config.wake);
+    }
+
+    #[test]
+    fn test_post_processing_replace_parsing() {
+        let toml_str = r#"
+            [[post_processing.replace]]
+            pattern = "end dict"
+            replacement = "ndict"
+
+            [[post_processing.replace]]
+            pattern = "rust"
+            replacement = "Rust"
+            case_sensitive = true
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let rules = &config.post_processing.replace;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].pattern, "end dict");
+        assert_eq!(rules[0].replacement, "ndict");
+        assert!(!rules[0].case_sensitive);
+        assert!(rules[1].case_sensitive);
+    }
+
+    #[test]
+    fn test_post_processing_fillers_parsing() {
+        let toml_str = r#"
+            [post_processing]
+            remove_fillers = true
+
+            [post_processing.fillers]
+            en = ["um", "like"]
+            fr = []
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.post_processing.remove_fillers);
+        assert_eq!(config.post_processing.fillers["en"], vec!["um", "like"]);
+        assert!(config.post_processing.fillers["fr"].is_empty());
+    }
+
+    #[test]
+    fn test_post_processing_brackets_parsing() {
+        let toml_str = r#"
+            [post_processing.brackets]
+            square = "all"
+            annotations = ["dog barking"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let brackets = &config.post_processing.brackets;
+        assert_eq!(brackets.square, "all");
+        assert_eq!(brackets.round, "annotations");
+        assert_eq!(brackets.annotations, vec!["dog barking"]);
+    }
+
+    #[test]
+    fn test_output_sinks() {
+        let mut config = Config::default();
+        config.output.transcript_file = Some("/tmp/transcript.txt".to_string());
+        assert!(config.writes_transcript());
+        assert!(!config.types_text());
+
+        config.output.sinks.keyboard = Some(true);
+        config.output.sinks.json = Some(true);
+        assert!(config.writes_transcript());
+        assert!(config.types_text());
+        assert_eq!(config.json_target(), Some("stdout"));
+
+        let toml_str = r#"
+            [output]
+            json_output = "/tmp/ndict.fifo"
+
+            [output.sinks]
+            keyboard = true
+            events = false
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.types_text());
+        assert!(!config.writes_transcript());
+        assert_eq!(config.json_target(), Some("/tmp/ndict.fifo"));
+        assert!(!config.output.sinks.events);
+
+        let toml_str = r#"
+            [output.sinks]
+            ibus = true
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.output.sinks.ibus);
+        assert!(!config.types_text());
+    }
+
+    #[test]
+    fn test_writes_transcript_for_loopback_source() {
+        let mut config = Config::default();
+        assert!(!config.writes_transcript());
+
+        config.audio.source = "loopback".to_string();
+        assert!(config.writes_transcript());
+    }
+
+    #[test]
+    fn test_output_snippets_parsing() {
+        let toml_str = r#"
+            [[output.snippets]]
+            trigger = "insert signature"
+            text = """
+Best regards,
+Uneeb"""
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.output.snippets,
+            vec![Snippet {
+                trigger: "insert signature".to_string(),
+                text: "Best regards,\nUneeb".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_wake_config_partial_section() {
+        let toml_str = r#"
+            [wake]
+            enabled = true
+            wake_phrase = "computer"
+            spotter = "vosk"
+            vosk_model = "/models/vosk-model-small-en-us-0.15"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(config.wake.enabled);
+        assert_eq!(config.wake.wake_phrase, "computer");
+        assert_eq!(config.wake.stop_phrase, "stop dictation");
+        assert_eq!(config.wake.spotter, "vosk");
+        assert_eq!(
+            config.wake.vosk_model.as_deref(),
+            Some("/models/vosk-model-small-en-us-0.15")
+        );
+    }
 }
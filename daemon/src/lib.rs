@@ -1,13 +1,16 @@
 pub mod audio;
 pub mod config;
+pub mod model;
 pub mod output;
 pub mod rate_limit;
 pub mod server;
 pub mod state;
 pub mod transcription;
 pub mod vad;
+pub mod wake;
 
 pub use audio::capture::AudioCapture;
+pub use audio::source::AudioSource;
 pub use output::keyboard::VirtualKeyboard;
 pub use rate_limit::CommandRateLimiter;
 pub use vad::detector::VoiceActivityDetector;
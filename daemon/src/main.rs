@@ -1,15 +1,7 @@
-mod audio;
-mod config;
-mod output;
-mod rate_limit;
-mod server;
-mod state;
-mod transcription;
-mod vad;
-
 use anyhow::Result;
-use server::DaemonServer;
-use state::DaemonState;
+use ndictd::config;
+use ndictd::server::DaemonServer;
+use ndictd::state::DaemonState;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -58,11 +50,29 @@ async fn main() -> Result<()> {
 
     info!("ndict daemon (ndictd) starting...");
     let daemon_state = DaemonState::new(config);
+    let transcription_abort = daemon_state.transcription_abort.clone();
     let state = Arc::new(Mutex::new(daemon_state));
 
     let socket_path = get_socket_path();
     let server = DaemonServer::new(socket_path, state);
-    server.run().await?;
+    tokio::select! {
+        result = server.run() => result?,
+        result = shutdown_signal() => {
+            result?;
+            info!("Shutting down, aborting transcriptions in progress");
+            transcription_abort.abort();
+        }
+    }
 
     Ok(())
 }
+
+/// Wait for Ctrl-C or SIGTERM (e.g. from systemd).
+async fn shutdown_signal() -> Result<()> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,709 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+use tracing::{debug, error, info, warn};
+
+use crate::config::DownloadConfig;
+
+/// Name of the manifest file in the model directory.
+const MANIFEST_FILE: &str = "models.json";
+
+/// Host the model URLs point at, replaced by `download.mirror`.
+const HUGGINGFACE: &str = "https://huggingface.co";
+
+/// Directory models are downloaded to.
+pub fn default_model_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
+        .join(".local/share/ndict/"))
+}
+
+/// Locate the file for a model URL: an existing copy in one of the usual
+/// directories, or where it should be downloaded to.
+pub fn find_model_path(model_url: &str) -> Result<PathBuf> {
+    let model_filename = model_url
+        .rsplit('/')
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Invalid model URL: cannot extract filename"))?;
+
+    info!("Extracted model filename from URL: {}", model_filename);
+
+    let possible_paths: Vec<Option<PathBuf>> = vec![
+        dirs::home_dir().map(|p| p.join(".local/share/ndict/").join(model_filename)),
+        Some(PathBuf::from("/usr/share/whisper/").join(model_filename)),
+        Some(PathBuf::from("./models/").join(model_filename)),
+        Some(PathBuf::from(model_filename)),
+    ];
+
+    for path in possible_paths.into_iter().flatten() {
+        if path.exists() {
+            info!("Found model at: {:?}", path);
+            return Ok(path);
+        }
+    }
+
+    let default_path = default_model_dir()?.join(model_filename);
+    warn!("Model not found, will use default path: {:?}", default_path);
+    Ok(default_path)
+}
+
+/// A model file ndict has downloaded or checked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    /// SHA-256 of the file when it was last verified.
+    pub sha256: String,
+    /// Local time of the last verification, e.g. "2026-10-15 09:30:00".
+    pub last_verified: String,
+}
+
+/// Model files with their size and checksum, persisted so a verified model
+/// does not have to be hashed again on every load.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    models: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Read the manifest at `path`. A missing or unreadable manifest is
+    /// treated as empty, which only costs a re-verification.
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable model manifest {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Write the manifest to `path` atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, model_path: &Path) -> Option<&ManifestEntry> {
+        self.models.get(model_path)
+    }
+
+    pub fn record(&mut self, entry: ManifestEntry) {
+        self.models.insert(entry.path.clone(), entry);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.models.values()
+    }
+}
+
+/// Downloads, verifies and keeps track of model files, for the Whisper
+/// engines and the `ListModels` and `SetModel` commands.
+pub struct ModelManager {
+    manifest_path: PathBuf,
+    manifest: Manifest,
+    download: DownloadConfig,
+}
+
+impl ModelManager {
+    pub fn new(manifest_path: PathBuf) -> Self {
+        let manifest = Manifest::load(&manifest_path);
+        Self {
+            manifest_path,
+            manifest,
+            download: DownloadConfig::default(),
+        }
+    }
+
+    /// Download through `download.mirror` and `download.proxy`.
+    pub fn with_download_config(mut self, download: DownloadConfig) -> Self {
+        self.download = download;
+        self
+    }
+
+    /// Manager for the manifest in the default model directory.
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(default_model_dir()?.join(MANIFEST_FILE)))
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Whether the manifest vouches for `path` having `expected_checksum`:
+    /// it was verified with that checksum and its size has not changed.
+    pub fn is_verified(&self, path: &Path, expected_checksum: &str) -> bool {
+        let Some(entry) = self.manifest.get(path) else {
+            return false;
+        };
+        let size = std::fs::metadata(path).map(|metadata| metadata.len()).ok();
+        entry.sha256 == expected_checksum && size == Some(entry.size)
+    }
+
+    /// Make sure the model from `url` is at `path`, downloading it when it is
+    /// missing and, with `expected_checksum`, when the file does not match.
+    pub async fn ensure(
+        &mut self,
+        url: &str,
+        path: &Path,
+        expected_checksum: Option<&str>,
+    ) -> Result<()> {
+        if !path.exists() {
+            warn!("Model file not found at {:?}. Attempting to download...", path);
+            return self.download(url, path, expected_checksum).await;
+        }
+
+        // Verify existing model if checksum is configured
+        let Some(expected_checksum) = expected_checksum else {
+            return Ok(());
+        };
+        if self.is_verified(path, expected_checksum) {
+            info!("Model checksum verified earlier, skipping: {}", expected_checksum);
+            return Ok(());
+        }
+
+        info!("Model file exists, verifying checksum...");
+        let actual_checksum = compute_file_checksum(path)?;
+        if actual_checksum == expected_checksum {
+            info!("Model checksum verification passed: {}", actual_checksum);
+            self.record(path, actual_checksum)
+        } else {
+            error!(
+                "Model checksum mismatch! Expected: {}, Got: {}",
+                expected_checksum, actual_checksum
+            );
+            warn!("Re-downloading model due to checksum mismatch...");
+            self.download(url, path, Some(expected_checksum)).await
+        }
+    }
+
+    /// Note `path` as verified with `sha256` and save the manifest.
+    fn record(&mut self, path: &Path, sha256: String) -> Result<()> {
+        let size = std::fs::metadata(path)?.len();
+        self.manifest.record(ManifestEntry {
+            path: path.to_path_buf(),
+            size,
+            sha256,
+            last_verified: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        if let Err(e) = self.manifest.save(&self.manifest_path) {
+            warn!("Failed to save model manifest {}: {}", self.manifest_path.display(), e);
+        }
+        Ok(())
+    }
+
+    async fn download(
+        &mut self,
+        model_url: &str,
+        path: &Path,
+        expected_checksum: Option<&str>,
+    ) -> Result<()> {
+        let model_dir = path
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Invalid model path"))?;
+
+        info!("Creating model directory: {:?}", model_dir);
+        tokio::fs::create_dir_all(model_dir).await?;
+
+        let model_url = &mirrored_url(model_url, self.download.mirror.as_deref());
+        info!("Downloading model from: {}", model_url);
+
+        // Without a configured checksum, verify against the one HuggingFace publishes
+        let fetched_checksum = match expected_checksum {
+            Some(_) => None,
+            None => match fetch_checksum(model_url, &self.download).await {
+                Ok(checksum) => checksum,
+                Err(e) => {
+                    warn!("Could not fetch the model checksum: {}", e);
+                    None
+                }
+            },
+        };
+        let expected_checksum = expected_checksum.or(fetched_checksum.as_deref());
+        if expected_checksum.is_none() {
+            warn!("No checksum known for {}, the download will not be verified", model_url);
+        }
+
+        // Create temporary file for atomic write. A partial download left by
+        // an earlier attempt, or an earlier run, is resumed.
+        let temp_path = PathBuf::from(format!("{}.tmp", path.display()));
+
+        // Retry logic with exponential backoff
+        let max_retries = 3;
+        let mut last_error = None;
+
+        for attempt in 1..=max_retries {
+            debug!("Download attempt {}/{}", attempt, max_retries);
+
+            match download_with_checksum(&temp_path, model_url, &self.download, attempt, max_retries)
+                .await
+            {
+                Ok(actual_checksum) => {
+                    if let Some(expected_checksum) = expected_checksum {
+                        if actual_checksum != expected_checksum {
+                            error!(
+                                "Checksum verification failed! Expected: {}, Got: {}",
+                                expected_checksum, actual_checksum
+                            );
+                            // Clean up the failed download
+                            tokio::fs::remove_file(&temp_path).await?;
+                            last_error = Some(anyhow::anyhow!(
+                                "Checksum mismatch: expected {}, got {}",
+                                expected_checksum,
+                                actual_checksum
+                            ));
+                            continue;
+                        }
+                        info!("Checksum verification passed: {}", actual_checksum);
+                    }
+
+                    // Atomic rename from temp to final path
+                    info!("Atomic rename: {:?} -> {:?}", temp_path, path);
+                    tokio::fs::rename(&temp_path, path).await?;
+                    info!("Model downloaded and verified successfully to: {:?}", path);
+                    return self.record(path, actual_checksum);
+                }
+                Err(e) => {
+                    let error_msg = format!("{}", e);
+                    error!("Download attempt {} failed: {}", attempt, error_msg);
+                    last_error = Some(anyhow::anyhow!(error_msg));
+
+                    // The partial download is kept for the next attempt to resume
+
+                    // Exponential backoff before next retry
+                    if attempt < max_retries {
+                        let delay_ms = 1000 * 2_u64.pow(attempt as u32);
+                        info!(
+                            "Waiting {} ms before retry (attempt {}/{})...",
+                            delay_ms, attempt + 1, max_retries
+                        );
+                        sleep(Duration::from_millis(delay_ms)).await;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            anyhow::anyhow!("Failed to download model after {} attempts", max_retries)
+        }))
+    }
+}
+
+/// Stream `model_url` into `temp_path`, returning the SHA-256 of the whole
+/// file. Bytes already in `temp_path` are kept and only the rest requested
+/// with a `Range` header, unless the server ignores it.
+async fn download_with_checksum(
+    temp_path: &Path,
+    model_url: &str,
+    download: &DownloadConfig,
+    attempt: usize,
+    max_attempts: usize,
+) -> Result<String> {
+    use futures_util::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    info!(
+        "Starting download (attempt {}/{}): {}",
+        attempt, max_attempts, model_url
+    );
+
+    let client = http_client(download, reqwest::redirect::Policy::limited(5))?;
+
+    // Send HEAD request to check ETag (HuggingFace CDN may not return Content-Length on HEAD)
+    let head_response = client
+        .head(model_url)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("HEAD request failed: {}", e))?;
+
+    // Check for ETag (optional, for HuggingFace)
+    let etag = head_response.headers().get("etag").and_then(|v| v.to_str().ok());
+    if let Some(etag) = etag {
+        info!("Server ETag: {}", etag);
+    }
+
+    // Resume from the end of an earlier partial download
+    let existing = tokio::fs::metadata(temp_path).await.map_or(0, |metadata| metadata.len());
+    let mut request = client.get(model_url);
+    if existing > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        // Resume only if the file has not changed since the HEAD request
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_RANGE, etag);
+        }
+    }
+
+    // Start streaming download
+    let response = request
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("GET request failed: {}", e))?;
+
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file is no shorter than the model, so it cannot be resumed
+        tokio::fs::remove_file(temp_path).await?;
+        return Err(anyhow::anyhow!("Partial download does not match the server's file"));
+    }
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "HTTP error: {}",
+            response.status()
+        ));
+    }
+
+    // Create SHA256 hasher
+    let mut hasher = Sha256::new();
+
+    let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = 0u64;
+    let mut file = if resumed {
+        info!("Resuming download after {}", pretty_bytes(existing));
+        // The checksum covers the whole file, so hash what is already there
+        hash_file_into(temp_path, &mut hasher)?;
+        downloaded = existing;
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(temp_path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open temp file: {}", e))?
+    } else {
+        if existing > 0 {
+            info!("Server did not resume the partial download, starting over");
+        }
+        tokio::fs::File::create(temp_path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create temp file: {}", e))?
+    };
+
+    let total_bytes = response.content_length().map(|length| length + downloaded);
+    let mut stream = response.bytes_stream();
+
+    let start_time = std::time::Instant::now();
+
+    // Download chunks with streaming checksum calculation
+    loop {
+        // Add 30-second timeout to each chunk read
+        let chunk_result = timeout(Duration::from_secs(30), stream.next()).await;
+
+        let chunk = match chunk_result {
+            Ok(Some(Ok(c))) => c,
+            Ok(Some(Err(e))) => {
+                return Err(anyhow::anyhow!("Download error: {}", e));
+            }
+            Ok(None) => {
+                // End of stream
+                break;
+            }
+            Err(_) => {
+                // Timeout occurred
+                return Err(anyhow::anyhow!(
+                    "Download chunk read timeout: server did not send data within 30 seconds"
+                ));
+            }
+        };
+        let chunk_len = chunk.len();
+        downloaded += chunk_len as u64;
+
+        // Update SHA256 hash with this chunk
+        hasher.update(&chunk);
+
+        // Write to file
+        file.write_all(&chunk).await.map_err(|e| {
+            anyhow::anyhow!("Failed to write to temp file: {}", e)
+        })?;
+
+        // Log progress every 10% or every 10 seconds
+        let elapsed = start_time.elapsed().as_secs();
+        if let Some(total) = total_bytes {
+            let progress = (downloaded * 100) / total;
+
+            if progress.is_multiple_of(10) || elapsed.is_multiple_of(10) {
+                let speed = downloaded.checked_div(elapsed).unwrap_or(0);
+                info!(
+                    "Download progress: {}% ({}/{} bytes, {} bytes/s)",
+                    progress,
+                    pretty_bytes(downloaded),
+                    pretty_bytes(total),
+                    pretty_bytes(speed)
+                );
+            }
+        } else if elapsed.is_multiple_of(10) {
+            info!(
+                "Download progress: {} bytes downloaded...",
+                pretty_bytes(downloaded)
+            );
+        }
+    }
+
+    // Flush and close the file
+    file.flush().await.map_err(|e| {
+        anyhow::anyhow!("Failed to flush temp file: {}", e)
+    })?;
+    drop(file);
+
+    // Verify file size matches expected size from GET response (HEAD may return 0 from some CDNs)
+    if let Some(expected) = total_bytes {
+        let metadata = tokio::fs::metadata(temp_path).await?;
+        let actual_size = metadata.len();
+
+        if actual_size != expected {
+            return Err(anyhow::anyhow!(
+                "File size mismatch: expected {} bytes, got {} bytes",
+                pretty_bytes(expected),
+                pretty_bytes(actual_size)
+            ));
+        }
+
+        info!(
+            "File size verification passed: {} bytes",
+            pretty_bytes(actual_size)
+        );
+    }
+
+    debug!("Download streaming complete, file written to: {:?}", temp_path);
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// SHA-256 HuggingFace publishes for the file at `model_url`, or `None`
+/// for files not stored in LFS and hosts that do not say.
+async fn fetch_checksum(model_url: &str, download: &DownloadConfig) -> Result<Option<String>> {
+    // The header is on HuggingFace's own response, not the CDN it redirects to
+    let client = http_client(download, reqwest::redirect::Policy::none())?;
+    let response = client
+        .head(model_url)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("HEAD request failed: {}", e))?;
+    let checksum = linked_sha256(response.headers());
+    if let Some(checksum) = &checksum {
+        info!("Expected SHA256 checksum from server: {}", checksum);
+    }
+    Ok(checksum)
+}
+
+/// SHA-256 in the `X-Linked-Etag` header HuggingFace sends for LFS files.
+fn linked_sha256(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let etag = headers.get("x-linked-etag")?.to_str().ok()?;
+    let etag = etag.trim_start_matches("W/").trim_matches('"');
+    let is_sha256 = etag.len() == 64 && etag.chars().all(|c| c.is_ascii_hexdigit());
+    is_sha256.then(|| etag.to_ascii_lowercase())
+}
+
+/// HTTP client for downloads, through `download.proxy` if one is set.
+fn http_client(
+    download: &DownloadConfig,
+    redirect: reqwest::redirect::Policy,
+) -> Result<reqwest::Client> {
+    // Configure client with timeouts
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300)) // 5 minute total timeout for download
+        .connect_timeout(Duration::from_secs(30)) // 30 second connect timeout
+        .redirect(redirect);
+    if let Some(proxy) = &download.proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| anyhow::anyhow!("Invalid download.proxy '{}': {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    builder
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to create HTTP client: {}", e))
+}
+
+/// `url` with HuggingFace replaced by `mirror`, if one is set.
+fn mirrored_url(url: &str, mirror: Option<&str>) -> String {
+    match (mirror, url.strip_prefix(HUGGINGFACE)) {
+        (Some(mirror), Some(rest)) => format!("{}{}", mirror.trim_end_matches('/'), rest),
+        _ => url.to_string(),
+    }
+}
+
+/// SHA-256 of a file, as lowercase hex.
+pub fn compute_file_checksum(file_path: &Path) -> Result<String> {
+    info!("Computing SHA256 checksum for: {:?}", file_path);
+
+    let mut hasher = Sha256::new();
+    hash_file_into(file_path, &mut hasher)?;
+
+    let checksum = hex::encode(hasher.finalize());
+    info!("Computed SHA256 checksum: {}", checksum);
+    Ok(checksum)
+}
+
+/// Feed the contents of a file to `hasher`.
+fn hash_file_into(file_path: &Path, hasher: &mut Sha256) -> Result<()> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut file = File::open(file_path)?;
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(())
+}
+
+/// Format bytes in human-readable form.
+pub fn pretty_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    format!("{:.2} {}", size, UNITS[unit_index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-256 of "hello".
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn test_find_model_path_existing() {
+        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin";
+        let path = find_model_path(url).unwrap();
+
+        assert!(path.to_str().unwrap().contains("ggml-base.bin"));
+        assert!(path.extension().unwrap() == "bin");
+    }
+
+    #[test]
+    fn test_find_model_path_fallback() {
+        let url = "https://example.com/models/ggml-nonexistent.bin";
+        let path = find_model_path(url).unwrap();
+
+        assert!(path.to_str().unwrap().contains("ggml-nonexistent.bin"));
+        assert!(path.to_str().unwrap().contains(".local/share/ndict"));
+    }
+
+    #[test]
+    fn test_find_model_path_from_different_url() {
+        let url = "https://custom-host.com/path/to/ggml-tiny.en.bin";
+        let path = find_model_path(url).unwrap();
+
+        assert!(path.to_str().unwrap().contains("ggml-tiny.en.bin"));
+        assert!(path.extension().unwrap() == "bin");
+    }
+
+    #[test]
+    fn test_compute_file_checksum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        std::fs::write(&path, "hello").unwrap();
+        assert_eq!(compute_file_checksum(&path).unwrap(), HELLO_SHA256);
+    }
+
+    #[test]
+    fn test_pretty_bytes() {
+        assert_eq!(pretty_bytes(512), "512.00 B");
+        assert_eq!(pretty_bytes(1536), "1.50 KB");
+        assert_eq!(pretty_bytes(3 * 1024 * 1024 * 1024), "3.00 GB");
+    }
+
+    #[test]
+    fn test_mirrored_url() {
+        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin";
+        assert_eq!(mirrored_url(url, None), url);
+        assert_eq!(
+            mirrored_url(url, Some("https://hf-mirror.com/")),
+            "https://hf-mirror.com/ggerganov/whisper.cpp/resolve/main/ggml-base.bin"
+        );
+        // Models hosted elsewhere are left alone
+        let other = "https://example.com/ggml-base.bin";
+        assert_eq!(mirrored_url(other, Some("https://hf-mirror.com")), other);
+    }
+
+    #[test]
+    fn test_http_client_proxy() {
+        let socks = DownloadConfig {
+            proxy: Some("socks5://127.0.0.1:1080".to_string()),
+            ..DownloadConfig::default()
+        };
+        assert!(http_client(&socks, reqwest::redirect::Policy::none()).is_ok());
+        let invalid = DownloadConfig {
+            proxy: Some("not a url".to_string()),
+            ..DownloadConfig::default()
+        };
+        assert!(http_client(&invalid, reqwest::redirect::Policy::none()).is_err());
+    }
+
+    #[test]
+    fn test_linked_sha256() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(linked_sha256(&headers), None);
+        headers.insert("x-linked-etag", HeaderValue::from_static("\"0123abcd\""));
+        assert_eq!(linked_sha256(&headers), None);
+        let etag = format!("\"{}\"", HELLO_SHA256.to_uppercase());
+        headers.insert("x-linked-etag", HeaderValue::from_str(&etag).unwrap());
+        assert_eq!(linked_sha256(&headers).as_deref(), Some(HELLO_SHA256));
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join(MANIFEST_FILE);
+        assert_eq!(Manifest::load(&manifest_path).entries().count(), 0);
+
+        let entry = ManifestEntry {
+            path: dir.path().join("ggml-base.bin"),
+            size: 5,
+            sha256: HELLO_SHA256.to_string(),
+            last_verified: "2026-10-15 09:30:00".to_string(),
+        };
+        let mut manifest = Manifest::default();
+        manifest.record(entry.clone());
+        manifest.save(&manifest_path).unwrap();
+
+        let loaded = Manifest::load(&manifest_path);
+        assert_eq!(loaded.get(&entry.path), Some(&entry));
+
+        std::fs::write(&manifest_path, "not json").unwrap();
+        assert_eq!(Manifest::load(&manifest_path).entries().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_records_and_trusts_verified_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_path = dir.path().join("ggml-base.bin");
+        std::fs::write(&model_path, "hello").unwrap();
+        let mut manager = ModelManager::new(dir.path().join(MANIFEST_FILE));
+        assert!(!manager.is_verified(&model_path, HELLO_SHA256));
+
+        // The file matches, so nothing is downloaded and the result is kept
+        manager
+            .ensure("http://127.0.0.1:9/ggml-base.bin", &model_path, Some(HELLO_SHA256))
+            .await
+            .unwrap();
+        assert!(manager.is_verified(&model_path, HELLO_SHA256));
+        let reopened = ModelManager::new(dir.path().join(MANIFEST_FILE));
+        assert!(reopened.is_verified(&model_path, HELLO_SHA256));
+
+        // A changed file is no longer vouched for
+        std::fs::write(&model_path, "hello, world").unwrap();
+        assert!(!reopened.is_verified(&model_path, HELLO_SHA256));
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+
+/// A modifier held while a `KeyChord`'s key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "ctrl" | "control" => Some(Self::Ctrl),
+            "shift" => Some(Self::Shift),
+            "alt" => Some(Self::Alt),
+            "super" | "logo" | "win" => Some(Self::Super),
+            _ => None,
+        }
+    }
+
+    /// Name of the modifier for `wtype -M`.
+    pub fn wtype_name(self) -> &'static str {
+        match self {
+            Self::Ctrl => "ctrl",
+            Self::Shift => "shift",
+            Self::Alt => "alt",
+            Self::Super => "logo",
+        }
+    }
+
+    /// Bit of the modifier in an IBus key event state.
+    pub fn ibus_mask(self) -> u32 {
+        match self {
+            Self::Shift => 1 << 0,
+            Self::Ctrl => 1 << 2,
+            Self::Alt => 1 << 3,
+            Self::Super => 1 << 26,
+        }
+    }
+
+    fn xdotool_name(self) -> &'static str {
+        match self {
+            Self::Ctrl => "ctrl",
+            Self::Shift => "shift",
+            Self::Alt => "alt",
+            Self::Super => "super",
+        }
+    }
+}
+
+/// Keys a chord can end in: name in settings, X keysym and the character
+/// typing it, if any.
+const KEYS: &[(&str, &str, Option<char>)] = &[
+    ("enter", "Return", Some('\n')),
+    ("return", "Return", Some('\n')),
+    ("tab", "Tab", Some('\t')),
+    ("space", "space", Some(' ')),
+    ("escape", "Escape", None),
+    ("esc", "Escape", None),
+];
+
+/// A key with optional modifiers, such as "enter" or "ctrl+enter", see
+/// `output.submit_key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChord {
+    modifiers: Vec<Modifier>,
+    keysym: &'static str,
+    character: Option<char>,
+}
+
+impl KeyChord {
+    pub fn parse(chord: &str) -> Result<Self> {
+        let lower = chord.trim().to_lowercase();
+        let mut parts: Vec<&str> = lower.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        let Some((_, keysym, character)) = KEYS.iter().find(|(name, _, _)| *name == key) else {
+            return Err(anyhow::anyhow!(
+                "Unknown key '{}' in '{}', expected enter, tab, space or escape",
+                key,
+                chord
+            ));
+        };
+        let modifiers = parts
+            .iter()
+            .map(|name| {
+                Modifier::parse(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unknown modifier '{}' in '{}', expected ctrl, shift, alt or super",
+                        name,
+                        chord
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            modifiers,
+            keysym: *keysym,
+            character: *character,
+        })
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    /// X keysym of the key, e.g. "Return".
+    pub fn keysym(&self) -> &str {
+        self.keysym
+    }
+
+    /// The character typing the chord, for chords without modifiers that
+    /// have one, e.g. '\n' for "enter".
+    pub fn as_char(&self) -> Option<char> {
+        if self.modifiers.is_empty() {
+            self.character
+        } else {
+            None
+        }
+    }
+
+    /// The chord as `xdotool key` takes it, e.g. "ctrl+Return".
+    pub fn xdotool_name(&self) -> String {
+        let mut name = String::new();
+        for modifier in &self.modifiers {
+            name.push_str(modifier.xdotool_name());
+            name.push('+');
+        }
+        name.push_str(self.keysym);
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chords() {
+        let enter = KeyChord::parse("Enter").unwrap();
+        assert_eq!(enter.keysym(), "Return");
+        assert_eq!(enter.as_char(), Some('\n'));
+        assert_eq!(enter.xdotool_name(), "Return");
+
+        let chord = KeyChord::parse("ctrl + shift+return").unwrap();
+        assert_eq!(chord.modifiers(), [Modifier::Ctrl, Modifier::Shift]);
+        assert_eq!(chord.as_char(), None);
+        assert_eq!(chord.xdotool_name(), "ctrl+shift+Return");
+        assert_eq!(
+            KeyChord::parse("super+esc").unwrap().modifiers()[0].wtype_name(),
+            "logo"
+        );
+
+        assert!(KeyChord::parse("ctrl+f5").is_err());
+        assert!(KeyChord::parse("hyper+enter").is_err());
+        assert!(KeyChord::parse("").is_err());
+    }
+}
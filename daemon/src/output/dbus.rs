@@ -0,0 +1,714 @@
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixStream};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long `Connection::call` waits for the reply.
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest message accepted, as in the D-Bus specification.
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    MethodCall = 1,
+    MethodReturn = 2,
+    Error = 3,
+    Signal = 4,
+}
+
+impl MessageType {
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            1 => Ok(Self::MethodCall),
+            2 => Ok(Self::MethodReturn),
+            3 => Ok(Self::Error),
+            4 => Ok(Self::Signal),
+            other => Err(anyhow::anyhow!("Unknown D-Bus message type {}", other)),
+        }
+    }
+}
+
+/// Writes values in the little-endian D-Bus wire format. The buffer must
+/// start 8-aligned within the message, as message bodies do.
+#[derive(Debug, Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn align(&mut self, alignment: usize) {
+        let aligned = self.bytes.len().next_multiple_of(alignment);
+        self.bytes.resize(aligned, 0);
+    }
+
+    pub fn byte(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn boolean(&mut self, value: bool) {
+        self.u32(u32::from(value));
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+        self.bytes.push(0);
+    }
+
+    pub fn object_path(&mut self, value: &str) {
+        self.string(value);
+    }
+
+    pub fn signature(&mut self, value: &str) {
+        self.bytes.push(value.len() as u8);
+        self.bytes.extend_from_slice(value.as_bytes());
+        self.bytes.push(0);
+    }
+
+    /// A struct or dict entry, whose fields `fields` writes.
+    pub fn structure(&mut self, fields: impl FnOnce(&mut Self)) {
+        self.align(8);
+        fields(self);
+    }
+
+    /// An array, whose elements `elements` writes. `element_alignment` is
+    /// 8 for structs and dict entries, 1 for variants.
+    pub fn array(&mut self, element_alignment: usize, elements: impl FnOnce(&mut Self)) {
+        self.u32(0);
+        let length_at = self.bytes.len() - 4;
+        self.align(element_alignment);
+        let start = self.bytes.len();
+        elements(self);
+        let length = (self.bytes.len() - start) as u32;
+        self.bytes[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+    }
+
+    /// A variant holding a value of type `signature`, which `value` writes.
+    pub fn variant(&mut self, signature: &str, value: impl FnOnce(&mut Self)) {
+        self.signature(signature);
+        value(self);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads values in the D-Bus wire format from a message body.
+#[derive(Debug)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            big_endian,
+        }
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.position = self.position.next_multiple_of(alignment);
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self.position + count;
+        let taken = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| anyhow::anyhow!("D-Bus message ends early"))?;
+        self.position = end;
+        Ok(taken)
+    }
+
+    pub fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn boolean(&mut self) -> Result<bool> {
+        Ok(self.u32()? != 0)
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        self.align(4);
+        let bytes: [u8; 4] = self.take(4)?.try_into()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    pub fn string(&mut self) -> Result<String> {
+        let length = self.u32()? as usize;
+        let bytes = self.take(length + 1)?;
+        Ok(std::str::from_utf8(&bytes[..length])?.to_string())
+    }
+
+    pub fn signature(&mut self) -> Result<String> {
+        let length = self.byte()? as usize;
+        let bytes = self.take(length + 1)?;
+        Ok(std::str::from_utf8(&bytes[..length])?.to_string())
+    }
+
+    /// Move to the first field of a struct or dict entry.
+    pub fn structure(&mut self) {
+        self.align(8);
+    }
+
+    /// Skip over an array whose elements have `element_alignment`.
+    pub fn skip_array(&mut self, element_alignment: usize) -> Result<()> {
+        let length = self.u32()? as usize;
+        self.align(element_alignment);
+        self.take(length)?;
+        Ok(())
+    }
+}
+
+/// One D-Bus message, as sent or received.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub kind: MessageType,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub signature: String,
+    body: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Message {
+    fn new(kind: MessageType) -> Self {
+        Self {
+            kind,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            signature: String::new(),
+            body: Vec::new(),
+            big_endian: false,
+        }
+    }
+
+    pub fn method_call(destination: &str, path: &str, interface: &str, member: &str) -> Self {
+        Self {
+            destination: Some(destination.to_string()),
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            ..Self::new(MessageType::MethodCall)
+        }
+    }
+
+    pub fn signal(path: &str, interface: &str, member: &str) -> Self {
+        Self {
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            ..Self::new(MessageType::Signal)
+        }
+    }
+
+    /// The reply to the method call `call`.
+    pub fn method_return(call: &Message) -> Self {
+        Self {
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            ..Self::new(MessageType::MethodReturn)
+        }
+    }
+
+    /// The error reply `name` to the method call `call`.
+    pub fn error(call: &Message, name: &str, text: &str) -> Self {
+        let mut body = Writer::new();
+        body.string(text);
+        Self {
+            error_name: Some(name.to_string()),
+            ..Self::method_return(call)
+        }
+        .with_body("s", body)
+        .with_kind(MessageType::Error)
+    }
+
+    fn with_kind(mut self, kind: MessageType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the arguments, of types `signature`, which `body` holds.
+    pub fn with_body(mut self, signature: &str, body: Writer) -> Self {
+        self.signature = signature.to_string();
+        self.body = body.into_bytes();
+        self
+    }
+
+    /// Read the arguments.
+    pub fn body(&self) -> Reader<'_> {
+        Reader::new(&self.body, self.big_endian)
+    }
+
+    pub fn is_call(&self, interface: &str, member: &str) -> bool {
+        self.kind == MessageType::MethodCall
+            && self.interface.as_deref() == Some(interface)
+            && self.member.as_deref() == Some(member)
+    }
+
+    /// The message on the wire, with serial number `serial`.
+    pub fn encode(&self, serial: u32) -> Vec<u8> {
+        let mut header = Writer::new();
+        header.byte(b'l');
+        header.byte(self.kind as u8);
+        header.byte(0);
+        header.byte(1);
+        header.u32(self.body.len() as u32);
+        header.u32(serial);
+        header.array(8, |fields| {
+            let strings = [
+                (FIELD_PATH, "o", &self.path),
+                (FIELD_INTERFACE, "s", &self.interface),
+                (FIELD_MEMBER, "s", &self.member),
+                (FIELD_ERROR_NAME, "s", &self.error_name),
+                (FIELD_DESTINATION, "s", &self.destination),
+                (FIELD_SENDER, "s", &self.sender),
+            ];
+            for (code, signature, value) in strings {
+                if let Some(value) = value {
+                    header_field(fields, code, signature, |w| w.string(value));
+                }
+            }
+            if let Some(reply_serial) = self.reply_serial {
+                header_field(fields, FIELD_REPLY_SERIAL, "u", |w| w.u32(reply_serial));
+            }
+            if !self.signature.is_empty() {
+                header_field(fields, FIELD_SIGNATURE, "g", |w| {
+                    w.signature(&self.signature)
+                });
+            }
+        });
+        header.align(8);
+
+        let mut bytes = header.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Parse a whole message as `read_message` reads it.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let big_endian = match bytes.first() {
+            Some(b'l') => false,
+            Some(b'B') => true,
+            _ => return Err(anyhow::anyhow!("Not a D-Bus message")),
+        };
+        let mut reader = Reader::new(bytes, big_endian);
+        reader.byte()?;
+        let mut message = Self::new(MessageType::from_byte(reader.byte()?)?);
+        message.big_endian = big_endian;
+        let _flags = reader.byte()?;
+        let _version = reader.byte()?;
+        let body_length = reader.u32()? as usize;
+        message.serial = reader.u32()?;
+
+        let fields_length = reader.u32()? as usize;
+        let fields_end = reader.position + fields_length;
+        while reader.position < fields_end {
+            reader.structure();
+            let code = reader.byte()?;
+            let signature = reader.signature()?;
+            match (code, signature.as_str()) {
+                (FIELD_PATH, "o") => message.path = Some(reader.string()?),
+                (FIELD_INTERFACE, "s") => message.interface = Some(reader.string()?),
+                (FIELD_MEMBER, "s") => message.member = Some(reader.string()?),
+                (FIELD_ERROR_NAME, "s") => message.error_name = Some(reader.string()?),
+                (FIELD_REPLY_SERIAL, "u") => message.reply_serial = Some(reader.u32()?),
+                (FIELD_DESTINATION, "s") => message.destination = Some(reader.string()?),
+                (FIELD_SENDER, "s") => message.sender = Some(reader.string()?),
+                (FIELD_SIGNATURE, "g") => message.signature = reader.signature()?,
+                // Fields added in later versions of the protocol
+                (_, "s" | "o") => {
+                    reader.string()?;
+                }
+                (_, "u") => {
+                    reader.u32()?;
+                }
+                (_, "g") => {
+                    reader.signature()?;
+                }
+                (_, other) => {
+                    return Err(anyhow::anyhow!(
+                        "Unsupported D-Bus header field type '{}'",
+                        other
+                    ))
+                }
+            }
+        }
+        reader.align(8);
+        message.body = reader.take(body_length)?.to_vec();
+        Ok(message)
+    }
+}
+
+fn header_field(fields: &mut Writer, code: u8, signature: &str, value: impl FnOnce(&mut Writer)) {
+    fields.structure(|field| {
+        field.byte(code);
+        field.variant(signature, value);
+    });
+}
+
+/// Read one message from `reader`.
+pub fn read_message(reader: &mut impl Read) -> Result<Message> {
+    let mut fixed = [0u8; 16];
+    reader.read_exact(&mut fixed)?;
+    let number = |at: usize| {
+        let bytes: [u8; 4] = fixed[at..at + 4].try_into().unwrap();
+        if fixed[0] == b'B' {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    let header_length = (16 + number(12) as usize).next_multiple_of(8);
+    let length = header_length + number(4) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(anyhow::anyhow!(
+            "D-Bus message of {} bytes is too large",
+            length
+        ));
+    }
+
+    let mut bytes = fixed.to_vec();
+    bytes.resize(length, 0);
+    reader.read_exact(&mut bytes[16..])?;
+    Message::decode(&bytes)
+}
+
+/// The socket of the first `unix:` entry of a D-Bus address, such as
+/// "unix:path=/run/user/1000/bus,guid=..." or "unix:abstract=/tmp/dbus-x".
+pub fn parse_address(address: &str) -> Result<SocketAddr> {
+    for entry in address.split(';') {
+        let Some(parameters) = entry.trim().strip_prefix("unix:") else {
+            continue;
+        };
+        for parameter in parameters.split(',') {
+            match parameter.split_once('=') {
+                Some(("path", value)) => {
+                    return Ok(SocketAddr::from_pathname(OsStr::from_bytes(&unescape(
+                        value,
+                    )?))?)
+                }
+                Some(("abstract", value)) => {
+                    return Ok(SocketAddr::from_abstract_name(unescape(value)?)?)
+                }
+                _ => {}
+            }
+        }
+    }
+    Err(anyhow::anyhow!("Unsupported D-Bus address '{}'", address))
+}
+
+/// An address value with its %XX escapes decoded.
+fn unescape(value: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .ok_or_else(|| anyhow::anyhow!("Bad escape in D-Bus address '{}'", value))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex)?, 16)?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Ok(bytes)
+}
+
+/// A connection to a D-Bus message bus, such as the one IBus runs.
+///
+/// Messages can be sent from any thread. Incoming messages are read with
+/// `receive`, from one thread at a time.
+pub struct Connection {
+    stream: UnixStream,
+    write_lock: Mutex<()>,
+    next_serial: AtomicU32,
+    unique_name: String,
+}
+
+impl Connection {
+    /// Connect to the bus at `address`, authenticate and say Hello.
+    pub fn open(address: &str) -> Result<Self> {
+        let socket = parse_address(address)?;
+        let mut stream = UnixStream::connect_addr(&socket)
+            .with_context(|| format!("Failed to connect to D-Bus at {}", address))?;
+        stream.set_read_timeout(Some(CALL_TIMEOUT))?;
+        authenticate(&mut stream)?;
+        stream.set_read_timeout(None)?;
+
+        let mut connection = Self {
+            stream,
+            write_lock: Mutex::new(()),
+            next_serial: AtomicU32::new(1),
+            unique_name: String::new(),
+        };
+        let hello = Message::method_call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+        );
+        let reply = connection.call(&hello, |_| {})?;
+        connection.unique_name = reply.body().string()?;
+        Ok(connection)
+    }
+
+    /// The name the bus gave this connection.
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Send `message`, returning its serial number.
+    pub fn send(&self, message: &Message) -> Result<u32> {
+        let serial = self.next_serial.fetch_add(1, Ordering::Relaxed);
+        let bytes = message.encode(serial);
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        (&self.stream).write_all(&bytes)?;
+        Ok(serial)
+    }
+
+    /// Send the method call `message` and wait for its reply, handing
+    /// whatever else arrives meanwhile to `other`. Only while nothing else
+    /// receives.
+    pub fn call(&self, message: &Message, mut other: impl FnMut(Message)) -> Result<Message> {
+        let serial = self.send(message)?;
+        self.stream.set_read_timeout(Some(CALL_TIMEOUT))?;
+        let reply = loop {
+            let received = self.receive();
+            match received {
+                Ok(reply) if reply.reply_serial == Some(serial) => break Ok(reply),
+                Ok(unrelated) => other(unrelated),
+                Err(e) => break Err(e),
+            }
+        };
+        self.stream.set_read_timeout(None)?;
+        let reply = reply.with_context(|| {
+            format!(
+                "No reply to {}",
+                message.member.as_deref().unwrap_or("D-Bus call")
+            )
+        })?;
+        if reply.kind == MessageType::Error {
+            let name = reply.error_name.clone().unwrap_or_default();
+            let text = if reply.signature.starts_with('s') {
+                reply.body().string().unwrap_or_default()
+            } else {
+                String::new()
+            };
+            return Err(anyhow::anyhow!(
+                "{} failed: {} {}",
+                message.member.as_deref().unwrap_or("D-Bus call"),
+                name,
+                text
+            ));
+        }
+        Ok(reply)
+    }
+
+    /// Wait for the next message.
+    pub fn receive(&self) -> Result<Message> {
+        read_message(&mut &self.stream)
+    }
+
+    /// Close the connection, which ends a `receive` waiting on another
+    /// thread.
+    pub fn shutdown(&self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+/// Log in as the user running the daemon, with the EXTERNAL mechanism.
+fn authenticate(stream: &mut UnixStream) -> Result<()> {
+    let uid = effective_uid()?;
+    stream.write_all(b"\0")?;
+    stream.write_all(format!("AUTH EXTERNAL {}\r\n", hex::encode(uid.to_string())).as_bytes())?;
+
+    // Byte by byte, so nothing after the line is read
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !line.ends_with(b"\r\n") {
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    let line = String::from_utf8_lossy(&line);
+    if !line.starts_with("OK ") {
+        return Err(anyhow::anyhow!(
+            "D-Bus refused to authenticate: {}",
+            line.trim()
+        ));
+    }
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(())
+}
+
+fn effective_uid() -> Result<u32> {
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().nth(1))
+        .and_then(|uid| uid.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Cannot find the user id in /proc/self/status"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_hello() {
+        let hello = Message::method_call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "Hello",
+        );
+        let bytes = hello.encode(1);
+        assert_eq!(&bytes[..4], b"l\x01\x00\x01");
+        assert_eq!(bytes.len() % 8, 0);
+
+        let decoded = Message::decode(&bytes).unwrap();
+        assert_eq!(decoded.kind, MessageType::MethodCall);
+        assert_eq!(decoded.serial, 1);
+        assert_eq!(decoded.path.as_deref(), Some("/org/freedesktop/DBus"));
+        assert!(decoded.is_call("org.freedesktop.DBus", "Hello"));
+        assert_eq!(decoded.signature, "");
+    }
+
+    #[test]
+    fn test_body_round_trip() {
+        let mut body = Writer::new();
+        body.byte(7);
+        body.string("héllo");
+        body.variant("(sa{sv}av)", |w| {
+            w.structure(|w| {
+                w.string("IBusAttrList");
+                w.array(8, |_| {});
+                w.array(1, |_| {});
+            })
+        });
+        body.boolean(true);
+        let message = Message::signal("/a", "b.c", "D").with_body("ysvb", body);
+
+        let decoded = read_message(&mut &message.encode(9)[..]).unwrap();
+        assert_eq!(decoded.member.as_deref(), Some("D"));
+        assert_eq!(decoded.signature, "ysvb");
+        let mut reader = decoded.body();
+        assert_eq!(reader.byte().unwrap(), 7);
+        assert_eq!(reader.string().unwrap(), "héllo");
+        assert_eq!(reader.signature().unwrap(), "(sa{sv}av)");
+        reader.structure();
+        assert_eq!(reader.string().unwrap(), "IBusAttrList");
+        reader.skip_array(8).unwrap();
+        reader.skip_array(1).unwrap();
+        assert!(reader.boolean().unwrap());
+    }
+
+    #[test]
+    fn test_empty_array_is_padded_to_its_elements() {
+        let mut body = Writer::new();
+        body.byte(1);
+        body.array(8, |_| {});
+        // Length at 4..8, then padding to 8 even though nothing follows
+        assert_eq!(body.into_bytes(), vec![1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_error_reply() {
+        let mut call = Message::method_call("x.y", "/", "x.y", "Z");
+        call.serial = 4;
+        call.sender = Some(":1.2".to_string());
+        let error = Message::error(&call, "x.Error", "nope");
+        let decoded = Message::decode(&error.encode(5)).unwrap();
+        assert_eq!(decoded.kind, MessageType::Error);
+        assert_eq!(decoded.reply_serial, Some(4));
+        assert_eq!(decoded.destination.as_deref(), Some(":1.2"));
+        assert_eq!(decoded.error_name.as_deref(), Some("x.Error"));
+        assert_eq!(decoded.body().string().unwrap(), "nope");
+    }
+
+    #[test]
+    fn test_truncated_message_is_rejected() {
+        let bytes = Message::signal("/a", "b.c", "D").encode(1);
+        assert!(read_message(&mut &bytes[..bytes.len() - 4]).is_err());
+        assert!(Message::decode(b"x").is_err());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let path = parse_address("unix:path=/run/user/1000/bus,guid=abc").unwrap();
+        assert_eq!(
+            path.as_pathname(),
+            Some(std::path::Path::new("/run/user/1000/bus"))
+        );
+        let hidden = parse_address("tcp:host=x;unix:abstract=/tmp/dbus-%41b").unwrap();
+        assert_eq!(hidden.as_abstract_name(), Some(&b"/tmp/dbus-Ab"[..]));
+        assert!(parse_address("(null)").is_err());
+        assert!(parse_address("unix:path=/tmp/%4").is_err());
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let bus = std::thread::spawn(move || {
+            let mut received = [0u8; 64];
+            let read = server.read(&mut received).unwrap();
+            server.write_all(b"OK 1234\r\n").unwrap();
+            let mut begin = [0u8; 7];
+            server.read_exact(&mut begin).unwrap();
+            (received[..read].to_vec(), begin)
+        });
+
+        authenticate(&mut client).unwrap();
+        let (auth, begin) = bus.join().unwrap();
+        assert!(auth.starts_with(b"\0AUTH EXTERNAL "));
+        assert_eq!(&begin, b"BEGIN\r\n");
+    }
+}
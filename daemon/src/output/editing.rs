@@ -0,0 +1,260 @@
+use crate::output::chord::KeyChord;
+
+/// Most edits remembered for `undo`.
+const MAX_EDITS: usize = 50;
+
+/// A spoken editing command, see `output.voice_editing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditCommand {
+    /// Remove the last dictated utterance.
+    ScratchThat,
+    DeleteLastWord,
+    /// Reverse the last dictation or edit.
+    Undo,
+}
+
+impl EditCommand {
+    /// Recognize an utterance that is only an editing phrase, ignoring case
+    /// and the punctuation Whisper adds, e.g. "Scratch that."
+    pub fn parse(text: &str) -> Option<Self> {
+        let phrase: String = text
+            .chars()
+            .filter(|c| !c.is_ascii_punctuation())
+            .collect::<String>()
+            .to_lowercase();
+        match phrase.split_whitespace().collect::<Vec<_>>().join(" ").as_str() {
+            "scratch that" | "delete that" => Some(Self::ScratchThat),
+            "delete last word" | "delete the last word" => Some(Self::DeleteLastWord),
+            "undo" | "undo that" => Some(Self::Undo),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with the keyboard to carry out an edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Press backspace this many times.
+    Backspace(usize),
+    Type(String),
+    /// Press a key with its modifiers, e.g. `output.submit_key`.
+    Press(KeyChord),
+}
+
+/// The keyboard actions turning `typed` on screen into `revised`:
+/// backspace over the end of `typed` the two do not share, then type the
+/// rest of `revised`.
+pub fn correction(typed: &str, revised: &str) -> Vec<KeyAction> {
+    let shared = typed
+        .chars()
+        .zip(revised.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut actions = Vec::new();
+    let erased = typed.chars().count() - shared;
+    if erased > 0 {
+        actions.push(KeyAction::Backspace(erased));
+    }
+    let tail: String = revised.chars().skip(shared).collect();
+    if !tail.is_empty() {
+        actions.push(KeyAction::Type(tail));
+    }
+    actions
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    Typed(String),
+    Removed(String),
+}
+
+/// The text typed this session, by utterance, so editing commands know how
+/// much to backspace. Text the user types themselves is not seen, so edits
+/// assume the cursor is still where dictation left it.
+#[derive(Debug, Default)]
+pub struct TypedHistory {
+    utterances: Vec<String>,
+    edits: Vec<Edit>,
+}
+
+impl TypedHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.utterances.clear();
+        self.edits.clear();
+    }
+
+    /// The last utterance still on screen, as typed.
+    pub fn last_typed(&self) -> Option<&str> {
+        self.utterances.last().map(String::as_str)
+    }
+
+    /// The utterance typed before the last one.
+    pub fn before_last(&self) -> Option<&str> {
+        self.utterances.iter().rev().nth(1).map(String::as_str)
+    }
+
+    /// Record an utterance that was typed.
+    pub fn typed(&mut self, text: &str) {
+        self.utterances.push(text.to_string());
+        self.push_edit(Edit::Typed(text.to_string()));
+    }
+
+    /// Replace the last utterance typed with `text`, returning the keyboard
+    /// actions that correct it on screen.
+    pub fn revise_last(&mut self, text: &str) -> Vec<KeyAction> {
+        let typed = self.utterances.pop().unwrap_or_default();
+        if let Some(Edit::Typed(last)) = self.edits.last_mut() {
+            if *last == typed {
+                *last = text.to_string();
+            }
+        }
+        self.utterances.push(text.to_string());
+        correction(&typed, text)
+    }
+
+    /// The keyboard action for `command`, or `None` when there is nothing
+    /// to edit.
+    pub fn apply(&mut self, command: EditCommand) -> Option<KeyAction> {
+        match command {
+            EditCommand::ScratchThat => {
+                let removed = self.utterances.pop()?;
+                self.push_edit(Edit::Removed(removed.clone()));
+                Some(KeyAction::Backspace(removed.chars().count()))
+            }
+            EditCommand::DeleteLastWord => {
+                while self.utterances.last().is_some_and(|last| last.trim().is_empty()) {
+                    self.utterances.pop();
+                }
+                let last = self.utterances.last_mut()?;
+                let word_start = last
+                    .trim_end()
+                    .rfind(char::is_whitespace)
+                    .map_or(0, |space| space + 1);
+                let removed = last.split_off(word_start);
+                if last.is_empty() {
+                    self.utterances.pop();
+                }
+                self.push_edit(Edit::Removed(removed.clone()));
+                Some(KeyAction::Backspace(removed.chars().count()))
+            }
+            EditCommand::Undo => match self.edits.pop()? {
+                Edit::Typed(text) => {
+                    self.remove_typed(&text);
+                    Some(KeyAction::Backspace(text.chars().count()))
+                }
+                Edit::Removed(text) => {
+                    self.utterances.push(text.clone());
+                    Some(KeyAction::Type(text))
+                }
+            },
+        }
+    }
+
+    /// Take the typed `text` back off the end of the history.
+    fn remove_typed(&mut self, text: &str) {
+        if let Some(last) = self.utterances.last_mut() {
+            if let Some(kept) = last.strip_suffix(text) {
+                *last = kept.to_string();
+            }
+            if last.is_empty() {
+                self.utterances.pop();
+            }
+        }
+    }
+
+    fn push_edit(&mut self, edit: Edit) {
+        if self.edits.len() == MAX_EDITS {
+            self.edits.remove(0);
+        }
+        self.edits.push(edit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_editing_phrases() {
+        assert_eq!(EditCommand::parse("Scratch that."), Some(EditCommand::ScratchThat));
+        assert_eq!(EditCommand::parse(" delete  last word!"), Some(EditCommand::DeleteLastWord));
+        assert_eq!(EditCommand::parse("Undo."), Some(EditCommand::Undo));
+        assert_eq!(EditCommand::parse("scratch that idea"), None);
+        assert_eq!(EditCommand::parse("Let's undo the merge."), None);
+    }
+
+    #[test]
+    fn test_scratch_that_removes_last_utterance() {
+        let mut history = TypedHistory::new();
+        history.typed("Hello there.");
+        history.typed("Wrong thing.");
+        assert_eq!(history.apply(EditCommand::ScratchThat), Some(KeyAction::Backspace(12)));
+        assert_eq!(history.apply(EditCommand::ScratchThat), Some(KeyAction::Backspace(12)));
+        assert_eq!(history.apply(EditCommand::ScratchThat), None);
+    }
+
+    #[test]
+    fn test_delete_last_word() {
+        let mut history = TypedHistory::new();
+        history.typed("Hi");
+        history.typed("see you tomorrow.");
+        assert_eq!(history.apply(EditCommand::DeleteLastWord), Some(KeyAction::Backspace(9)));
+        assert_eq!(history.apply(EditCommand::DeleteLastWord), Some(KeyAction::Backspace(4)));
+        assert_eq!(history.apply(EditCommand::DeleteLastWord), Some(KeyAction::Backspace(4)));
+        assert_eq!(history.apply(EditCommand::DeleteLastWord), Some(KeyAction::Backspace(2)));
+        assert_eq!(history.apply(EditCommand::DeleteLastWord), None);
+    }
+
+    #[test]
+    fn test_correction_keeps_the_shared_start() {
+        assert_eq!(
+            correction("I sea the", "I see the shore"),
+            vec![
+                KeyAction::Backspace(5),
+                KeyAction::Type("e the shore".to_string())
+            ]
+        );
+        assert_eq!(
+            correction("Hello", "Hello there"),
+            vec![KeyAction::Type(" there".to_string())]
+        );
+        assert_eq!(correction("Hello there", "Hello"), vec![KeyAction::Backspace(6)]);
+        assert!(correction("café", "café").is_empty());
+    }
+
+    #[test]
+    fn test_revised_draft_is_undone_as_revised() {
+        let mut history = TypedHistory::new();
+        history.typed("Hi.");
+        history.typed(" I sea");
+        assert_eq!(history.before_last(), Some("Hi."));
+        assert_eq!(
+            history.revise_last(" I see."),
+            vec![KeyAction::Backspace(1), KeyAction::Type("e.".to_string())]
+        );
+        assert_eq!(history.last_typed(), Some(" I see."));
+        assert_eq!(history.apply(EditCommand::Undo), Some(KeyAction::Backspace(7)));
+        assert_eq!(history.last_typed(), Some("Hi."));
+    }
+
+    #[test]
+    fn test_undo_reverses_edits_and_dictation() {
+        let mut history = TypedHistory::new();
+        history.typed("Keep this.");
+        history.typed("Maybe this.");
+        history.apply(EditCommand::ScratchThat);
+
+        assert_eq!(
+            history.apply(EditCommand::Undo),
+            Some(KeyAction::Type("Maybe this.".to_string()))
+        );
+        assert_eq!(history.apply(EditCommand::Undo), Some(KeyAction::Backspace(11)));
+        assert_eq!(history.apply(EditCommand::Undo), Some(KeyAction::Backspace(10)));
+        assert_eq!(history.apply(EditCommand::Undo), None);
+        assert_eq!(history.apply(EditCommand::ScratchThat), None);
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// One finalized transcription.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub time: DateTime<Local>,
+    pub language: String,
+    pub text: String,
+}
+
+/// Layout of `SessionLog::export`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Text,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "text" | "txt" => Ok(Self::Text),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(anyhow::anyhow!(
+                "Unknown export format '{}'. Expected \"text\" or \"markdown\"",
+                other
+            )),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+/// Transcriptions of the current session, optionally also appended to a
+/// per-day history file such as `~/.local/share/ndict/history/2026-10-14.txt`.
+pub struct SessionLog {
+    started: DateTime<Local>,
+    entries: Vec<Entry>,
+    history_dir: Option<PathBuf>,
+}
+
+impl SessionLog {
+    pub fn new(history_dir: Option<PathBuf>) -> Self {
+        Self {
+            started: Local::now(),
+            entries: Vec::new(),
+            history_dir,
+        }
+    }
+
+    /// Forget the previous session's transcriptions. The history is kept.
+    pub fn start_session(&mut self) {
+        self.started = Local::now();
+        self.entries.clear();
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, text: &str, language: &str) {
+        let entry = Entry {
+            time: Local::now(),
+            language: language.to_string(),
+            text: text.trim().to_string(),
+        };
+        if let Some(dir) = &self.history_dir {
+            if let Err(e) = append_history(dir, &entry) {
+                tracing::error!("Failed to write history in {}: {}", dir.display(), e);
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn export(&self, format: ExportFormat) -> String {
+        let mut out = String::new();
+        match format {
+            ExportFormat::Text => {
+                for entry in &self.entries {
+                    out.push_str(&history_line(entry));
+                    out.push('\n');
+                }
+            }
+            ExportFormat::Markdown => {
+                out.push_str(&format!(
+                    "# Dictation session {}\n\n",
+                    self.started.format("%Y-%m-%d %H:%M")
+                ));
+                for entry in &self.entries {
+                    out.push_str(&format!(
+                        "- **{}** _({})_ {}\n",
+                        entry.time.format("%H:%M:%S"),
+                        entry.language,
+                        entry.text
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    /// Write the session export to `path`, creating parent directories.
+    pub fn export_to(&self, path: &Path, format: ExportFormat) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.export(format))
+            .map_err(|e| anyhow::anyhow!("Failed to write export {}: {}", path.display(), e))
+    }
+
+    /// Where `export_to` writes when no path is given, e.g.
+    /// `~/.local/share/ndict/exports/session-20261014-093012.md`.
+    pub fn default_export_path(&self, format: ExportFormat) -> PathBuf {
+        data_dir().join("exports").join(format!(
+            "session-{}.{}",
+            self.started.format("%Y%m%d-%H%M%S"),
+            format.extension()
+        ))
+    }
+}
+
+fn history_line(entry: &Entry) -> String {
+    format!(
+        "[{}] [{}] {}",
+        entry.time.format("%Y-%m-%d %H:%M:%S"),
+        entry.language,
+        entry.text
+    )
+}
+
+fn append_history(dir: &Path, entry: &Entry) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.txt", entry.time.format("%Y-%m-%d")));
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", history_line(entry))?;
+    Ok(())
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ndict")
+}
+
+/// Default history location: `~/.local/share/ndict/history`.
+pub fn default_history_dir() -> PathBuf {
+    data_dir().join("history")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_appends_to_daily_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = SessionLog::new(Some(dir.path().to_path_buf()));
+        log.record(" hello world ", "en");
+        log.record("hola", "es");
+
+        let day = log.entries()[0].time.format("%Y-%m-%d").to_string();
+        let contents = std::fs::read_to_string(dir.path().join(format!("{}.txt", day))).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("] [en] hello world"));
+        assert!(lines[1].ends_with("] [es] hola"));
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let mut log = SessionLog::new(None);
+        log.record("First line.", "en");
+
+        let text = log.export(ExportFormat::Text);
+        assert!(text.ends_with("] [en] First line.\n"));
+
+        let markdown = log.export(ExportFormat::Markdown);
+        assert!(markdown.starts_with("# Dictation session "));
+        assert!(markdown.ends_with("_(en)_ First line.\n"));
+
+        log.start_session();
+        assert!(log.export(ExportFormat::Text).is_empty());
+    }
+
+    #[test]
+    fn test_parse_export_format() {
+        assert_eq!(ExportFormat::parse("md").unwrap(), ExportFormat::Markdown);
+        assert_eq!(ExportFormat::parse("text").unwrap(), ExportFormat::Text);
+        assert!(ExportFormat::parse("pdf").is_err());
+    }
+}
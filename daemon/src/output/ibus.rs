@@ -0,0 +1,574 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tracing::{debug, info};
+
+use crate::output::chord::KeyChord;
+use crate::output::dbus::{Connection, Message, MessageType, Writer};
+use crate::output::editing::KeyAction;
+use crate::output::sink::{Delivery, OutputSink};
+
+/// Name of the input method to select while dictating, e.g. with
+/// `ibus engine ndict`.
+pub const ENGINE_NAME: &str = "ndict";
+
+/// Failed sends in a row after which an `IbusSink` asks to be reconnected.
+const MAX_FAILURES: u32 = 3;
+
+const IBUS_SERVICE: &str = "org.freedesktop.IBus";
+const IBUS_PATH: &str = "/org/freedesktop/IBus";
+const FACTORY_INTERFACE: &str = "org.freedesktop.IBus.Factory";
+const ENGINE_INTERFACE: &str = "org.freedesktop.IBus.Engine";
+const ENGINE_PATH: &str = "/org/freedesktop/IBus/Engine/Ndict";
+
+const COMPONENT_SIGNATURE: &str = "(sa{sv}ssssssssavav)";
+const ENGINE_DESC_SIGNATURE: &str = "(sa{sv}ssssssssussssssss)";
+const TEXT_SIGNATURE: &str = "(sa{sv}sv)";
+const ATTR_LIST_SIGNATURE: &str = "(sa{sv}av)";
+
+/// Set in a key event state for the release.
+const RELEASE_MASK: u32 = 1 << 30;
+
+/// IBus keyval and evdev keycode of a key `KeyChord` or backspace presses.
+fn key_codes(keysym: &str) -> Option<(u32, u32)> {
+    match keysym {
+        "BackSpace" => Some((0xff08, 14)),
+        "Return" => Some((0xff0d, 28)),
+        "Tab" => Some((0xff09, 15)),
+        "space" => Some((0x20, 57)),
+        "Escape" => Some((0xff1b, 1)),
+        _ => None,
+    }
+}
+
+/// The engines IBus created from the ndict input method, one per input
+/// context it was selected in.
+#[derive(Debug, Default)]
+struct Engines {
+    created: Vec<String>,
+    focused: Option<String>,
+    next_id: u32,
+}
+
+impl Engines {
+    /// The engine text goes to: the focused one, else the newest.
+    fn target(&self) -> Option<&str> {
+        self.focused
+            .as_deref()
+            .or(self.created.last().map(String::as_str))
+    }
+
+    /// Answer a call from IBus, returning the reply.
+    fn handle(&mut self, message: &Message) -> Option<Message> {
+        if message.kind != MessageType::MethodCall {
+            return None;
+        }
+        if message.is_call(FACTORY_INTERFACE, "CreateEngine") {
+            self.next_id += 1;
+            let path = format!("{}/{}", ENGINE_PATH, self.next_id);
+            info!("IBus created input method engine {}", path);
+            self.created.push(path.clone());
+            let mut body = Writer::new();
+            body.object_path(&path);
+            return Some(Message::method_return(message).with_body("o", body));
+        }
+
+        let path = message.path.as_deref().unwrap_or_default();
+        debug!("IBus called {:?} on {}", message.member, path);
+        match message.member.as_deref() {
+            // Keys the user types pass through untouched
+            Some("ProcessKeyEvent") => {
+                let mut body = Writer::new();
+                body.boolean(false);
+                return Some(Message::method_return(message).with_body("b", body));
+            }
+            Some("FocusIn" | "FocusInId") => self.focused = Some(path.to_string()),
+            Some("FocusOut" | "FocusOutId") if self.focused.as_deref() == Some(path) => {
+                self.focused = None;
+            }
+            Some("Destroy") => {
+                self.created.retain(|created| created != path);
+                if self.focused.as_deref() == Some(path) {
+                    self.focused = None;
+                }
+            }
+            _ => {}
+        }
+        Some(Message::method_return(message))
+    }
+}
+
+/// Commits text through IBus as the "ndict" input method, instead of
+/// simulating key presses. Works where typed keys go wrong, such as in
+/// applications with their own input handling, but only while the input
+/// method is selected.
+///
+/// Fcitx5 offers no way for another program to commit text, so there the
+/// keyboard sink is the way to go.
+pub struct IbusSink {
+    connection: Arc<Connection>,
+    engines: Arc<Mutex<Engines>>,
+    listener: Option<JoinHandle<()>>,
+    failures: u32,
+}
+
+impl IbusSink {
+    /// Register the input method with the IBus daemon of the session.
+    pub fn connect() -> Result<Self> {
+        Self::connect_to(&ibus_address()?)
+    }
+
+    /// Register the input method with the IBus daemon at `address`.
+    pub fn connect_to(address: &str) -> Result<Self> {
+        let connection = Arc::new(Connection::open(address)?);
+        let engines = Arc::new(Mutex::new(Engines::default()));
+
+        let mut body = Writer::new();
+        body.variant(COMPONENT_SIGNATURE, write_component);
+        let register =
+            Message::method_call(IBUS_SERVICE, IBUS_PATH, IBUS_SERVICE, "RegisterComponent")
+                .with_body("v", body);
+        // IBus may create an engine straight away if ndict was selected
+        connection.call(&register, |call| answer(&connection, &engines, &call))?;
+
+        let listener = {
+            let connection = Arc::clone(&connection);
+            let engines = Arc::clone(&engines);
+            std::thread::spawn(move || {
+                while let Ok(call) = connection.receive() {
+                    answer(&connection, &engines, &call);
+                }
+                info!("IBus connection closed");
+            })
+        };
+
+        info!(
+            "Registered the '{}' input method with IBus, select it to dictate",
+            ENGINE_NAME
+        );
+        Ok(Self {
+            connection,
+            engines,
+            listener: Some(listener),
+            failures: 0,
+        })
+    }
+
+    /// The engine to send to, or why there is none.
+    fn engine(&self) -> Result<String> {
+        let engines = self.engines.lock().unwrap_or_else(|e| e.into_inner());
+        engines.target().map(str::to_string).ok_or_else(|| {
+            anyhow::anyhow!(
+                "The '{}' input method is not in use, select it, e.g. with `ibus engine {}`",
+                ENGINE_NAME,
+                ENGINE_NAME
+            )
+        })
+    }
+
+    fn commit(&mut self, text: &str) -> Result<()> {
+        let engine = self.engine()?;
+        let mut body = Writer::new();
+        body.variant(TEXT_SIGNATURE, |w| write_text(w, text));
+        self.send(&Message::signal(&engine, ENGINE_INTERFACE, "CommitText").with_body("v", body))
+    }
+
+    fn press(&mut self, chord: &KeyChord) -> Result<()> {
+        let state = chord
+            .modifiers()
+            .iter()
+            .fold(0, |state, modifier| state | modifier.ibus_mask());
+        self.forward_key(chord.keysym(), state)
+    }
+
+    /// Send a press and a release of `keysym` to the application.
+    fn forward_key(&mut self, keysym: &str, state: u32) -> Result<()> {
+        let engine = self.engine()?;
+        let (keyval, keycode) =
+            key_codes(keysym).ok_or_else(|| anyhow::anyhow!("IBus cannot press {}", keysym))?;
+        for state in [state, state | RELEASE_MASK] {
+            let mut body = Writer::new();
+            body.u32(keyval);
+            body.u32(keycode);
+            body.u32(state);
+            let event = Message::signal(&engine, ENGINE_INTERFACE, "ForwardKeyEvent")
+                .with_body("uuu", body);
+            self.send(&event)?;
+        }
+        Ok(())
+    }
+
+    /// Send `message`, counting failures towards `needs_reconnect`.
+    fn send(&mut self, message: &Message) -> Result<()> {
+        match self.connection.send(message) {
+            Ok(_) => {
+                self.failures = 0;
+                Ok(())
+            }
+            Err(e) => {
+                self.failures += 1;
+                Err(e.context("Failed to send to IBus"))
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.connection.shutdown();
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+    }
+}
+
+impl Drop for IbusSink {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl OutputSink for IbusSink {
+    fn name(&self) -> &str {
+        "ibus"
+    }
+
+    fn write<'a>(&'a mut self, delivery: Delivery<'a>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.commit(delivery.text) })
+    }
+
+    fn types(&self) -> bool {
+        true
+    }
+
+    fn edit<'a>(&'a mut self, action: &'a KeyAction) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            match action {
+                KeyAction::Backspace(count) => {
+                    for _ in 0..*count {
+                        self.forward_key("BackSpace", 0)?;
+                    }
+                    Ok(())
+                }
+                KeyAction::Type(text) => self.commit(text),
+                KeyAction::Press(chord) => self.press(chord),
+            }
+        })
+    }
+
+    fn needs_reconnect(&self) -> bool {
+        self.failures >= MAX_FAILURES
+    }
+
+    fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        // IBus listens somewhere new after a restart
+        self.close();
+        *self = Self::connect()?;
+        Ok(())
+    }
+}
+
+/// Reply to a call IBus made.
+fn answer(connection: &Connection, engines: &Mutex<Engines>, call: &Message) {
+    let reply = engines
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .handle(call);
+    if let Some(reply) = reply {
+        if let Err(e) = connection.send(&reply) {
+            debug!("Failed to answer IBus: {}", e);
+        }
+    }
+}
+
+/// Where the IBus daemon of the session listens: `IBUS_ADDRESS`, else
+/// what `ibus address` prints.
+fn ibus_address() -> Result<String> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        if !address.is_empty() {
+            return Ok(address);
+        }
+    }
+    let output = std::process::Command::new("ibus")
+        .arg("address")
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run `ibus address`, is IBus installed? {}", e))?;
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || address.is_empty() || address == "(null)" {
+        return Err(anyhow::anyhow!("IBus is not running"));
+    }
+    Ok(address)
+}
+
+/// The IBusComponent describing ndict and its one engine.
+fn write_component(w: &mut Writer) {
+    w.structure(|w| {
+        w.string("IBusComponent");
+        w.array(8, |_| {});
+        let version = env!("CARGO_PKG_VERSION");
+        // name, description, version, license, author, homepage, exec,
+        // textdomain
+        for field in [
+            "org.freedesktop.IBus.Ndict",
+            "Voice dictation",
+            version,
+            "",
+            "",
+            "",
+            "",
+            "",
+        ] {
+            w.string(field);
+        }
+        // Observed paths
+        w.array(1, |_| {});
+        w.array(1, |w| w.variant(ENGINE_DESC_SIGNATURE, write_engine_desc));
+    });
+}
+
+/// The IBusEngineDesc of the ndict engine. Layout "default" keeps the
+/// user's keyboard layout.
+fn write_engine_desc(w: &mut Writer) {
+    w.structure(|w| {
+        w.string("IBusEngineDesc");
+        w.array(8, |_| {});
+        // name, longname, description, language, license, author, icon,
+        // layout
+        for field in [
+            ENGINE_NAME,
+            "ndict",
+            "Types what ndict transcribes",
+            "other",
+            "",
+            "",
+            "audio-input-microphone",
+            "default",
+        ] {
+            w.string(field);
+        }
+        // Rank
+        w.u32(0);
+        // hotkeys, symbol, setup, layout_variant, layout_option, version,
+        // textdomain, icon_prop_key
+        for field in ["", "nd", "", "", "", env!("CARGO_PKG_VERSION"), "", ""] {
+            w.string(field);
+        }
+    });
+}
+
+/// An IBusText of `text` without attributes.
+fn write_text(w: &mut Writer, text: &str) {
+    w.structure(|w| {
+        w.string("IBusText");
+        w.array(8, |_| {});
+        w.string(text);
+        w.variant(ATTR_LIST_SIGNATURE, |w| {
+            w.structure(|w| {
+                w.string("IBusAttrList");
+                w.array(8, |_| {});
+                w.array(1, |_| {});
+            })
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::dbus::read_message;
+    use std::io::{Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    fn call(path: &str, interface: &str, member: &str) -> Message {
+        let mut call = Message::method_call("org.freedesktop.IBus.Ndict", path, interface, member);
+        call.serial = 7;
+        call.sender = Some(":1.0".to_string());
+        call
+    }
+
+    #[test]
+    fn test_engines_follow_focus() {
+        let mut engines = Engines::default();
+        assert_eq!(engines.target(), None);
+
+        let reply = engines
+            .handle(&call(
+                "/org/freedesktop/IBus/Factory",
+                FACTORY_INTERFACE,
+                "CreateEngine",
+            ))
+            .unwrap();
+        assert_eq!(reply.kind, MessageType::MethodReturn);
+        assert_eq!(reply.reply_serial, Some(7));
+        assert_eq!(reply.destination.as_deref(), Some(":1.0"));
+        let first = reply.body().string().unwrap();
+        assert_eq!(engines.target(), Some(first.as_str()));
+
+        let second = engines
+            .handle(&call(
+                "/org/freedesktop/IBus/Factory",
+                FACTORY_INTERFACE,
+                "CreateEngine",
+            ))
+            .unwrap()
+            .body()
+            .string()
+            .unwrap();
+        assert_ne!(first, second);
+        assert_eq!(engines.target(), Some(second.as_str()));
+
+        engines.handle(&call(&first, ENGINE_INTERFACE, "FocusIn"));
+        assert_eq!(engines.target(), Some(first.as_str()));
+        engines.handle(&call(&first, ENGINE_INTERFACE, "FocusOut"));
+        assert_eq!(engines.target(), Some(second.as_str()));
+        engines.handle(&call(&second, "org.freedesktop.IBus.Service", "Destroy"));
+        assert_eq!(engines.target(), Some(first.as_str()));
+    }
+
+    #[test]
+    fn test_key_events_pass_through() {
+        let mut engines = Engines::default();
+        let reply = engines
+            .handle(&call(ENGINE_PATH, ENGINE_INTERFACE, "ProcessKeyEvent"))
+            .unwrap();
+        assert_eq!(reply.signature, "b");
+        assert!(!reply.body().boolean().unwrap());
+
+        let mut signal = Message::signal(ENGINE_PATH, ENGINE_INTERFACE, "FocusIn");
+        signal.serial = 3;
+        assert!(engines.handle(&signal).is_none());
+    }
+
+    #[test]
+    fn test_key_codes() {
+        for chord in ["enter", "tab", "space", "escape"] {
+            let chord = KeyChord::parse(chord).unwrap();
+            assert!(key_codes(chord.keysym()).is_some());
+        }
+        assert_eq!(key_codes("BackSpace"), Some((0xff08, 14)));
+    }
+
+    /// Answer the bus side of `Connection::open` and `RegisterComponent`.
+    fn serve_registration(bus: &mut UnixStream) -> Message {
+        let mut auth = Vec::new();
+        let mut byte = [0u8; 1];
+        while !auth.ends_with(b"\r\n") {
+            bus.read_exact(&mut byte).unwrap();
+            auth.push(byte[0]);
+        }
+        bus.write_all(b"OK 0123456789abcdef\r\n").unwrap();
+        let mut begin = [0u8; 7];
+        bus.read_exact(&mut begin).unwrap();
+
+        let hello = read_message(bus).unwrap();
+        let mut name = Writer::new();
+        name.string(":1.5");
+        let reply = Message::method_return(&hello).with_body("s", name);
+        bus.write_all(&reply.encode(1)).unwrap();
+
+        let register = read_message(bus).unwrap();
+        assert!(register.is_call(IBUS_SERVICE, "RegisterComponent"));
+        bus.write_all(&Message::method_return(&register).encode(2))
+            .unwrap();
+        register
+    }
+
+    #[tokio::test]
+    async fn test_commits_through_fake_ibus() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("ibus");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let ibus = std::thread::spawn(move || {
+            let (mut bus, _) = listener.accept().unwrap();
+            let register = serve_registration(&mut bus);
+            let mut component = register.body();
+            assert_eq!(component.signature().unwrap(), COMPONENT_SIGNATURE);
+            component.structure();
+            assert_eq!(component.string().unwrap(), "IBusComponent");
+
+            let mut create = Message::method_call(
+                ":1.5",
+                "/org/freedesktop/IBus/Factory",
+                FACTORY_INTERFACE,
+                "CreateEngine",
+            );
+            let mut name = Writer::new();
+            name.string(ENGINE_NAME);
+            create = create.with_body("s", name);
+            bus.write_all(&create.encode(3)).unwrap();
+            let engine = read_message(&mut bus).unwrap().body().string().unwrap();
+
+            // Dictated text, then a backspace press and release
+            let commit = read_message(&mut bus).unwrap();
+            assert_eq!(commit.path.as_deref(), Some(engine.as_str()));
+            assert_eq!(commit.member.as_deref(), Some("CommitText"));
+            let mut text = commit.body();
+            assert_eq!(text.signature().unwrap(), TEXT_SIGNATURE);
+            text.structure();
+            assert_eq!(text.string().unwrap(), "IBusText");
+            text.skip_array(8).unwrap();
+            let committed = text.string().unwrap();
+
+            let mut states = Vec::new();
+            for _ in 0..2 {
+                let key = read_message(&mut bus).unwrap();
+                assert_eq!(key.member.as_deref(), Some("ForwardKeyEvent"));
+                let mut args = key.body();
+                assert_eq!(args.u32().unwrap(), 0xff08);
+                assert_eq!(args.u32().unwrap(), 14);
+                states.push(args.u32().unwrap());
+            }
+            (committed, states)
+        });
+
+        let address = format!("unix:path={}", socket.display());
+        let mut sink = IbusSink::connect_to(&address).unwrap();
+        // The engine is created once IBus gets to it
+        for _ in 0..100 {
+            if sink.engine().is_ok() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let delivery = Delivery {
+            text: "héllo wörld",
+            language: "de",
+            confidence: None,
+        };
+        sink.write(delivery).await.unwrap();
+        sink.edit(&KeyAction::Backspace(1)).await.unwrap();
+
+        let (committed, states) = ibus.join().unwrap();
+        assert_eq!(committed, "héllo wörld");
+        assert_eq!(states, vec![0, RELEASE_MASK]);
+        assert!(!sink.needs_reconnect());
+    }
+
+    #[tokio::test]
+    async fn test_write_without_engine_explains() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("ibus");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let ibus = std::thread::spawn(move || {
+            let (mut bus, _) = listener.accept().unwrap();
+            serve_registration(&mut bus);
+            bus
+        });
+
+        let address = format!("unix:path={}", socket.display());
+        let mut sink = IbusSink::connect_to(&address).unwrap();
+        let _bus = ibus.join().unwrap();
+        let delivery = Delivery {
+            text: "hello",
+            language: "en",
+            confidence: None,
+        };
+        let error = sink.write(delivery).await.unwrap_err();
+        assert!(error.to_string().contains("ibus engine ndict"));
+        assert!(!sink.needs_reconnect());
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+
+use crate::config::OutputConfig;
+
+/// What goes between two typed utterances, see `output.separator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Space,
+    Newline,
+    None,
+}
+
+impl Separator {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "space" => Ok(Self::Space),
+            "newline" => Ok(Self::Newline),
+            "none" => Ok(Self::None),
+            other => Err(anyhow::anyhow!(
+                "Unknown output.separator '{}', expected \"space\", \"newline\" or \"none\"",
+                other
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Space => " ",
+            Self::Newline => "\n",
+            Self::None => "",
+        }
+    }
+}
+
+/// Turns each utterance into the text to type so that consecutive
+/// utterances don't run together.
+#[derive(Debug, Clone, Copy)]
+pub struct UtteranceJoiner {
+    separator: Separator,
+    trailing_space: bool,
+}
+
+impl UtteranceJoiner {
+    pub fn from_config(config: &OutputConfig) -> Result<Self> {
+        Ok(Self {
+            separator: Separator::parse(&config.separator)?,
+            trailing_space: config.trailing_space,
+        })
+    }
+
+    /// `text` as typed after `previous`, the last text typed this session.
+    /// No separator is added at the start of a session or after text that
+    /// already ends in whitespace.
+    pub fn join(&self, previous: Option<&str>, text: &str) -> String {
+        let mut joined = String::with_capacity(text.len() + 2);
+        if previous.is_some_and(|previous| !previous.ends_with(char::is_whitespace)) {
+            joined.push_str(self.separator.as_str());
+        }
+        joined.push_str(text);
+        if self.trailing_space {
+            joined.push(' ');
+        }
+        joined
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joiner(separator: &str, trailing_space: bool) -> UtteranceJoiner {
+        let config = OutputConfig {
+            separator: separator.to_string(),
+            trailing_space,
+            ..OutputConfig::default()
+        };
+        UtteranceJoiner::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_separator_between_utterances() {
+        let space = joiner("space", false);
+        assert_eq!(space.join(None, "Hello."), "Hello.");
+        assert_eq!(space.join(Some("Hello."), "Bye."), " Bye.");
+
+        let newline = joiner("newline", false);
+        assert_eq!(newline.join(Some("Hello."), "Bye."), "\nBye.");
+
+        let none = joiner("none", false);
+        assert_eq!(none.join(Some("Hello."), "Bye."), "Bye.");
+    }
+
+    #[test]
+    fn test_trailing_space() {
+        let space = joiner("space", true);
+        assert_eq!(space.join(None, "Hello."), "Hello. ");
+        assert_eq!(space.join(Some("Hello. "), "Bye."), "Bye. ");
+    }
+
+    #[test]
+    fn test_unknown_separator() {
+        assert!(Separator::parse("tab").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc;
+
+use crate::output::sink::{Delivery, OutputSink};
+
+/// One line of `output.json_output`.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    /// RFC 3339 local time.
+    time: String,
+    language: &'a str,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<f32>,
+}
+
+/// Writes transcriptions as line-delimited JSON to the daemon's stdout or
+/// a file or named pipe, for programs that consume dictation without
+/// keyboard emulation:
+///
+/// `{"time":"2026-10-15T09:30:00+02:00","language":"en","text":"Hello."}`
+///
+/// Lines are written on a thread of their own, since opening a named pipe
+/// blocks until a reader opens it. When the reader goes away the pipe is
+/// opened again for the next line.
+pub struct JsonSink {
+    target: String,
+    lines: mpsc::Sender<String>,
+}
+
+impl JsonSink {
+    /// A sink writing to `target`: "stdout" or a path.
+    pub fn open(target: &str) -> Self {
+        let (lines, receiver) = mpsc::channel();
+        let writer_target = target.to_string();
+        std::thread::spawn(move || write_lines(&writer_target, receiver));
+        tracing::info!("Writing JSON transcriptions to {}", target);
+        Self {
+            target: target.to_string(),
+            lines,
+        }
+    }
+
+    pub fn write_line(&self, text: &str, language: &str, confidence: Option<f32>) -> Result<()> {
+        let line = to_line(text, language, confidence)?;
+        self.lines
+            .send(line)
+            .map_err(|_| anyhow::anyhow!("JSON writer for {} stopped", self.target))
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn write<'a>(&'a mut self, delivery: Delivery<'a>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(
+            async move { self.write_line(delivery.text, delivery.language, delivery.confidence) },
+        )
+    }
+}
+
+fn to_line(text: &str, language: &str, confidence: Option<f32>) -> Result<String> {
+    Ok(serde_json::to_string(&Record {
+        time: chrono::Local::now().to_rfc3339(),
+        language,
+        text: text.trim(),
+        confidence,
+    })?)
+}
+
+fn open(target: &str) -> std::io::Result<Box<dyn Write>> {
+    if target == "stdout" {
+        return Ok(Box::new(std::io::stdout()));
+    }
+    let file = OpenOptions::new().create(true).append(true).open(target)?;
+    Ok(Box::new(file))
+}
+
+/// Write each line received to `target`, opening it on the first line and
+/// again after a write fails.
+fn write_lines(target: &str, receiver: mpsc::Receiver<String>) {
+    let mut output: Option<Box<dyn Write>> = None;
+    for line in receiver {
+        if output.is_none() {
+            match open(target) {
+                Ok(opened) => output = Some(opened),
+                Err(e) => {
+                    tracing::error!("Failed to open JSON output {}: {}", target, e);
+                    continue;
+                }
+            }
+        }
+        if let Some(writer) = output.as_mut() {
+            if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+                tracing::warn!("Failed to write JSON output {}: {}", target, e);
+                output = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_format() {
+        let line = to_line(" Hello \"there\". ", "en", Some(0.5)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["text"], "Hello \"there\".");
+        assert_eq!(value["language"], "en");
+        assert_eq!(value["confidence"], 0.5);
+        assert!(value["time"].is_string());
+
+        let line = to_line("Hi", "de", None).unwrap();
+        assert!(!line.contains("confidence"));
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn test_writes_lines_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dictation.jsonl");
+        let sink = JsonSink::open(&path.to_string_lossy());
+        sink.write_line("One.", "en", None).unwrap();
+        sink.write_line("Two.", "en", None).unwrap();
+        drop(sink);
+
+        // The writer thread finishes once the sink is dropped
+        for _ in 0..100 {
+            let contents = std::fs::read_to_string(&path).unwrap_or_default();
+            if contents.lines().count() == 2 {
+                assert!(contents.lines().last().unwrap().contains("\"Two.\""));
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("JSON lines were not written");
+    }
+}
@@ -1,24 +1,160 @@
 use anyhow::Result;
+use futures_util::future::BoxFuture;
+use thiserror::Error;
+use tokio::time::Duration;
 use tracing::info;
 use wrtype::WrtypeClient;
 
+use crate::config::OutputConfig;
+use crate::output::chord::KeyChord;
+use crate::output::editing::KeyAction;
+use crate::output::sink::{Delivery, OutputSink};
+use crate::output::typing::{KeyDelay, TypingMode};
+use crate::output::x11::XdotoolKeyboard;
+
+/// Failed writes in a row after which a `KeyboardSink` asks to be
+/// reconnected.
+const MAX_FAILURES: u32 = 3;
+
+/// Why a keyboard could not type.
+#[derive(Debug, Error)]
+pub enum KeyboardError {
+    /// The keyboard could not be set up, e.g. without a display.
+    #[error("Keyboard unavailable: {0}")]
+    Unavailable(String),
+    /// Key events could not be sent, e.g. because the compositor went away.
+    #[error("Failed to send key events: {0}")]
+    Send(String),
+}
+
+/// Which keyboard emulation types, see `output.backend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardBackend {
+    Wayland,
+    X11,
+}
+
+impl KeyboardBackend {
+    /// The backend for an `output.backend` value. "auto" picks Wayland
+    /// when `WAYLAND_DISPLAY` is set, X11 when only `DISPLAY` is.
+    pub fn resolve(name: &str) -> Result<Self> {
+        match name {
+            "wayland" => Ok(Self::Wayland),
+            "x11" => Ok(Self::X11),
+            "auto" => Ok(Self::detect(
+                std::env::var_os("WAYLAND_DISPLAY").is_some(),
+                std::env::var_os("DISPLAY").is_some(),
+            )),
+            other => Err(anyhow::anyhow!(
+                "Unknown output.backend '{}', expected \"auto\", \"wayland\" or \"x11\"",
+                other
+            )),
+        }
+    }
+
+    fn detect(wayland: bool, x11: bool) -> Self {
+        if !wayland && x11 {
+            Self::X11
+        } else {
+            Self::Wayland
+        }
+    }
+}
+
+/// The keyboard a `KeyboardSink` types with.
+pub enum Keyboard {
+    Wayland(Box<VirtualKeyboard>),
+    X11(XdotoolKeyboard),
+}
+
+impl Keyboard {
+    pub fn new(backend: KeyboardBackend) -> Result<Self, KeyboardError> {
+        match backend {
+            KeyboardBackend::Wayland => Ok(Self::Wayland(Box::new(VirtualKeyboard::new()?))),
+            KeyboardBackend::X11 => Ok(Self::X11(XdotoolKeyboard::new()?)),
+        }
+    }
+
+    pub fn from_config(config: &OutputConfig) -> Result<Self> {
+        Ok(Self::new(KeyboardBackend::resolve(&config.backend)?)?)
+    }
+
+    pub fn backend(&self) -> KeyboardBackend {
+        match self {
+            Self::Wayland(_) => KeyboardBackend::Wayland,
+            Self::X11(_) => KeyboardBackend::X11,
+        }
+    }
+
+    /// Replace the keyboard with a new one on the same backend.
+    pub fn reconnect(&mut self) -> Result<(), KeyboardError> {
+        *self = Self::new(self.backend())?;
+        Ok(())
+    }
+
+    pub async fn type_text(&mut self, text: &str) -> Result<(), KeyboardError> {
+        match self {
+            Self::Wayland(keyboard) => keyboard.type_text(text).await,
+            Self::X11(keyboard) => keyboard.type_text(text).await,
+        }
+    }
+
+    pub async fn type_delayed(
+        &mut self,
+        text: &str,
+        delay: &mut KeyDelay,
+    ) -> Result<(), KeyboardError> {
+        match self {
+            Self::Wayland(keyboard) => keyboard.type_delayed(text, delay).await,
+            Self::X11(keyboard) => keyboard.type_delayed(text, delay).await,
+        }
+    }
+
+    pub async fn backspace(&mut self, count: usize) -> Result<(), KeyboardError> {
+        match self {
+            Self::Wayland(keyboard) => keyboard.backspace(count).await,
+            Self::X11(keyboard) => keyboard.backspace(count).await,
+        }
+    }
+
+    pub async fn press(&mut self, chord: &KeyChord) -> Result<(), KeyboardError> {
+        match self {
+            Self::Wayland(keyboard) => keyboard.press(chord).await,
+            Self::X11(keyboard) => keyboard.press(chord).await,
+        }
+    }
+}
+
+/// Types text through the Wayland virtual keyboard protocol
+/// (`zwp_virtual_keyboard_v1`) of the user's session. The compositor routes
+/// the key events, so unlike /dev/uinput this needs no capability or input
+/// group membership; Sway, Hyprland, River and niri support it, GNOME does
+/// not. The keymap is wrtype's own rather than the user's XKB layout, so
+/// text comes out the same on QWERTY, AZERTY, QWERTZ or Dvorak.
 pub struct VirtualKeyboard {
     client: WrtypeClient,
 }
 
 impl VirtualKeyboard {
-    pub fn new() -> Result<Self> {
+    pub fn new() -> Result<Self, KeyboardError> {
         info!("Creating VirtualKeyboard using wrtype");
 
         // Initialize the Wayland virtual keyboard client
-        let client = WrtypeClient::new()
-            .map_err(|e| anyhow::anyhow!("Failed to create WrtypeClient: {:?}", e))?;
+        let client = WrtypeClient::new().map_err(|e| {
+            KeyboardError::Unavailable(format!(
+                "Failed to create WrtypeClient: {:?} (does the compositor support the virtual keyboard protocol?)",
+                e
+            ))
+        })?;
 
         info!("VirtualKeyboard created successfully");
         Ok(Self { client })
     }
 
-    pub async fn type_text(&mut self, text: &str) -> Result<()> {
+    /// Type `text`. Every character can be typed, ASCII symbols like ":",
+    /// "@" or "#" included: wrtype maps each one to its keysym in a keymap
+    /// it uploads, so there is no per-character table here.
+    pub async fn type_text(&mut self, text: &str) -> Result<(), KeyboardError> {
         info!("Typing text: '{}'", text);
 
         // Use block_in_place to allow blocking synchronous code in async context
@@ -32,9 +168,191 @@ impl VirtualKeyboard {
                 Err(e) => {
                     // Log the specific error from wrtype
                     info!("Error: {:?}", e);
-                    Err(anyhow::anyhow!("Failed to type text: {:?}", e))
+                    Err(KeyboardError::Send(format!("{:?}", e)))
                 }
             }
         })
     }
+
+    /// Type `text` a character at a time, pausing after each as `delay`
+    /// says.
+    pub async fn type_delayed(
+        &mut self,
+        text: &str,
+        delay: &mut KeyDelay,
+    ) -> Result<(), KeyboardError> {
+        info!("Typing text with key delays: '{}'", text);
+
+        let mut buf = [0u8; 4];
+        for c in text.chars() {
+            let key = c.encode_utf8(&mut buf);
+            tokio::task::block_in_place(|| {
+                self.client
+                    .type_text(key)
+                    .map_err(|e| KeyboardError::Send(format!("{:?}", e)))
+            })?;
+            tokio::time::sleep(delay.pause()).await;
+        }
+        Ok(())
+    }
+
+    /// Press backspace `count` times.
+    pub async fn backspace(&mut self, count: usize) -> Result<(), KeyboardError> {
+        info!("Pressing backspace {} times", count);
+
+        // U+0008 maps to the BackSpace keysym
+        let backspaces = "\u{8}".repeat(count);
+        tokio::task::block_in_place(|| {
+            self.client
+                .type_text(&backspaces)
+                .map_err(|e| KeyboardError::Send(format!("{:?}", e)))
+        })
+    }
+
+    /// Press `chord`. Chords with modifiers go through `wtype`, as wrtype
+    /// only types characters.
+    pub async fn press(&mut self, chord: &KeyChord) -> Result<(), KeyboardError> {
+        info!("Pressing {:?}", chord);
+        if let Some(c) = chord.as_char() {
+            return self.type_text(c.encode_utf8(&mut [0u8; 4])).await;
+        }
+
+        let mut args = Vec::new();
+        for modifier in chord.modifiers() {
+            args.extend(["-M", modifier.wtype_name()]);
+        }
+        args.extend(["-k", chord.keysym()]);
+        for modifier in chord.modifiers().iter().rev() {
+            args.extend(["-m", modifier.wtype_name()]);
+        }
+        let status = tokio::process::Command::new("wtype")
+            .args(&args)
+            .status()
+            .await
+            .map_err(|e| KeyboardError::Send(format!("Failed to run wtype: {}", e)))?;
+        if !status.success() {
+            return Err(KeyboardError::Send(format!("wtype exited with {}", status)));
+        }
+        Ok(())
+    }
+}
+
+/// The sink typing into the focused window, by key events or, in
+/// `typing_mode = "paste"`, through the clipboard.
+pub struct KeyboardSink {
+    keyboard: Keyboard,
+    mode: TypingMode,
+    /// Writes and edits that failed since the last that worked.
+    failures: u32,
+}
+
+impl KeyboardSink {
+    pub fn new(keyboard: Keyboard, mode: TypingMode) -> Result<Self> {
+        if matches!((&keyboard, &mode), (Keyboard::X11(_), TypingMode::Paste(_))) {
+            return Err(anyhow::anyhow!(
+                "typing_mode = \"paste\" needs a Wayland session"
+            ));
+        }
+        Ok(Self {
+            keyboard,
+            mode,
+            failures: 0,
+        })
+    }
+
+    /// Count `result` towards `needs_reconnect`.
+    fn tally(&mut self, result: Result<(), KeyboardError>) -> Result<()> {
+        match result {
+            Ok(()) => self.failures = 0,
+            Err(_) => self.failures += 1,
+        }
+        Ok(result?)
+    }
+}
+
+impl OutputSink for KeyboardSink {
+    fn name(&self) -> &str {
+        match self.mode {
+            TypingMode::Paste(_) => "clipboard",
+            _ => "keyboard",
+        }
+    }
+
+    fn write<'a>(&'a mut self, delivery: Delivery<'a>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let result = match &mut self.mode {
+                TypingMode::Instant => self.keyboard.type_text(delivery.text).await,
+                TypingMode::Delayed(delay) => {
+                    self.keyboard.type_delayed(delivery.text, delay).await
+                }
+                TypingMode::Paste(paster) => match paster.paste(delivery.text).await {
+                    Ok(()) => Ok(()),
+                    Err(e) => {
+                        tracing::warn!("Paste failed, typing instead: {}", e);
+                        self.keyboard.type_text(delivery.text).await
+                    }
+                },
+            };
+            self.tally(result)
+        })
+    }
+
+    fn expected_duration(&self, text: &str) -> Duration {
+        match &self.mode {
+            TypingMode::Delayed(delay) => delay.longest(text),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn types(&self) -> bool {
+        true
+    }
+
+    fn edit<'a>(&'a mut self, action: &'a KeyAction) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let result = match action {
+                KeyAction::Backspace(count) => self.keyboard.backspace(*count).await,
+                KeyAction::Type(text) => self.keyboard.type_text(text).await,
+                KeyAction::Press(chord) => self.keyboard.press(chord).await,
+            };
+            self.tally(result)
+        })
+    }
+
+    fn needs_reconnect(&self) -> bool {
+        self.failures >= MAX_FAILURES
+    }
+
+    fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        self.keyboard.reconnect()?;
+        self.failures = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_detection() {
+        assert_eq!(
+            KeyboardBackend::detect(true, true),
+            KeyboardBackend::Wayland
+        );
+        assert_eq!(KeyboardBackend::detect(false, true), KeyboardBackend::X11);
+        assert_eq!(
+            KeyboardBackend::detect(false, false),
+            KeyboardBackend::Wayland
+        );
+        assert_eq!(
+            KeyboardBackend::resolve("x11").unwrap(),
+            KeyboardBackend::X11
+        );
+        assert!(KeyboardBackend::resolve("uinput").is_err());
+    }
 }
@@ -1,3 +1,18 @@
+pub mod chord;
+pub mod dbus;
+pub mod editing;
+pub mod history;
+pub mod ibus;
+pub mod joining;
+pub mod json;
 pub mod keyboard;
+pub mod paste;
+pub mod pause;
+pub mod sink;
+pub mod snippets;
+pub mod subtitles;
+pub mod transcript;
+pub mod typing;
+pub mod x11;
 
 pub use keyboard::VirtualKeyboard;
@@ -0,0 +1,96 @@
+/// A final transcription held back while paused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Held {
+    pub text: String,
+    pub confidence: Option<f32>,
+}
+
+/// Text transcribed while dictation is paused with
+/// `output.buffer_while_paused`, typed on Resume or Flush instead of into
+/// whatever window has focus meanwhile.
+#[derive(Debug, Default)]
+pub struct PauseBuffer {
+    holding: bool,
+    queued: Vec<Held>,
+}
+
+impl PauseBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_holding(&self) -> bool {
+        self.holding
+    }
+
+    /// Hold back text from now on.
+    pub fn hold(&mut self) {
+        self.holding = true;
+    }
+
+    /// Queue `text` while holding. Returns false, queuing nothing, when not
+    /// holding.
+    pub fn offer(&mut self, text: &str, confidence: Option<f32>) -> bool {
+        if !self.holding {
+            return false;
+        }
+        self.queued.push(Held {
+            text: text.to_string(),
+            confidence,
+        });
+        true
+    }
+
+    /// Take the text queued so far, in the order it was spoken. Once the
+    /// queue is empty and `resume` is set, stop holding; checking and
+    /// resuming together means no text slips in between.
+    pub fn take(&mut self, resume: bool) -> Vec<Held> {
+        if self.queued.is_empty() && resume {
+            self.holding = false;
+        }
+        std::mem::take(&mut self.queued)
+    }
+
+    /// Stop holding and drop what was queued, returning how much that was.
+    pub fn clear(&mut self) -> usize {
+        self.holding = false;
+        std::mem::take(&mut self.queued).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holds_text_until_resumed() {
+        let mut buffer = PauseBuffer::new();
+        assert!(!buffer.offer("Typed right away.", None));
+
+        buffer.hold();
+        assert!(buffer.offer("First.", Some(0.9)));
+        assert!(buffer.offer("Second.", None));
+        let held = buffer.take(true);
+        assert_eq!(held.len(), 2);
+        assert_eq!(held[0].text, "First.");
+        assert_eq!(held[0].confidence, Some(0.9));
+        // Still holding until a take finds the queue empty
+        assert!(buffer.is_holding());
+        assert!(buffer.take(true).is_empty());
+        assert!(!buffer.is_holding());
+    }
+
+    #[test]
+    fn test_flush_keeps_holding() {
+        let mut buffer = PauseBuffer::new();
+        buffer.hold();
+        buffer.offer("Later.", None);
+        assert_eq!(buffer.take(false).len(), 1);
+        assert!(buffer.take(false).is_empty());
+        assert!(buffer.is_holding());
+
+        buffer.offer("Dropped.", None);
+        assert_eq!(buffer.clear(), 1);
+        assert!(!buffer.is_holding());
+    }
+}
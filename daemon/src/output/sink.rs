@@ -0,0 +1,120 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use tokio::time::Duration;
+
+use crate::config::Config;
+use crate::output::editing::KeyAction;
+use crate::output::ibus::IbusSink;
+use crate::output::json::JsonSink;
+use crate::output::keyboard::{Keyboard, KeyboardSink};
+use crate::output::transcript::{self, TranscriptWriter};
+use crate::output::typing::TypingMode;
+
+/// One final transcription as handed to a sink.
+#[derive(Debug, Clone, Copy)]
+pub struct Delivery<'a> {
+    pub text: &'a str,
+    pub language: &'a str,
+    pub confidence: Option<f32>,
+}
+
+/// Somewhere final text goes: the keyboard, the clipboard, IBus, a
+/// transcript file or the JSON output.
+///
+/// The processing loops only hand text to the sinks through this trait, so
+/// adding one means implementing it and creating it in `create_sinks`.
+pub trait OutputSink: Send {
+    /// Short human readable name used in logs.
+    fn name(&self) -> &str;
+
+    fn write<'a>(&'a mut self, delivery: Delivery<'a>) -> BoxFuture<'a, Result<()>>;
+
+    /// How long writing `text` is expected to take on purpose, on top of
+    /// `timeouts.keyboard_timeout_seconds`.
+    fn expected_duration(&self, _text: &str) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Whether the sink types into the focused window. Typing sinks get
+    /// text joined to the previous utterance (`output.separator`) and carry
+    /// out spoken editing commands.
+    fn types(&self) -> bool {
+        false
+    }
+
+    /// Whether writes failed often enough in a row that the sink should be
+    /// set up again with `reconnect`.
+    fn needs_reconnect(&self) -> bool {
+        false
+    }
+
+    /// Count a write or edit that never finished towards `needs_reconnect`,
+    /// like one that failed.
+    fn record_failure(&mut self) {}
+
+    /// Set up the sink's connection again, e.g. a keyboard after the
+    /// compositor restarted.
+    fn reconnect(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Carry out an editing command; only typing sinks can.
+    fn edit<'a>(&'a mut self, _action: &'a KeyAction) -> BoxFuture<'a, Result<()>> {
+        let name = self.name().to_string();
+        Box::pin(async move { Err(anyhow::anyhow!("{} output cannot edit text", name)) })
+    }
+}
+
+/// Build the sinks enabled in `output.sinks`.
+pub fn create_sinks(config: &Config) -> Result<Vec<Box<dyn OutputSink>>> {
+    let mut sinks: Vec<Box<dyn OutputSink>> = Vec::new();
+    if config.writes_transcript() {
+        let path = config
+            .output
+            .transcript_file
+            .as_ref()
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(transcript::default_transcript_path);
+        sinks.push(Box::new(TranscriptWriter::new(path)?));
+    }
+    if let Some(target) = config.json_target() {
+        sinks.push(Box::new(JsonSink::open(target)));
+    }
+    if config.output.sinks.ibus {
+        sinks.push(Box::new(IbusSink::connect()?));
+    }
+    if config.types_text() {
+        // Settings first, so a typo is reported rather than a missing display
+        let mode = TypingMode::from_config(&config.output)?;
+        let keyboard = Keyboard::from_config(&config.output)?;
+        sinks.push(Box::new(KeyboardSink::new(keyboard, mode)?));
+    }
+    Ok(sinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_sinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.txt");
+        let mut config = Config::default();
+        config.output.transcript_file = Some(path.to_string_lossy().to_string());
+        config.output.sinks.json = Some(true);
+
+        let sinks = create_sinks(&config).unwrap();
+        let names: Vec<&str> = sinks.iter().map(|sink| sink.name()).collect();
+        assert_eq!(names, vec!["transcript", "json"]);
+        assert!(sinks.iter().all(|sink| !sink.types()));
+    }
+
+    #[test]
+    fn test_create_sinks_rejects_unknown_typing_mode() {
+        let mut config = Config::default();
+        config.output.typing_mode = "fast".to_string();
+        let error = create_sinks(&config).err().unwrap();
+        assert!(error.to_string().contains("typing_mode"));
+    }
+}
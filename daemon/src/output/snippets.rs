@@ -0,0 +1,100 @@
+use crate::config::Snippet;
+use crate::transcription::tokens::normalize;
+
+/// Spoken macros from `[[output.snippets]]`: saying a trigger phrase such as
+/// "insert signature" types the snippet's text, which may span lines.
+///
+/// Expansion happens after post-processing and the LLM pass, so the text is
+/// typed exactly as written in the config.
+#[derive(Debug, Clone, Default)]
+pub struct Snippets {
+    /// Lowercase trigger words and the text, longest trigger first.
+    entries: Vec<(Vec<String>, String)>,
+}
+
+impl Snippets {
+    pub fn new(snippets: &[Snippet]) -> Self {
+        let mut entries: Vec<(Vec<String>, String)> = snippets
+            .iter()
+            .map(|snippet| {
+                let words: Vec<String> =
+                    snippet.trigger.split_whitespace().map(normalize).collect();
+                (words, snippet.text.clone())
+            })
+            .filter(|(words, _)| !words.is_empty())
+            .collect();
+        entries.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+        Self { entries }
+    }
+
+    /// Replace trigger phrases in `text` with their snippets, ignoring case
+    /// and the punctuation Whisper puts around them.
+    pub fn expand(&self, text: &str) -> String {
+        if self.entries.is_empty() {
+            return text.to_string();
+        }
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let matched = self.entries.iter().find(|(words, _)| {
+                i + words.len() <= tokens.len()
+                    && words
+                        .iter()
+                        .zip(&tokens[i..])
+                        .all(|(word, token)| *word == normalize(token))
+            });
+            match matched {
+                Some((words, snippet)) => {
+                    tracing::info!("Expanding snippet '{}'", words.join(" "));
+                    out.push(snippet.clone());
+                    i += words.len();
+                }
+                None => {
+                    out.push(tokens[i].to_string());
+                    i += 1;
+                }
+            }
+        }
+        out.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippets() -> Snippets {
+        Snippets::new(&[
+            Snippet {
+                trigger: "insert signature".to_string(),
+                text: "Best regards,\nUneeb".to_string(),
+            },
+            Snippet {
+                trigger: "insert address".to_string(),
+                text: "1 Main St\nSpringfield".to_string(),
+            },
+        ])
+    }
+
+    #[test]
+    fn test_expand_whole_utterance() {
+        assert_eq!(
+            snippets().expand("Insert signature."),
+            "Best regards,\nUneeb"
+        );
+    }
+
+    #[test]
+    fn test_expand_inside_text() {
+        assert_eq!(
+            snippets().expand("Ship to insert address, please"),
+            "Ship to 1 Main St\nSpringfield please"
+        );
+        assert_eq!(
+            snippets().expand("insert the address"),
+            "insert the address"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::transcription::backend::{Transcription, WordTiming};
+use crate::transcription::post_process_transcription;
+
+/// Output format of `ndict transcribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Text,
+    Srt,
+    Vtt,
+    Json,
+}
+
+impl SubtitleFormat {
+    pub fn parse(format: &str) -> Result<Self> {
+        match format {
+            "text" | "txt" => Ok(Self::Text),
+            "srt" => Ok(Self::Srt),
+            "vtt" | "webvtt" => Ok(Self::Vtt),
+            "json" => Ok(Self::Json),
+            other => Err(anyhow::anyhow!(
+                "Unknown transcript format '{}'. Expected \"text\", \"srt\", \"vtt\" or \"json\"",
+                other
+            )),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// One subtitle: a piece of text and when it is spoken in the file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// A transcribed file, ready to be written in any `SubtitleFormat`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileTranscript {
+    pub language: Option<String>,
+    pub segments: Vec<Cue>,
+    /// Word timings, when the backend aligns words (`whisper.dtw_timestamps`).
+    pub words: Vec<Cue>,
+}
+
+impl FileTranscript {
+    /// Build the transcript from transcribed chunks, each with its start in
+    /// ms and its length in ms. Chunks whose backend reports no segments
+    /// become one cue each.
+    pub fn from_chunks(chunks: impl IntoIterator<Item = (u64, u64, Transcription)>) -> Self {
+        let mut transcript = Self::default();
+        for (offset_ms, length_ms, transcription) in chunks {
+            transcript.language = transcript.language.or(transcription.language);
+            if transcription.segments.is_empty() {
+                transcript.push_segment(offset_ms, offset_ms + length_ms, &transcription.text);
+            }
+            for segment in &transcription.segments {
+                transcript.push_segment(
+                    offset_ms + segment.start_ms,
+                    // Whisper can place the last segment's end in the padding
+                    offset_ms + segment.end_ms.min(length_ms),
+                    &segment.text,
+                );
+            }
+            transcript
+                .words
+                .extend(transcription.words.iter().map(|word| word_cue(offset_ms, word)));
+        }
+        transcript
+    }
+
+    fn push_segment(&mut self, start_ms: u64, end_ms: u64, text: &str) {
+        let text = post_process_transcription(text);
+        if !text.is_empty() {
+            self.segments.push(Cue {
+                start_ms,
+                end_ms: end_ms.max(start_ms),
+                text,
+            });
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|cue| cue.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn render(&self, format: SubtitleFormat) -> Result<String> {
+        let mut out = String::new();
+        match format {
+            SubtitleFormat::Text => {
+                out.push_str(&self.text());
+                out.push('\n');
+            }
+            SubtitleFormat::Srt => {
+                for (index, cue) in self.segments.iter().enumerate() {
+                    out.push_str(&format!(
+                        "{}\n{} --> {}\n{}\n\n",
+                        index + 1,
+                        timestamp(cue.start_ms, ','),
+                        timestamp(cue.end_ms, ','),
+                        cue.text
+                    ));
+                }
+            }
+            SubtitleFormat::Vtt => {
+                out.push_str("WEBVTT\n\n");
+                for cue in &self.segments {
+                    out.push_str(&format!(
+                        "{} --> {}\n{}\n\n",
+                        timestamp(cue.start_ms, '.'),
+                        timestamp(cue.end_ms, '.'),
+                        cue.text
+                    ));
+                }
+            }
+            SubtitleFormat::Json => {
+                out = serde_json::to_string_pretty(self)?;
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    }
+
+    /// Write the transcript to `path`, creating parent directories.
+    pub fn write_to(&self, path: &Path, format: SubtitleFormat) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.render(format)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+fn word_cue(offset_ms: u64, word: &WordTiming) -> Cue {
+    Cue {
+        start_ms: offset_ms + word.start_ms,
+        end_ms: offset_ms + word.end_ms,
+        text: word.text.clone(),
+    }
+}
+
+/// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for WebVTT.
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// Default output for a transcribed `input`: the same path with the
+/// format's extension, e.g. `talk.wav` becomes `talk.srt`.
+pub fn default_output_path(input: &Path, format: SubtitleFormat) -> PathBuf {
+    input.with_extension(format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::backend::SegmentTiming;
+
+    fn segment(text: &str, start_ms: u64, end_ms: u64) -> SegmentTiming {
+        SegmentTiming {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+        }
+    }
+
+    fn transcript() -> FileTranscript {
+        let first = Transcription {
+            text: "Hello there. General Kenobi.".to_string(),
+            language: Some("en".to_string()),
+            segments: vec![
+                segment(" Hello there.", 0, 1_500),
+                segment(" [Music]", 1_500, 2_000),
+                segment(" General Kenobi.", 2_000, 31_000),
+            ],
+            ..Transcription::default()
+        };
+        // A backend without segments
+        let second = Transcription {
+            text: "You are a bold one.".to_string(),
+            ..Transcription::default()
+        };
+        FileTranscript::from_chunks(vec![(0, 30_000, first), (30_000, 3_723_456, second)])
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(SubtitleFormat::parse("srt").unwrap(), SubtitleFormat::Srt);
+        assert_eq!(SubtitleFormat::parse("webvtt").unwrap(), SubtitleFormat::Vtt);
+        assert!(SubtitleFormat::parse("ass").is_err());
+    }
+
+    #[test]
+    fn test_from_chunks_offsets_and_cleans_segments() {
+        let transcript = transcript();
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(
+            transcript.segments,
+            vec![
+                Cue {
+                    start_ms: 0,
+                    end_ms: 1_500,
+                    text: "Hello there.".to_string()
+                },
+                Cue {
+                    start_ms: 2_000,
+                    end_ms: 30_000,
+                    text: "General Kenobi.".to_string()
+                },
+                Cue {
+                    start_ms: 30_000,
+                    end_ms: 3_753_456,
+                    text: "You are a bold one.".to_string()
+                },
+            ]
+        );
+        assert_eq!(transcript.text(), "Hello there. General Kenobi. You are a bold one.");
+    }
+
+    #[test]
+    fn test_render_srt_and_vtt() {
+        let transcript = transcript();
+        let srt = transcript.render(SubtitleFormat::Srt).unwrap();
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n2\n"));
+        assert!(srt.contains("3\n00:00:30,000 --> 01:02:33,456\nYou are a bold one.\n"));
+
+        let vtt = transcript.render(SubtitleFormat::Vtt).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello there.\n"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = transcript().render(SubtitleFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["language"], "en");
+        assert_eq!(value["segments"][1]["start_ms"], 2_000);
+        assert_eq!(value["segments"][1]["text"], "General Kenobi.");
+    }
+
+    #[test]
+    fn test_default_output_path() {
+        assert_eq!(
+            default_output_path(Path::new("/talks/keynote.wav"), SubtitleFormat::Vtt),
+            PathBuf::from("/talks/keynote.vtt")
+        );
+    }
+}
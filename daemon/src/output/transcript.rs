@@ -0,0 +1,99 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::output::sink::{Delivery, OutputSink};
+
+/// Appends finalized transcriptions to a plain-text transcript file, one
+/// timestamped line per utterance.
+pub struct TranscriptWriter {
+    file: File,
+}
+
+impl TranscriptWriter {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to open transcript {}: {}", path.display(), e))?;
+
+        tracing::info!("Writing transcript to {}", path.display());
+        Ok(Self { file })
+    }
+
+    pub fn write_line(&mut self, text: &str) -> Result<()> {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        writeln!(self.file, "[{}] {}", timestamp, text.trim())?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+impl OutputSink for TranscriptWriter {
+    fn name(&self) -> &str {
+        "transcript"
+    }
+
+    fn write<'a>(&'a mut self, delivery: Delivery<'a>) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.write_line(delivery.text) })
+    }
+}
+
+/// Default transcript location: `~/.local/share/ndict/transcripts/loopback.txt`.
+pub fn default_transcript_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ndict")
+        .join("transcripts")
+        .join("loopback.txt")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_line_appends_timestamped_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("transcript.txt");
+
+        let mut writer = TranscriptWriter::new(&path).unwrap();
+        writer.write_line("hello world ").unwrap();
+        writer.write_line("second line").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with('['));
+        assert!(lines[0].ends_with("] hello world"));
+        assert!(lines[1].ends_with("] second line"));
+    }
+
+    #[test]
+    fn test_write_line_appends_to_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.txt");
+        std::fs::write(&path, "existing\n").unwrap();
+
+        let mut writer = TranscriptWriter::new(&path).unwrap();
+        writer.write_line("new").unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("existing\n"));
+        assert_eq!(contents.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_default_transcript_path() {
+        let path = default_transcript_path();
+        assert!(path.ends_with("ndict/transcripts/loopback.txt"));
+    }
+}
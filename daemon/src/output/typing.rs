@@ -0,0 +1,116 @@
+use anyhow::Result;
+use tokio::time::Duration;
+
+use crate::config::OutputConfig;
+use crate::output::paste::Paster;
+
+/// How the keyboard sink gets text into the focused window, see
+/// `output.typing_mode`.
+#[derive(Debug, Clone)]
+pub enum TypingMode {
+    /// All key events at once.
+    Instant,
+    /// One character at a time, for applications and remote desktops that
+    /// drop keys sent too quickly.
+    Delayed(KeyDelay),
+    Paste(Paster),
+}
+
+impl TypingMode {
+    pub fn from_config(config: &OutputConfig) -> Result<Self> {
+        match config.typing_mode.as_str() {
+            "instant" => Ok(Self::Instant),
+            "delayed" => Ok(Self::Delayed(KeyDelay::new(
+                config.key_delay_ms,
+                config.key_jitter_ms,
+            ))),
+            "paste" => Ok(Self::Paste(Paster::from_config(config)?)),
+            other => Err(anyhow::anyhow!(
+                "Unknown output.typing_mode '{}', expected \"instant\", \"delayed\" or \"paste\"",
+                other
+            )),
+        }
+    }
+}
+
+/// The pause after each typed character: `delay_ms` give or take up to
+/// `jitter_ms`, so keystrokes arrive at a natural, uneven pace.
+#[derive(Debug, Clone)]
+pub struct KeyDelay {
+    delay_ms: u64,
+    jitter_ms: u64,
+    /// xorshift state; the jitter needs to look random, not be random.
+    state: u64,
+}
+
+impl KeyDelay {
+    pub fn new(delay_ms: u64, jitter_ms: u64) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            delay_ms,
+            jitter_ms,
+            // xorshift gets stuck at zero
+            state: seed | 1,
+        }
+    }
+
+    pub fn pause(&mut self) -> Duration {
+        if self.jitter_ms == 0 {
+            return Duration::from_millis(self.delay_ms);
+        }
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let offset = self.state % (2 * self.jitter_ms + 1);
+        let delay = (self.delay_ms + offset).saturating_sub(self.jitter_ms);
+        Duration::from_millis(delay)
+    }
+
+    /// The longest typing `text` can take.
+    pub fn longest(&self, text: &str) -> Duration {
+        Duration::from_millis((self.delay_ms + self.jitter_ms) * text.chars().count() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let mut config = OutputConfig {
+            typing_mode: "delayed".to_string(),
+            key_delay_ms: 30,
+            ..OutputConfig::default()
+        };
+        assert!(matches!(
+            TypingMode::from_config(&config).unwrap(),
+            TypingMode::Delayed(_)
+        ));
+        config.typing_mode = "instant".to_string();
+        assert!(matches!(
+            TypingMode::from_config(&config).unwrap(),
+            TypingMode::Instant
+        ));
+        config.typing_mode = "slow".to_string();
+        assert!(TypingMode::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_key_delay_stays_within_jitter() {
+        let mut delay = KeyDelay::new(30, 10);
+        let delays: Vec<Duration> = (0..200).map(|_| delay.pause()).collect();
+        assert!(delays
+            .iter()
+            .all(|d| (20..=40).contains(&(d.as_millis() as u64))));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+        assert_eq!(delay.longest("abc"), Duration::from_millis(120));
+
+        let mut steady = KeyDelay::new(5, 0);
+        assert_eq!(steady.pause(), Duration::from_millis(5));
+        assert!(KeyDelay::new(5, 20).pause() <= Duration::from_millis(25));
+    }
+}
@@ -0,0 +1,94 @@
+use tokio::process::Command;
+use tracing::info;
+
+use crate::output::chord::KeyChord;
+use crate::output::keyboard::KeyboardError;
+use crate::output::typing::KeyDelay;
+
+/// Types text in X11 sessions through the XTEST extension, using
+/// `xdotool`. Needs no privileges beyond access to the display.
+pub struct XdotoolKeyboard;
+
+impl XdotoolKeyboard {
+    pub fn new() -> Result<Self, KeyboardError> {
+        if std::env::var_os("DISPLAY").is_none() {
+            return Err(KeyboardError::Unavailable(
+                "X11 typing needs DISPLAY to be set".to_string(),
+            ));
+        }
+        let found = std::process::Command::new("xdotool")
+            .arg("version")
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !found {
+            return Err(KeyboardError::Unavailable(
+                "X11 typing needs xdotool, which was not found".to_string(),
+            ));
+        }
+        info!("Typing with xdotool on {:?}", std::env::var_os("DISPLAY"));
+        Ok(Self)
+    }
+
+    pub async fn type_text(&mut self, text: &str) -> Result<(), KeyboardError> {
+        info!("Typing text: '{}'", text);
+        xdotool(&["type", "--clearmodifiers", "--delay", "0", "--", text]).await
+    }
+
+    /// Type `text` a character at a time, pausing after each as `delay`
+    /// says.
+    pub async fn type_delayed(
+        &mut self,
+        text: &str,
+        delay: &mut KeyDelay,
+    ) -> Result<(), KeyboardError> {
+        info!("Typing text with key delays: '{}'", text);
+
+        let mut buf = [0u8; 4];
+        for c in text.chars() {
+            let key = c.encode_utf8(&mut buf);
+            xdotool(&["type", "--clearmodifiers", "--delay", "0", "--", key]).await?;
+            tokio::time::sleep(delay.pause()).await;
+        }
+        Ok(())
+    }
+
+    /// Press backspace `count` times.
+    pub async fn backspace(&mut self, count: usize) -> Result<(), KeyboardError> {
+        info!("Pressing backspace {} times", count);
+        if count == 0 {
+            return Ok(());
+        }
+        let repeat = count.to_string();
+        xdotool(&[
+            "key",
+            "--clearmodifiers",
+            "--delay",
+            "0",
+            "--repeat",
+            &repeat,
+            "BackSpace",
+        ])
+        .await
+    }
+
+    pub async fn press(&mut self, chord: &KeyChord) -> Result<(), KeyboardError> {
+        info!("Pressing {:?}", chord);
+        xdotool(&["key", "--clearmodifiers", &chord.xdotool_name()]).await
+    }
+}
+
+async fn xdotool(args: &[&str]) -> Result<(), KeyboardError> {
+    let output = Command::new("xdotool")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| KeyboardError::Send(format!("Failed to run xdotool: {}", e)))?;
+    if !output.status.success() {
+        return Err(KeyboardError::Send(format!(
+            "xdotool exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
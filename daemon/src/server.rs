@@ -1,4 +1,4 @@
-use shared::ipc::{Command, Response};
+use shared::ipc::{Command, InstalledModel, Response};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -7,12 +7,15 @@ use tokio::sync::Mutex;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
 
-use crate::audio::capture::AudioCapture;
-use crate::output::keyboard::VirtualKeyboard;
+use crate::model::{self, ModelManager};
+use crate::output::history::ExportFormat;
+use crate::output::subtitles::SubtitleFormat;
 use crate::state::DaemonState;
-use crate::transcription::engine::WhisperEngine;
+use crate::transcription::backend::{self, TranscriptionBackend, WorkerPool};
+use crate::transcription::chunking::Chunker;
+use crate::transcription::engine::{self, AUTO_LANGUAGE};
+use crate::transcription::files::{self, FileTranscriber};
 use crate::transcription::llm::LlmCleaner;
-use crate::transcription::streaming_engine::StreamingEngine;
 
 fn get_state_file_path() -> PathBuf {
     PathBuf::from("/tmp/ndict.state")
@@ -93,6 +96,22 @@ impl DaemonServer {
         }
     }
 
+    /// Build the backend for the active model and the current task, ready
+    /// for `load`.
+    async fn new_backend(state: &DaemonState) -> anyhow::Result<Box<dyn TranscriptionBackend>> {
+        let model = state.active_model.lock().await.clone();
+        Self::new_named_backend(state, &model).await
+    }
+
+    /// Build a backend for the model called `name` in `whisper.models`.
+    async fn new_named_backend(
+        state: &DaemonState,
+        name: &str,
+    ) -> anyhow::Result<Box<dyn TranscriptionBackend>> {
+        let translate = engine::parse_task(&state.task.lock().await)?;
+        backend::create_backend(&state.config, name, translate)
+    }
+
     /// Helper to handle the logic for starting audio processing.
     /// Used by Command::Start and Command::Toggle.
     async fn handle_start(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
@@ -102,48 +121,46 @@ impl DaemonServer {
         if *state_guard.is_processing.lock().await {
             return Err(anyhow::anyhow!("Already processing audio"));
         }
+        state_guard.session_log.lock().await.start_session();
+        state_guard.typed_history.lock().await.clear();
+        *state_guard.drafting.lock().await = false;
 
         let use_streaming = state_guard.config.whisper.streaming_mode;
+        if use_streaming && state_guard.config.wake.enabled {
+            warn!("Wake-word mode is only supported in batch mode, ignoring wake.enabled");
+        }
+        if use_streaming && state_guard.config.cloud.enabled {
+            warn!("Cloud transcription is not supported in streaming mode, ignoring cloud.enabled");
+        }
 
         if use_streaming {
-            if state_guard.streaming_engine.lock().await.is_none() {
-                let model_path = crate::transcription::engine::WhisperEngine::find_model_path(
-                    &state_guard.config.whisper.model_url,
-                )?;
-
-                let model_path_str = model_path.to_string_lossy().to_string();
-
-                let mut streaming_engine = StreamingEngine::new(
-                    model_path_str.clone(),
-                    state_guard.config.whisper.language.clone(),
-                    state_guard.config.streaming.step_ms,
-                    state_guard.config.streaming.length_ms,
-                    state_guard.config.streaming.keep_ms,
-                    state_guard.config.audio.sample_rate,
-                );
-                streaming_engine.load_model(&model_path_str).await?;
-                *state_guard.streaming_engine.lock().await = Some(streaming_engine);
-                info!("Streaming engine loaded");
+            if state_guard.streaming_backend.lock().await.is_none() {
+                let language = state_guard.language.lock().await.clone();
+                let translate = engine::parse_task(&state_guard.task.lock().await)?;
+                let mut streaming_backend =
+                    backend::create_streaming_backend(&state_guard.config, &language, translate)?;
+                streaming_backend.set_abort_signal(state_guard.transcription_abort.clone());
+                streaming_backend.load().await?;
+                info!("{} backend loaded", streaming_backend.name());
+                *state_guard.streaming_backend.lock().await = Some(streaming_backend);
             }
         } else {
-            if state_guard.whisper_engine.lock().await.is_none() {
-                let mut whisper_engine = WhisperEngine::new_with_checksum_and_params(
-                    state_guard.config.whisper.model_url.clone(),
-                    state_guard.config.whisper.backend.clone(),
-                    state_guard.config.whisper.model_checksum.clone(),
-                    state_guard.config.whisper.min_audio_samples,
-                    state_guard.config.whisper.sampling_strategy.clone(),
-                )?;
-                whisper_engine.load_model().await?;
-                *state_guard.whisper_engine.lock().await = Some(whisper_engine);
-                info!("Whisper engine loaded into memory");
+            if state_guard.backend.lock().await.is_none() {
+                let backend = Self::new_backend(&state_guard).await?;
+                let backend = Self::load_workers(&state_guard, backend).await?;
+                info!("{} backend loaded with {} worker(s)", backend.name(), backend.worker_count());
+                *state_guard.backend.lock().await = Some(backend);
+            }
+            if state_guard.config.wake.enabled && state_guard.wake_engine.lock().await.is_none() {
+                let mut wake_engine = backend::create_wake_backend(&state_guard.config)?;
+                wake_engine.set_abort_signal(state_guard.transcription_abort.clone());
+                let wake_engine = WorkerPool::load(wake_engine, 1).await?;
+                *state_guard.wake_engine.lock().await = Some(Arc::new(wake_engine));
+                info!("Wake-word engine loaded into memory");
             }
         }
 
-        if state_guard.virtual_keyboard.lock().await.is_none() {
-            let virtual_keyboard = VirtualKeyboard::new()?;
-            *state_guard.virtual_keyboard.lock().await = Some(virtual_keyboard);
-        }
+        state_guard.init_output().await?;
 
         if state_guard.config.llm.enabled && state_guard.llm_cleaner.lock().await.is_none() {
             let llm_cleaner = LlmCleaner::new(&state_guard.config.llm);
@@ -151,18 +168,12 @@ impl DaemonServer {
             info!("LLM cleaner initialized");
         }
 
-        let (audio_tx, audio_rx) = tokio::sync::broadcast::channel(state_guard.config.buffer.broadcast_capacity);
-        let sample_rate = state_guard.config.audio.sample_rate;
-        let channels = state_guard.config.audio.channels;
-        let mut new_capture = AudioCapture::new_with_channels(sample_rate, channels)?;
-        new_capture.start(audio_tx)?;
-        *state_guard.audio_capture.lock().await = Some(new_capture);
-        *state_guard.audio_rx.lock().await = Some(audio_rx);
+        state_guard.start_audio_source().await?;
 
         debug!("Audio capture started, VAD, Whisper, and Keyboard ready");
 
         if use_streaming {
-            let mut engine_lock = state_guard.streaming_engine.lock().await;
+            let mut engine_lock = state_guard.streaming_backend.lock().await;
             if let Some(ref mut engine) = *engine_lock {
                 engine.start()?;
                 info!("Streaming engine started");
@@ -190,13 +201,16 @@ impl DaemonServer {
     /// Used by Command::Stop and Command::Toggle.
     async fn handle_stop(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
         let mut state_guard = state.lock().await;
+        // First, as a streaming decode holds the engine stop_vad_processing waits for
+        state_guard.transcription_abort.abort();
         state_guard.stop_vad_processing().await;
-        if let Some(capture) = state_guard.audio_capture.lock().await.as_mut() {
-            capture.stop().await?;
-        }
-        *state_guard.audio_capture.lock().await = None;
-        *state_guard.audio_rx.lock().await = None;
+        state_guard.stop_audio_source().await?;
         state_guard.deactivate().await?;
+        let dropped = state_guard.pause_buffer.lock().await.clear();
+        if dropped > 0 {
+            warn!("Dropped {} transcriptions held while paused", dropped);
+        }
+        state_guard.schedule_idle_unload().await;
         remove_state_file();
         info!("Stopped audio processing, model kept in memory");
         Ok(Response::Ok)
@@ -212,12 +226,27 @@ impl DaemonServer {
             return Err(anyhow::anyhow!("Already paused or not started"));
         }
 
+        if state_guard.config.output.buffer_while_paused {
+            state_guard.pause_buffer.lock().await.hold();
+            state_guard.deactivate().await?;
+            info!("Paused, holding transcriptions until Resume or Flush");
+            return Ok(Response::Ok);
+        }
+
         state_guard.stop_vad_processing().await;
         state_guard.deactivate().await?;
         info!("Paused transcription, audio capture continues");
         Ok(Response::Ok)
     }
 
+    /// Helper to type the text held back while paused, staying paused.
+    async fn handle_flush(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
+        let state_guard = state.lock().await;
+        let flushed = state_guard.flush_held(false).await?;
+        info!("Flushed {} held transcriptions", flushed);
+        Ok(Response::Ok)
+    }
+
     /// Helper to handle the logic for resuming transcription.
     /// Sets is_active to true and restarts VAD or streaming processing.
     async fn handle_resume(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
@@ -228,7 +257,15 @@ impl DaemonServer {
             return Err(anyhow::anyhow!("Already active, cannot resume"));
         }
 
-        let has_audio = state_guard.audio_capture.lock().await.is_some();
+        // Transcription kept running while the text was held
+        if state_guard.pause_buffer.lock().await.is_holding() {
+            let flushed = state_guard.flush_held(true).await?;
+            state_guard.activate().await?;
+            info!("Resumed, typed {} held transcriptions", flushed);
+            return Ok(Response::Ok);
+        }
+
+        let has_audio = state_guard.audio_source.lock().await.is_some();
         if !has_audio {
             return Err(anyhow::anyhow!("Cannot resume: audio capture not running. Use Start instead."));
         }
@@ -246,23 +283,111 @@ impl DaemonServer {
         Ok(Response::Ok)
     }
 
+    /// Helper to handle the logic for setting the Whisper task.
+    /// Validates it, stores it in DaemonState and applies it to loaded engines.
+    async fn handle_set_task(state: Arc<Mutex<DaemonState>>, task: String) -> anyhow::Result<Response> {
+        let translate = engine::parse_task(&task)?;
+
+        let state_guard = state.lock().await;
+        *state_guard.task.lock().await = task.clone();
+
+        let backend = state_guard.backend.lock().await.clone();
+        if let Some(backend) = backend {
+            backend.set_translate(translate).await;
+        }
+        if let Some(ref mut backend) = *state_guard.streaming_backend.lock().await {
+            backend.set_translate(translate);
+        }
+
+        info!("Task set to: {}", task);
+        Ok(Response::Ok)
+    }
+
+    /// Helper to handle the logic for switching models.
+    /// The new model is loaded (or taken from the pool) before the swap, so
+    /// audio keeps flowing and queued segments only wait for the swap itself.
+    async fn handle_set_model(state: Arc<Mutex<DaemonState>>, name: String) -> anyhow::Result<Response> {
+        let state_guard = state.lock().await;
+        let Some(model_url) = state_guard.config.whisper.model_url_for(&name) else {
+            return Err(anyhow::anyhow!("Unknown model '{}'. Add it to [whisper.models]", name));
+        };
+        let model_path = match state_guard.config.whisper.model_path_for(&name) {
+            Some(path) => path,
+            None => model::find_model_path(&model_url)?,
+        };
+        if !model_path.exists() {
+            info!("Model '{}' is not installed yet, it will be downloaded to {:?}", name, model_path);
+        }
+
+        let previous = state_guard.active_model.lock().await.clone();
+        if previous == name {
+            return Ok(Response::Ok);
+        }
+
+        // Before the first Start there is no backend yet; Start loads the new model
+        if state_guard.backend.lock().await.is_some() {
+            let backend = Self::take_or_load(&state_guard, &name).await?;
+            let old_backend = state_guard.backend.lock().await.replace(backend);
+            if let Some(old_backend) = old_backend {
+                state_guard.engine_pool.lock().await.park(previous, old_backend);
+            }
+        }
+
+        *state_guard.active_model.lock().await = name.clone();
+        info!("Model switched to: {}", name);
+        Ok(Response::Ok)
+    }
+
+    async fn take_or_load(
+        state: &DaemonState,
+        name: &str,
+    ) -> anyhow::Result<Arc<WorkerPool>> {
+        if let Some(backend) = state.engine_pool.lock().await.take(name) {
+            // The task may have changed while the backend was parked
+            backend
+                .set_translate(engine::parse_task(&state.task.lock().await)?)
+                .await;
+            return Ok(backend);
+        }
+        info!("Loading model '{}'", name);
+        let backend = Self::new_named_backend(state, name).await?;
+        Self::load_workers(state, backend).await
+    }
+
+    /// Load `backend` and fork it into `whisper.workers` workers.
+    async fn load_workers(
+        state: &DaemonState,
+        mut backend: Box<dyn TranscriptionBackend>,
+    ) -> anyhow::Result<Arc<WorkerPool>> {
+        backend.set_abort_signal(state.transcription_abort.clone());
+        let workers = state.config.whisper.workers.max(1);
+        Ok(Arc::new(WorkerPool::load(backend, workers).await?))
+    }
+
     /// Helper to handle the logic for setting language.
     /// Validates and stores the language in DaemonState.
-    async fn handle_set_language(state: Arc<Mutex<DaemonState>>, lang: String) -> anyhow::Result<Response> {
+    /// Check a language code given to `SetLanguage` or `SetNextLanguage`.
+    fn validate_language(lang: &str) -> anyhow::Result<()> {
         // Validate language code (basic validation: 2-3 letter ISO 639-1 codes)
-        if lang.len() < 2 || lang.len() > 3 {
+        if lang != AUTO_LANGUAGE && (lang.len() < 2 || lang.len() > 3) {
             return Err(anyhow::anyhow!("Invalid language code: '{}'. Expected 2-3 letter ISO 639-1 code (e.g., 'en', 'es', 'fr')", lang));
         }
 
         if !lang.chars().all(|c| c.is_ascii_lowercase()) {
             return Err(anyhow::anyhow!("Invalid language code: '{}'. Must be lowercase ASCII letters only", lang));
         }
+        Ok(())
+    }
+
+    async fn handle_set_language(state: Arc<Mutex<DaemonState>>, lang: String) -> anyhow::Result<Response> {
+        Self::validate_language(&lang)?;
 
         let state_guard = state.lock().await;
         *state_guard.language.lock().await = lang.clone();
+        *state_guard.detected_language.lock().await = None;
 
         // Update streaming engine language if it's loaded
-        if let Some(ref mut engine) = *state_guard.streaming_engine.lock().await {
+        if let Some(ref mut engine) = *state_guard.streaming_backend.lock().await {
             engine.set_language(lang.clone());
         }
 
@@ -270,6 +395,21 @@ impl DaemonServer {
         Ok(Response::Ok)
     }
 
+    /// Helper to set the language of the next utterance only. Streaming mode
+    /// has no utterances to apply it to.
+    async fn handle_set_next_language(state: Arc<Mutex<DaemonState>>, lang: String) -> anyhow::Result<Response> {
+        Self::validate_language(&lang)?;
+
+        let state_guard = state.lock().await;
+        if state_guard.config.whisper.streaming_mode {
+            return Err(anyhow::anyhow!("SetNextLanguage is not available in streaming mode"));
+        }
+        *state_guard.next_language.lock().await = Some(lang.clone());
+
+        info!("Next utterance language set to: {}", lang);
+        Ok(Response::Ok)
+    }
+
     /// Helper for manual mode start.
     /// Loads engines, starts audio capture, begins buffering speech segments.
     /// If already in manual mode, discards current buffer and starts fresh.
@@ -286,11 +426,7 @@ impl DaemonServer {
             *state_guard.is_manual_mode.lock().await = false;
             *state_guard.is_processing.lock().await = false;
 
-            if let Some(capture) = state_guard.audio_capture.lock().await.as_mut() {
-                let _ = capture.stop().await;
-            }
-            *state_guard.audio_capture.lock().await = None;
-            *state_guard.audio_rx.lock().await = None;
+            let _ = state_guard.stop_audio_source().await;
 
             if state_guard.config.llm.enabled && state_guard.llm_cleaner.lock().await.is_none() {
                 let llm_cleaner = LlmCleaner::new(&state_guard.config.llm);
@@ -298,37 +434,29 @@ impl DaemonServer {
                 info!("LLM cleaner initialized for manual mode (restart)");
             }
 
-            let (audio_tx, audio_rx) = tokio::sync::broadcast::channel(state_guard.config.buffer.broadcast_capacity);
-            let sample_rate = state_guard.config.audio.sample_rate;
-            let channels = state_guard.config.audio.channels;
-            let mut new_capture = AudioCapture::new_with_channels(sample_rate, channels)?;
-            new_capture.start(audio_tx)?;
-            *state_guard.audio_capture.lock().await = Some(new_capture);
-            *state_guard.audio_rx.lock().await = Some(audio_rx);
+            state_guard.start_audio_source().await?;
 
             info!("Manual mode: buffer discarded, audio capture restarted");
         } else {
             if *state_guard.is_processing.lock().await {
                 return Err(anyhow::anyhow!("Already processing audio"));
             }
-
-            if state_guard.whisper_engine.lock().await.is_none() {
-                let mut whisper_engine = WhisperEngine::new_with_checksum_and_params(
-                    state_guard.config.whisper.model_url.clone(),
-                    state_guard.config.whisper.backend.clone(),
-                    state_guard.config.whisper.model_checksum.clone(),
-                    state_guard.config.whisper.min_audio_samples,
-                    state_guard.config.whisper.sampling_strategy.clone(),
-                )?;
-                whisper_engine.load_model().await?;
-                *state_guard.whisper_engine.lock().await = Some(whisper_engine);
-                info!("Whisper engine loaded for manual mode");
+            state_guard.session_log.lock().await.start_session();
+            state_guard.typed_history.lock().await.clear();
+            *state_guard.drafting.lock().await = false;
+
+            if state_guard.backend.lock().await.is_none() {
+                let backend = Self::new_backend(&state_guard).await?;
+                let backend = Self::load_workers(&state_guard, backend).await?;
+                info!(
+                    "{} backend loaded for manual mode with {} worker(s)",
+                    backend.name(),
+                    backend.worker_count()
+                );
+                *state_guard.backend.lock().await = Some(backend);
             }
 
-            if state_guard.virtual_keyboard.lock().await.is_none() {
-                let virtual_keyboard = VirtualKeyboard::new()?;
-                *state_guard.virtual_keyboard.lock().await = Some(virtual_keyboard);
-            }
+            state_guard.init_output().await?;
 
             if state_guard.config.llm.enabled && state_guard.llm_cleaner.lock().await.is_none() {
                 let llm_cleaner = LlmCleaner::new(&state_guard.config.llm);
@@ -336,13 +464,7 @@ impl DaemonServer {
                 info!("LLM cleaner initialized for manual mode");
             }
 
-            let (audio_tx, audio_rx) = tokio::sync::broadcast::channel(state_guard.config.buffer.broadcast_capacity);
-            let sample_rate = state_guard.config.audio.sample_rate;
-            let channels = state_guard.config.audio.channels;
-            let mut new_capture = AudioCapture::new_with_channels(sample_rate, channels)?;
-            new_capture.start(audio_tx)?;
-            *state_guard.audio_capture.lock().await = Some(new_capture);
-            *state_guard.audio_rx.lock().await = Some(audio_rx);
+            state_guard.start_audio_source().await?;
         }
 
         debug!("Manual mode: audio capture started, beginning speech buffering");
@@ -384,18 +506,115 @@ impl DaemonServer {
     /// Stops audio capture, clears buffer, exits manual mode.
     async fn handle_mstop(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
         let mut state_guard = state.lock().await;
+        state_guard.transcription_abort.abort();
         state_guard.stop_manual_mode().await;
-        if let Some(capture) = state_guard.audio_capture.lock().await.as_mut() {
-            capture.stop().await?;
-        }
-        *state_guard.audio_capture.lock().await = None;
-        *state_guard.audio_rx.lock().await = None;
+        state_guard.stop_audio_source().await?;
         state_guard.deactivate().await?;
+        state_guard.schedule_idle_unload().await;
         remove_state_file();
         info!("Manual mode stopped (MStop)");
         Ok(Response::Ok)
     }
 
+    /// Helper to handle exporting the current session's transcriptions.
+    async fn handle_export(
+        state: Arc<Mutex<DaemonState>>,
+        format: String,
+        path: Option<String>,
+    ) -> anyhow::Result<Response> {
+        let format = ExportFormat::parse(&format)?;
+        let state_guard = state.lock().await;
+        let session_log = state_guard.session_log.lock().await;
+        let path = path
+            .map(PathBuf::from)
+            .unwrap_or_else(|| session_log.default_export_path(format));
+        session_log.export_to(&path, format)?;
+        info!(
+            "Exported {} transcriptions to {}",
+            session_log.entries().len(),
+            path.display()
+        );
+        Ok(Response::Exported(path.display().to_string()))
+    }
+
+    /// Helper to handle transcribing the last utterance again. The retry
+    /// model may need loading first, so the correction is sent as an event.
+    async fn handle_retry(state: Arc<Mutex<DaemonState>>) -> anyhow::Result<Response> {
+        let second_pass = state.lock().await.second_pass();
+        let utterance = second_pass.last_utterance().await?;
+        tokio::spawn(async move {
+            if let Err(e) = second_pass.retry(&utterance).await {
+                error!("Retry failed: {:#}", e);
+            }
+        });
+        info!("Retrying last utterance");
+        Ok(Response::Ok)
+    }
+
+    /// Helper to transcribe one or more WAV files into `format`, see
+    /// `Command::TranscribeFile`. Loads the batch backend if no session has
+    /// yet.
+    async fn handle_transcribe_file(
+        state: Arc<Mutex<DaemonState>>,
+        path: String,
+        format: String,
+        output: Option<String>,
+    ) -> anyhow::Result<Response> {
+        let format = SubtitleFormat::parse(&format)?;
+        let path = PathBuf::from(path);
+        let inputs = files::expand_inputs(&path)?;
+        let jobs = files::plan_outputs(&path, inputs, output.map(PathBuf::from), format);
+
+        let (transcriber, parallel_files) = {
+            let state_guard = state.lock().await;
+            if state_guard.backend.lock().await.is_none() {
+                let backend = Self::new_backend(&state_guard).await?;
+                let backend = Self::load_workers(&state_guard, backend).await?;
+                info!("{} backend loaded with {} worker(s)", backend.name(), backend.worker_count());
+                *state_guard.backend.lock().await = Some(backend);
+            }
+            let workers = state_guard
+                .backend
+                .lock()
+                .await
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("Transcription backend not available"))?;
+            let config = &state_guard.config;
+            let transcriber = FileTranscriber::new(
+                workers,
+                Chunker::from_config(&config.whisper.chunking, config.audio.sample_rate),
+                state_guard.language.lock().await.clone(),
+                config.audio.sample_rate,
+                format,
+            );
+            (transcriber, config.transcribe.parallel_files)
+        };
+
+        info!("Transcribing {} file(s), {} at a time", jobs.len(), parallel_files);
+        let report = transcriber.transcribe_all(jobs, parallel_files).await;
+        state.lock().await.schedule_idle_unload().await;
+
+        let failed = report.iter().filter(|file| file.error.is_some()).count();
+        info!("Transcribed {} file(s), {} failed", report.len() - failed, failed);
+        Ok(Response::Transcribed(report))
+    }
+
+    /// Helper to list the models recorded in the model manifest.
+    fn handle_list_models() -> anyhow::Result<Response> {
+        let manager = ModelManager::open_default()?;
+        let models = manager
+            .manifest()
+            .entries()
+            .map(|entry| InstalledModel {
+                path: entry.path.display().to_string(),
+                size_bytes: entry.size,
+                sha256: entry.sha256.clone(),
+                last_verified: entry.last_verified.clone(),
+            })
+            .collect();
+        Ok(Response::Models(models))
+    }
+
     pub async fn execute_command(
         state: Arc<Mutex<DaemonState>>,
         command: Command,
@@ -425,6 +644,9 @@ impl DaemonServer {
                 Response::Status(status)
             }
             Command::SetLanguage(lang) => Self::handle_set_language(state, lang).await?,
+            Command::SetNextLanguage(lang) => Self::handle_set_next_language(state, lang).await?,
+            Command::SetTask(task) => Self::handle_set_task(state, task).await?,
+            Command::SetModel(name) => Self::handle_set_model(state, name).await?,
             Command::Toggle => {
                 let status = state.lock().await.get_status().await;
 
@@ -440,11 +662,71 @@ impl DaemonServer {
             Command::MComplete => Self::handle_mcomplete(state).await?,
             Command::MCompleteRaw => Self::handle_mcomplete_raw(state).await?,
             Command::MStop => Self::handle_mstop(state).await?,
+            Command::Export { format, path } => Self::handle_export(state, format, path).await?,
+            Command::Flush => Self::handle_flush(state).await?,
+            Command::Retry => Self::handle_retry(state).await?,
+            Command::ListModels => Self::handle_list_models()?,
+            Command::TranscribeFile { path, format, output } => {
+                Self::handle_transcribe_file(state, path, format, output).await?
+            }
+            Command::Subscribe => Response::Error(
+                "Subscribe opens an event stream and must be sent as its own connection".to_string(),
+            ),
         };
 
         Ok(response)
     }
 
+    /// Acknowledge a subscription, then forward daemon events to the client as
+    /// newline-delimited JSON until it disconnects.
+    async fn handle_subscribe(
+        state: Arc<Mutex<DaemonState>>,
+        mut stream: tokio::net::UnixStream,
+    ) -> anyhow::Result<()> {
+        let (rate_limiter, mut events) = {
+            let state_guard = state.lock().await;
+            (state_guard.get_rate_limiter(), state_guard.subscribe_events())
+        };
+
+        if !rate_limiter.check() {
+            warn!("Subscription rate limited");
+            let response = Response::Error(
+                "Rate limit exceeded. Please wait before sending more commands.".to_string(),
+            );
+            return Self::write_line(&mut stream, &response).await;
+        }
+
+        Self::write_line(&mut stream, &Response::Ok).await?;
+        info!("Client subscribed to events");
+
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    if Self::write_line(&mut stream, &event).await.is_err() {
+                        debug!("Event subscriber disconnected");
+                        return Ok(());
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    debug!("Event subscriber lagged, skipped {} events", n);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    async fn write_line<T: serde::Serialize>(
+        stream: &mut tokio::net::UnixStream,
+        value: &T,
+    ) -> anyhow::Result<()> {
+        let mut json = serde_json::to_vec(value)?;
+        json.push(b'\n');
+        match timeout(IO_TIMEOUT, stream.write_all(&json)).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(anyhow::anyhow!("Connection timeout during write")),
+        }
+    }
+
     async fn handle_connection(
         state: Arc<Mutex<DaemonState>>,
         mut stream: tokio::net::UnixStream,
@@ -478,6 +760,10 @@ impl DaemonServer {
             }
         };
 
+        if command == Command::Subscribe {
+            return Self::handle_subscribe(state, stream).await;
+        }
+
         let response = Self::execute_command(state.clone(), command).await?;
 
         let response_json = serde_json::to_vec(&response)?;
@@ -507,6 +793,56 @@ mod tests {
     use super::*;
     use crate::config::Config;
 
+    #[tokio::test]
+    async fn test_execute_command_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.md");
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+        {
+            let state_guard = state.lock().await;
+            state_guard.session_log.lock().await.record("Hello.", "en");
+        }
+
+        let command = Command::Export {
+            format: "markdown".to_string(),
+            path: Some(path.display().to_string()),
+        };
+        let result = DaemonServer::execute_command(state.clone(), command).await;
+        assert_eq!(result.unwrap(), Response::Exported(path.display().to_string()));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("_(en)_ Hello."));
+
+        let command = Command::Export {
+            format: "pdf".to_string(),
+            path: None,
+        };
+        assert!(DaemonServer::execute_command(state, command).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_transcribe_file_rejects_unknown_format() {
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+        let command = Command::TranscribeFile {
+            path: "/tmp/talk.wav".to_string(),
+            format: "ass".to_string(),
+            output: None,
+        };
+        let result = DaemonServer::execute_command(state, command).await;
+        assert!(result.unwrap_err().to_string().contains("Unknown transcript format"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_retry_needs_model_and_utterance() {
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+        let result = DaemonServer::execute_command(state, Command::Retry).await;
+        assert!(result.unwrap_err().to_string().contains("whisper.retry.model"));
+
+        let mut config = Config::default();
+        config.whisper.retry.model = Some("large-v3".to_string());
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+        let result = DaemonServer::execute_command(state, Command::Retry).await;
+        assert!(result.unwrap_err().to_string().contains("No utterance"));
+    }
+
     #[tokio::test]
     async fn test_daemon_server_new() {
         let socket_path = PathBuf::from("/tmp/test.sock");
@@ -542,6 +878,28 @@ mod tests {
         assert!(!status.is_active);
     }
 
+    #[tokio::test]
+    async fn test_pause_holds_text_when_buffering() {
+        let mut config = Config::default();
+        config.output.buffer_while_paused = true;
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+        state.lock().await.activate().await.unwrap();
+
+        let result = DaemonServer::execute_command(state.clone(), Command::Pause).await;
+        assert!(matches!(result, Ok(Response::Ok)));
+        assert!(state.lock().await.pause_buffer.lock().await.is_holding());
+
+        let result = DaemonServer::execute_command(state.clone(), Command::Flush).await;
+        assert!(matches!(result, Ok(Response::Ok)));
+        assert!(state.lock().await.pause_buffer.lock().await.is_holding());
+
+        // Transcription never stopped, so no audio capture is needed to resume
+        let result = DaemonServer::execute_command(state.clone(), Command::Resume).await;
+        assert!(matches!(result, Ok(Response::Ok)));
+        assert!(!state.lock().await.pause_buffer.lock().await.is_holding());
+        assert!(state.lock().await.get_status().await.is_active);
+    }
+
     #[tokio::test]
     async fn test_execute_command_resume() {
         let config = Config::default();
@@ -640,6 +998,89 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_execute_command_set_next_language() {
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetNextLanguage("de".to_string()))
+                .await;
+        assert!(matches!(result, Ok(Response::Ok)));
+        let state_guard = state.lock().await;
+        assert_eq!(state_guard.next_language.lock().await.as_deref(), Some("de"));
+        // The session language is left alone
+        assert_eq!(state_guard.get_status().await.language, "en");
+        drop(state_guard);
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetNextLanguage("German".to_string()))
+                .await;
+        assert!(result.is_err());
+
+        let mut config = Config::default();
+        config.whisper.streaming_mode = true;
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+        let result =
+            DaemonServer::execute_command(state, Command::SetNextLanguage("de".to_string())).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_set_language_auto() {
+        let config = Config::default();
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetLanguage("auto".to_string()))
+                .await;
+        assert!(result.is_ok());
+
+        let status = state.lock().await.get_status().await;
+        assert_eq!(status.language, "auto");
+        assert_eq!(status.detected_language, None);
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_set_task() {
+        let config = Config::default();
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetTask("translate".to_string()))
+                .await;
+        assert!(result.is_ok());
+        assert_eq!(*state.lock().await.task.lock().await, "translate");
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetTask("summarize".to_string()))
+                .await;
+        assert!(result.is_err());
+        assert_eq!(*state.lock().await.task.lock().await, "translate");
+    }
+
+    #[tokio::test]
+    async fn test_execute_command_set_model() {
+        let mut config = Config::default();
+        config
+            .whisper
+            .models
+            .insert("tiny".to_string(), "https://example.com/ggml-tiny.bin".to_string());
+        let state = Arc::new(Mutex::new(DaemonState::new(config)));
+
+        // Before Start this only selects the model for the next load
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetModel("tiny".to_string()))
+                .await;
+        assert!(result.is_ok());
+        assert_eq!(*state.lock().await.active_model.lock().await, "tiny");
+
+        let result =
+            DaemonServer::execute_command(state.clone(), Command::SetModel("large".to_string()))
+                .await;
+        assert!(result.is_err());
+        assert_eq!(*state.lock().await.active_model.lock().await, "tiny");
+    }
+
     #[tokio::test]
     async fn test_execute_command_set_language_invalid() {
         let config = Config::default();
@@ -792,4 +1233,40 @@ mod tests {
         let result = DaemonServer::execute_command(state.clone(), Command::SetLanguage("es".to_string())).await;
         assert!(matches!(result, Ok(Response::Error(_))), "SetLanguage should also be rate limited");
     }
+
+    #[tokio::test]
+    async fn test_execute_command_subscribe_requires_own_connection() {
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+        let result = DaemonServer::execute_command(state, Command::Subscribe).await;
+        assert!(matches!(result, Ok(Response::Error(_))));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_streams_events() {
+        use shared::ipc::{AudioLevel, Event};
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        let state = Arc::new(Mutex::new(DaemonState::new(Config::default())));
+        let (mut client, server) = tokio::net::UnixStream::pair().unwrap();
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            let _ = DaemonServer::handle_connection(server_state, server).await;
+        });
+
+        client
+            .write_all(&serde_json::to_vec(&Command::Subscribe).unwrap())
+            .await
+            .unwrap();
+        let mut reader = BufReader::new(client);
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(serde_json::from_str::<Response>(&line).unwrap(), Response::Ok);
+
+        let event = Event::AudioLevel(AudioLevel { rms: 0.05, peak: 0.1 });
+        state.lock().await.event_tx.send(event.clone()).unwrap();
+
+        line.clear();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(serde_json::from_str::<Event>(&line).unwrap(), event);
+    }
 }
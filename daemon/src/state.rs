@@ -1,39 +1,111 @@
-use crate::audio::capture::AudioCapture;
-use crate::config::Config;
-use crate::output::VirtualKeyboard;
+use crate::audio::gate::InputGate;
+use crate::audio::noise_gate::NoiseGate;
+use crate::audio::{clipping, level, recorder, watchdog};
+use crate::audio::source::{self, AudioSource};
+use crate::config::{Config, VadConfig, DEFAULT_MODEL};
+use crate::output::chord::KeyChord;
+use crate::output::editing::{EditCommand, KeyAction, TypedHistory};
+use crate::output::history::{self, SessionLog};
+use crate::output::joining::UtteranceJoiner;
+use crate::output::pause::PauseBuffer;
+use crate::output::snippets::Snippets;
+use crate::output::sink::{self, Delivery, OutputSink};
 use crate::rate_limit::CommandRateLimiter;
-use crate::transcription;
-use crate::transcription::engine::WhisperEngine;
-use crate::transcription::llm::LlmCleaner;
-use crate::transcription::streaming_engine::StreamingEngine;
+use crate::transcription::abort::AbortSignal;
+use crate::transcription::backend::{SharedBackend, StreamingBackend, Transcription};
+use crate::transcription::chunking::Chunker;
+use crate::transcription::confidence::{ConfidenceFilter, Verdict};
+use crate::transcription::engine::AUTO_LANGUAGE;
+use crate::transcription::llm::{LlmCleaner, LlmInput};
+use crate::transcription::pool::EnginePool;
+use crate::transcription::post_processor::PostProcessor;
+use crate::transcription::punctuation::restore_punctuation;
+use crate::transcription::retry::{SecondPass, Utterance};
+use crate::vad::calibration::{self, AmbientCalibration};
 use crate::vad::speech_detector::SpeechDetector;
-use shared::ipc::StatusInfo;
+use crate::wake::WakeWord;
+use shared::ipc::{amplitude_to_dbfs, AudioLevel, Event, StatusInfo};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use tokio::sync::watch;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
 pub struct DaemonState {
     pub config: Config,
     pub language: Arc<Mutex<String>>,
+    /// Current `whisper.task`, changed at runtime with `SetTask`.
+    pub task: Arc<Mutex<String>>,
     pub is_active: Arc<Mutex<bool>>,
     pub is_processing: Arc<Mutex<bool>>,
     pub is_manual_mode: Arc<Mutex<bool>>,
     pub manual_speech_buffer: Arc<Mutex<Vec<f32>>>,
-    pub audio_capture: Arc<Mutex<Option<AudioCapture>>>,
+    pub audio_source: Arc<Mutex<Option<Box<dyn AudioSource>>>>,
     pub audio_rx: Arc<Mutex<Option<broadcast::Receiver<Vec<f32>>>>>,
-    pub whisper_engine: Arc<Mutex<Option<WhisperEngine>>>,
-    pub streaming_engine: Arc<Mutex<Option<StreamingEngine>>>,
-    pub virtual_keyboard: Arc<Mutex<Option<VirtualKeyboard>>>,
+    /// Transcribes utterances in batch and manual mode, see
+    /// `backend::create_backend`.
+    pub backend: SharedBackend,
+    /// Name of the model `backend` runs, see `whisper.models`.
+    pub active_model: Arc<Mutex<String>>,
+    /// Loaded models not currently in use.
+    pub engine_pool: Arc<Mutex<EnginePool>>,
+    /// Small model listening for the wake phrase when `wake.enabled` is set.
+    pub wake_engine: SharedBackend,
+    /// Larger model for `whisper.retry`, loaded on the first retry.
+    pub retry_backend: SharedBackend,
+    /// Most recent utterance, kept for `Retry` while `whisper.retry` is on.
+    pub last_utterance: Arc<Mutex<Option<Utterance>>>,
+    pub streaming_backend: Arc<Mutex<Option<Box<dyn StreamingBackend>>>>,
+    /// Where final text goes, see `output.sinks`. Empty until `init_output`.
+    pub sinks: Arc<Mutex<Vec<Box<dyn OutputSink>>>>,
+    /// Final transcriptions since the last Start, see `output.history`.
+    pub session_log: Arc<Mutex<SessionLog>>,
+    /// Text typed since the last Start, for spoken editing commands.
+    pub typed_history: Arc<Mutex<TypedHistory>>,
+    /// Whether the last utterance typed is a streaming draft, shared by
+    /// every `TextOutput` so a restarted stream still corrects it.
+    pub drafting: Arc<Mutex<bool>>,
+    /// Text held back while paused, see `output.buffer_while_paused`.
+    pub pause_buffer: Arc<Mutex<PauseBuffer>>,
     pub llm_cleaner: Arc<Mutex<Option<LlmCleaner>>>,
     pub vad_task_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
     pub streaming_task_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    pub watchdog_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Timer unloading models after `timeouts.model_idle_unload_seconds`.
+    pub idle_unload_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
     pub rate_limiter: Arc<CommandRateLimiter>,
+    pub audio_level: Arc<Mutex<Option<AudioLevel>>>,
+    /// Language Whisper last detected while `language` is "auto".
+    pub detected_language: Arc<Mutex<Option<String>>>,
+    /// Language for the next utterance only, from `SetNextLanguage`.
+    pub next_language: Arc<Mutex<Option<String>>>,
+    pub input_gate: Arc<InputGate>,
+    /// Interrupts transcriptions in progress on Stop, MStop and shutdown.
+    pub transcription_abort: AbortSignal,
+    /// Order in which batch and manual utterances are delivered.
+    delivery_order: DeliveryOrder,
+    pub event_tx: broadcast::Sender<Event>,
 }
 
+/// Capacity of the event channel feeding subscribed clients. Slow clients
+/// skip events rather than holding up the daemon.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
 impl DaemonState {
     pub fn new(config: Config) -> Self {
         let language = config.whisper.language.clone();
+        let task = config.whisper.task.clone();
+        let sample_rate = config.audio.sample_rate;
+        let history_dir = config.output.history.then(|| {
+            config
+                .output
+                .history_dir
+                .as_ref()
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(history::default_history_dir)
+        });
         let rate_limiter = Arc::new(CommandRateLimiter::new(
             config.rate_limit.commands_per_second,
             config.rate_limit.burst_capacity,
@@ -42,23 +114,45 @@ impl DaemonState {
         Self {
             config,
             language: Arc::new(Mutex::new(language)),
+            task: Arc::new(Mutex::new(task)),
             is_active: Arc::new(Mutex::new(false)),
             is_processing: Arc::new(Mutex::new(false)),
             is_manual_mode: Arc::new(Mutex::new(false)),
             manual_speech_buffer: Arc::new(Mutex::new(Vec::new())),
-            audio_capture: Arc::new(Mutex::new(None)),
+            audio_source: Arc::new(Mutex::new(None)),
             audio_rx: Arc::new(Mutex::new(None)),
-            whisper_engine: Arc::new(Mutex::new(None)),
-            streaming_engine: Arc::new(Mutex::new(None)),
-            virtual_keyboard: Arc::new(Mutex::new(None)),
+            backend: Arc::new(Mutex::new(None)),
+            active_model: Arc::new(Mutex::new(DEFAULT_MODEL.to_string())),
+            engine_pool: Arc::new(Mutex::new(EnginePool::new())),
+            wake_engine: Arc::new(Mutex::new(None)),
+            retry_backend: Arc::new(Mutex::new(None)),
+            last_utterance: Arc::new(Mutex::new(None)),
+            streaming_backend: Arc::new(Mutex::new(None)),
+            sinks: Arc::new(Mutex::new(Vec::new())),
+            session_log: Arc::new(Mutex::new(SessionLog::new(history_dir))),
+            typed_history: Arc::new(Mutex::new(TypedHistory::new())),
+            drafting: Arc::new(Mutex::new(false)),
+            pause_buffer: Arc::new(Mutex::new(PauseBuffer::new())),
             llm_cleaner: Arc::new(Mutex::new(None)),
             vad_task_handle: Arc::new(Mutex::new(None)),
             streaming_task_handle: Arc::new(Mutex::new(None)),
+            watchdog_handle: Arc::new(Mutex::new(None)),
+            idle_unload_handle: Arc::new(Mutex::new(None)),
             rate_limiter,
+            audio_level: Arc::new(Mutex::new(None)),
+            detected_language: Arc::new(Mutex::new(None)),
+            next_language: Arc::new(Mutex::new(None)),
+            input_gate: Arc::new(InputGate::new(sample_rate)),
+            transcription_abort: AbortSignal::new(),
+            delivery_order: DeliveryOrder::new(),
+            event_tx: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
         }
     }
 
     pub async fn activate(&mut self) -> anyhow::Result<()> {
+        if let Some(handle) = self.idle_unload_handle.lock().await.take() {
+            handle.abort();
+        }
         *self.is_active.lock().await = true;
         tracing::info!("Daemon activated");
         Ok(())
@@ -70,13 +164,201 @@ impl DaemonState {
         Ok(())
     }
 
+    /// Create the configured audio source, start it and store its receiver
+    /// for the processing loops.
+    pub async fn start_audio_source(&self) -> anyhow::Result<()> {
+        let (audio_tx, audio_rx) = broadcast::channel(self.config.buffer.broadcast_capacity);
+        if self.config.debug.record_audio {
+            // The recorder ends on its own once the source drops the sender
+            recorder::spawn_session_recorder(
+                &self.config.debug,
+                audio_tx.subscribe(),
+                self.config.audio.sample_rate,
+            )?;
+        }
+        level::spawn_level_meter(
+            audio_tx.subscribe(),
+            self.config.audio.sample_rate,
+            self.config.audio.level_interval_ms,
+            self.audio_level.clone(),
+            self.event_tx.clone(),
+        );
+        if self.config.audio.clipping_warnings {
+            clipping::spawn_clipping_monitor(
+                audio_tx.subscribe(),
+                self.config.audio.sample_rate,
+                self.config.audio.gain,
+                self.event_tx.clone(),
+            );
+        }
+        let mut audio_source = source::create_audio_source(&self.config.audio)?;
+        audio_source.start(audio_tx.clone())?;
+        tracing::info!("Audio source '{}' started", audio_source.name());
+        *self.audio_source.lock().await = Some(audio_source);
+        *self.audio_rx.lock().await = Some(audio_rx);
+
+        let watchdog_seconds = self.config.timeouts.audio_watchdog_seconds;
+        if watchdog_seconds > 0 {
+            let audio_config = self.config.audio.clone();
+            let handle = watchdog::spawn_watchdog(
+                audio_tx,
+                self.audio_source.clone(),
+                move || source::create_audio_source(&audio_config),
+                std::time::Duration::from_secs(watchdog_seconds),
+                self.event_tx.clone(),
+            );
+            *self.watchdog_handle.lock().await = Some(handle);
+        }
+        Ok(())
+    }
+
+    /// Stop the running audio source, if any, and drop its receiver.
+    pub async fn stop_audio_source(&self) -> anyhow::Result<()> {
+        if let Some(handle) = self.watchdog_handle.lock().await.take() {
+            handle.abort();
+        }
+        let audio_source = self.audio_source.lock().await.take();
+        *self.audio_rx.lock().await = None;
+        if let Some(mut audio_source) = audio_source {
+            audio_source.stop()?;
+        }
+        Ok(())
+    }
+
+    /// Mute the first `audio.cue_gate_ms` of a new listening session so a
+    /// start cue is not transcribed, and hand back the gate for the loop.
+    fn engage_cue_gate(&self) -> Arc<InputGate> {
+        self.input_gate.engage(self.config.audio.cue_gate_ms);
+        Arc::clone(&self.input_gate)
+    }
+
+    /// Create the outputs enabled in `output.sinks` unless they exist
+    /// already.
+    pub async fn init_output(&self) -> anyhow::Result<()> {
+        let mut sinks = self.sinks.lock().await;
+        if sinks.is_empty() {
+            *sinks = sink::create_sinks(&self.config)?;
+            let names: Vec<&str> = sinks.iter().map(|sink| sink.name()).collect();
+            tracing::info!("Output to: {}", names.join(", "));
+        }
+        Ok(())
+    }
+
+    fn text_output(&self) -> anyhow::Result<TextOutput> {
+        Ok(TextOutput {
+            sinks: self.sinks.clone(),
+            session_log: self.session_log.clone(),
+            typed_history: self.typed_history.clone(),
+            pause_buffer: self.pause_buffer.clone(),
+            drafting: self.drafting.clone(),
+            voice_editing: self.config.output.voice_editing,
+            typing: self.config.types_text(),
+            events: self.config.output.sinks.events,
+            joiner: UtteranceJoiner::from_config(&self.config.output)?,
+            submit_key: self
+                .config
+                .output
+                .submit_key
+                .as_deref()
+                .map(KeyChord::parse)
+                .transpose()?,
+            snippets: Snippets::new(&self.config.output.snippets),
+            language: self.language.clone(),
+            detected_language: self.detected_language.clone(),
+            llm_cleaner: self.llm_cleaner.clone(),
+            llm_enabled: self.config.llm.enabled,
+            llm_input: LlmInput::parse(&self.config.llm.input)?,
+            post_processor: PostProcessor::from_config(&self.config)?,
+            keyboard_timeout_seconds: self.config.timeouts.keyboard_timeout_seconds,
+            event_tx: self.event_tx.clone(),
+        })
+    }
+
+    /// Deliver the text held back while paused, in the order it was spoken.
+    /// With `resume`, text stops being held once the queue is empty.
+    /// Returns how many transcriptions were delivered.
+    pub async fn flush_held(&self, resume: bool) -> anyhow::Result<usize> {
+        let text_output = self.text_output()?;
+        let mut flushed = 0;
+        loop {
+            // Text transcribed while flushing is queued behind the rest
+            let held = self.pause_buffer.lock().await.take(resume);
+            if held.is_empty() {
+                break;
+            }
+            for item in &held {
+                text_output.deliver_now(&item.text, item.confidence).await;
+            }
+            flushed += held.len();
+        }
+        Ok(flushed)
+    }
+
+    fn loaded_models(&self) -> LoadedModels {
+        LoadedModels {
+            backend: self.backend.clone(),
+            wake_engine: self.wake_engine.clone(),
+            retry_backend: self.retry_backend.clone(),
+            streaming_backend: self.streaming_backend.clone(),
+            engine_pool: self.engine_pool.clone(),
+        }
+    }
+
+    /// Handles for transcribing utterances again, see `whisper.retry`.
+    pub fn second_pass(&self) -> SecondPass {
+        SecondPass::new(
+            Arc::new(self.config.clone()),
+            self.retry_backend.clone(),
+            self.task.clone(),
+            self.last_utterance.clone(),
+            self.event_tx.clone(),
+        )
+    }
+
+    /// Unload models after `timeouts.model_idle_unload_seconds` unless the
+    /// daemon is activated again first. Start loads them again as needed.
+    pub async fn schedule_idle_unload(&self) {
+        let idle_seconds = self.config.timeouts.model_idle_unload_seconds;
+        if idle_seconds == 0 {
+            return;
+        }
+        let models = self.loaded_models();
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(idle_seconds)).await;
+            if models.unload().await {
+                tracing::info!("Unloaded models after {}s idle", idle_seconds);
+            }
+        });
+        if let Some(previous) = self.idle_unload_handle.lock().await.replace(handle) {
+            previous.abort();
+        }
+    }
+
+    fn language_tracker(&self) -> LanguageTracker {
+        LanguageTracker {
+            detected: self.detected_language.clone(),
+            next: self.next_language.clone(),
+            lock_to_first: self.config.whisper.detect_language_once,
+            event_tx: self.event_tx.clone(),
+        }
+    }
+
+    /// Receive daemon events such as live audio levels.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Event> {
+        self.event_tx.subscribe()
+    }
+
     pub async fn get_status(&self) -> StatusInfo {
         let is_active = *self.is_active.lock().await;
         let language = self.language.lock().await.clone();
+        let audio_level = *self.audio_level.lock().await;
+        let detected_language = self.detected_language.lock().await.clone();
         StatusInfo {
             is_running: true,
             is_active,
             language,
+            audio_level,
+            detected_language,
         }
     }
 
@@ -90,17 +372,19 @@ impl DaemonState {
             return Err(anyhow::anyhow!("Already processing audio"));
         }
 
+        let text_output = self.text_output()?;
         let audio_rx_option: Option<broadcast::Receiver<Vec<f32>>> =
             self.audio_rx.lock().await.take();
-        let whisper_engine = self.whisper_engine.clone();
-        let virtual_keyboard = self.virtual_keyboard.clone();
-        let llm_cleaner = self.llm_cleaner.clone();
+        let backend = self.backend.clone();
+        let wake_engine = self.wake_engine.clone();
+        let second_pass = self.second_pass();
         let language = self.language.clone();
+        let language_tracker = self.language_tracker();
+        language_tracker.reset().await;
         let config = self.config.clone();
-        let vad_threshold_start = self.config.vad.threshold_start;
-        let vad_threshold_stop = self.config.vad.threshold_stop;
-        let silence_duration_ms = self.config.vad.min_silence_duration_ms;
+        let vad_config = self.config.vad.clone();
         let gain = self.config.audio.gain;
+        let sample_rate = self.config.audio.sample_rate;
 
         if audio_rx_option.is_none() {
             return Err(anyhow::anyhow!("Audio receiver not available"));
@@ -108,23 +392,46 @@ impl DaemonState {
 
         let mut audio_rx = audio_rx_option.unwrap();
         let is_processing_flag = self.is_processing.clone();
+        // Created up front so a bad VAD setup (e.g. missing model) is reported
+        let mut speech_detector = SpeechDetector::from_config(&vad_config, gain, sample_rate)?;
+        let confidence_filter = ConfidenceFilter::from_config(&config.whisper)?;
+        let chunker = Chunker::from_config(&config.whisper.chunking, sample_rate);
+        let wake = config
+            .wake
+            .enabled
+            .then(|| WakeWord::from_config(&config.wake, sample_rate));
+        let mut calibration = (vad_config.calibration_ms > 0)
+            .then(|| AmbientCalibration::new(sample_rate, vad_config.calibration_ms));
+        let event_tx = self.event_tx.clone();
+        let input_gate = self.engage_cue_gate();
+        let mut noise_gate = NoiseGate::from_config(&self.config.audio);
+        let order = self.delivery_order.clone();
 
         let vad_task = tokio::spawn(async move {
             *is_processing_flag.lock().await = true;
 
             tracing::info!("VAD processing task started");
 
-            let mut speech_detector = SpeechDetector::new(
-                vad_threshold_start,
-                vad_threshold_stop,
-                silence_duration_ms,
-                gain,
-            )
-            .unwrap();
 
             loop {
                 match audio_rx.recv().await {
-                    Ok(samples) => {
+                    Ok(mut samples) => {
+                        input_gate.apply(&mut samples);
+                        if let Some(noise_gate) = noise_gate.as_mut() {
+                            noise_gate.apply(&mut samples);
+                        }
+                        if let Some(ambient) = calibration.as_mut() {
+                            if let Some(level) = ambient.push(&samples) {
+                                finish_calibration(
+                                    &mut speech_detector,
+                                    level,
+                                    &vad_config,
+                                    &event_tx,
+                                );
+                                calibration = None;
+                            }
+                            continue;
+                        }
                         tracing::debug!(
                             "Received audio chunk: {} samples, first 3 values: {:.4}, {:.4}, {:.4}",
                             samples.len(),
@@ -135,17 +442,37 @@ impl DaemonState {
                         let vad_result = speech_detector.process_audio(&samples);
                         tracing::debug!("VAD returned: Some={}", vad_result.is_some());
                         if let Some(speech_audio) = vad_result {
+                            if let Some(wake) = wake.as_ref().filter(|w| !w.is_dictating()) {
+                                // Keep receiving audio while the spotter runs
+                                let wake = wake.clone();
+                                let wake_engine = wake_engine.clone();
+                                tokio::spawn(async move {
+                                    wake.listen(&wake_engine, &speech_audio).await;
+                                });
+                                continue;
+                            }
                             tracing::info!(
                                 "Speech detected, starting transcription: {} samples",
                                 speech_audio.len()
                             );
+                            recorder::save_segment(
+                                &config.debug,
+                                &speech_audio,
+                                config.audio.sample_rate,
+                            );
 
-                            let engine_ref = whisper_engine.clone();
-                            let keyboard_ref = virtual_keyboard.clone();
-                            let llm_cleaner_ref = llm_cleaner.clone();
-                            let lang = language.lock().await.clone();
+                            let engine_ref = backend.clone();
+                            let chunker = chunker.clone();
+                            let text_output = text_output.clone();
+                            let second_pass = second_pass.clone();
+                            let language_tracker = language_tracker.clone();
+                            let lang = language_tracker
+                                .language_for(&language.lock().await)
+                                .await;
                             let timeout_config = config.timeouts.clone();
-                            let llm_enabled = config.llm.enabled;
+                            let confidence_filter = confidence_filter.clone();
+                            let wake = wake.clone();
+                            let ticket = order.ticket();
                             tokio::spawn(async move {
                                 tracing::debug!(
                                     "Starting Whisper transcription for {} samples",
@@ -154,89 +481,43 @@ impl DaemonState {
 
                                 let transcription_result = tokio::time::timeout(
                                     tokio::time::Duration::from_secs(timeout_config.whisper_timeout_seconds),
-                                    async {
-                                        let mut engine_lock = engine_ref.lock().await;
-                                        if let Some(ref mut engine) = *engine_lock {
-                                            engine.transcribe(&speech_audio, &lang).await
-                                        } else {
-                                            Err(anyhow::anyhow!("Whisper engine not available"))
-                                        }
-                                    },
+                                    transcribe_utterance(&engine_ref, &chunker, &speech_audio, &lang),
                                 )
                                 .await;
+                                // Workers may finish out of order; type in the order spoken
+                                ticket.wait_turn().await;
 
                                 match transcription_result {
-                                    Ok(Ok(text)) => {
+                                    Ok(Ok(Transcription {
+                                        text,
+                                        language: detected,
+                                        confidence,
+                                        ..
+                                    })) => {
                                         tracing::info!("Whisper raw: '{}'", text);
-                                        let post_processed =
-                                            transcription::post_process_transcription(&text);
-                                        tracing::info!("Post-processed: '{}'", post_processed);
-
-                                        let final_text = if llm_enabled {
-                                            match llm_cleaner_ref.lock().await.as_ref() {
-                                                Some(cleaner) => {
-                                                    match cleaner.clean(&post_processed).await {
-                                                        Ok(cleaned) => {
-                                                            tracing::info!(
-                                                                "LLM output: '{}'",
-                                                                cleaned
-                                                            );
-                                                            cleaned
-                                                        }
-                                                        Err(e) => {
-                                                            tracing::warn!(
-                                                                "LLM cleanup failed, using raw transcription: {}",
-                                                                e
-                                                            );
-                                                            post_processed
-                                                        }
-                                                    }
-                                                }
-                                                None => {
-                                                    tracing::warn!("LLM cleaner not initialized");
-                                                    post_processed
-                                                }
-                                            }
-                                        } else {
-                                            post_processed
+                                        language_tracker.record(detected).await;
+                                        let text = match wake {
+                                            Some(wake) => wake.filter_dictated(&text),
+                                            None => text,
                                         };
-
-                                        tracing::info!("Typing: '{}'", final_text);
-
-                                        let mut keyboard_lock = keyboard_ref.lock().await;
-                                        if let Some(ref mut keyboard) = *keyboard_lock {
-                                            tracing::debug!(
-                                                "Starting keyboard typing for: '{}'",
-                                                final_text
-                                            );
-                                            let typing_result = tokio::time::timeout(
-                                                tokio::time::Duration::from_secs(timeout_config.keyboard_timeout_seconds),
-                                                async {
-                                                    let result =
-                                                        keyboard.type_text(&final_text).await;
-                                                    Ok::<_, anyhow::Error>(result)
-                                                },
-                                            )
-                                            .await;
-
-                                            match typing_result {
-                                                Ok(Ok(_)) => {
-                                                    tracing::info!("Successfully typed text");
-                                                    tracing::debug!("Finished keyboard typing");
-                                                }
-                                                Ok(Err(e)) => {
-                                                    tracing::error!("Keyboard typing error: {}", e);
-                                                }
-                                                Err(_) => {
-                                                    tracing::error!(
-                                                        "Keyboard typing operation timed out after {} seconds",
-                                                        timeout_config.keyboard_timeout_seconds
-                                                    );
-                                                }
-                                            }
-                                        } else {
-                                            tracing::warn!("Virtual keyboard not available");
+                                        if text.trim().is_empty() {
+                                            return;
+                                        }
+                                        let utterance = Utterance {
+                                            audio: speech_audio,
+                                            language: lang,
+                                            text: text.clone(),
+                                        };
+                                        second_pass.offer(utterance, confidence).await;
+                                        if !text_output.screen_confidence(
+                                            &confidence_filter,
+                                            &text,
+                                            confidence,
+                                        ) {
+                                            return;
                                         }
+                                        let final_text = text_output.finalize(&text).await;
+                                        text_output.deliver(&final_text, confidence).await;
                                     }
                                     Ok(Err(e)) => {
                                         tracing::error!("Transcription error: {}", e);
@@ -275,12 +556,11 @@ impl DaemonState {
             return Err(anyhow::anyhow!("Already processing audio"));
         }
 
+        let text_output = self.text_output()?;
         let audio_rx_option: Option<broadcast::Receiver<Vec<f32>>> =
             self.audio_rx.lock().await.take();
-        let streaming_engine = self.streaming_engine.clone();
-        let virtual_keyboard = self.virtual_keyboard.clone();
-        let llm_cleaner = self.llm_cleaner.clone();
-        let config = self.config.clone();
+        let streaming_backend = self.streaming_backend.clone();
+        let event_tx = self.event_tx.clone();
 
         if audio_rx_option.is_none() {
             return Err(anyhow::anyhow!("Audio receiver not available"));
@@ -288,6 +568,9 @@ impl DaemonState {
 
         let mut audio_rx = audio_rx_option.unwrap();
         let is_processing_flag = self.is_processing.clone();
+        let input_gate = self.engage_cue_gate();
+        let mut noise_gate = NoiseGate::from_config(&self.config.audio);
+        let restore = self.config.streaming.restore_punctuation;
 
         let streaming_task = tokio::spawn(async move {
             *is_processing_flag.lock().await = true;
@@ -296,91 +579,41 @@ impl DaemonState {
 
             loop {
                 match audio_rx.recv().await {
-                    Ok(samples) => {
+                    Ok(mut samples) => {
+                        input_gate.apply(&mut samples);
+                        if let Some(noise_gate) = noise_gate.as_mut() {
+                            noise_gate.apply(&mut samples);
+                        }
                         tracing::debug!("Received audio chunk: {} samples", samples.len());
 
-                        let mut engine_lock = streaming_engine.lock().await;
+                        let mut engine_lock = streaming_backend.lock().await;
                         if let Some(ref mut engine) = *engine_lock {
-                            match engine.send_audio(&samples) {
-                            Ok(Some(text)) => {
-                                tracing::info!("Whisper raw: '{}'", text);
-
-                                let post_processed =
-                                    transcription::post_process_transcription(&text);
-                                tracing::info!("Post-processed: '{}'", post_processed);
-
-                                let final_text = if config.llm.enabled {
-                                    match llm_cleaner.lock().await.as_ref() {
-                                        Some(cleaner) => {
-                                            match cleaner.clean(&post_processed).await {
-                                                Ok(cleaned) => {
-                                                    tracing::info!(
-                                                        "LLM output: '{}'",
-                                                        cleaned
-                                                    );
-                                                    cleaned
-                                                }
-                                                Err(e) => {
-                                                    tracing::warn!(
-                                                        "LLM cleanup failed, using raw transcription: {}",
-                                                        e
-                                                    );
-                                                    post_processed
-                                                }
-                                            }
-                                        }
-                                        None => {
-                                            tracing::warn!("LLM cleaner not initialized");
-                                            post_processed
-                                        }
+                            match engine.push_audio(&samples) {
+                                Ok(Some(mut text)) => {
+                                    let draft = engine.is_draft();
+                                    tracing::info!("Whisper raw: '{}'", text);
+                                    if restore {
+                                        let language = text_output.current_language().await;
+                                        text = restore_punctuation(&text, &language);
                                     }
-                                } else {
-                                    post_processed
-                                };
-
-                                let mut keyboard_lock = virtual_keyboard.lock().await;
-                                if let Some(ref mut keyboard) = *keyboard_lock {
-                                    tracing::debug!(
-                                        "Starting keyboard typing for: '{}'",
-                                        final_text
-                                    );
-                                    let typing_result = tokio::time::timeout(
-                                        tokio::time::Duration::from_secs(config.timeouts.keyboard_timeout_seconds),
-                                        async {
-                                            let result =
-                                                keyboard.type_text(&final_text).await;
-                                            Ok::<_, anyhow::Error>(result)
-                                        },
-                                    )
-                                    .await;
-
-                                    match typing_result {
-                                        Ok(Ok(_)) => {
-                                            tracing::info!("Successfully typed text");
-                                            tracing::debug!("Finished keyboard typing");
-                                        }
-                                        Ok(Err(e)) => {
-                                            tracing::error!("Keyboard typing error: {}", e);
-                                        }
-                                        Err(_) => {
-                                            tracing::error!(
-                                                "Keyboard typing operation timed out after {} seconds",
-                                                config.timeouts.keyboard_timeout_seconds
-                                            );
-                                        }
+                                    let final_text = text_output.finalize(&text).await;
+                                    text_output.deliver_streamed(&final_text, draft).await;
+                                }
+                                Ok(None) => {
+                                    if let Some(partial) = engine.take_partial() {
+                                        tracing::debug!("Partial: '{}'", partial);
+                                        let _ = event_tx.send(Event::Partial(partial));
                                     }
                                 }
-                            }
-                            Ok(None) => {}
-                            Err(e) => {
-                                tracing::error!(
-                                    "Failed to send audio to streaming engine: {}",
-                                    e
-                                );
+                                Err(e) => {
+                                    tracing::error!(
+                                        "Failed to send audio to streaming engine: {}",
+                                        e
+                                    );
+                                }
                             }
                         }
                     }
-                    }
                     Err(broadcast::error::RecvError::Lagged(n)) => {
                         tracing::warn!("Streaming lagged, dropped {} audio chunks", n);
                     }
@@ -401,8 +634,8 @@ impl DaemonState {
     pub async fn stop_vad_processing(&self) {
         *self.is_processing.lock().await = false;
 
-        if let Some(mut streaming_engine) = self.streaming_engine.lock().await.take() {
-            streaming_engine.stop().await;
+        if let Some(mut streaming_backend) = self.streaming_backend.lock().await.take() {
+            streaming_backend.stop().await;
             tracing::info!("Streaming engine stopped");
         }
 
@@ -425,10 +658,9 @@ impl DaemonState {
 
         let audio_rx_option: Option<broadcast::Receiver<Vec<f32>>> =
             self.audio_rx.lock().await.take();
-        let vad_threshold_start = self.config.vad.threshold_start;
-        let vad_threshold_stop = self.config.vad.threshold_stop;
-        let silence_duration_ms = self.config.vad.min_silence_duration_ms;
+        let vad_config = self.config.vad.clone();
         let gain = self.config.audio.gain;
+        let sample_rate = self.config.audio.sample_rate;
         let manual_buffer = self.manual_speech_buffer.clone();
         let is_manual_mode = self.is_manual_mode.clone();
 
@@ -438,6 +670,12 @@ impl DaemonState {
 
         let mut audio_rx = audio_rx_option.unwrap();
         let is_processing_flag = self.is_processing.clone();
+        let mut speech_detector = SpeechDetector::from_config(&vad_config, gain, sample_rate)?;
+        let mut calibration = (vad_config.calibration_ms > 0)
+            .then(|| AmbientCalibration::new(sample_rate, vad_config.calibration_ms));
+        let event_tx = self.event_tx.clone();
+        let input_gate = self.engage_cue_gate();
+        let mut noise_gate = NoiseGate::from_config(&self.config.audio);
 
         let vad_task = tokio::spawn(async move {
             *is_processing_flag.lock().await = true;
@@ -445,17 +683,26 @@ impl DaemonState {
 
             tracing::info!("Manual mode VAD task started");
 
-            let mut speech_detector = SpeechDetector::new(
-                vad_threshold_start,
-                vad_threshold_stop,
-                silence_duration_ms,
-                gain,
-            )
-            .unwrap();
 
             loop {
                 match audio_rx.recv().await {
-                    Ok(samples) => {
+                    Ok(mut samples) => {
+                        input_gate.apply(&mut samples);
+                        if let Some(noise_gate) = noise_gate.as_mut() {
+                            noise_gate.apply(&mut samples);
+                        }
+                        if let Some(ambient) = calibration.as_mut() {
+                            if let Some(level) = ambient.push(&samples) {
+                                finish_calibration(
+                                    &mut speech_detector,
+                                    level,
+                                    &vad_config,
+                                    &event_tx,
+                                );
+                                calibration = None;
+                            }
+                            continue;
+                        }
                         let vad_result = speech_detector.process_audio(&samples);
                         if let Some(speech_audio) = vad_result {
                             tracing::info!(
@@ -491,6 +738,7 @@ impl DaemonState {
             return Err(anyhow::anyhow!("Not in manual mode"));
         }
 
+        let text_output = self.text_output()?;
         let buffer = {
             let mut buf = self.manual_speech_buffer.lock().await;
             if buf.is_empty() {
@@ -504,97 +752,57 @@ impl DaemonState {
             buffer.len()
         );
 
-        let whisper_engine = self.whisper_engine.clone();
-        let virtual_keyboard = self.virtual_keyboard.clone();
-        let llm_cleaner = self.llm_cleaner.clone();
-        let language = self.language.lock().await.clone();
+        recorder::save_segment(&self.config.debug, &buffer, self.config.audio.sample_rate);
+
+        let backend = self.backend.clone();
+        let chunker =
+            Chunker::from_config(&self.config.whisper.chunking, self.config.audio.sample_rate);
+        let second_pass = self.second_pass();
+        let confidence_filter = ConfidenceFilter::from_config(&self.config.whisper)?;
+        let language_tracker = self.language_tracker();
+        let language = language_tracker
+            .language_for(&self.language.lock().await)
+            .await;
         let timeout_config = self.config.timeouts.clone();
-        let llm_enabled = self.config.llm.enabled;
+        let ticket = self.delivery_order.ticket();
 
         tokio::spawn(async move {
             let transcription_result = tokio::time::timeout(
                 tokio::time::Duration::from_secs(timeout_config.whisper_timeout_seconds),
-                async {
-                    let mut engine_lock = whisper_engine.lock().await;
-                    if let Some(ref mut engine) = *engine_lock {
-                        engine.transcribe(&buffer, &language).await
-                    } else {
-                        Err(anyhow::anyhow!("Whisper engine not available"))
-                    }
-                },
+                transcribe_utterance(&backend, &chunker, &buffer, &language),
             )
             .await;
+            // An earlier utterance may still be transcribing
+            ticket.wait_turn().await;
 
             match transcription_result {
-                Ok(Ok(text)) => {
+                Ok(Ok(Transcription {
+                    text,
+                    language: detected,
+                    confidence,
+                    ..
+                })) => {
                     tracing::info!("Whisper raw (manual): '{}'", text);
+                    language_tracker.record(detected).await;
+                    let utterance = Utterance {
+                        audio: buffer,
+                        language,
+                        text: text.clone(),
+                    };
+                    second_pass.offer(utterance, confidence).await;
+                    if !text_output.screen_confidence(&confidence_filter, &text, confidence) {
+                        return;
+                    }
                     let final_text = if skip_post_process {
                         tracing::info!("Skipping post-process, using raw text");
                         text
                     } else {
-                        let post_processed = transcription::post_process_transcription(&text);
-                        tracing::info!("Post-processed (manual): '{}'", post_processed);
-
-                        if llm_enabled {
-                            match llm_cleaner.lock().await.as_ref() {
-                                Some(cleaner) => {
-                                    match cleaner.clean(&post_processed).await {
-                                        Ok(cleaned) => {
-                                            tracing::info!(
-                                                "LLM output (manual): '{}'",
-                                                cleaned
-                                            );
-                                            cleaned
-                                        }
-                                        Err(e) => {
-                                            tracing::warn!(
-                                                "LLM cleanup failed, using raw transcription: {}",
-                                                e
-                                            );
-                                            post_processed
-                                        }
-                                    }
-                                }
-                                None => {
-                                    tracing::warn!("LLM cleaner not initialized");
-                                    post_processed
-                                }
-                            }
-                        } else {
-                            post_processed
-                        }
+                        text_output.finalize(&text).await
                     };
 
-                    tracing::info!("Typing (manual): '{}'", final_text);
-
-                    let mut keyboard_lock = virtual_keyboard.lock().await;
-                    if let Some(ref mut keyboard) = *keyboard_lock {
-                        let typing_result = tokio::time::timeout(
-                            tokio::time::Duration::from_secs(timeout_config.keyboard_timeout_seconds),
-                            async {
-                                keyboard.type_text(&final_text).await
-                            },
-                        )
-                        .await;
-
-                        match typing_result {
-                            Ok(Ok(_)) => {
-                                tracing::info!("Manual mode: successfully typed text");
-                            }
-                            Ok(Err(e)) => {
-                                tracing::error!("Manual mode: keyboard typing error: {}", e);
-                            }
-                            Err(_) => {
-                                tracing::error!(
-                                    "Manual mode: keyboard typing timed out after {} seconds",
-                                    timeout_config.keyboard_timeout_seconds
-                                );
-                            }
-                        }
-                    } else {
-                        tracing::warn!("Manual mode: virtual keyboard not available");
-                    }
+                    text_output.deliver(&final_text, confidence).await;
                 }
+
                 Ok(Err(e)) => {
                     tracing::error!("Manual mode: transcription error: {}", e);
                 }
@@ -619,9 +827,537 @@ impl DaemonState {
     }
 }
 
+/// Transcribe one utterance with the loaded backend, in chunks if it is long.
+async fn transcribe_utterance(
+    backend: &SharedBackend,
+    chunker: &Chunker,
+    audio: &[f32],
+    language: &str,
+) -> anyhow::Result<Transcription> {
+    // Release the lock before transcribing so other utterances can use idle workers
+    let workers = backend.lock().await.clone();
+    match workers {
+        Some(workers) => chunker.transcribe(&workers, audio, language).await,
+        None => Err(anyhow::anyhow!("Transcription backend not available")),
+    }
+}
+
+/// Apply the ambient level measured on `Start` and warn when the room is too
+/// loud for fixed energy thresholds.
+fn finish_calibration(
+    detector: &mut SpeechDetector,
+    level: f32,
+    vad_config: &VadConfig,
+    event_tx: &broadcast::Sender<Event>,
+) {
+    tracing::info!("Ambient noise level: {:.1} dBFS", amplitude_to_dbfs(level));
+    detector.calibrate(level);
+
+    // Adaptive thresholds follow the room, and other backends do not use RMS
+    let fixed_energy = matches!(vad_config.backend.as_str(), "energy" | "rms")
+        && !vad_config.adaptive_thresholds;
+    if fixed_energy {
+        let threshold_start = vad_config.effective_thresholds().0;
+        if let Some(warning) = calibration::ambient_warning(level, threshold_start) {
+            tracing::warn!("{}", warning);
+            // No subscribers is not an error
+            let _ = event_tx.send(Event::Warning(warning));
+        }
+    }
+}
+
+/// Follows the language Whisper reports while `language` is "auto".
+#[derive(Clone)]
+struct LanguageTracker {
+    detected: Arc<Mutex<Option<String>>>,
+    /// One-off language, used up by the next `language_for`.
+    next: Arc<Mutex<Option<String>>>,
+    /// Keep transcribing in the first language detected this session
+    /// instead of detecting again on every segment.
+    lock_to_first: bool,
+    event_tx: broadcast::Sender<Event>,
+}
+
+impl LanguageTracker {
+    /// Forget the language detected in a previous session.
+    async fn reset(&self) {
+        *self.detected.lock().await = None;
+    }
+
+    /// Language to hand Whisper for the next segment.
+    async fn language_for(&self, configured: &str) -> String {
+        if let Some(next) = self.next.lock().await.take() {
+            tracing::info!("Transcribing this utterance in {}", next);
+            return next;
+        }
+        if configured == AUTO_LANGUAGE && self.lock_to_first {
+            if let Some(detected) = self.detected.lock().await.clone() {
+                return detected;
+            }
+        }
+        configured.to_string()
+    }
+
+    /// Store a language Whisper detected, announcing it when it changed.
+    async fn record(&self, detected: Option<String>) {
+        let Some(language) = detected else {
+            return;
+        };
+        let mut current = self.detected.lock().await;
+        if current.as_deref() != Some(language.as_str()) {
+            tracing::info!("Detected spoken language: {}", language);
+            *current = Some(language.clone());
+            // No subscribers is not an error
+            let _ = self.event_tx.send(Event::LanguageDetected(language));
+        }
+    }
+}
+
+/// Handles to every loaded model, for unloading them while idle.
+#[derive(Clone)]
+struct LoadedModels {
+    backend: SharedBackend,
+    wake_engine: SharedBackend,
+    retry_backend: SharedBackend,
+    streaming_backend: Arc<Mutex<Option<Box<dyn StreamingBackend>>>>,
+    engine_pool: Arc<Mutex<EnginePool>>,
+}
+
+impl LoadedModels {
+    /// Drop every loaded model, returning whether there were any.
+    /// Transcriptions still running keep their model until they finish.
+    async fn unload(&self) -> bool {
+        let mut unloaded = self.backend.lock().await.take().is_some();
+        unloaded |= self.wake_engine.lock().await.take().is_some();
+        unloaded |= self.retry_backend.lock().await.take().is_some();
+        unloaded |= self.streaming_backend.lock().await.take().is_some();
+        unloaded |= self.engine_pool.lock().await.clear() > 0;
+        unloaded
+    }
+}
+
+/// Hands out tickets in the order utterances are spoken, so results from
+/// parallel workers, and manual completions, are delivered in that order.
+#[derive(Clone)]
+struct DeliveryOrder {
+    issued: Arc<AtomicU64>,
+    /// The ticket whose turn it is.
+    next: Arc<watch::Sender<u64>>,
+    /// Dropped tickets still waiting on an earlier one.
+    finished: Arc<std::sync::Mutex<BTreeSet<u64>>>,
+}
+
+impl DeliveryOrder {
+    fn new() -> Self {
+        Self {
+            issued: Arc::new(AtomicU64::new(0)),
+            next: Arc::new(watch::Sender::new(0)),
+            finished: Arc::new(std::sync::Mutex::new(BTreeSet::new())),
+        }
+    }
+
+    fn ticket(&self) -> Ticket {
+        Ticket {
+            order: self.clone(),
+            number: self.issued.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Pass the turn on past `number` and any later tickets already done.
+    fn finish(&self, number: u64) {
+        let mut finished = self.finished.lock().unwrap_or_else(|e| e.into_inner());
+        finished.insert(number);
+        self.next.send_modify(|next| {
+            while finished.remove(next) {
+                *next += 1;
+            }
+        });
+    }
+}
+
+/// A place in the `DeliveryOrder`. The turn passes on when the ticket is
+/// dropped, so an utterance that fails or whose task dies does not hold up
+/// the ones after it.
+struct Ticket {
+    order: DeliveryOrder,
+    number: u64,
+}
+
+impl Ticket {
+    /// Wait until every earlier ticket has been delivered or dropped.
+    async fn wait_turn(&self) {
+        let mut next = self.order.next.subscribe();
+        // The sender lives in `self.order`, so this cannot fail
+        let _ = next.wait_for(|next| *next >= self.number).await;
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.order.finish(self.number);
+    }
+}
+
+/// Handles shared by the batch, streaming and manual paths to turn raw
+/// Whisper output into delivered text.
+#[derive(Clone)]
+struct TextOutput {
+    sinks: Arc<Mutex<Vec<Box<dyn OutputSink>>>>,
+    session_log: Arc<Mutex<SessionLog>>,
+    typed_history: Arc<Mutex<TypedHistory>>,
+    pause_buffer: Arc<Mutex<PauseBuffer>>,
+    /// Whether the last utterance typed is a streaming draft, which the
+    /// next streamed text corrects.
+    drafting: Arc<Mutex<bool>>,
+    /// Carry out "scratch that" and friends instead of typing them.
+    voice_editing: bool,
+    /// `output.sinks.keyboard`
+    typing: bool,
+    /// `output.sinks.events`
+    events: bool,
+    joiner: UtteranceJoiner,
+    /// `output.submit_key`
+    submit_key: Option<KeyChord>,
+    snippets: Snippets,
+    language: Arc<Mutex<String>>,
+    detected_language: Arc<Mutex<Option<String>>>,
+    llm_cleaner: Arc<Mutex<Option<LlmCleaner>>>,
+    llm_enabled: bool,
+    llm_input: LlmInput,
+    post_processor: PostProcessor,
+    keyboard_timeout_seconds: u64,
+    event_tx: broadcast::Sender<Event>,
+}
+
+impl TextOutput {
+    /// Apply `whisper.min_confidence`, reporting results that fall below it.
+    /// Returns false when the text should be discarded.
+    fn screen_confidence(
+        &self,
+        filter: &ConfidenceFilter,
+        text: &str,
+        confidence: Option<f32>,
+    ) -> bool {
+        let verdict = filter.check(confidence);
+        if let (Verdict::Flag | Verdict::Drop, Some(confidence)) = (verdict, confidence) {
+            let dropped = verdict == Verdict::Drop;
+            tracing::info!(
+                "Low confidence ({:.2}) transcription '{}'{}",
+                confidence,
+                text,
+                if dropped { " dropped" } else { "" }
+            );
+            // No subscribers is not an error
+            let _ = self.event_tx.send(Event::LowConfidence {
+                text: text.to_string(),
+                confidence,
+                dropped,
+            });
+        }
+        verdict != Verdict::Drop
+    }
+
+    /// Run post-processing and, when enabled, the LLM cleanup pass, before
+    /// or after it as `llm.input` says.
+    async fn finalize(&self, text: &str) -> String {
+        let language = self.current_language().await;
+        if self.llm_enabled && self.llm_input == LlmInput::Raw {
+            let cleaned = self.llm_pass(text).await;
+            let post_processed = self.post_processor.process(&cleaned, &language);
+            tracing::info!("Post-processed: '{}'", post_processed);
+            return post_processed;
+        }
+
+        let post_processed = self.post_processor.process(text, &language);
+        tracing::info!("Post-processed: '{}'", post_processed);
+        if !self.llm_enabled {
+            return post_processed;
+        }
+        self.llm_pass(&post_processed).await
+    }
+
+    /// The LLM's correction of `text`, or `text` itself when the LLM fails
+    /// or times out.
+    async fn llm_pass(&self, text: &str) -> String {
+        match self.llm_cleaner.lock().await.as_ref() {
+            Some(cleaner) => match cleaner.clean(text).await {
+                Ok(cleaned) => {
+                    tracing::info!("LLM output: '{}'", cleaned);
+                    cleaned
+                }
+                Err(e) => {
+                    tracing::warn!("LLM cleanup failed, using raw transcription: {}", e);
+                    text.to_string()
+                }
+            },
+            None => {
+                tracing::warn!("LLM cleaner not initialized");
+                text.to_string()
+            }
+        }
+    }
+
+    /// The configured language, or the detected one while it is "auto".
+    async fn current_language(&self) -> String {
+        let language = self.language.lock().await.clone();
+        if language == AUTO_LANGUAGE {
+            if let Some(detected) = self.detected_language.lock().await.clone() {
+                return detected;
+            }
+        }
+        language
+    }
+
+    /// Expand snippets, then hand the text to every output sink and
+    /// `Event::Transcribed` subscribers, unless it is held back while paused.
+    async fn deliver(&self, text: &str, confidence: Option<f32>) {
+        if self.pause_buffer.lock().await.offer(text, confidence) {
+            tracing::info!("Paused, holding '{}'", text);
+            return;
+        }
+        self.deliver_now(text, confidence).await;
+    }
+
+    /// `deliver`, even while paused.
+    async fn deliver_now(&self, text: &str, confidence: Option<f32>) {
+        if self.try_edit(text).await {
+            return;
+        }
+        self.publish(text, confidence, SinkSet::All).await;
+    }
+
+    /// Deliver text from a streaming backend. A draft is only typed, and
+    /// the next draft or final text of the same audio corrects it with
+    /// backspaces instead of being typed after it; the other sinks only
+    /// get the final text.
+    async fn deliver_streamed(&self, text: &str, draft: bool) {
+        let mut drafting = self.drafting.lock().await;
+        if !*drafting {
+            if !draft {
+                drop(drafting);
+                self.deliver(text, None).await;
+            } else if !text.trim().is_empty() && !self.pause_buffer.lock().await.is_holding() {
+                let expanded = self.snippets.expand(text);
+                let language = self.current_language().await;
+                *drafting = self
+                    .write_to_sinks(&expanded, &language, None, SinkSet::Typing)
+                    .await;
+            }
+            return;
+        }
+
+        let expanded = self.snippets.expand(text);
+        let mut history = self.typed_history.lock().await;
+        let joined = self.join(history.before_last(), &expanded);
+        let actions = history.revise_last(&joined);
+        drop(history);
+        tracing::info!("Correcting draft: {:?}", actions);
+        for action in &actions {
+            self.edit_typing_sinks(action).await;
+        }
+
+        if !draft {
+            *drafting = false;
+            drop(drafting);
+            if !text.trim().is_empty() {
+                self.submit().await;
+                self.publish(text, None, SinkSet::Other).await;
+            }
+        }
+    }
+
+    /// Expand snippets, then hand the text to `sinks`, the session log and
+    /// `Event::Transcribed` subscribers.
+    async fn publish(&self, text: &str, confidence: Option<f32>, sinks: SinkSet) {
+        let expanded = self.snippets.expand(text);
+        let text = expanded.as_str();
+        let language = self.current_language().await;
+        if self.write_to_sinks(text, &language, confidence, sinks).await {
+            self.submit().await;
+        }
+        self.session_log.lock().await.record(text, &language);
+        if self.events {
+            // No subscribers is not an error
+            let _ = self.event_tx.send(Event::Transcribed {
+                text: text.to_string(),
+                confidence,
+            });
+        }
+    }
+
+    /// Carry out `text` if it is a spoken editing command and ndict is
+    /// typing. Returns true when the text was a command, even if there was
+    /// nothing left to edit.
+    async fn try_edit(&self, text: &str) -> bool {
+        if !self.voice_editing || !self.typing {
+            return false;
+        }
+        let Some(command) = EditCommand::parse(text) else {
+            return false;
+        };
+
+        let Some(action) = self.typed_history.lock().await.apply(command) else {
+            tracing::info!("Nothing to edit for {:?}", command);
+            return true;
+        };
+        tracing::info!("Editing command {:?}: {:?}", command, action);
+        self.edit_typing_sinks(&action).await;
+        true
+    }
+
+    /// `text` as typed after `previous`. With `output.submit_key` every
+    /// utterance starts a new message, so nothing joins them.
+    fn join(&self, previous: Option<&str>, text: &str) -> String {
+        let previous = previous.filter(|_| self.submit_key.is_none());
+        self.joiner.join(previous, text)
+    }
+
+    /// Press `output.submit_key` after a final utterance was typed.
+    async fn submit(&self) {
+        if let Some(chord) = &self.submit_key {
+            self.edit_typing_sinks(&KeyAction::Press(chord.clone())).await;
+        }
+    }
+
+    /// Carry out `action` with every typing sink.
+    async fn edit_typing_sinks(&self, action: &KeyAction) {
+        let mut sinks = self.sinks.lock().await;
+        let mut typed = false;
+        for sink in sinks.iter_mut().filter(|sink| sink.types()) {
+            typed = true;
+            let result = tokio::time::timeout(
+                tokio::time::Duration::from_secs(self.keyboard_timeout_seconds),
+                sink.edit(action),
+            )
+            .await;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    tracing::error!("Editing command failed: {}", e);
+                    self.reconnect_if_failing(sink.as_mut());
+                }
+                Err(_) => {
+                    tracing::error!(
+                        "Editing command timed out after {} seconds",
+                        self.keyboard_timeout_seconds
+                    );
+                    sink.record_failure();
+                    self.reconnect_if_failing(sink.as_mut());
+                }
+            }
+        }
+        if !typed {
+            tracing::warn!("Virtual keyboard not available");
+        }
+    }
+
+    /// Hand `text` to the sinks in `set`. Typing sinks get it joined to the
+    /// text typed before, which the typed history then records. Returns
+    /// whether a typing sink typed it.
+    async fn write_to_sinks(
+        &self,
+        text: &str,
+        language: &str,
+        confidence: Option<f32>,
+        set: SinkSet,
+    ) -> bool {
+        let joined = self.join(self.typed_history.lock().await.last_typed(), text);
+        let mut typed = false;
+
+        let mut sinks = self.sinks.lock().await;
+        if sinks.is_empty() {
+            tracing::warn!("No output available for '{}'", text);
+        }
+        for sink in sinks.iter_mut() {
+            let types = sink.types();
+            if !set.includes(types) {
+                continue;
+            }
+            let delivery = Delivery {
+                text: if types { joined.as_str() } else { text },
+                language,
+                confidence,
+            };
+            tracing::info!("Sending to {}: '{}'", sink.name(), delivery.text);
+            let timeout = tokio::time::Duration::from_secs(self.keyboard_timeout_seconds)
+                + sink.expected_duration(delivery.text);
+            let result = tokio::time::timeout(timeout, sink.write(delivery)).await;
+            match result {
+                Ok(Ok(())) => typed |= types,
+                Ok(Err(e)) => {
+                    tracing::error!("Failed to write to {}: {}", sink.name(), e);
+                    self.reconnect_if_failing(sink.as_mut());
+                }
+                Err(_) => {
+                    tracing::error!(
+                        "Writing to {} timed out after {:.1} seconds",
+                        sink.name(),
+                        timeout.as_secs_f32()
+                    );
+                    sink.record_failure();
+                    self.reconnect_if_failing(sink.as_mut());
+                }
+            }
+        }
+        drop(sinks);
+
+        if typed {
+            self.typed_history.lock().await.typed(&joined);
+        }
+        typed
+    }
+
+    /// Set up `sink` again once it keeps failing, telling subscribers with
+    /// `Event::OutputRestarted`, or with a warning when that fails too.
+    fn reconnect_if_failing(&self, sink: &mut dyn OutputSink) {
+        if !sink.needs_reconnect() {
+            return;
+        }
+        let name = sink.name().to_string();
+        tracing::warn!("Output {} keeps failing, reconnecting", name);
+        match sink.reconnect() {
+            Ok(()) => {
+                tracing::info!("Output {} reconnected", name);
+                // No subscribers is not an error
+                let _ = self.event_tx.send(Event::OutputRestarted(name));
+            }
+            Err(e) => {
+                tracing::error!("Failed to reconnect output {}: {}", name, e);
+                // No subscribers is not an error
+                let _ = self.event_tx.send(Event::Warning(format!(
+                    "Output {} is not working: {}",
+                    name, e
+                )));
+            }
+        }
+    }
+}
+
+/// Which sinks `TextOutput::write_to_sinks` writes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SinkSet {
+    All,
+    /// Only sinks that type into the focused window.
+    Typing,
+    /// Only sinks that do not type.
+    Other,
+}
+
+impl SinkSet {
+    fn includes(self, types: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Typing => types,
+            Self::Other => !types,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transcription::backend::WorkerPool;
+    use crate::transcription::engine::WhisperEngine;
 
     #[tokio::test]
     async fn test_daemon_state_new() {
@@ -631,13 +1367,464 @@ mod tests {
         assert_eq!(state.config, config);
         assert!(!*state.is_active.lock().await);
         assert!(!*state.is_processing.lock().await);
-        assert!(state.audio_capture.lock().await.is_none());
+        assert!(state.audio_source.lock().await.is_none());
         assert!(state.audio_rx.lock().await.is_none());
-        assert!(state.whisper_engine.lock().await.is_none());
-        assert!(state.virtual_keyboard.lock().await.is_none());
+        assert!(state.backend.lock().await.is_none());
+        assert!(state.sinks.lock().await.is_empty());
         assert!(state.vad_task_handle.lock().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_unload_drops_loaded_models() {
+        let state = DaemonState::new(Config::default());
+        assert!(!state.loaded_models().unload().await);
+
+        let engine = WhisperEngine::new(
+            "https://example.com/ggml-tiny.bin".to_string(),
+            "cpu".to_string(),
+        )
+        .unwrap();
+        let backend = Arc::new(WorkerPool::new(vec![Box::new(engine)]));
+        *state.backend.lock().await = Some(backend.clone());
+        state.engine_pool.lock().await.park("tiny".to_string(), backend);
+
+        assert!(state.loaded_models().unload().await);
+        assert!(state.backend.lock().await.is_none());
+        assert!(state.engine_pool.lock().await.take("tiny").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_activate_cancels_idle_unload() {
+        // Disabled by default
+        let state = DaemonState::new(Config::default());
+        state.schedule_idle_unload().await;
+        assert!(state.idle_unload_handle.lock().await.is_none());
+
+        let mut config = Config::default();
+        config.timeouts.model_idle_unload_seconds = 60;
+        let mut state = DaemonState::new(config);
+        state.schedule_idle_unload().await;
+        assert!(state.idle_unload_handle.lock().await.is_some());
+        state.activate().await.unwrap();
+        assert!(state.idle_unload_handle.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_order_follows_tickets() {
+        let order = DeliveryOrder::new();
+        let first = order.ticket();
+        let second = order.ticket();
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+
+        // The second utterance finishes first but waits for the first
+        let later = tokio::spawn({
+            let delivered = delivered.clone();
+            async move {
+                second.wait_turn().await;
+                delivered.lock().await.push(second.number);
+            }
+        });
+        tokio::task::yield_now().await;
+        assert!(delivered.lock().await.is_empty());
+
+        first.wait_turn().await;
+        delivered.lock().await.push(first.number);
+        drop(first);
+        later.await.unwrap();
+        assert_eq!(*delivered.lock().await, vec![0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_ticket_does_not_hold_up_delivery() {
+        let order = DeliveryOrder::new();
+        let first = order.ticket();
+        let second = order.ticket();
+        let third = order.ticket();
+
+        // Dropped out of turn, e.g. by a failed task
+        drop(second);
+        drop(first);
+        tokio::time::timeout(std::time::Duration::from_secs(1), third.wait_turn())
+            .await
+            .expect("the third ticket's turn never came");
+    }
+
+    #[tokio::test]
+    async fn test_language_tracker_announces_changes() {
+        let state = DaemonState::new(Config::default());
+        let tracker = state.language_tracker();
+        let mut events = state.subscribe_events();
+
+        tracker.record(Some("de".to_string())).await;
+        tracker.record(Some("de".to_string())).await;
+        tracker.record(None).await;
+        tracker.record(Some("fr".to_string())).await;
+
+        assert_eq!(events.recv().await.unwrap(), Event::LanguageDetected("de".to_string()));
+        assert_eq!(events.recv().await.unwrap(), Event::LanguageDetected("fr".to_string()));
+        assert!(events.try_recv().is_err());
+        assert_eq!(state.get_status().await.detected_language.as_deref(), Some("fr"));
+    }
+
+    #[tokio::test]
+    async fn test_finalize_applies_replacement_rules() {
+        let mut config = Config::default();
+        config.post_processing.replace = vec![crate::config::ReplaceRule {
+            pattern: r"\bend dict\b".to_string(),
+            replacement: "ndict".to_string(),
+            case_sensitive: false,
+        }];
+        let output = DaemonState::new(config).text_output().unwrap();
+        assert_eq!(output.finalize(" I use End Dict [Music] daily").await, "I use ndict daily");
+
+        let mut config = Config::default();
+        config.post_processing.replace = vec![crate::config::ReplaceRule {
+            pattern: "(".to_string(),
+            replacement: String::new(),
+            case_sensitive: false,
+        }];
+        assert!(DaemonState::new(config).text_output().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_editing_commands_are_not_typed() {
+        let state = DaemonState::new(Config::default());
+        let output = state.text_output().unwrap();
+        state.typed_history.lock().await.typed("Hello there.");
+
+        assert!(!output.try_edit("Hello again.").await);
+        assert!(output.try_edit("Scratch that.").await);
+        // Already scratched, but still not typed
+        assert!(output.try_edit("Scratch that.").await);
+        assert_eq!(
+            state.typed_history.lock().await.apply(EditCommand::Undo),
+            Some(KeyAction::Type("Hello there.".to_string()))
+        );
+
+        let mut config = Config::default();
+        config.output.voice_editing = false;
+        let output = DaemonState::new(config).text_output().unwrap();
+        assert!(!output.try_edit("Scratch that.").await);
+    }
+
+    /// Keeps what it is sent, as typed on screen when it `types`.
+    struct ScreenSink {
+        screen: Arc<std::sync::Mutex<String>>,
+        types: bool,
+    }
+
+    impl OutputSink for ScreenSink {
+        fn name(&self) -> &str {
+            "screen"
+        }
+
+        fn write<'a>(
+            &'a mut self,
+            delivery: Delivery<'a>,
+        ) -> futures_util::future::BoxFuture<'a, anyhow::Result<()>> {
+            self.screen.lock().unwrap().push_str(delivery.text);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn types(&self) -> bool {
+            self.types
+        }
+
+        fn edit<'a>(
+            &'a mut self,
+            action: &'a KeyAction,
+        ) -> futures_util::future::BoxFuture<'a, anyhow::Result<()>> {
+            let mut screen = self.screen.lock().unwrap();
+            match action {
+                KeyAction::Backspace(count) => {
+                    for _ in 0..*count {
+                        screen.pop();
+                    }
+                }
+                KeyAction::Type(text) => screen.push_str(text),
+                KeyAction::Press(chord) => screen.push_str(&format!("<{}>", chord.keysym())),
+            }
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_drafts_are_corrected_on_screen() {
+        let state = DaemonState::new(Config::default());
+        let screen = Arc::new(std::sync::Mutex::new("Hi.".to_string()));
+        let log = Arc::new(std::sync::Mutex::new(String::new()));
+        state.typed_history.lock().await.typed("Hi.");
+        *state.sinks.lock().await = vec![
+            Box::new(ScreenSink {
+                screen: screen.clone(),
+                types: true,
+            }),
+            Box::new(ScreenSink {
+                screen: log.clone(),
+                types: false,
+            }),
+        ];
+        let output = state.text_output().unwrap();
+
+        output.deliver_streamed("I sea", true).await;
+        assert_eq!(*screen.lock().unwrap(), "Hi. I sea");
+        // A restarted stream gets a new output, which still corrects the draft
+        let output = state.text_output().unwrap();
+        output.deliver_streamed("I see the", true).await;
+        assert_eq!(*screen.lock().unwrap(), "Hi. I see the");
+        output.deliver_streamed("I see the sea.", false).await;
+        assert_eq!(*screen.lock().unwrap(), "Hi. I see the sea.");
+        // Only the final text reaches sinks that cannot correct it
+        assert_eq!(*log.lock().unwrap(), "I see the sea.");
+
+        output.deliver_streamed("Bye.", false).await;
+        assert_eq!(*screen.lock().unwrap(), "Hi. I see the sea. Bye.");
+        assert_eq!(
+            state.typed_history.lock().await.apply(EditCommand::ScratchThat),
+            Some(KeyAction::Backspace(5))
+        );
+    }
+
+    /// Fails every write.
+    struct BrokenSink;
+
+    impl OutputSink for BrokenSink {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        fn write<'a>(
+            &'a mut self,
+            _delivery: Delivery<'a>,
+        ) -> futures_util::future::BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(async { Err(anyhow::anyhow!("gone")) })
+        }
+
+        fn needs_reconnect(&self) -> bool {
+            true
+        }
+
+        fn reconnect(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_sink_is_reconnected() {
+        let state = DaemonState::new(Config::default());
+        *state.sinks.lock().await = vec![Box::new(BrokenSink)];
+        let mut events = state.subscribe_events();
+        let output = state.text_output().unwrap();
+
+        output.deliver("Hello.", None).await;
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::OutputRestarted("broken".to_string())
+        );
+    }
+
+    /// Never finishes a write.
+    #[derive(Default)]
+    struct StuckSink {
+        failures: u32,
+    }
+
+    impl OutputSink for StuckSink {
+        fn name(&self) -> &str {
+            "stuck"
+        }
+
+        fn write<'a>(
+            &'a mut self,
+            _delivery: Delivery<'a>,
+        ) -> futures_util::future::BoxFuture<'a, anyhow::Result<()>> {
+            Box::pin(std::future::pending())
+        }
+
+        fn needs_reconnect(&self) -> bool {
+            self.failures >= 2
+        }
+
+        fn record_failure(&mut self) {
+            self.failures += 1;
+        }
+
+        fn reconnect(&mut self) -> anyhow::Result<()> {
+            self.failures = 0;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_sink_is_reconnected() {
+        let mut config = Config::default();
+        config.timeouts.keyboard_timeout_seconds = 0;
+        config.output.sinks.events = false;
+        let state = DaemonState::new(config);
+        *state.sinks.lock().await = vec![Box::new(StuckSink::default())];
+        let mut events = state.subscribe_events();
+        let output = state.text_output().unwrap();
+
+        output.deliver("Hello.", None).await;
+        assert!(events.try_recv().is_err());
+        output.deliver("Hello again.", None).await;
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::OutputRestarted("stuck".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_held_text_is_typed_on_flush() {
+        let state = DaemonState::new(Config::default());
+        let screen = Arc::new(std::sync::Mutex::new(String::new()));
+        *state.sinks.lock().await = vec![Box::new(ScreenSink {
+            screen: screen.clone(),
+            types: true,
+        })];
+        let output = state.text_output().unwrap();
+
+        state.pause_buffer.lock().await.hold();
+        output.deliver("One.", None).await;
+        output.deliver_streamed("Draft", true).await;
+        output.deliver("Two.", None).await;
+        assert!(screen.lock().unwrap().is_empty());
+
+        assert_eq!(state.flush_held(true).await.unwrap(), 2);
+        assert_eq!(*screen.lock().unwrap(), "One. Two.");
+        output.deliver("Three.", None).await;
+        assert_eq!(*screen.lock().unwrap(), "One. Two. Three.");
+    }
+
+    #[tokio::test]
+    async fn test_submit_key_follows_final_text() {
+        let mut config = Config::default();
+        config.output.submit_key = Some("enter".to_string());
+        let state = DaemonState::new(config);
+        let screen = Arc::new(std::sync::Mutex::new(String::new()));
+        *state.sinks.lock().await = vec![Box::new(ScreenSink {
+            screen: screen.clone(),
+            types: true,
+        })];
+        let output = state.text_output().unwrap();
+
+        output.deliver("Hello.", None).await;
+        output.deliver_streamed("How are", true).await;
+        // Each utterance is a new message, without a separator
+        assert_eq!(*screen.lock().unwrap(), "Hello.<Return>How are");
+        output.deliver_streamed("How are you?", false).await;
+        assert_eq!(*screen.lock().unwrap(), "Hello.<Return>How are you?<Return>");
+
+        let mut config = Config::default();
+        config.output.submit_key = Some("ctrl+f5".to_string());
+        assert!(DaemonState::new(config).text_output().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raw_llm_input_still_post_processes_fallback() {
+        let mut config = Config::default();
+        config.llm.enabled = true;
+        config.llm.input = "raw".to_string();
+        // No cleaner loaded, so the LLM pass falls back to its input
+        let output = DaemonState::new(config).text_output().unwrap();
+        assert_eq!(output.finalize(" hello [Music]  world ").await, "hello world");
+
+        let mut config = Config::default();
+        config.llm.input = "corrected".to_string();
+        assert!(DaemonState::new(config).text_output().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_text_output_rejects_unknown_separator() {
+        let mut config = Config::default();
+        config.output.separator = "tab".to_string();
+        assert!(DaemonState::new(config).text_output().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_screen_confidence_reports_low_results() {
+        let mut config = Config::default();
+        config.whisper.min_confidence = 0.5;
+        let state = DaemonState::new(config.clone());
+        let output = state.text_output().unwrap();
+        let mut events = state.subscribe_events();
+
+        let dropping = ConfidenceFilter::from_config(&config.whisper).unwrap();
+        assert!(output.screen_confidence(&dropping, "clear", Some(0.9)));
+        assert!(!output.screen_confidence(&dropping, "mumble", Some(0.2)));
+
+        config.whisper.low_confidence_action = "flag".to_string();
+        let flagging = ConfidenceFilter::from_config(&config.whisper).unwrap();
+        assert!(output.screen_confidence(&flagging, "mumble", Some(0.2)));
+
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::LowConfidence { text: "mumble".to_string(), confidence: 0.2, dropped: true }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::LowConfidence { text: "mumble".to_string(), confidence: 0.2, dropped: false }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_language_tracker_locks_to_first_detection() {
+        let mut config = Config::default();
+        config.whisper.detect_language_once = true;
+        let tracker = DaemonState::new(config).language_tracker();
+
+        assert_eq!(tracker.language_for("auto").await, "auto");
+        tracker.record(Some("es".to_string())).await;
+        assert_eq!(tracker.language_for("auto").await, "es");
+        // An explicit language always wins
+        assert_eq!(tracker.language_for("en").await, "en");
+
+        tracker.reset().await;
+        assert_eq!(tracker.language_for("auto").await, "auto");
+    }
+
+    #[tokio::test]
+    async fn test_language_tracker_next_language_applies_once() {
+        let state = DaemonState::new(Config::default());
+        let tracker = state.language_tracker();
+        *state.next_language.lock().await = Some("de".to_string());
+
+        assert_eq!(tracker.language_for("en").await, "de");
+        assert_eq!(tracker.language_for("en").await, "en");
+    }
+
+    async fn sink_names(state: &DaemonState) -> Vec<String> {
+        let sinks = state.sinks.lock().await;
+        sinks.iter().map(|sink| sink.name().to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_init_output_opens_transcript_instead_of_keyboard() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.txt");
+        let mut config = Config::default();
+        config.audio.source = "loopback".to_string();
+        config.output.transcript_file = Some(path.to_string_lossy().to_string());
+
+        let state = DaemonState::new(config);
+        state.init_output().await.unwrap();
+
+        assert_eq!(sink_names(&state).await, vec!["transcript"]);
+        assert!(path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_init_output_opens_json_sink_instead_of_keyboard() {
+        let mut config = Config::default();
+        config.output.json_output = Some("stdout".to_string());
+
+        let state = DaemonState::new(config);
+        state.init_output().await.unwrap();
+
+        assert_eq!(sink_names(&state).await, vec!["json"]);
+    }
+
     #[tokio::test]
     async fn test_activate() {
         let config = Config::default();
@@ -674,6 +1861,27 @@ mod tests {
         assert_eq!(status.is_active, false);
         assert_eq!(status.language, config.whisper.language);
         assert_eq!(status.language, state.language.lock().await.as_str());
+        assert_eq!(status.audio_level, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_status_reports_audio_level() {
+        let state = DaemonState::new(Config::default());
+        let level = AudioLevel { rms: 0.1, peak: 0.4 };
+        *state.audio_level.lock().await = Some(level);
+
+        let status = state.get_status().await;
+        assert_eq!(status.audio_level, Some(level));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_events_receives_published_events() {
+        let state = DaemonState::new(Config::default());
+        let mut events = state.subscribe_events();
+
+        let event = Event::AudioLevel(AudioLevel { rms: 0.2, peak: 0.3 });
+        state.event_tx.send(event.clone()).unwrap();
+        assert_eq!(events.recv().await.unwrap(), event);
     }
 
     #[tokio::test]
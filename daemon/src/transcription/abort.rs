@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Interrupts Whisper decodes in progress, for Stop, MStop and shutdown.
+///
+/// Each decode takes an `AbortToken` when it starts and polls it from
+/// whisper.cpp's abort callback; `abort` cancels every token taken so far,
+/// while decodes started afterwards run normally.
+#[derive(Debug, Clone, Default)]
+pub struct AbortSignal {
+    generation: Arc<AtomicU64>,
+}
+
+impl AbortSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every decode currently running.
+    pub fn abort(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Token for a decode about to start.
+    pub fn token(&self) -> AbortToken {
+        AbortToken {
+            generation: self.generation.clone(),
+            started: self.generation.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Tells one decode whether it was aborted.
+#[derive(Debug, Clone)]
+pub struct AbortToken {
+    generation: Arc<AtomicU64>,
+    started: u64,
+}
+
+impl AbortToken {
+    pub fn is_aborted(&self) -> bool {
+        self.generation.load(Ordering::SeqCst) != self.started
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abort_cancels_only_running_decodes() {
+        let signal = AbortSignal::new();
+        let running = signal.token();
+        assert!(!running.is_aborted());
+
+        signal.clone().abort();
+        assert!(running.is_aborted());
+        assert!(!signal.token().is_aborted());
+    }
+}
@@ -0,0 +1,752 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Semaphore};
+
+use crate::config::{Config, VoskConfig};
+use crate::transcription::abort::AbortSignal;
+use crate::transcription::cloud::CloudTranscriber;
+use crate::transcription::engine::WhisperEngine;
+use crate::transcription::grammar;
+use crate::transcription::models;
+use crate::transcription::streaming_engine::StreamingEngine;
+use crate::transcription::vocabulary;
+use crate::transcription::vosk::VoskEngine;
+use crate::transcription::whisper_server::WhisperServerBackend;
+use crate::vad::speech_detector::SpeechDetector;
+use crate::wake::WAKE_LANGUAGE;
+
+/// The result of transcribing one utterance.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Transcription {
+    pub text: String,
+    /// Language the backend detected, when asked to detect it.
+    pub language: Option<String>,
+    /// Mean token probability, for backends that report one.
+    pub confidence: Option<f32>,
+    /// When each word was spoken, for backends that align words to audio.
+    pub words: Vec<WordTiming>,
+    /// Sentence-sized pieces of the text and when they were spoken, for
+    /// backends that report them.
+    pub segments: Vec<SegmentTiming>,
+}
+
+/// A word and where it starts and ends in the utterance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// A segment of a transcription and where it starts and ends in the
+/// utterance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentTiming {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Speech-to-text for complete utterances, used by batch, manual and
+/// wake-word mode.
+///
+/// The daemon only talks to backends through this trait, so adding one
+/// means implementing it and choosing it in `create_backend`.
+pub trait TranscriptionBackend: Send {
+    /// Short human readable name used in logs.
+    fn name(&self) -> &str;
+
+    /// Load models or open connections before the first `transcribe`.
+    fn load(&mut self) -> BoxFuture<'_, Result<()>>;
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<Transcription>>;
+
+    /// Translate speech to English instead of transcribing it as spoken.
+    fn set_translate(&mut self, translate: bool);
+
+    /// Another loaded backend sharing this one's model, for the worker pool,
+    /// or `None` if this backend cannot run in parallel.
+    fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(None)
+    }
+
+    /// Interrupt decodes in progress when `abort` fires. Backends that
+    /// cannot be interrupted ignore it and run into the Whisper timeout.
+    fn set_abort_signal(&mut self, _abort: AbortSignal) {}
+}
+
+/// Speech-to-text fed continuously with audio, emitting text as it goes.
+pub trait StreamingBackend: Send {
+    fn name(&self) -> &str;
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>>;
+
+    /// Begin a new stream, discarding buffered audio.
+    fn start(&mut self) -> Result<()>;
+
+    /// Feed an audio chunk, returning any newly transcribed text.
+    fn push_audio(&mut self, chunk: &[f32]) -> Result<Option<String>>;
+
+    /// Text of the utterance still being spoken, when it changed since the
+    /// last call. Only backends with partial results return anything.
+    fn take_partial(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Whether the text `push_audio` last returned is a draft of audio
+    /// still being heard. The next text returned replaces a draft rather
+    /// than following it.
+    fn is_draft(&self) -> bool {
+        false
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, ()>;
+
+    fn set_language(&mut self, language: String);
+
+    fn set_translate(&mut self, translate: bool);
+
+    /// See `TranscriptionBackend::set_abort_signal`.
+    fn set_abort_signal(&mut self, _abort: AbortSignal) {}
+}
+
+/// Loaded backends transcribing utterances side by side, so a short
+/// utterance does not wait behind a long one. Each worker is a fork of the
+/// first, see `TranscriptionBackend::fork`.
+pub struct WorkerPool {
+    workers: Vec<Mutex<Box<dyn TranscriptionBackend>>>,
+    /// One permit per worker; fair, so utterances start in the order queued.
+    idle: Semaphore,
+    name: String,
+}
+
+impl WorkerPool {
+    pub fn new(workers: Vec<Box<dyn TranscriptionBackend>>) -> Self {
+        let name = workers.first().map_or_else(String::new, |worker| worker.name().to_string());
+        Self {
+            idle: Semaphore::new(workers.len()),
+            workers: workers.into_iter().map(Mutex::new).collect(),
+            name,
+        }
+    }
+
+    /// Load `backend` and fork it into `count` workers.
+    pub async fn load(mut backend: Box<dyn TranscriptionBackend>, count: usize) -> Result<Self> {
+        backend.load().await?;
+        let mut workers = Vec::with_capacity(count);
+        while workers.len() + 1 < count {
+            match backend.fork()? {
+                Some(worker) => workers.push(worker),
+                None => {
+                    tracing::warn!("{} cannot run in parallel, using one worker", backend.name());
+                    break;
+                }
+            }
+        }
+        workers.insert(0, backend);
+        Ok(Self::new(workers))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Transcribe on the first idle worker, waiting for one if all are busy.
+    pub async fn transcribe(&self, audio: &[f32], language: &str) -> Result<Transcription> {
+        let _permit = self
+            .idle
+            .acquire()
+            .await
+            .map_err(|_| anyhow::anyhow!("Transcription workers shut down"))?;
+        for worker in &self.workers {
+            if let Ok(mut worker) = worker.try_lock() {
+                return worker.transcribe(audio, language).await;
+            }
+        }
+        // Every worker is briefly held by `set_translate`
+        let mut worker = self
+            .workers
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No transcription workers"))?
+            .lock()
+            .await;
+        worker.transcribe(audio, language).await
+    }
+
+    pub async fn set_translate(&self, translate: bool) {
+        for worker in &self.workers {
+            worker.lock().await.set_translate(translate);
+        }
+    }
+}
+
+/// Loaded workers shared between the server and processing tasks. Tasks
+/// clone the `Arc` out, so a model switch does not wait for transcriptions
+/// in flight.
+pub type SharedBackend = Arc<Mutex<Option<Arc<WorkerPool>>>>;
+
+/// Tries `primary` first and uses `fallback` when it fails, e.g. a cloud
+/// service that cannot be reached backed by a local model.
+pub struct FallbackBackend {
+    name: String,
+    primary: Box<dyn TranscriptionBackend>,
+    fallback: Box<dyn TranscriptionBackend>,
+    /// An aborted utterance is not handed to the fallback.
+    abort: AbortSignal,
+}
+
+impl FallbackBackend {
+    pub fn new(primary: Box<dyn TranscriptionBackend>, fallback: Box<dyn TranscriptionBackend>) -> Self {
+        Self {
+            name: format!("{} (falling back to {})", primary.name(), fallback.name()),
+            primary,
+            fallback,
+            abort: AbortSignal::new(),
+        }
+    }
+}
+
+impl TranscriptionBackend for FallbackBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.primary.load().await?;
+            self.fallback.load().await
+        })
+    }
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<Transcription>> {
+        Box::pin(async move {
+            let abort = self.abort.token();
+            match self.primary.transcribe(audio, language).await {
+                Ok(transcription) => Ok(transcription),
+                Err(e) if abort.is_aborted() => Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        "{} transcription failed, using {}: {:#}",
+                        self.primary.name(),
+                        self.fallback.name(),
+                        e
+                    );
+                    self.fallback.transcribe(audio, language).await
+                }
+            }
+        })
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        self.primary.set_translate(translate);
+        self.fallback.set_translate(translate);
+    }
+
+    fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+        match (self.primary.fork()?, self.fallback.fork()?) {
+            (Some(primary), Some(fallback)) => {
+                let mut forked = Self::new(primary, fallback);
+                forked.abort = self.abort.clone();
+                Ok(Some(Box::new(forked)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn set_abort_signal(&mut self, abort: AbortSignal) {
+        self.primary.set_abort_signal(abort.clone());
+        self.fallback.set_abort_signal(abort.clone());
+        self.abort = abort;
+    }
+}
+
+/// Build the utterance backend: the `stt.backend` engine, running the model
+/// called `model` in `whisper.models` for Whisper, or the cloud service when
+/// `cloud.enabled` is set, backed by the local engine if
+/// `cloud.fallback_to_local` allows it. The backend still has to be loaded.
+pub fn create_backend(config: &Config, model: &str, translate: bool) -> Result<Box<dyn TranscriptionBackend>> {
+    let local = config
+        .cloud
+        .needs_local_engine()
+        .then(|| create_local_backend(config, model))
+        .transpose()?;
+
+    let mut backend: Box<dyn TranscriptionBackend> = if config.cloud.enabled {
+        let cloud = CloudTranscriber::new(&config.cloud, config.audio.sample_rate)
+            .with_prompt(vocabulary::prompt_from_config(&config.whisper)?);
+        tracing::info!("Cloud transcription via {}", cloud.base_url());
+        match local {
+            Some(local) => Box::new(FallbackBackend::new(Box::new(cloud), local)),
+            None => Box::new(cloud),
+        }
+    } else {
+        local.expect("local engine is needed without the cloud service")
+    };
+    backend.set_translate(translate);
+    Ok(backend)
+}
+
+fn create_local_backend(config: &Config, model: &str) -> Result<Box<dyn TranscriptionBackend>> {
+    match config.stt.backend.as_str() {
+        "whisper" => Ok(Box::new(create_whisper_engine(config, model)?)),
+        "vosk" => Ok(Box::new(VoskEngine::new(
+            &config.stt.vosk,
+            &config.whisper.language,
+            config.audio.sample_rate,
+        ))),
+        "server" => Ok(Box::new(
+            WhisperServerBackend::new(&config.stt.server, config.audio.sample_rate)
+                .with_prompt(vocabulary::prompt_from_config(&config.whisper)?),
+        )),
+        #[cfg(feature = "ctranslate2")]
+        "ctranslate2" => Ok(Box::new(
+            crate::transcription::ctranslate2::CTranslate2Engine::new(
+                &config.stt.ctranslate2,
+                config.audio.sample_rate,
+                config.whisper.effective_threads() as usize,
+            ),
+        )),
+        #[cfg(not(feature = "ctranslate2"))]
+        "ctranslate2" => Err(anyhow::anyhow!(
+            "stt.backend = \"ctranslate2\" requires ndictd to be built with `--features ctranslate2`"
+        )),
+        other => Err(unknown_stt_backend(other)),
+    }
+}
+
+fn unknown_stt_backend(name: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown stt.backend '{}'. Expected \"whisper\", \"vosk\", \"server\" or \"ctranslate2\"",
+        name
+    )
+}
+
+/// Build a local Whisper engine for the model called `name`.
+fn create_whisper_engine(config: &Config, name: &str) -> Result<WhisperEngine> {
+    let diarize = config.whisper.diarize && config.writes_transcript();
+    if config.whisper.diarize && !diarize {
+        tracing::warn!("whisper.diarize only applies when writing a transcript, ignoring it");
+    }
+    let sample_rate = config.audio.sample_rate;
+    let download = config.download.clone();
+    let config = &config.whisper;
+    let model_url = config
+        .model_url_for(name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown model '{}'. Add it to [whisper.models]", name))?;
+    if let Some(model) = config.model_info_for(name) {
+        tracing::info!("Model '{}' is {} (~{} MiB)", name, model.alias, model.size_mib);
+        if config.backend == "cpu" {
+            if let Some(quantized) = models::quantized_alternative(model) {
+                tracing::info!(
+                    "Tip: '{}' (~{} MiB) transcribes much faster on CPU",
+                    quantized.alias,
+                    quantized.size_mib
+                );
+            }
+        }
+    }
+    Ok(WhisperEngine::new_with_checksum_and_params(
+        model_url,
+        config.backend.clone(),
+        config.model_checksum_for(name),
+        config.min_audio_samples,
+        config.sampling_strategy.clone(),
+    )?
+    .with_model_path(config.model_path_for(name))
+    .with_sample_rate(sample_rate)
+    .with_download_config(download)
+    .with_no_speech_threshold(config.no_speech_threshold)
+    .with_gpu_device(config.gpu_device)
+    .with_diarization(diarize)
+    .with_dtw_timestamps(config.dtw_timestamps)
+    .with_threads(config.effective_threads())
+    .with_decoding(config.decoding.clone())
+    .with_grammar(grammar::grammar_from_config(&config.grammar)?, config.grammar.penalty)
+    .with_prompt(vocabulary::prompt_from_config(config)?))
+}
+
+/// Build the small model listening for `wake.wake_phrase`, see
+/// `wake.spotter`.
+pub fn create_wake_backend(config: &Config) -> Result<Box<dyn TranscriptionBackend>> {
+    match config.wake.spotter.as_str() {
+        "vosk" => {
+            let model = config.wake.vosk_model.clone().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "wake.spotter = \"vosk\" needs wake.vosk_model, the directory of a small Vosk model"
+                )
+            })?;
+            let vosk = VoskConfig {
+                library: config.stt.vosk.library.clone(),
+                models: HashMap::from([(WAKE_LANGUAGE.to_string(), model)]),
+            };
+            let engine = VoskEngine::new(&vosk, WAKE_LANGUAGE, config.audio.sample_rate)
+                .with_phrases(&[&config.wake.wake_phrase, &config.wake.stop_phrase]);
+            return Ok(Box::new(engine));
+        }
+        "whisper" => {}
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unknown wake.spotter '{}'. Expected \"vosk\" or \"whisper\"",
+                other
+            ))
+        }
+    }
+
+    let engine = WhisperEngine::new_with_checksum_and_params(
+        config.wake.model_url.clone(),
+        config.whisper.backend.clone(),
+        None,
+        config.whisper.min_audio_samples,
+        "greedy".to_string(),
+    )?
+    .with_sample_rate(config.audio.sample_rate)
+    .with_download_config(config.download.clone())
+    .with_gpu_device(config.whisper.gpu_device)
+    .with_threads(config.whisper.effective_threads());
+    Ok(Box::new(engine))
+}
+
+/// Build the streaming-mode backend: the `stt.backend` engine, with
+/// `streaming.model` for Whisper.
+pub fn create_streaming_backend(
+    config: &Config,
+    language: &str,
+    translate: bool,
+) -> Result<Box<dyn StreamingBackend>> {
+    match config.stt.backend.as_str() {
+        "whisper" => {}
+        "vosk" => {
+            let mut engine = VoskEngine::new(&config.stt.vosk, language, config.audio.sample_rate);
+            StreamingBackend::set_translate(&mut engine, translate);
+            return Ok(Box::new(engine));
+        }
+        "server" | "ctranslate2" => {
+            return Err(anyhow::anyhow!(
+                "stt.backend = \"{}\" does not support streaming mode",
+                config.stt.backend
+            ))
+        }
+        other => return Err(unknown_stt_backend(other)),
+    }
+
+    let model_name = config
+        .streaming
+        .model
+        .as_deref()
+        .unwrap_or(crate::config::DEFAULT_MODEL);
+    let model_url = config.whisper.model_url_for(model_name).ok_or_else(|| {
+        anyhow::anyhow!("Unknown streaming.model '{}'. Add it to [whisper.models]", model_name)
+    })?;
+    let model_path = match config.whisper.model_path_for(model_name) {
+        Some(model_path) => model_path,
+        None => crate::model::find_model_path(&model_url)?,
+    };
+
+    let mut engine = StreamingEngine::new(
+        model_path.to_string_lossy().to_string(),
+        language.to_string(),
+        config.streaming.step_ms,
+        config.streaming.length_ms,
+        config.streaming.keep_ms,
+        config.audio.sample_rate,
+    )
+    .with_backend(config.whisper.backend.clone())
+    .with_gpu_device(config.whisper.gpu_device);
+    if config.streaming.vad_segmentation {
+        engine = engine.with_segmenter(streaming_segmenter(config)?);
+    }
+    let partial_samples =
+        (config.audio.sample_rate as u64 * config.streaming.partial_ms as u64 / 1000) as usize;
+    engine = engine
+        .with_partial_interval(partial_samples)
+        .with_redrafts(config.streaming.correct_drafts);
+    engine.set_threads(config.whisper.effective_threads());
+    engine.set_thresholds(config.whisper.no_speech_threshold, config.whisper.decoding.clone());
+    engine.set_translate(translate);
+    engine.set_prompt(vocabulary::prompt_from_config(&config.whisper)?);
+    Ok(Box::new(engine))
+}
+
+/// Speech detector cutting streaming windows at pauses, never letting one
+/// grow past `streaming.length_ms`.
+fn streaming_segmenter(config: &Config) -> Result<SpeechDetector> {
+    let sample_rate = config.audio.sample_rate;
+    let mut max_ms = config.streaming.length_ms;
+    if config.vad.max_segment_ms > 0 {
+        max_ms = max_ms.min(config.vad.max_segment_ms);
+    }
+    let max_samples = (sample_rate as u64 * max_ms as u64 / 1000) as usize;
+    Ok(SpeechDetector::from_config(&config.vad, config.audio.gain, sample_rate)?
+        .with_max_segment(max_samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns canned results and records what it was asked to do.
+    struct Scripted {
+        name: &'static str,
+        result: std::result::Result<&'static str, &'static str>,
+        calls: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl TranscriptionBackend for Scripted {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+            self.calls.lock().unwrap().push(format!("{} load", self.name));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn transcribe<'a>(
+            &'a mut self,
+            _audio: &'a [f32],
+            _language: &'a str,
+        ) -> BoxFuture<'a, Result<Transcription>> {
+            self.calls.lock().unwrap().push(format!("{} transcribe", self.name));
+            let result = self
+                .result
+                .map(|text| Transcription {
+                    text: text.to_string(),
+                    ..Transcription::default()
+                })
+                .map_err(|e| anyhow::anyhow!(e));
+            Box::pin(async move { result })
+        }
+
+        fn set_translate(&mut self, translate: bool) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} translate={}", self.name, translate));
+        }
+
+        fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+            Ok(Some(Box::new(Scripted {
+                name: self.name,
+                result: self.result,
+                calls: self.calls.clone(),
+            })))
+        }
+    }
+
+    /// Holds every call until `release` is notified.
+    struct Blocking {
+        release: Arc<tokio::sync::Notify>,
+    }
+
+    impl TranscriptionBackend for Blocking {
+        fn name(&self) -> &str {
+            "blocking"
+        }
+
+        fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn transcribe<'a>(
+            &'a mut self,
+            _audio: &'a [f32],
+            _language: &'a str,
+        ) -> BoxFuture<'a, Result<Transcription>> {
+            Box::pin(async move {
+                self.release.notified().await;
+                Ok(Transcription::default())
+            })
+        }
+
+        fn set_translate(&mut self, _translate: bool) {}
+    }
+
+    fn fallback_pair(
+        primary: std::result::Result<&'static str, &'static str>,
+    ) -> (FallbackBackend, Arc<std::sync::Mutex<Vec<String>>>) {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let backend = FallbackBackend::new(
+            Box::new(Scripted {
+                name: "cloud",
+                result: primary,
+                calls: calls.clone(),
+            }),
+            Box::new(Scripted {
+                name: "whisper",
+                result: Ok("local text"),
+                calls: calls.clone(),
+            }),
+        );
+        (backend, calls)
+    }
+
+    #[tokio::test]
+    async fn test_fallback_uses_primary_when_it_works() {
+        let (mut backend, calls) = fallback_pair(Ok("cloud text"));
+        let result = backend.transcribe(&[0.0; 16], "en").await.unwrap();
+        assert_eq!(result.text, "cloud text");
+        assert_eq!(*calls.lock().unwrap(), vec!["cloud transcribe"]);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_on_primary_error() {
+        let (mut backend, calls) = fallback_pair(Err("connection refused"));
+        assert_eq!(backend.name(), "cloud (falling back to whisper)");
+
+        let result = backend.transcribe(&[0.0; 16], "en").await.unwrap();
+        assert_eq!(result.text, "local text");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["cloud transcribe", "whisper transcribe"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fallback_loads_and_configures_both() {
+        let (mut backend, calls) = fallback_pair(Ok("cloud text"));
+        backend.load().await.unwrap();
+        backend.set_translate(true);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["cloud load", "whisper load", "cloud translate=true", "whisper translate=true"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_forks_workers() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let backend = Box::new(Scripted {
+            name: "whisper",
+            result: Ok("text"),
+            calls: calls.clone(),
+        });
+        let pool = WorkerPool::load(backend, 3).await.unwrap();
+        assert_eq!(pool.worker_count(), 3);
+        assert_eq!(pool.name(), "whisper");
+        // Only the first worker loads; the others share its model
+        assert_eq!(*calls.lock().unwrap(), vec!["whisper load"]);
+
+        pool.set_translate(true).await;
+        assert_eq!(calls.lock().unwrap().len(), 4);
+        assert_eq!(pool.transcribe(&[0.0; 16], "en").await.unwrap().text, "text");
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_falls_back_to_one_worker() {
+        let release = Arc::new(tokio::sync::Notify::new());
+        let pool = WorkerPool::load(Box::new(Blocking { release }), 4).await.unwrap();
+        assert_eq!(pool.worker_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_runs_utterances_in_parallel() {
+        let release = Arc::new(tokio::sync::Notify::new());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let pool = Arc::new(WorkerPool::new(vec![
+            Box::new(Blocking {
+                release: release.clone(),
+            }),
+            Box::new(Scripted {
+                name: "whisper",
+                result: Ok("short"),
+                calls,
+            }),
+        ]));
+
+        let long = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.transcribe(&[0.0; 16], "en").await }
+        });
+        tokio::task::yield_now().await;
+
+        // The second worker answers while the first is still busy
+        let short = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            pool.transcribe(&[0.0; 16], "en"),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(short.text, "short");
+
+        release.notify_one();
+        long.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_create_backend_selects_from_config() {
+        let mut config = Config::default();
+        assert_eq!(create_backend(&config, "default", false).unwrap().name(), "whisper");
+
+        config.cloud.enabled = true;
+        config.cloud.api_key = Some("sk-test".to_string());
+        assert_eq!(
+            create_backend(&config, "default", false).unwrap().name(),
+            "cloud (falling back to whisper)"
+        );
+
+        config.cloud.fallback_to_local = false;
+        assert_eq!(create_backend(&config, "default", false).unwrap().name(), "cloud");
+
+        config.stt.backend = "vosk".to_string();
+        assert_eq!(create_backend(&config, "default", false).unwrap().name(), "cloud");
+        config.cloud.fallback_to_local = true;
+        assert_eq!(
+            create_backend(&config, "default", false).unwrap().name(),
+            "cloud (falling back to vosk)"
+        );
+        config.cloud.enabled = false;
+        assert_eq!(create_backend(&config, "default", false).unwrap().name(), "vosk");
+        config.stt.backend = "server".to_string();
+        assert_eq!(
+            create_backend(&config, "default", false).unwrap().name(),
+            "whisper server"
+        );
+
+        config.stt.backend = "ctranslate2".to_string();
+        #[cfg(feature = "ctranslate2")]
+        assert_eq!(
+            create_backend(&config, "default", false).unwrap().name(),
+            "ctranslate2"
+        );
+        #[cfg(not(feature = "ctranslate2"))]
+        assert!(create_backend(&config, "default", false)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("--features ctranslate2"));
+
+        // Without the cloud service the model must exist
+        config.stt.backend = "whisper".to_string();
+        assert!(create_backend(&config, "missing", false).is_err());
+        config.stt.backend = "kaldi".to_string();
+        assert!(create_backend(&config, "default", false).is_err());
+    }
+
+    #[test]
+    fn test_create_streaming_backend_selects_from_config() {
+        let mut config = Config::default();
+        config.stt.backend = "vosk".to_string();
+        assert_eq!(create_streaming_backend(&config, "en", false).unwrap().name(), "vosk");
+        config.stt.backend = "server".to_string();
+        assert!(create_streaming_backend(&config, "en", false).is_err());
+        config.stt.backend = "ctranslate2".to_string();
+        assert!(create_streaming_backend(&config, "en", false).is_err());
+        config.stt.backend = "kaldi".to_string();
+        assert!(create_streaming_backend(&config, "en", false).is_err());
+    }
+}
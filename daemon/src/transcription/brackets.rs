@@ -0,0 +1,210 @@
+use anyhow::Result;
+use regex::{Captures, Regex};
+
+use crate::config::BracketsConfig;
+use crate::transcription::collapse_whitespace;
+
+/// Non-speech annotations Whisper is known to emit, lowercase with spaces
+/// for underscores: "[BLANK_AUDIO]", "(laughs)", "[clears throat]".
+const ANNOTATIONS: &[&str] = &[
+    "blank audio",
+    "no speech",
+    "silence",
+    "silent",
+    "pause",
+    "music",
+    "music playing",
+    "applause",
+    "clapping",
+    "cheering",
+    "laughs",
+    "laughing",
+    "laughter",
+    "chuckles",
+    "chuckling",
+    "giggles",
+    "sighs",
+    "sigh",
+    "coughs",
+    "coughing",
+    "cough",
+    "sneezes",
+    "clears throat",
+    "throat clearing",
+    "breathing",
+    "breathes",
+    "inhales",
+    "exhales",
+    "sniffs",
+    "yawns",
+    "groans",
+    "mumbles",
+    "mumbling",
+    "whispers",
+    "whispering",
+    "noise",
+    "background noise",
+    "static",
+    "sound",
+    "inaudible",
+    "indistinct",
+    "unintelligible",
+    "crosstalk",
+    "typing",
+    "clicking",
+    "keyboard clicking",
+    "beep",
+    "beeping",
+    "phone ringing",
+    "bell ringing",
+    "footsteps",
+    "door closes",
+    "foreign language",
+    "speaking foreign language",
+    "speaking in foreign language",
+];
+
+/// How much of one bracket type is stripped, see `post_processing.brackets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketMode {
+    /// Everything in these brackets.
+    All,
+    /// Only known annotations; dictated text in these brackets is kept.
+    Annotations,
+    /// Nothing.
+    None,
+}
+
+impl BracketMode {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "all" => Ok(Self::All),
+            "annotations" => Ok(Self::Annotations),
+            "none" => Ok(Self::None),
+            other => Err(anyhow::anyhow!(
+                "Unknown bracket mode '{}', expected \"all\", \"annotations\" or \"none\"",
+                other
+            )),
+        }
+    }
+}
+
+/// Removes bracketed annotations such as "[BLANK_AUDIO]" and "(laughs)"
+/// from Whisper output, per bracket type.
+#[derive(Debug, Clone)]
+pub struct BracketStripper {
+    square: BracketMode,
+    round: BracketMode,
+    curly: BracketMode,
+    /// `annotations` from the config, normalized like the bracket contents.
+    extra: Vec<String>,
+    bracketed: Regex,
+}
+
+impl BracketStripper {
+    pub fn from_config(config: &BracketsConfig) -> Result<Self> {
+        Ok(Self {
+            square: BracketMode::parse(&config.square)?,
+            round: BracketMode::parse(&config.round)?,
+            curly: BracketMode::parse(&config.curly)?,
+            extra: config.annotations.iter().map(|a| normalize(a)).collect(),
+            bracketed: Regex::new(r"\[([^\]]*)\]|\(([^)]*)\)|\{([^}]*)\}").unwrap(),
+        })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let stripped = self.bracketed.replace_all(text, |caps: &Captures| {
+            let (mode, content) = match (caps.get(1), caps.get(2), caps.get(3)) {
+                (Some(content), _, _) => (self.square, content.as_str()),
+                (_, Some(content), _) => (self.round, content.as_str()),
+                (_, _, Some(content)) => (self.curly, content.as_str()),
+                _ => (BracketMode::None, ""),
+            };
+            let strip = match mode {
+                BracketMode::All => true,
+                BracketMode::Annotations => self.is_annotation(content),
+                BracketMode::None => false,
+            };
+            if strip {
+                String::new()
+            } else {
+                caps[0].to_string()
+            }
+        });
+        collapse_whitespace(&stripped)
+    }
+
+    /// Whether bracketed `content` is a non-speech annotation rather than
+    /// dictated text. Music descriptions like "(upbeat music)" and "♪"
+    /// count as annotations.
+    fn is_annotation(&self, content: &str) -> bool {
+        let content = normalize(content);
+        content.is_empty()
+            || content.ends_with(" music")
+            || ANNOTATIONS.contains(&content.as_str())
+            || self.extra.contains(&content)
+    }
+}
+
+/// Lowercase, underscores as spaces, without the punctuation and music
+/// notes around it.
+fn normalize(content: &str) -> String {
+    content
+        .replace('_', " ")
+        .trim_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation() || "♪♫".contains(c))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripper(square: &str, round: &str, curly: &str) -> BracketStripper {
+        BracketStripper::from_config(&BracketsConfig {
+            square: square.to_string(),
+            round: round.to_string(),
+            curly: curly.to_string(),
+            annotations: vec!["dog barking".to_string()],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_annotations_only_by_default() {
+        let stripper = BracketStripper::from_config(&BracketsConfig::default()).unwrap();
+        assert_eq!(
+            stripper.apply("[BLANK_AUDIO] call me (laughs) (after lunch) today"),
+            "call me (after lunch) today"
+        );
+        assert_eq!(
+            stripper.apply("hello [ Clears throat ] (upbeat music) [♪] world"),
+            "hello world"
+        );
+        assert_eq!(
+            stripper.apply("use {key: value} here"),
+            "use {key: value} here"
+        );
+    }
+
+    #[test]
+    fn test_modes_per_bracket_type() {
+        let stripper = stripper("all", "none", "annotations");
+        assert_eq!(
+            stripper.apply("[note] keep (laughs) {Dog Barking} {x}"),
+            "keep (laughs) {x}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_mode() {
+        assert!(BracketMode::parse("some").is_err());
+        assert!(BracketStripper::from_config(&BracketsConfig {
+            round: "everything".to_string(),
+            ..BracketsConfig::default()
+        })
+        .is_err());
+    }
+}
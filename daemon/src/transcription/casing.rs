@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+/// Capitalizes sentence starts, the pronoun "I" and configured proper
+/// nouns, for streaming mode and models that emit lowercase text. See
+/// `post_processing.capitalize`.
+#[derive(Debug, Clone, Default)]
+pub struct Capitalizer {
+    /// Proper nouns by their lowercase form.
+    proper_nouns: HashMap<String, String>,
+}
+
+impl Capitalizer {
+    pub fn new(proper_nouns: &[String]) -> Self {
+        Self {
+            proper_nouns: proper_nouns
+                .iter()
+                .map(|noun| noun.trim())
+                .filter(|noun| !noun.is_empty())
+                .map(|noun| (noun.to_lowercase(), noun.to_string()))
+                .collect(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut sentence_start = true;
+        text.split(' ')
+            .map(|token| {
+                let mut word = self.case_word(token);
+                if sentence_start {
+                    word = capitalize_first(&word);
+                }
+                if !token.is_empty() {
+                    sentence_start = token
+                        .trim_end_matches(['"', '\'', ')'])
+                        .ends_with(['.', '!', '?']);
+                }
+                word
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `token` with "i" and proper nouns cased, keeping the punctuation
+    /// around the word.
+    fn case_word(&self, token: &str) -> String {
+        let start = token
+            .find(|c: char| c.is_alphanumeric())
+            .unwrap_or(token.len());
+        let end = token
+            .rfind(|c: char| c.is_alphanumeric())
+            .map_or(start, |i| i + token[i..].chars().next().map_or(1, char::len_utf8));
+        if start >= end {
+            return token.to_string();
+        }
+        let word = &token[start..end];
+        let lower = word.to_lowercase();
+
+        let cased = if let Some(noun) = self.proper_nouns.get(&lower) {
+            noun.clone()
+        } else if lower == "i" || ["i'm", "i'll", "i've", "i'd"].contains(&lower.as_str()) {
+            capitalize_first(word)
+        } else {
+            return token.to_string();
+        };
+        format!("{}{}{}", &token[..start], cased, &token[end..])
+    }
+}
+
+/// `text` with its first letter uppercased, skipping leading punctuation.
+fn capitalize_first(text: &str) -> String {
+    match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, c)) => {
+            let mut out = String::with_capacity(text.len());
+            out.push_str(&text[..i]);
+            out.extend(c.to_uppercase());
+            out.push_str(&text[i + c.len_utf8()..]);
+            out
+        }
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence_starts_are_capitalized() {
+        let capitalizer = Capitalizer::default();
+        assert_eq!(
+            capitalizer.apply("hello there. how are you? \"fine!\" she said"),
+            "Hello there. How are you? \"Fine!\" She said"
+        );
+    }
+
+    #[test]
+    fn test_pronoun_i() {
+        let capitalizer = Capitalizer::default();
+        assert_eq!(
+            capitalizer.apply("so i think i'm right, and i'll see (i)"),
+            "So I think I'm right, and I'll see (I)"
+        );
+        assert_eq!(capitalizer.apply("it is in iowa"), "It is in iowa");
+    }
+
+    #[test]
+    fn test_proper_nouns() {
+        let capitalizer = Capitalizer::new(&["Kubernetes".to_string(), "macOS".to_string()]);
+        assert_eq!(
+            capitalizer.apply("deploy kubernetes on MACOS, then kubernetes."),
+            "Deploy Kubernetes on macOS, then Kubernetes."
+        );
+    }
+}
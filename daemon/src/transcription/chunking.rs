@@ -0,0 +1,238 @@
+use anyhow::Result;
+use futures_util::future::try_join_all;
+use std::ops::Range;
+
+use crate::config::ChunkingConfig;
+use crate::transcription::backend::{SegmentTiming, Transcription, WorkerPool, WordTiming};
+
+/// Frame length used to look for the quietest place to cut.
+const FRAME_MS: usize = 20;
+
+/// Splits long utterances at pauses so the pieces can be transcribed on
+/// several workers at once, see `[whisper.chunking]`.
+#[derive(Debug, Clone)]
+pub struct Chunker {
+    sample_rate: u32,
+    /// Utterances shorter than this are transcribed whole; 0 never splits.
+    min_samples: usize,
+    chunk_samples: usize,
+}
+
+impl Chunker {
+    pub fn from_config(config: &ChunkingConfig, sample_rate: u32) -> Self {
+        let samples = |ms: u64| (ms as usize * sample_rate as usize) / 1000;
+        Self {
+            sample_rate,
+            min_samples: samples(config.min_length_ms),
+            chunk_samples: samples(config.chunk_ms),
+        }
+    }
+
+    /// Sample ranges to transcribe separately, in order and covering all of
+    /// `audio`. Each cut is made at the quietest frame in the last quarter
+    /// before a chunk would exceed `chunk_ms`.
+    pub fn split(&self, audio: &[f32]) -> Vec<Range<usize>> {
+        if self.min_samples == 0 || audio.len() < self.min_samples || self.chunk_samples == 0 {
+            let whole = 0..audio.len();
+            return vec![whole];
+        }
+
+        let frame = (self.sample_rate as usize * FRAME_MS / 1000).max(1);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while audio.len() - start > self.chunk_samples {
+            let end = start + self.chunk_samples;
+            let search_from = end - self.chunk_samples / 4;
+            let cut = (search_from..end.saturating_sub(frame) + 1)
+                .step_by(frame)
+                .min_by(|&a, &b| {
+                    energy(&audio[a..a + frame]).total_cmp(&energy(&audio[b..b + frame]))
+                })
+                // The cut goes in the middle of the quietest frame
+                .map_or(end, |quietest| quietest + frame / 2);
+            chunks.push(start..cut);
+            start = cut;
+        }
+        chunks.push(start..audio.len());
+        chunks
+    }
+
+    /// Transcribe `audio` in chunks on `workers`, in parallel as far as there
+    /// are idle workers, and stitch the results back together.
+    pub async fn transcribe(
+        &self,
+        workers: &WorkerPool,
+        audio: &[f32],
+        language: &str,
+    ) -> Result<Transcription> {
+        if self.split(audio).len() == 1 {
+            return workers.transcribe(audio, language).await;
+        }
+        Ok(stitch(self.transcribe_chunks(workers, audio, language).await?))
+    }
+
+    /// Transcribe each chunk of `audio` on `workers`. Returns each chunk's
+    /// start in ms, its length in samples and its transcription, in order.
+    pub async fn transcribe_chunks(
+        &self,
+        workers: &WorkerPool,
+        audio: &[f32],
+        language: &str,
+    ) -> Result<Vec<(u64, usize, Transcription)>> {
+        let chunks = self.split(audio);
+        if chunks.len() > 1 {
+            tracing::info!(
+                "Transcribing {} ms of audio as {} chunks on {} workers",
+                self.duration_ms(audio.len()),
+                chunks.len(),
+                workers.worker_count()
+            );
+        }
+        let transcriptions = try_join_all(
+            chunks
+                .iter()
+                .map(|chunk| workers.transcribe(&audio[chunk.clone()], language)),
+        )
+        .await?;
+
+        Ok(chunks
+            .iter()
+            .map(|chunk| (self.duration_ms(chunk.start), chunk.len()))
+            .zip(transcriptions)
+            .map(|((offset_ms, samples), transcription)| (offset_ms, samples, transcription))
+            .collect())
+    }
+
+    fn duration_ms(&self, samples: usize) -> u64 {
+        (samples as u64 * 1000) / self.sample_rate as u64
+    }
+}
+
+/// Mean square of a frame.
+fn energy(frame: &[f32]) -> f32 {
+    frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32
+}
+
+/// Join chunk transcriptions in order. Takes each chunk's start in ms, its
+/// length in samples and its transcription; word and segment timings are
+/// shifted to the whole utterance and confidences averaged by chunk length.
+fn stitch(pieces: impl IntoIterator<Item = (u64, usize, Transcription)>) -> Transcription {
+    let mut text: Vec<String> = Vec::new();
+    let mut language = None;
+    let mut words = Vec::new();
+    let mut segments = Vec::new();
+    let mut weighted_confidence = 0.0;
+    let mut scored_samples = 0;
+
+    for (offset_ms, samples, transcription) in pieces {
+        let piece = transcription.text.trim();
+        if !piece.is_empty() {
+            text.push(piece.to_string());
+        }
+        language = language.or(transcription.language);
+        if let Some(confidence) = transcription.confidence {
+            weighted_confidence += confidence * samples as f32;
+            scored_samples += samples;
+        }
+        words.extend(transcription.words.into_iter().map(|word| WordTiming {
+            start_ms: word.start_ms + offset_ms,
+            end_ms: word.end_ms + offset_ms,
+            ..word
+        }));
+        segments.extend(transcription.segments.into_iter().map(|segment| SegmentTiming {
+            start_ms: segment.start_ms + offset_ms,
+            end_ms: segment.end_ms + offset_ms,
+            ..segment
+        }));
+    }
+
+    Transcription {
+        text: text.join(" "),
+        language,
+        confidence: (scored_samples > 0).then(|| weighted_confidence / scored_samples as f32),
+        words,
+        segments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunker(min_length_ms: u64, chunk_ms: u64) -> Chunker {
+        Chunker::from_config(
+            &ChunkingConfig {
+                min_length_ms,
+                chunk_ms,
+            },
+            1000,
+        )
+    }
+
+    #[test]
+    fn test_split_short_audio_is_one_chunk() {
+        let audio = vec![0.5; 5_000];
+        assert_eq!(chunker(10_000, 3_000).split(&audio), vec![0..5_000]);
+        // min_length_ms = 0 disables chunking
+        assert_eq!(chunker(0, 3_000).split(&audio), vec![0..5_000]);
+    }
+
+    #[test]
+    fn test_split_cuts_at_quietest_frame() {
+        // 1 kHz, so one sample per ms; a pause at 2610..2630 ms
+        let mut audio = vec![0.5; 7_000];
+        audio[2_610..2_630].fill(0.0);
+        let chunks = chunker(5_000, 3_000).split(&audio);
+
+        assert_eq!(chunks[0], 0..2_620);
+        assert_eq!(chunks.last().unwrap().end, 7_000);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 3_000));
+    }
+
+    #[test]
+    fn test_stitch_joins_in_order() {
+        let first = Transcription {
+            text: " Hello there.".to_string(),
+            language: Some("en".to_string()),
+            confidence: Some(0.9),
+            words: vec![WordTiming {
+                text: "Hello".to_string(),
+                start_ms: 100,
+                end_ms: 400,
+            }],
+            segments: Vec::new(),
+        };
+        let second = Transcription {
+            text: "General Kenobi.".to_string(),
+            language: None,
+            confidence: Some(0.6),
+            words: vec![WordTiming {
+                text: "General".to_string(),
+                start_ms: 50,
+                end_ms: 300,
+            }],
+            segments: vec![SegmentTiming {
+                text: "General Kenobi.".to_string(),
+                start_ms: 0,
+                end_ms: 900,
+            }],
+        };
+        let silent = Transcription::default();
+
+        let stitched = stitch(vec![
+            (0, 2_000, first),
+            (30_000, 1_000, second),
+            (45_000, 1_000, silent),
+        ]);
+        assert_eq!(stitched.text, "Hello there. General Kenobi.");
+        assert_eq!(stitched.language.as_deref(), Some("en"));
+        assert!((stitched.confidence.unwrap() - 0.8).abs() < 1e-6);
+        assert_eq!(stitched.words[1].start_ms, 30_050);
+        assert_eq!(stitched.words[1].end_ms, 30_300);
+        assert_eq!(stitched.segments[0].start_ms, 30_000);
+        assert_eq!(stitched.segments[0].end_ms, 30_900);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::config::CloudConfig;
+use crate::transcription::backend::{Transcription, TranscriptionBackend};
+use crate::transcription::engine::AUTO_LANGUAGE;
+use crate::transcription::multipart;
+use anyhow::Context;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Transcribes utterances with an OpenAI-compatible speech-to-text API.
+#[derive(Clone)]
+pub struct CloudTranscriber {
+    base_url: String,
+    client: reqwest::Client,
+    model: String,
+    api_key: Option<String>,
+    prompt: Option<String>,
+    translate: bool,
+    sample_rate: u32,
+}
+
+impl CloudTranscriber {
+    pub fn new(config: &CloudConfig, sample_rate: u32) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .connect_timeout(Duration::from_secs(5))
+            .build()
+            .expect("Failed to build reqwest client for cloud transcription");
+
+        let api_key = config.resolve_api_key();
+        if api_key.is_none() {
+            tracing::warn!(
+                "No cloud API key in cloud.api_key or ${}, sending unauthenticated requests",
+                config.api_key_env
+            );
+        }
+
+        Self {
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            client,
+            model: config.model.clone(),
+            api_key,
+            prompt: None,
+            translate: false,
+            sample_rate,
+        }
+    }
+
+    /// Text sent as the `prompt` field, see `vocabulary::prompt_from_config`.
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn transcribe(&self, audio: &[f32], language: &str) -> anyhow::Result<String> {
+        let wav = multipart::encode_wav(audio, self.sample_rate)?;
+
+        let endpoint = if self.translate { "translations" } else { "transcriptions" };
+        let url = format!("{}/audio/{}", self.base_url, endpoint);
+
+        let mut fields = vec![("model", self.model.as_str()), ("response_format", "json")];
+        // The translation endpoint always answers in English
+        if language != AUTO_LANGUAGE && !self.translate {
+            fields.push(("language", language));
+        }
+        if let Some(prompt) = &self.prompt {
+            fields.push(("prompt", prompt));
+        }
+
+        let (content_type, body) = multipart::form(&fields, &wav);
+
+        tracing::debug!(
+            "Sending {} samples ({} bytes) to {}",
+            audio.len(),
+            body.len(),
+            url
+        );
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to send request to cloud transcription API")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Cloud transcription API returned error {}: {}",
+                status.as_u16(),
+                body
+            ));
+        }
+
+        let raw_body = response
+            .text()
+            .await
+            .context("Failed to read cloud transcription response body")?;
+        parse_transcription(&raw_body)
+    }
+}
+
+impl TranscriptionBackend for CloudTranscriber {
+    fn name(&self) -> &str {
+        "cloud"
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Transcription>> {
+        Box::pin(async move {
+            let text = CloudTranscriber::transcribe(self, audio, language).await?;
+            Ok(Transcription {
+                text,
+                ..Transcription::default()
+            })
+        })
+    }
+
+    /// Use the `/audio/translations` endpoint, which answers in English.
+    fn set_translate(&mut self, translate: bool) {
+        self.translate = translate;
+    }
+
+    /// Requests are independent, so forks share the connection pool.
+    fn fork(&self) -> anyhow::Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(Some(Box::new(self.clone())))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+fn parse_transcription(body: &str) -> anyhow::Result<String> {
+    let response: TranscriptionResponse = serde_json::from_str(body)
+        .with_context(|| format!("Failed to parse cloud transcription response: {}", body))?;
+    Ok(response.text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transcription() {
+        assert_eq!(
+            parse_transcription(r#"{"text": " Hello world. "}"#).unwrap(),
+            "Hello world."
+        );
+        assert!(parse_transcription(r#"{"error": {"message": "bad key"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_new_trims_base_url() {
+        let config = CloudConfig {
+            base_url: "http://localhost:8000/v1/".to_string(),
+            api_key: Some("sk-test".to_string()),
+            ..CloudConfig::default()
+        };
+        assert_eq!(CloudTranscriber::new(&config, 16000).base_url(), "http://localhost:8000/v1");
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::Result;
+
+use crate::config::WhisperConfig;
+
+/// What to do with a transcription scored below `whisper.min_confidence`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Accept,
+    /// Deliver the text but report it as uncertain.
+    Flag,
+    /// Discard the text instead of typing it.
+    Drop,
+}
+
+/// Mean probability Whisper assigned to the text tokens of an utterance.
+///
+/// Returns `None` when there are no tokens to score.
+pub fn utterance_confidence(token_probabilities: &[f32]) -> Option<f32> {
+    if token_probabilities.is_empty() {
+        return None;
+    }
+    Some(token_probabilities.iter().sum::<f32>() / token_probabilities.len() as f32)
+}
+
+/// Applies `whisper.min_confidence` and `whisper.low_confidence_action`.
+#[derive(Debug, Clone)]
+pub struct ConfidenceFilter {
+    min_confidence: f32,
+    drop_low: bool,
+}
+
+impl ConfidenceFilter {
+    pub fn from_config(config: &WhisperConfig) -> Result<Self> {
+        let drop_low = match config.low_confidence_action.as_str() {
+            "drop" => true,
+            "flag" => false,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "Unknown whisper.low_confidence_action '{}'. Expected \"drop\" or \"flag\"",
+                    other
+                ))
+            }
+        };
+        Ok(Self {
+            min_confidence: config.min_confidence,
+            drop_low,
+        })
+    }
+
+    /// Judge a transcription by its confidence. Unscored results are accepted.
+    pub fn check(&self, confidence: Option<f32>) -> Verdict {
+        match confidence {
+            Some(confidence) if confidence < self.min_confidence => {
+                if self.drop_low {
+                    Verdict::Drop
+                } else {
+                    Verdict::Flag
+                }
+            }
+            _ => Verdict::Accept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(min_confidence: f32, action: &str) -> ConfidenceFilter {
+        let config = WhisperConfig {
+            min_confidence,
+            low_confidence_action: action.to_string(),
+            ..WhisperConfig::default()
+        };
+        ConfidenceFilter::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn test_utterance_confidence_is_mean_probability() {
+        assert_eq!(utterance_confidence(&[]), None);
+        assert!((utterance_confidence(&[0.9, 0.5, 0.7]).unwrap() - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_filter_drops_or_flags_low_confidence() {
+        assert_eq!(filter(0.5, "drop").check(Some(0.4)), Verdict::Drop);
+        assert_eq!(filter(0.5, "flag").check(Some(0.4)), Verdict::Flag);
+        assert_eq!(filter(0.5, "drop").check(Some(0.6)), Verdict::Accept);
+        assert_eq!(filter(0.5, "drop").check(None), Verdict::Accept);
+        // The default threshold keeps everything
+        assert_eq!(filter(0.0, "drop").check(Some(0.01)), Verdict::Accept);
+    }
+
+    #[test]
+    fn test_filter_rejects_unknown_action() {
+        let config = WhisperConfig {
+            low_confidence_action: "shout".to_string(),
+            ..WhisperConfig::default()
+        };
+        assert!(ConfidenceFilter::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use ct2rs::{ComputeType, Config, Device, Whisper, WhisperOptions};
+use futures_util::future::BoxFuture;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use crate::audio::resample::{resample, WHISPER_SAMPLE_RATE};
+use crate::config::CTranslate2Config;
+use crate::transcription::abort::AbortSignal;
+use crate::transcription::backend::{Transcription, TranscriptionBackend};
+use crate::transcription::engine::AUTO_LANGUAGE;
+
+/// Whisper through CTranslate2, the runtime behind faster-whisper.
+///
+/// Loads a converted model directory (e.g. one of the
+/// `Systran/faster-whisper-*` repositories) and is usually several times
+/// faster than whisper.cpp on CPU with int8 weights. Batch and manual mode
+/// only; built with `--features ctranslate2`.
+pub struct CTranslate2Engine {
+    config: CTranslate2Config,
+    sample_rate: u32,
+    n_threads: usize,
+    model: Option<Arc<Whisper>>,
+    abort: AbortSignal,
+}
+
+impl CTranslate2Engine {
+    pub fn new(config: &CTranslate2Config, sample_rate: u32, n_threads: usize) -> Self {
+        Self {
+            config: config.clone(),
+            sample_rate,
+            n_threads,
+            model: None,
+            abort: AbortSignal::new(),
+        }
+    }
+
+    fn load_model(&mut self) -> Result<()> {
+        if self.model.is_some() {
+            return Ok(());
+        }
+        let model_dir = self.config.model_dir.as_deref().ok_or_else(|| {
+            anyhow::anyhow!("stt.backend = \"ctranslate2\" needs stt.ctranslate2.model_dir")
+        })?;
+        let config = Config {
+            device: parse_device(&self.config.device)?,
+            compute_type: parse_compute_type(&self.config.compute_type)?,
+            num_threads_per_replica: self.n_threads,
+            ..Config::default()
+        };
+
+        info!(
+            "Loading CTranslate2 model from {} ({}, {})",
+            model_dir, self.config.device, self.config.compute_type
+        );
+        let model = Whisper::new(model_dir, config)
+            .with_context(|| format!("Failed to load CTranslate2 model from {}", model_dir))?;
+        self.model = Some(Arc::new(model));
+        Ok(())
+    }
+
+    fn transcribe_utterance(&mut self, audio: &[f32], language: &str) -> Result<String> {
+        let model = self
+            .model
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?;
+        let audio = resample(audio, self.sample_rate, WHISPER_SAMPLE_RATE);
+        let options = WhisperOptions {
+            beam_size: self.config.beam_size,
+            ..WhisperOptions::default()
+        };
+
+        // CTranslate2 cannot be interrupted mid-decode, so an aborted
+        // result is only discarded once it is ready
+        let abort = self.abort.token();
+        let segments = model
+            .generate(&audio, language_option(language), false, &options)
+            .map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
+        if abort.is_aborted() {
+            return Err(anyhow::anyhow!("Transcription aborted"));
+        }
+
+        let text = segments.join(" ").trim().to_string();
+        debug!("CTranslate2 transcription: '{}'", text);
+        Ok(text)
+    }
+}
+
+/// CTranslate2 detects the spoken language when none is given.
+fn language_option(language: &str) -> Option<&str> {
+    (language != AUTO_LANGUAGE).then_some(language)
+}
+
+fn parse_device(device: &str) -> Result<Device> {
+    match device {
+        "cpu" => Ok(Device::CPU),
+        "cuda" => Ok(Device::CUDA),
+        other => Err(anyhow::anyhow!(
+            "Unknown stt.ctranslate2.device '{}'. Expected \"cpu\" or \"cuda\"",
+            other
+        )),
+    }
+}
+
+fn parse_compute_type(compute_type: &str) -> Result<ComputeType> {
+    match compute_type {
+        "default" => Ok(ComputeType::DEFAULT),
+        "auto" => Ok(ComputeType::AUTO),
+        "float32" => Ok(ComputeType::FLOAT32),
+        "float16" => Ok(ComputeType::FLOAT16),
+        "bfloat16" => Ok(ComputeType::BFLOAT16),
+        "int8" => Ok(ComputeType::INT8),
+        "int8_float32" => Ok(ComputeType::INT8_FLOAT32),
+        "int8_float16" => Ok(ComputeType::INT8_FLOAT16),
+        "int8_bfloat16" => Ok(ComputeType::INT8_BFLOAT16),
+        "int16" => Ok(ComputeType::INT16),
+        other => Err(anyhow::anyhow!(
+            "Unknown stt.ctranslate2.compute_type '{}'",
+            other
+        )),
+    }
+}
+
+impl TranscriptionBackend for CTranslate2Engine {
+    fn name(&self) -> &str {
+        "ctranslate2"
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.load_model() })
+    }
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<Transcription>> {
+        Box::pin(async move {
+            Ok(Transcription {
+                text: self.transcribe_utterance(audio, language)?,
+                ..Transcription::default()
+            })
+        })
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        if translate {
+            warn!("The CTranslate2 backend cannot translate, transcribing speech as spoken");
+        }
+    }
+
+    /// CTranslate2 models take concurrent requests, so forks share the
+    /// loaded model.
+    fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(Some(Box::new(Self {
+            model: self.model.clone(),
+            abort: self.abort.clone(),
+            ..Self::new(&self.config, self.sample_rate, self.n_threads)
+        })))
+    }
+
+    fn set_abort_signal(&mut self, abort: AbortSignal) {
+        self.abort = abort;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_language_is_detected() {
+        assert_eq!(language_option("auto"), None);
+        assert_eq!(language_option("de"), Some("de"));
+    }
+
+    #[test]
+    fn test_parse_device_and_compute_type() {
+        assert!(matches!(parse_device("cuda").unwrap(), Device::CUDA));
+        assert!(parse_device("tpu").is_err());
+        assert!(matches!(parse_compute_type("int8").unwrap(), ComputeType::INT8));
+        assert!(parse_compute_type("int4").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_requires_model_dir() {
+        let mut engine = CTranslate2Engine::new(&CTranslate2Config::default(), 16000, 4);
+        let error = TranscriptionBackend::load(&mut engine).await.unwrap_err();
+        assert!(error.to_string().contains("stt.ctranslate2.model_dir"));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_before_load_fails() {
+        let mut engine = CTranslate2Engine::new(&CTranslate2Config::default(), 16000, 4);
+        let result = TranscriptionBackend::transcribe(&mut engine, &[0.0; 16000], "en").await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::transcription::locale::Locale;
+use crate::transcription::tokens::{leading_punctuation, normalize, trailing_punctuation};
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const SPANISH_MONTHS: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+/// English ordinals a day may be spoken as, "March fifth".
+const ENGLISH_ORDINALS: [&str; 20] = [
+    "first",
+    "second",
+    "third",
+    "fourth",
+    "fifth",
+    "sixth",
+    "seventh",
+    "eighth",
+    "ninth",
+    "tenth",
+    "eleventh",
+    "twelfth",
+    "thirteenth",
+    "fourteenth",
+    "fifteenth",
+    "sixteenth",
+    "seventeenth",
+    "eighteenth",
+    "nineteenth",
+    "twentieth",
+];
+
+/// Spanish days below ten, which the `numbers` stage leaves as words.
+const SPANISH_DAYS: [&str; 9] = [
+    "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+];
+
+/// Writes dates whose day and year are digits in the locale's format, for
+/// the `numbers` stage: "March 5th, 2024" or "5 de marzo de 2024" becomes
+/// "03/05/2024" in the US and "05.03.2024" in Germany.
+pub fn format_dates(text: &str, language: &str, locale: &Locale) -> String {
+    let months = match language {
+        "en" => &ENGLISH_MONTHS,
+        "es" => &SPANISH_MONTHS,
+        _ => return text.to_string(),
+    };
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match parse_date(&tokens[i..], language, months) {
+            Some((len, year, month, day)) => {
+                let leading = leading_punctuation(tokens[i]);
+                let trailing = trailing_punctuation(tokens[i + len - 1]);
+                let date = locale.format_date(year, month, day);
+                out.push(format!("{}{}{}", leading, date, trailing));
+                i += len;
+            }
+            None => {
+                out.push(tokens[i].to_string());
+                i += 1;
+            }
+        }
+    }
+    out.join(" ")
+}
+
+/// The words used and the date, for a date at the start of `tokens`.
+fn parse_date(
+    tokens: &[&str],
+    language: &str,
+    months: &[&str; 12],
+) -> Option<(usize, u32, u32, u32)> {
+    let month_of = |token: &str| {
+        let word = normalize(token);
+        months
+            .iter()
+            .position(|month| *month == word)
+            .map(|index| index as u32 + 1)
+    };
+    let words: Vec<String> = tokens
+        .iter()
+        .take(6)
+        .map(|token| normalize(token))
+        .collect();
+    let word = |i: usize| words.get(i).map(String::as_str);
+
+    let (len, year, month, day) = if language == "es" {
+        // "5 de marzo de 2024", "primero de mayo de 2024"
+        let day = match word(0)? {
+            "primero" => 1,
+            day => SPANISH_DAYS
+                .iter()
+                .position(|name| *name == day)
+                .map(|index| index as u32 + 1)
+                .or_else(|| parse_day_digits(day))?,
+        };
+        if word(1)? != "de" || word(3)? != "de" {
+            return None;
+        }
+        (5, parse_year(word(4)?)?, month_of(tokens[2])?, day)
+    } else if let Some(month) = month_of(tokens[0]) {
+        // "March 5th, 2024", "March twenty first 2024"
+        let (day, day_len) = parse_english_day(&words[1..])?;
+        if day_len > 1 && !trailing_punctuation(tokens[1]).is_empty() {
+            return None;
+        }
+        (day_len + 2, parse_year(word(day_len + 1)?)?, month, day)
+    } else {
+        // "5 March 2024"
+        let day = parse_day_digits(word(0)?)?;
+        (3, parse_year(word(2)?)?, month_of(tokens.get(1)?)?, day)
+    };
+    if !(1..=31).contains(&day) {
+        return None;
+    }
+    // Only the year may carry punctuation after it, besides "March 5,"
+    let inner_punctuation = tokens[..len - 1].iter().enumerate().any(|(i, token)| {
+        let trailing = trailing_punctuation(token);
+        !trailing.is_empty() && (i != len - 2 || trailing != ",")
+    });
+    if inner_punctuation {
+        return None;
+    }
+    Some((len, year, month, day))
+}
+
+/// "5", "5th" or "fifth", "twenty first"; with the words used.
+fn parse_english_day(words: &[String]) -> Option<(u32, usize)> {
+    let ordinal = |word: &str| {
+        ENGLISH_ORDINALS
+            .iter()
+            .position(|ordinal| *ordinal == word)
+            .map(|index| index as u32 + 1)
+    };
+    let first = words.first()?;
+    // The numbers stage has already written "twenty" as "20"
+    let tens = match first.as_str() {
+        "twenty" | "20" => Some(20),
+        "thirty" | "30" => Some(30),
+        _ => None,
+    };
+    let unit = words
+        .get(1)
+        .and_then(|word| ordinal(word))
+        .filter(|unit| *unit < 10);
+    if let (Some(tens), Some(unit)) = (tens, unit) {
+        return Some((tens + unit, 2));
+    }
+    if first == "thirtieth" {
+        return Some((30, 1));
+    }
+    parse_day_digits(first)
+        .or_else(|| ordinal(first))
+        .map(|day| (day, 1))
+}
+
+fn parse_day_digits(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    if digits.is_empty() || digits.len() > 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn parse_year(word: &str) -> Option<u32> {
+    if word.len() != 4 || !word.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    word.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_dates() {
+        let us = Locale::resolve("en-US", "en");
+        assert_eq!(
+            format_dates("Due March 5th, 2024.", "en", &us),
+            "Due 03/05/2024."
+        );
+        assert_eq!(
+            format_dates("on march twenty first 2024", "en", &us),
+            "on 03/21/2024"
+        );
+        assert_eq!(
+            format_dates("on march 20 first 2024", "en", &us),
+            "on 03/21/2024"
+        );
+        let uk = Locale::resolve("en-GB", "en");
+        assert_eq!(format_dates("(5 March 2024)", "en", &uk), "(05/03/2024)");
+    }
+
+    #[test]
+    fn test_spanish_dates() {
+        let spain = Locale::resolve("auto", "es");
+        assert_eq!(
+            format_dates("el 5 de marzo de 2024", "es", &spain),
+            "el 05/03/2024"
+        );
+        assert_eq!(
+            format_dates("primero de mayo de 2025,", "es", &spain),
+            "01/05/2025,"
+        );
+    }
+
+    #[test]
+    fn test_non_dates_are_kept() {
+        let us = Locale::resolve("en-US", "en");
+        assert_eq!(
+            format_dates("May I have 2024 of them", "en", &us),
+            "May I have 2024 of them"
+        );
+        assert_eq!(format_dates("March 40 2024", "en", &us), "March 40 2024");
+        assert_eq!(format_dates("March. 5 2024", "en", &us), "March. 5 2024");
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::transcription::tokens::{leading_punctuation, normalize, trailing_punctuation};
+
+/// Word replacements from `post_processing.dictionary_file`, mapping how a
+/// phrase is spoken to how it is written.
+///
+/// The file has one `spoken form = written form` entry per line, e.g.
+/// `doctor jones = Dr. Jones`; blank lines and lines starting with `#` are
+/// ignored. It is read again whenever it changes, so entries can be added
+/// while dictating.
+#[derive(Debug)]
+pub struct Dictionary {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    /// Lowercase words of the spoken form.
+    spoken: Vec<String>,
+    written: String,
+}
+
+impl Dictionary {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut dictionary = Self {
+            path: path.as_ref().to_path_buf(),
+            modified: None,
+            entries: Vec::new(),
+        };
+        dictionary.reload()?;
+        tracing::info!(
+            "Loaded {} dictionary entries from {}",
+            dictionary.entries.len(),
+            dictionary.path.display()
+        );
+        Ok(dictionary)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.path).map_err(|e| {
+            anyhow::anyhow!("Failed to read dictionary file {}: {}", self.path.display(), e)
+        })?;
+        self.entries = parse_entries(&contents)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    /// Pick up edits to the file. A file that no longer parses keeps the
+    /// previous entries.
+    pub fn reload_if_changed(&mut self) {
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        match self.reload() {
+            Ok(()) => tracing::info!(
+                "Reloaded {} dictionary entries from {}",
+                self.entries.len(),
+                self.path.display()
+            ),
+            Err(e) => {
+                // Don't retry until the file changes again
+                self.modified = modified;
+                tracing::warn!("Keeping the previous dictionary: {:#}", e);
+            }
+        }
+    }
+
+    /// Replace spoken forms with their written forms, word by word. The
+    /// longest matching phrase wins; case and the punctuation around a
+    /// phrase are ignored when matching and the punctuation is kept.
+    pub fn apply(&self, text: &str) -> String {
+        if self.entries.is_empty() {
+            return text.to_string();
+        }
+        let longest = self.entries.iter().map(|entry| entry.spoken.len()).max().unwrap_or(0);
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let matched = (1..=longest.min(tokens.len() - i)).rev().find_map(|n| {
+                let phrase = &tokens[i..i + n];
+                self.entries
+                    .iter()
+                    .find(|entry| {
+                        entry.spoken.len() == n
+                            && entry
+                                .spoken
+                                .iter()
+                                .zip(phrase)
+                                .all(|(spoken, token)| *spoken == normalize(token))
+                    })
+                    .map(|entry| (n, entry))
+            });
+            match matched {
+                Some((n, entry)) => {
+                    let leading = leading_punctuation(tokens[i]);
+                    let trailing = trailing_punctuation(tokens[i + n - 1]);
+                    // "Dr. Jones." should not end up as "Dr. Jones.."
+                    let trailing = if entry.written.ends_with(trailing) { "" } else { trailing };
+                    out.push(format!("{}{}{}", leading, entry.written, trailing));
+                    i += n;
+                }
+                None => {
+                    out.push(tokens[i].to_string());
+                    i += 1;
+                }
+            }
+        }
+        out.join(" ")
+    }
+}
+
+fn parse_entries(contents: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (spoken, written) = line.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("Dictionary line {} is not 'spoken form = written form'", number + 1)
+        })?;
+        let spoken: Vec<String> = spoken.split_whitespace().map(normalize).collect();
+        let written = written.trim();
+        if spoken.is_empty() || written.is_empty() {
+            return Err(anyhow::anyhow!("Dictionary line {} has an empty side", number + 1));
+        }
+        entries.push(Entry {
+            spoken,
+            written: written.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(contents: &str) -> (tempfile::TempDir, Dictionary) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dictionary.txt");
+        std::fs::write(&path, contents).unwrap();
+        let dictionary = Dictionary::load(&path).unwrap();
+        (dir, dictionary)
+    }
+
+    #[test]
+    fn test_apply_replaces_phrases_and_keeps_punctuation() {
+        let (_dir, dictionary) = dictionary(
+            "# spoken = written\ndoctor jones = Dr. Jones\neip = EIP\ndoctor = Dr.\n",
+        );
+        assert_eq!(
+            dictionary.apply("Ask Doctor Jones about (eip), doctor."),
+            "Ask Dr. Jones about (EIP), Dr."
+        );
+        assert_eq!(dictionary.apply("Jones and doctors"), "Jones and doctors");
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_lines() {
+        assert!(parse_entries("no separator here").is_err());
+        assert!(parse_entries(" = Written").is_err());
+        assert_eq!(parse_entries("# comment\n\n").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let (dir, mut dictionary) = dictionary("eip = EIP\n");
+        let path = dir.path().join("dictionary.txt");
+        std::fs::write(&path, "eip = E.I.P.\n").unwrap();
+        // Make sure the modification time moves even on coarse filesystems
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+
+        dictionary.reload_if_changed();
+        assert_eq!(dictionary.apply("eip"), "E.I.P.");
+
+        // A broken edit keeps the previous entries
+        std::fs::write(&path, "broken\n").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + std::time::Duration::from_secs(5))
+            .unwrap();
+        dictionary.reload_if_changed();
+        assert_eq!(dictionary.apply("eip"), "E.I.P.");
+    }
+}
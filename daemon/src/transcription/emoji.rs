@@ -0,0 +1,187 @@
+use regex::Regex;
+
+use crate::transcription::tokens::normalize;
+
+/// Emoji by shortcode. The spoken name is the shortcode with spaces for
+/// underscores, so "thumbs up emoji" and ":thumbs_up:" both give 👍.
+const EMOJI: &[(&[&str], &str)] = &[
+    (
+        &["smile", "smiley", "smiley_face", "smiling_face"],
+        "\u{1F604}",
+    ),
+    (&["slightly_smiling_face", "slight_smile"], "\u{1F642}"),
+    (&["grin", "grinning", "grinning_face"], "\u{1F600}"),
+    (&["laughing", "laugh"], "\u{1F606}"),
+    (
+        &["joy", "tears_of_joy", "crying_laughing", "laughing_crying"],
+        "\u{1F602}",
+    ),
+    (&["rofl", "rolling_on_the_floor_laughing"], "\u{1F923}"),
+    (&["wink", "winking_face"], "\u{1F609}"),
+    (&["blush"], "\u{1F60A}"),
+    (&["heart_eyes"], "\u{1F60D}"),
+    (&["sunglasses", "cool"], "\u{1F60E}"),
+    (&["thinking", "thinking_face"], "\u{1F914}"),
+    (&["neutral_face"], "\u{1F610}"),
+    (&["rolling_eyes", "eye_roll"], "\u{1F644}"),
+    (&["sweat_smile"], "\u{1F605}"),
+    (&["sad", "disappointed", "sad_face"], "\u{1F61E}"),
+    (&["cry", "crying", "crying_face"], "\u{1F622}"),
+    (&["sob", "sobbing"], "\u{1F62D}"),
+    (&["angry", "angry_face"], "\u{1F620}"),
+    (&["scream", "screaming"], "\u{1F631}"),
+    (&["shrug", "shrugging"], "\u{1F937}"),
+    (&["facepalm", "face_palm"], "\u{1F926}"),
+    (&["+1", "thumbsup", "thumbs_up"], "\u{1F44D}"),
+    (&["-1", "thumbsdown", "thumbs_down"], "\u{1F44E}"),
+    (&["ok_hand", "okay_hand"], "\u{1F44C}"),
+    (&["clap", "clapping", "clapping_hands"], "\u{1F44F}"),
+    (&["wave", "waving", "waving_hand"], "\u{1F44B}"),
+    (&["pray", "praying_hands", "folded_hands"], "\u{1F64F}"),
+    (&["muscle", "flexed_biceps"], "\u{1F4AA}"),
+    (&["raised_hands"], "\u{1F64C}"),
+    (&["point_up"], "\u{261D}\u{FE0F}"),
+    (&["eyes"], "\u{1F440}"),
+    (&["heart", "red_heart"], "\u{2764}\u{FE0F}"),
+    (&["broken_heart"], "\u{1F494}"),
+    (&["fire", "flame"], "\u{1F525}"),
+    (&["100", "hundred", "hundred_points"], "\u{1F4AF}"),
+    (
+        &["tada", "party", "party_popper", "celebration"],
+        "\u{1F389}",
+    ),
+    (&["sparkles"], "\u{2728}"),
+    (&["star"], "\u{2B50}"),
+    (&["rocket"], "\u{1F680}"),
+    (&["bug"], "\u{1F41B}"),
+    (&["coffee"], "\u{2615}"),
+    (&["beer", "beers"], "\u{1F37B}"),
+    (&["pizza"], "\u{1F355}"),
+    (&["sun", "sunny"], "\u{2600}\u{FE0F}"),
+    (&["rainbow"], "\u{1F308}"),
+    (&["check", "white_check_mark", "check_mark"], "\u{2705}"),
+    (&["x", "cross_mark"], "\u{274C}"),
+    (&["warning"], "\u{26A0}\u{FE0F}"),
+    (&["question", "question_mark"], "\u{2753}"),
+    (&["bulb", "light_bulb", "idea"], "\u{1F4A1}"),
+    (&["memo", "note"], "\u{1F4DD}"),
+    (&["lock"], "\u{1F512}"),
+    (&["zap", "lightning"], "\u{26A1}"),
+    (&["skull"], "\u{1F480}"),
+    (&["poop", "pile_of_poo"], "\u{1F4A9}"),
+    (&["see_no_evil"], "\u{1F648}"),
+    (&["robot"], "\u{1F916}"),
+];
+
+/// Most words in a spoken emoji name, "rolling on the floor laughing".
+const MAX_NAME_WORDS: usize = 5;
+
+/// Inserts emoji for shortcodes (":rocket:") and spoken names followed by
+/// "emoji" ("thumbs up emoji"). See `post_processing.emoji`.
+#[derive(Debug, Clone)]
+pub struct EmojiInserter {
+    shortcode: Regex,
+}
+
+impl Default for EmojiInserter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmojiInserter {
+    pub fn new() -> Self {
+        Self {
+            shortcode: Regex::new(r":([a-z0-9_+\-]+):").unwrap(),
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let text = self.shortcode.replace_all(text, |caps: &regex::Captures| {
+            lookup(&caps[1]).unwrap_or(&caps[0]).to_string()
+        });
+        replace_spoken(&text)
+    }
+}
+
+/// The emoji for a shortcode, ignoring case.
+fn lookup(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    EMOJI
+        .iter()
+        .find(|(names, _)| names.contains(&name.as_str()))
+        .map(|(_, emoji)| *emoji)
+}
+
+/// "thumbs up emoji" as 👍, trying the longest name before "emoji" first.
+/// Unknown names are left as they were spoken.
+fn replace_spoken(text: &str) -> String {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+
+    for token in &tokens {
+        let word = normalize(token);
+        if word != "emoji" {
+            out.push(token.to_string());
+            continue;
+        }
+        let found = (1..=MAX_NAME_WORDS.min(out.len())).rev().find_map(|len| {
+            let name_words = &out[out.len() - len..];
+            // Punctuation inside the name means it isn't one
+            if name_words
+                .iter()
+                .any(|word| word.trim_matches(|c: char| c.is_ascii_punctuation()) != word)
+            {
+                return None;
+            }
+            let name = name_words
+                .iter()
+                .map(|word| normalize(word))
+                .collect::<Vec<_>>()
+                .join("_");
+            lookup(&name).map(|emoji| (len, emoji))
+        });
+        match found {
+            Some((len, emoji)) => {
+                out.truncate(out.len() - len);
+                let word_end = token
+                    .trim_end_matches(|c: char| c.is_ascii_punctuation())
+                    .len();
+                out.push(format!("{}{}", emoji, &token[word_end..]));
+            }
+            None => out.push(token.to_string()),
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcodes() {
+        let inserter = EmojiInserter::new();
+        assert_eq!(
+            inserter.apply("Ship it :rocket: :+1:"),
+            "Ship it \u{1F680} \u{1F44D}"
+        );
+        assert_eq!(inserter.apply("at 10:30:00 :nope:"), "at 10:30:00 :nope:");
+    }
+
+    #[test]
+    fn test_spoken_names() {
+        let inserter = EmojiInserter::new();
+        assert_eq!(
+            inserter.apply("Great work thumbs up emoji."),
+            "Great work \u{1F44D}."
+        );
+        assert_eq!(
+            inserter.apply("rolling on the floor laughing emoji"),
+            "\u{1F923}"
+        );
+        assert_eq!(inserter.apply("Heart Emoji"), "\u{2764}\u{FE0F}");
+        assert_eq!(inserter.apply("a new emoji set"), "a new emoji set");
+        assert_eq!(inserter.apply("fire, emoji"), "fire, emoji");
+    }
+}
@@ -1,25 +1,83 @@
 use anyhow::Result;
-use sha2::{Digest, Sha256};
 use std::path::PathBuf;
-use std::time::Duration;
-use tokio::time::{sleep, timeout};
-use tracing::{debug, error, info, warn};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use super::abort::AbortSignal;
+use super::backend::{SegmentTiming, Transcription, TranscriptionBackend, WordTiming};
+use super::confidence::utterance_confidence;
+use futures_util::future::BoxFuture;
+use crate::audio::resample::{duration_ms, resample, WHISPER_SAMPLE_RATE};
+use crate::config::{DecodingConfig, DownloadConfig};
+use crate::model::{find_model_path, ModelManager};
+use super::models::{dtw_preset_of, quantization_of};
 use whisper_rs::{
-    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+    DtwMode, DtwParameters, FullParams, SamplingStrategy, WhisperContext,
+    WhisperContextParameters, WhisperGrammarElement, WhisperState,
 };
 
 pub struct WhisperEngine {
-    context: Option<WhisperContext>,
+    /// Shared with engines forked from this one.
+    context: Option<Arc<WhisperContext>>,
     state: Option<WhisperState>,
     model_loaded: bool,
     model_path: PathBuf,
+    /// `model_path` was configured rather than derived from the URL.
+    explicit_path: bool,
     model_url: String,
     model_checksum: Option<String>,
     backend: String,
+    gpu_device: i32,
+    /// Rate of the audio passed to `transcribe`; converted to 16kHz for Whisper.
+    sample_rate: u32,
+    /// Shorter audio is padded with silence, counted at 16kHz.
     min_audio_samples: usize,
     sampling_strategy: String,
+    decoding: DecodingConfig,
+    no_speech_threshold: f32,
+    detected_language: Option<String>,
+    confidence: Option<f32>,
+    translate: bool,
+    prompt: Option<String>,
+    n_threads: i32,
+    diarize: bool,
+    dtw_timestamps: bool,
+    words: Vec<WordTiming>,
+    segments: Vec<SegmentTiming>,
+    grammar: Option<Vec<WhisperGrammarElement>>,
+    grammar_penalty: f32,
+    abort: AbortSignal,
+    download: DownloadConfig,
 }
 
+/// Inserted where tinydiarize hears a new speaker, as in TV captions.
+pub const SPEAKER_TURN: &str = ">>";
+
+/// Language setting that lets Whisper pick the spoken language itself.
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Parse a `whisper.task` value, returning whether Whisper should translate
+/// to English rather than transcribe in the spoken language.
+pub fn parse_task(task: &str) -> Result<bool> {
+    match task {
+        "transcribe" => Ok(false),
+        "translate" => Ok(true),
+        other => Err(anyhow::anyhow!(
+            "Unknown Whisper task '{}'. Expected \"transcribe\" or \"translate\"",
+            other
+        )),
+    }
+}
+
+/// Phrases Whisper is known to produce from silence or noise, having learnt
+/// them from video subtitles.
+const HALLUCINATED_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "please subscribe",
+    "subtitles by",
+    "like and subscribe",
+];
+
 impl WhisperEngine {
     pub fn new(model_url: String, backend: String) -> Result<Self> {
         Self::new_with_checksum_and_params(model_url, backend, None, 18000, "greedy".to_string())
@@ -40,68 +98,197 @@ impl WhisperEngine {
         min_audio_samples: usize,
         sampling_strategy: String,
     ) -> Result<Self> {
-        let model_path = Self::find_model_path(&model_url)?;
+        let model_path = find_model_path(&model_url)?;
 
         Ok(Self {
             context: None,
             state: None,
             model_loaded: false,
             model_path,
+            explicit_path: false,
             model_url,
             model_checksum,
             backend,
+            gpu_device: 0,
+            sample_rate: WHISPER_SAMPLE_RATE,
             min_audio_samples,
             sampling_strategy,
+            decoding: DecodingConfig::default(),
+            no_speech_threshold: 1.0,
+            detected_language: None,
+            confidence: None,
+            translate: false,
+            prompt: None,
+            n_threads: 4,
+            diarize: false,
+            dtw_timestamps: false,
+            words: Vec::new(),
+            segments: Vec::new(),
+            grammar: None,
+            grammar_penalty: 100.0,
+            abort: AbortSignal::new(),
+            download: DownloadConfig::default(),
         })
     }
 
+    /// Load the model from `model_path` instead of the file found from the
+    /// URL. The file must exist; it is never downloaded.
+    pub fn with_model_path(mut self, model_path: Option<PathBuf>) -> Self {
+        if let Some(model_path) = model_path {
+            info!("Using configured model path: {:?}", model_path);
+            self.model_path = model_path;
+            self.explicit_path = true;
+        }
+        self
+    }
+
+    /// Download the model as configured under `[download]`.
+    pub fn with_download_config(mut self, download: DownloadConfig) -> Self {
+        self.download = download;
+        self
+    }
+
+    /// Accept audio captured at `sample_rate` (`audio.sample_rate`).
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Drop segments whose no-speech probability exceeds `threshold`.
+    pub fn with_no_speech_threshold(mut self, threshold: f32) -> Self {
+        self.no_speech_threshold = threshold;
+        self
+    }
+
+    /// Beam size, patience and best-of used by the sampling strategy.
+    pub fn with_decoding(mut self, decoding: DecodingConfig) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Index of the GPU to load the model on with a GPU backend.
+    pub fn with_gpu_device(mut self, gpu_device: i32) -> Self {
+        self.gpu_device = gpu_device;
+        self
+    }
+
+    /// Mark speaker turns with `SPEAKER_TURN`. Needs a tinydiarize
+    /// (`-tdrz`) model; other models never report a turn.
+    pub fn with_diarization(mut self, diarize: bool) -> Self {
+        self.diarize = diarize;
+        self
+    }
+
+    /// Time each word with whisper.cpp's DTW alignment, see `words`. Only
+    /// the official models have the alignment heads this needs.
+    pub fn with_dtw_timestamps(mut self, dtw_timestamps: bool) -> Self {
+        self.dtw_timestamps = dtw_timestamps;
+        self
+    }
+
+    /// Constrain decoding to `grammar` (see `grammar::phrase_grammar`),
+    /// penalizing tokens outside it by `penalty`.
+    pub fn with_grammar(mut self, grammar: Option<Vec<WhisperGrammarElement>>, penalty: f32) -> Self {
+        self.grammar = grammar;
+        self.grammar_penalty = penalty;
+        self
+    }
+
+    /// Number of CPU threads Whisper decodes with.
+    pub fn with_threads(mut self, n_threads: i32) -> Self {
+        self.n_threads = n_threads;
+        self
+    }
+
+    /// Context passed to Whisper before every utterance, biasing it towards
+    /// the vocabulary and style it contains.
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Translate speech to English instead of transcribing it as spoken.
+    pub fn set_translate(&mut self, translate: bool) {
+        self.translate = translate;
+        info!("Whisper task set to {}", if translate { "translate" } else { "transcribe" });
+    }
+
+    /// Mean token probability of the text returned by the last `transcribe`
+    /// call, or `None` if nothing was transcribed.
+    pub fn confidence(&self) -> Option<f32> {
+        self.confidence
+    }
+
+    /// Words of the last `transcribe` call with their DTW timings, empty
+    /// unless DTW timestamps are enabled.
+    pub fn words(&self) -> &[WordTiming] {
+        &self.words
+    }
+
+    /// Whisper's segments of the last `transcribe` call with their timings.
+    pub fn segments(&self) -> &[SegmentTiming] {
+        &self.segments
+    }
+
+    /// Language Whisper detected during the last `transcribe` call made with
+    /// `language = "auto"`.
+    pub fn detected_language(&self) -> Option<&str> {
+        self.detected_language.as_deref()
+    }
+
     pub async fn load_model(&mut self) -> Result<()> {
         info!("Loading Whisper model from: {:?}", self.model_path);
+        let file_name = self.model_path.file_name().and_then(|name| name.to_str());
+        if let Some(quantization) = file_name.and_then(quantization_of) {
+            info!("Model is quantized ({})", quantization);
+        }
 
-        if !self.model_path.exists() {
-            warn!(
-                "Model file not found at {:?}. Attempting to download...",
+        if self.explicit_path && !self.model_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Model file {:?} from whisper.model_path does not exist",
                 self.model_path
-            );
-            self.download_model().await?;
-        } else {
-            // Verify existing model if checksum is configured
-            if let Some(ref expected_checksum) = self.model_checksum {
-                info!("Model file exists, verifying checksum...");
-                let actual_checksum = self.compute_file_checksum(&self.model_path)?;
-                if &actual_checksum == expected_checksum {
-                    info!("Model checksum verification passed: {}", actual_checksum);
-                } else {
-                    error!(
-                        "Model checksum mismatch! Expected: {}, Got: {}",
-                        expected_checksum, actual_checksum
-                    );
-                    warn!("Re-downloading model due to checksum mismatch...");
-                    self.download_model().await?;
-                }
-            }
+            ));
         }
 
-        let use_gpu = match self.backend.to_lowercase().as_str() {
-            "gpu" => true,
-            "cuda" => true,
-            "cpu" => false,
-            _ => {
-                warn!(
-                    "Invalid backend value '{}', defaulting to CPU. Valid options: cpu, gpu, cuda",
-                    self.backend
-                );
-                false
+        ModelManager::open_default()?
+            .with_download_config(self.download.clone())
+            .ensure(&self.model_url, &self.model_path, self.model_checksum.as_deref())
+            .await?;
+
+        let use_gpu = uses_gpu(&self.backend);
+
+        // File name of a model DTW can align, when DTW timestamps are wanted
+        let dtw_model = if self.dtw_timestamps {
+            let name = self
+                .model_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .filter(|name| dtw_preset_of(name).is_some());
+            if name.is_none() {
+                warn!("DTW timestamps need an official Whisper model, disabling them");
+            }
+            name
+        } else {
+            None
+        };
+        let context_params = |use_gpu: bool| {
+            let mut params = WhisperContextParameters::default();
+            params.use_gpu(use_gpu);
+            if let Some(model_preset) = dtw_model.and_then(dtw_preset_of) {
+                params.dtw_parameters(DtwParameters {
+                    mode: DtwMode::ModelPreset { model_preset },
+                    ..DtwParameters::default()
+                });
             }
+            params
         };
 
-        let mut params = WhisperContextParameters::default();
+        let mut params = context_params(use_gpu);
         if use_gpu {
-            info!("Attempting to use GPU backend for Whisper");
-            params.use_gpu(true);
+            info!("Attempting to use GPU backend for Whisper on device {}", self.gpu_device);
+            params.gpu_device(self.gpu_device);
         } else {
             info!("Using CPU backend for Whisper");
-            params.use_gpu(false);
         }
 
         let (ctx, actually_using_gpu) = if use_gpu {
@@ -114,8 +301,7 @@ impl WhisperEngine {
                         See: https://github.com/tazz4843/whisper-rs/issues/135",
                         e
                     );
-                    let mut cpu_params = WhisperContextParameters::default();
-                    cpu_params.use_gpu(false);
+                    let cpu_params = context_params(false);
                     let ctx = WhisperContext::new_with_params(self.model_path.to_str().unwrap(), cpu_params)
                         .map_err(|e| {
                         anyhow::anyhow!("Failed to load Whisper model (CPU fallback): {}", e)
@@ -131,9 +317,10 @@ impl WhisperEngine {
             .create_state()
             .map_err(|e| anyhow::anyhow!("Failed to create Whisper state: {}", e))?;
 
-        self.context = Some(ctx);
+        self.context = Some(Arc::new(ctx));
         self.state = Some(state);
         self.model_loaded = true;
+        self.dtw_timestamps = dtw_model.is_some();
 
         let backend_name = if actually_using_gpu { "GPU" } else { "CPU" };
         if use_gpu && !actually_using_gpu {
@@ -149,6 +336,49 @@ impl WhisperEngine {
         Ok(())
     }
 
+    /// Another engine on this engine's loaded model with its own decoding
+    /// state, so the two can transcribe at the same time without loading the
+    /// model twice.
+    pub fn fork(&self) -> Result<Self> {
+        let context = self
+            .context
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Model not loaded"))?;
+        let state = context
+            .create_state()
+            .map_err(|e| anyhow::anyhow!("Failed to create Whisper state: {}", e))?;
+
+        Ok(Self {
+            context: Some(context),
+            state: Some(state),
+            model_loaded: true,
+            model_path: self.model_path.clone(),
+            explicit_path: self.explicit_path,
+            model_url: self.model_url.clone(),
+            model_checksum: self.model_checksum.clone(),
+            backend: self.backend.clone(),
+            gpu_device: self.gpu_device,
+            sample_rate: self.sample_rate,
+            min_audio_samples: self.min_audio_samples,
+            sampling_strategy: self.sampling_strategy.clone(),
+            decoding: self.decoding.clone(),
+            no_speech_threshold: self.no_speech_threshold,
+            detected_language: None,
+            confidence: None,
+            translate: self.translate,
+            prompt: self.prompt.clone(),
+            n_threads: self.n_threads,
+            diarize: self.diarize,
+            dtw_timestamps: self.dtw_timestamps,
+            words: Vec::new(),
+            segments: Vec::new(),
+            grammar: self.grammar.clone(),
+            grammar_penalty: self.grammar_penalty,
+            abort: self.abort.clone(),
+            download: self.download.clone(),
+        })
+    }
+
     pub async fn transcribe(&mut self, audio: &[f32], language: &str) -> Result<String> {
         if !self.model_loaded {
             return Err(anyhow::anyhow!("Model not loaded"));
@@ -156,10 +386,16 @@ impl WhisperEngine {
 
         debug!("Transcribing {} audio samples with language: {}", audio.len(), language);
 
-        let audio = self.pad_audio(audio, self.min_audio_samples as u32);
+        let audio = resample(audio, self.sample_rate, WHISPER_SAMPLE_RATE);
+        let audio = self.pad_audio(&audio, self.min_audio_samples);
 
         debug!("Setting transcription parameters...");
         let sampling_strategy = self.parse_sampling_strategy();
+        // Tokens from the end-of-text id up are timestamps and other markers
+        let first_special_token = self
+            .context
+            .as_ref()
+            .map_or(i32::MAX, |context| context.token_eot());
 
         let state = self
             .state
@@ -172,53 +408,132 @@ impl WhisperEngine {
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
         params.set_language(Some(language));
+        params.set_n_threads(self.n_threads);
+        params.set_translate(self.translate);
+        // whisper.cpp retries hotter when the entropy or log-probability checks fail
+        params.set_temperature(self.decoding.temperature);
+        params.set_temperature_inc(self.decoding.temperature_increment);
+        params.set_entropy_thold(self.decoding.entropy_threshold);
+        apply_thresholds(&mut params, self.no_speech_threshold, &self.decoding);
+        if let Some(prompt) = &self.prompt {
+            params.set_initial_prompt(prompt);
+        }
+        params.set_tdrz_enable(self.diarize);
+        if let Some(grammar) = &self.grammar {
+            params.set_grammar(Some(grammar));
+            params.set_start_rule(0);
+            params.set_grammar_penalty(self.grammar_penalty);
+        }
+        let abort = self.abort.token();
+        let callback_abort = abort.clone();
+        params.set_abort_callback_safe(move || callback_abort.is_aborted());
 
         debug!("Running Whisper transcription...");
-        state
-            .full(params, &audio)
-            .map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
+        let result = state.full(params, &audio);
+        if abort.is_aborted() {
+            return Err(anyhow::anyhow!("Transcription aborted"));
+        }
+        result.map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
+
+        self.detected_language = if language == AUTO_LANGUAGE {
+            let detected = whisper_rs::get_lang_str(state.full_lang_id_from_state());
+            debug!("Detected language: {:?}", detected);
+            detected.map(str::to_string)
+        } else {
+            None
+        };
 
         debug!("Whisper transcription complete, getting segments...");
         let num_segments = state.full_n_segments();
 
         debug!("Extracting {} text segments...", num_segments);
         let mut transcription = String::new();
+        let mut token_probabilities = Vec::new();
+        let mut timed_tokens = Vec::new();
+        let mut segments = Vec::new();
+        let mut audio_end_cs = 0;
         for i in 0..num_segments {
             if let Some(segment) = state.get_segment(i) {
                 if let Ok(text) = segment.to_str() {
+                    let no_speech = segment.no_speech_probability();
+                    if is_likely_hallucination(text, no_speech, self.no_speech_threshold) {
+                        info!(
+                            "Dropping segment '{}' (no-speech probability {:.2})",
+                            text.trim(),
+                            no_speech
+                        );
+                        continue;
+                    }
                     transcription.push_str(text);
                     transcription.push(' ');
+                    // Segment timestamps are in centiseconds
+                    segments.push(SegmentTiming {
+                        text: text.trim().to_string(),
+                        start_ms: segment.start_timestamp().max(0) as u64 * 10,
+                        end_ms: segment.end_timestamp().max(0) as u64 * 10,
+                    });
+                    if self.diarize && segment.next_segment_speaker_turn() {
+                        transcription.push_str(SPEAKER_TURN);
+                        transcription.push(' ');
+                    }
+                    for token in (0..segment.n_tokens())
+                        .filter_map(|t| segment.get_token(t))
+                        .filter(|token| token.token_id() < first_special_token)
+                    {
+                        token_probabilities.push(token.token_probability());
+                        if self.dtw_timestamps {
+                            let t_dtw = token.token_data().t_dtw;
+                            timed_tokens.push((token.to_str_lossy()?.into_owned(), t_dtw));
+                        }
+                    }
+                    audio_end_cs = segment.end_timestamp();
                 }
             }
         }
 
-        let cleaned = transcription.trim().to_string();
-        let duration_ms = (audio.len() * 1000) / 16000;
+        let cleaned = transcription
+            .trim()
+            .trim_end_matches(SPEAKER_TURN)
+            .trim_end()
+            .to_string();
+        let duration_ms = duration_ms(audio.len(), WHISPER_SAMPLE_RATE);
+        self.confidence = utterance_confidence(&token_probabilities);
+        self.words = words_from_tokens(&timed_tokens, audio_end_cs);
+        self.segments = segments;
+        if !self.words.is_empty() {
+            debug!("Word timings: {:?}", self.words);
+        }
 
-        debug!("Transcription: '{}' ({} ms)", cleaned, duration_ms);
+        debug!(
+            "Transcription: '{}' ({} ms, confidence {:?})",
+            cleaned, duration_ms, self.confidence
+        );
 
         Ok(cleaned)
     }
 
     fn parse_sampling_strategy(&self) -> SamplingStrategy {
+        let greedy = SamplingStrategy::Greedy {
+            best_of: self.decoding.best_of,
+        };
         match self.sampling_strategy.to_lowercase().as_str() {
-            "greedy" => SamplingStrategy::Greedy { best_of: 1 },
+            "greedy" => greedy,
             "beam" => SamplingStrategy::BeamSearch {
-                beam_size: 5,
-                patience: 1.0,
+                beam_size: self.decoding.beam_size,
+                patience: self.decoding.patience,
             },
             _ => {
                 tracing::warn!(
                     "Unknown sampling strategy '{}', defaulting to greedy",
                     self.sampling_strategy
                 );
-                SamplingStrategy::Greedy { best_of: 1 }
+                greedy
             }
         }
     }
 
-    fn pad_audio(&self, audio: &[f32], sample_rate: u32) -> Vec<f32> {
-        let min_samples = sample_rate as usize;
+    /// Pad 16kHz `audio` with silence to at least `min_samples`.
+    fn pad_audio(&self, audio: &[f32], min_samples: usize) -> Vec<f32> {
         if audio.len() >= min_samples {
             return audio.to_vec();
         }
@@ -229,337 +544,140 @@ impl WhisperEngine {
             audio.len(),
             padding_len,
             min_samples,
-            (min_samples * 1000) / sample_rate as usize
+            duration_ms(min_samples, WHISPER_SAMPLE_RATE)
         );
 
         let mut padded = audio.to_vec();
-        padded.extend(std::iter::repeat(0.0).take(padding_len));
+        padded.extend(std::iter::repeat_n(0.0, padding_len));
         padded
     }
+}
 
-    pub fn find_model_path(model_url: &str) -> Result<PathBuf> {
-        let model_filename = model_url
-            .rsplit('/')
-            .next()
-            .ok_or_else(|| anyhow::anyhow!("Invalid model URL: cannot extract filename"))?;
-
-        info!("Extracted model filename from URL: {}", model_filename);
-
-        let possible_paths: Vec<Option<PathBuf>> = vec![
-            dirs::home_dir().map(|p| p.join(".local/share/ndict/").join(model_filename)),
-            Some(PathBuf::from("/usr/share/whisper/").join(model_filename)),
-            Some(PathBuf::from("./models/").join(model_filename)),
-            Some(PathBuf::from(model_filename)),
-        ];
-
-        for path in possible_paths {
-            if let Some(p) = path {
-                let path: PathBuf = p;
-                if path.exists() {
-                    info!("Found model at: {:?}", path);
-                    return Ok(path);
-                }
-            }
-        }
-
-        let default_path = dirs::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
-            .join(".local/share/ndict/")
-            .join(model_filename);
-
-        warn!("Model not found, will use default path: {:?}", default_path);
-        Ok(default_path)
+impl TranscriptionBackend for WhisperEngine {
+    fn name(&self) -> &str {
+        "whisper"
     }
 
-    async fn download_model(&mut self) -> Result<()> {
-        let model_url = &self.model_url;
-        let model_dir = self
-            .model_path
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Invalid model path"))?;
-
-        info!("Creating model directory: {:?}", model_dir);
-        tokio::fs::create_dir_all(model_dir).await?;
-
-        info!("Downloading model from: {}", model_url);
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(self.load_model())
+    }
 
-        // Create temporary file for atomic write
-        let temp_path = format!("{}.tmp", self.model_path.display());
-        let temp_path = PathBuf::from(&temp_path);
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<Transcription>> {
+        Box::pin(async move {
+            let text = WhisperEngine::transcribe(self, audio, language).await?;
+            Ok(Transcription {
+                text,
+                language: self.detected_language().map(str::to_string),
+                confidence: self.confidence(),
+                words: self.words().to_vec(),
+                segments: self.segments().to_vec(),
+            })
+        })
+    }
 
-        // Clean up any existing temporary file
-        if temp_path.exists() {
-            warn!("Removing existing temporary file: {:?}", temp_path);
-            tokio::fs::remove_file(&temp_path).await?;
-        }
+    fn set_translate(&mut self, translate: bool) {
+        WhisperEngine::set_translate(self, translate);
+    }
 
-        // Retry logic with exponential backoff
-        let max_retries = 3;
-        let mut last_error = None;
-
-        for attempt in 1..=max_retries {
-            debug!("Download attempt {}/{}", attempt, max_retries);
-
-            match self
-                .download_model_with_checksum(&temp_path, model_url, attempt, max_retries)
-                .await
-            {
-                Ok(()) => {
-                    // Verify checksum if provided
-                    if let Some(ref expected_checksum) = self.model_checksum {
-                        info!("Verifying model checksum...");
-                        let actual_checksum = self.compute_file_checksum(&temp_path)?;
-                        if &actual_checksum != expected_checksum {
-                            error!(
-                                "Checksum verification failed! Expected: {}, Got: {}",
-                                expected_checksum, actual_checksum
-                            );
-                            // Clean up the failed download
-                            tokio::fs::remove_file(&temp_path).await?;
-                            last_error = Some(anyhow::anyhow!(
-                                "Checksum mismatch: expected {}, got {}",
-                                expected_checksum,
-                                actual_checksum
-                            ));
-                            continue;
-                        }
-                        info!("Checksum verification passed: {}", actual_checksum);
-                    }
+    fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(Some(Box::new(WhisperEngine::fork(self)?)))
+    }
 
-                    // Atomic rename from temp to final path
-                    info!("Atomic rename: {:?} -> {:?}", temp_path, self.model_path);
-                    tokio::fs::rename(&temp_path, &self.model_path).await?;
-                    info!("Model downloaded and verified successfully to: {:?}", self.model_path);
-                    return Ok(());
-                }
-                Err(e) => {
-                    let error_msg = format!("{}", e);
-                    error!("Download attempt {} failed: {}", attempt, error_msg);
-                    last_error = Some(anyhow::anyhow!(error_msg));
-
-                    // Clean up partial download
-                    if temp_path.exists() {
-                        warn!("Cleaning up partial download: {:?}", temp_path);
-                        if let Err(cleanup_err) = tokio::fs::remove_file(&temp_path).await {
-                            warn!("Failed to clean up temporary file: {}", cleanup_err);
-                        }
-                    }
+    fn set_abort_signal(&mut self, abort: AbortSignal) {
+        self.abort = abort;
+    }
+}
 
-                    // Exponential backoff before next retry
-                    if attempt < max_retries {
-                        let delay_ms = 1000 * 2_u64.pow(attempt as u32);
-                        info!(
-                            "Waiting {} ms before retry (attempt {}/{})...",
-                            delay_ms, attempt + 1, max_retries
-                        );
-                        sleep(Duration::from_millis(delay_ms)).await;
-                    }
+/// Join sub-word tokens, each with its DTW time in centiseconds, into
+/// words. A token starting with a space begins a new word; a word lasts
+/// until the next one starts, the last one until `end_cs`. Tokens DTW could
+/// not place (negative times) are dropped.
+fn words_from_tokens(tokens: &[(String, i64)], end_cs: i64) -> Vec<WordTiming> {
+    let mut words: Vec<WordTiming> = Vec::new();
+    for (text, t_dtw) in tokens.iter().filter(|(_, t_dtw)| *t_dtw >= 0) {
+        let time_ms = *t_dtw as u64 * 10;
+        match words.last_mut() {
+            Some(word) if !text.starts_with(' ') => word.text.push_str(text),
+            _ => {
+                if let Some(previous) = words.last_mut() {
+                    previous.end_ms = time_ms;
                 }
+                words.push(WordTiming {
+                    text: text.trim_start().to_string(),
+                    start_ms: time_ms,
+                    end_ms: time_ms,
+                });
             }
         }
-
-        Err(last_error.unwrap_or_else(|| {
-            anyhow::anyhow!("Failed to download model after {} attempts", max_retries)
-        }))
     }
+    if let Some(last) = words.last_mut() {
+        last.end_ms = last.end_ms.max(end_cs.max(0) as u64 * 10);
+    }
+    words.retain(|word| !word.text.is_empty());
+    words
+}
 
-    async fn download_model_with_checksum(
-        &self,
-        temp_path: &PathBuf,
-        model_url: &str,
-        attempt: usize,
-        max_attempts: usize,
-    ) -> Result<()> {
-        use futures_util::StreamExt;
-        use tokio::io::AsyncWriteExt;
-
-        info!(
-            "Starting download (attempt {}/{}): {}",
-            attempt, max_attempts, model_url
-        );
-
-        // Configure client with timeouts
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(300)) // 5 minute total timeout for download
-            .connect_timeout(Duration::from_secs(30)) // 30 second connect timeout
-            .redirect(reqwest::redirect::Policy::limited(5)) // Max 5 redirects
-            .build()
-            .map_err(|e| anyhow::anyhow!("Failed to create HTTP client: {}", e))?;
-
-        // Send HEAD request to check ETag (HuggingFace CDN may not return Content-Length on HEAD)
-        let head_response = client
-            .head(model_url)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("HEAD request failed: {}", e))?;
-
-        // Check for ETag (optional, for HuggingFace)
-        let etag = head_response.headers().get("etag").and_then(|v| v.to_str().ok());
-        if let Some(etag) = etag {
-            info!("Server ETag: {}", etag);
-        }
-
-        // Start streaming download
-        let response = client
-            .get(model_url)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("GET request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "HTTP error: {}",
-                response.status()
-            ));
-        }
-
-        let total_bytes = response.content_length();
-        let mut downloaded = 0u64;
-        let mut stream = response.bytes_stream();
-
-        // Create SHA256 hasher
-        let mut hasher = Sha256::new();
-
-        // Open temp file for writing
-        let mut file = tokio::fs::File::create(temp_path)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to create temp file: {}", e))?;
-
-        let start_time = std::time::Instant::now();
-
-        // Download chunks with streaming checksum calculation
-        loop {
-            // Add 30-second timeout to each chunk read
-            let chunk_result = timeout(Duration::from_secs(30), stream.next()).await;
-
-            let chunk = match chunk_result {
-                Ok(Some(Ok(c))) => c,
-                Ok(Some(Err(e))) => {
-                    return Err(anyhow::anyhow!("Download error: {}", e));
-                }
-                Ok(None) => {
-                    // End of stream
-                    break;
-                }
-                Err(_) => {
-                    // Timeout occurred
-                    return Err(anyhow::anyhow!(
-                        "Download chunk read timeout: server did not send data within 30 seconds"
-                    ));
-                }
-            };
-            let chunk_len = chunk.len();
-            downloaded += chunk_len as u64;
-
-            // Update SHA256 hash with this chunk
-            hasher.update(&chunk);
-
-            // Write to file
-            file.write_all(&chunk).await.map_err(|e| {
-                anyhow::anyhow!("Failed to write to temp file: {}", e)
-            })?;
-
-            // Log progress every 10% or every 10 seconds
-            if total_bytes.is_some() {
-                let total = total_bytes.unwrap();
-                let progress = (downloaded * 100) / total;
-                let elapsed = start_time.elapsed().as_secs();
-
-                if progress % 10 == 0 || elapsed % 10 == 0 {
-                    let speed = if elapsed > 0 {
-                        downloaded / elapsed
-                    } else {
-                        0
-                    };
-                    info!(
-                        "Download progress: {}% ({}/{} bytes, {} bytes/s)",
-                        progress,
-                        Self::pretty_bytes(downloaded),
-                        Self::pretty_bytes(total),
-                        Self::pretty_bytes(speed)
-                    );
-                }
-            } else {
-                let elapsed = start_time.elapsed().as_secs();
-                if elapsed % 10 == 0 {
-                    info!(
-                        "Download progress: {} bytes downloaded...",
-                        Self::pretty_bytes(downloaded)
-                    );
-                }
-            }
+/// Whether `whisper.backend` asks for the GPU, warning about values that
+/// fall back to the CPU.
+pub fn uses_gpu(backend: &str) -> bool {
+    match backend.to_lowercase().as_str() {
+        "gpu" => true,
+        "cuda" => true,
+        "vulkan" if cfg!(feature = "vulkan") => true,
+        "vulkan" => {
+            warn!("ndictd was built without the \"vulkan\" feature, using CPU backend");
+            false
         }
-
-        // Flush and close the file
-        file.flush().await.map_err(|e| {
-            anyhow::anyhow!("Failed to flush temp file: {}", e)
-        })?;
-        drop(file);
-
-        // Verify file size matches expected size from GET response (HEAD may return 0 from some CDNs)
-        if let Some(expected) = total_bytes {
-            let metadata = tokio::fs::metadata(temp_path).await?;
-            let actual_size = metadata.len();
-
-            if actual_size != expected {
-                return Err(anyhow::anyhow!(
-                    "File size mismatch: expected {} bytes, got {} bytes",
-                    Self::pretty_bytes(expected),
-                    Self::pretty_bytes(actual_size)
-                ));
-            }
-
-            info!(
-                "File size verification passed: {} bytes",
-                Self::pretty_bytes(actual_size)
+        "cpu" => false,
+        _ => {
+            warn!(
+                "Invalid backend value '{}', defaulting to CPU. Valid options: cpu, gpu, cuda, vulkan",
+                backend
             );
+            false
         }
-
-        // Note: Checksum verification is handled by the caller
-        debug!("Download streaming complete, file written to: {:?}", temp_path);
-
-        Ok(())
     }
+}
 
-    fn compute_file_checksum(&self, file_path: &PathBuf) -> Result<String> {
-        use std::fs::File;
-        use std::io::Read;
-
-        info!("Computing SHA256 checksum for: {:?}", file_path);
-
-        let mut file = File::open(file_path)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0u8; 8192];
-
-        loop {
-            let n = file.read(&mut buffer)?;
-            if n == 0 {
-                break;
-            }
-            hasher.update(&buffer[..n]);
-        }
-
-        let result = hasher.finalize();
-        let checksum = hex::encode(result);
-
-        info!("Computed SHA256 checksum: {}", checksum);
+/// Apply the silence and blank-output settings shared by the batch and
+/// streaming engines, so whisper.cpp itself skips non-speech rather than
+/// leaving it to post-processing.
+pub fn apply_thresholds(
+    params: &mut FullParams<'_, '_>,
+    no_speech_threshold: f32,
+    decoding: &DecodingConfig,
+) {
+    params.set_no_speech_thold(no_speech_threshold);
+    params.set_logprob_thold(decoding.logprob_threshold);
+    params.set_suppress_blank(decoding.suppress_blank);
+    params.set_suppress_nst(decoding.suppress_non_speech);
+}
 
-        Ok(checksum)
+/// Whether a decoded segment is probably not real speech: Whisper itself
+/// rates it above `threshold`, or it is a known hallucination and Whisper
+/// is at least half that unsure.
+fn is_likely_hallucination(text: &str, no_speech_probability: f32, threshold: f32) -> bool {
+    if threshold >= 1.0 {
+        return false;
     }
-
-    /// Helper function to format bytes in human-readable format
-    fn pretty_bytes(bytes: u64) -> String {
-        const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-        let mut size = bytes as f64;
-        let mut unit_index = 0;
-
-        while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-            size /= 1024.0;
-            unit_index += 1;
-        }
-
-        format!("{:.2} {}", size, UNITS[unit_index])
+    if no_speech_probability > threshold {
+        return true;
     }
+
+    let normalized: String = text
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let normalized = normalized.trim();
+    no_speech_probability > threshold / 2.0
+        && HALLUCINATED_PHRASES
+            .iter()
+            .any(|phrase| normalized.starts_with(phrase))
 }
 
 #[cfg(test)]
@@ -567,30 +685,77 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_find_model_path_existing() {
-        let url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin";
-        let path = WhisperEngine::find_model_path(url).unwrap();
-
-        assert!(path.to_str().unwrap().contains("ggml-base.bin"));
-        assert!(path.extension().unwrap() == "bin");
+    fn test_is_likely_hallucination_uses_threshold() {
+        assert!(is_likely_hallucination("Hello there", 0.8, 0.6));
+        assert!(!is_likely_hallucination("Hello there", 0.4, 0.6));
+        // 1.0 disables the filter entirely
+        assert!(!is_likely_hallucination("Hello there", 0.99, 1.0));
     }
 
     #[test]
-    fn test_find_model_path_fallback() {
-        let url = "https://example.com/models/ggml-nonexistent.bin";
-        let path = WhisperEngine::find_model_path(url).unwrap();
+    fn test_parse_sampling_strategy_uses_decoding_config() {
+        let decoding = DecodingConfig {
+            beam_size: 8,
+            patience: 2.0,
+            best_of: 3,
+            ..DecodingConfig::default()
+        };
+        let mut engine =
+            WhisperEngine::new("https://example.com/ggml-base.bin".to_string(), "cpu".to_string())
+                .unwrap()
+                .with_decoding(decoding);
+        assert!(matches!(
+            engine.parse_sampling_strategy(),
+            SamplingStrategy::Greedy { best_of: 3 }
+        ));
+
+        engine.sampling_strategy = "beam".to_string();
+        assert!(matches!(
+            engine.parse_sampling_strategy(),
+            SamplingStrategy::BeamSearch { beam_size: 8, patience } if patience == 2.0
+        ));
+    }
 
-        assert!(path.to_str().unwrap().contains("ggml-nonexistent.bin"));
-        assert!(path.to_str().unwrap().contains(".local/share/ndict"));
+    #[test]
+    fn test_words_from_tokens() {
+        let tokens = [
+            (" Hel".to_string(), 12),
+            ("lo".to_string(), 20),
+            (" wor".to_string(), 45),
+            ("ld".to_string(), -1),
+            ("!".to_string(), 60),
+        ];
+        let words = words_from_tokens(&tokens, 90);
+        assert_eq!(
+            words,
+            vec![
+                WordTiming {
+                    text: "Hello".to_string(),
+                    start_ms: 120,
+                    end_ms: 450,
+                },
+                WordTiming {
+                    text: "wor!".to_string(),
+                    start_ms: 450,
+                    end_ms: 900,
+                },
+            ]
+        );
+        assert!(words_from_tokens(&[], 90).is_empty());
     }
 
     #[test]
-    fn test_find_model_path_from_different_url() {
-        let url = "https://custom-host.com/path/to/ggml-tiny.en.bin";
-        let path = WhisperEngine::find_model_path(url).unwrap();
+    fn test_parse_task() {
+        assert!(!parse_task("transcribe").unwrap());
+        assert!(parse_task("translate").unwrap());
+        assert!(parse_task("summarize").is_err());
+    }
 
-        assert!(path.to_str().unwrap().contains("ggml-tiny.en.bin"));
-        assert!(path.extension().unwrap() == "bin");
+    #[test]
+    fn test_is_likely_hallucination_known_phrases() {
+        assert!(is_likely_hallucination(" Thanks for watching!", 0.35, 0.6));
+        assert!(!is_likely_hallucination(" Thanks for watching!", 0.1, 0.6));
+        assert!(!is_likely_hallucination("Thanks for the update", 0.35, 0.6));
     }
 
     #[test]
@@ -668,6 +833,48 @@ mod tests {
         assert!(engine.state.is_none());
     }
 
+    #[test]
+    fn test_with_model_path_overrides_search() {
+        let engine = WhisperEngine::new(
+            "https://example.com/ggml-base.bin".to_string(),
+            "cpu".to_string(),
+        )
+        .unwrap();
+        assert!(!engine.explicit_path);
+
+        let engine = engine.with_model_path(Some(PathBuf::from("/mnt/models/whisper.bin")));
+        assert_eq!(engine.model_path, PathBuf::from("/mnt/models/whisper.bin"));
+        assert!(engine.explicit_path);
+    }
+
+    #[tokio::test]
+    async fn test_missing_model_path_is_not_downloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut engine = WhisperEngine::new(
+            "https://example.com/ggml-base.bin".to_string(),
+            "cpu".to_string(),
+        )
+        .unwrap()
+        .with_model_path(Some(dir.path().join("missing.bin")));
+
+        let error = engine.load_model().await.unwrap_err();
+        assert!(error.to_string().contains("whisper.model_path"));
+        assert!(!dir.path().join("missing.bin").exists());
+    }
+
+    #[test]
+    fn test_with_diarization() {
+        let engine = WhisperEngine::new(
+            "http://custom.com/ggml-small.en-tdrz.bin".to_string(),
+            "cpu".to_string(),
+        )
+        .unwrap()
+        .with_diarization(true);
+        assert!(engine.diarize);
+        // Forking shares a loaded model, so needs one
+        assert!(engine.fork().is_err());
+    }
+
     #[test]
     fn test_new_whisper_engine_custom_url() {
         let custom_url = "http://custom.com/model.bin".to_string();
@@ -675,6 +882,8 @@ mod tests {
 
         assert_eq!(engine.model_url, custom_url);
         assert_eq!(engine.backend, "gpu");
+        assert_eq!(engine.gpu_device, 0);
+        assert_eq!(engine.with_gpu_device(1).gpu_device, 1);
     }
 
     #[test]
@@ -0,0 +1,279 @@
+use anyhow::Result;
+use futures_util::stream::{self, StreamExt};
+use shared::ipc::TranscribedFile;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::audio::file::read_wav_resampled;
+use crate::audio::resample::duration_ms;
+use crate::output::subtitles::{self, FileTranscript, SubtitleFormat};
+use crate::transcription::backend::WorkerPool;
+use crate::transcription::chunking::Chunker;
+
+/// Transcribes WAV files for `ndict transcribe`, one transcript per file.
+pub struct FileTranscriber {
+    workers: Arc<WorkerPool>,
+    chunker: Chunker,
+    language: String,
+    sample_rate: u32,
+    format: SubtitleFormat,
+}
+
+impl FileTranscriber {
+    pub fn new(
+        workers: Arc<WorkerPool>,
+        chunker: Chunker,
+        language: String,
+        sample_rate: u32,
+        format: SubtitleFormat,
+    ) -> Self {
+        Self {
+            workers,
+            chunker,
+            language,
+            sample_rate,
+            format,
+        }
+    }
+
+    /// Transcribe `input` into `output`; returns the length of the audio in ms.
+    pub async fn transcribe(&self, input: &Path, output: &Path) -> Result<u64> {
+        let audio = read_wav_resampled(input, self.sample_rate)?;
+        let audio_ms = duration_ms(audio.len(), self.sample_rate);
+        tracing::info!("Transcribing {} ({} ms)", input.display(), audio_ms);
+
+        let chunks = self
+            .chunker
+            .transcribe_chunks(&self.workers, &audio, &self.language)
+            .await?;
+        let transcript = FileTranscript::from_chunks(chunks.into_iter().map(
+            |(offset_ms, samples, transcription)| {
+                (offset_ms, duration_ms(samples, self.sample_rate), transcription)
+            },
+        ));
+        transcript.write_to(output, self.format)?;
+
+        tracing::info!(
+            "Wrote {} segments of {} to {}",
+            transcript.segments.len(),
+            input.display(),
+            output.display()
+        );
+        Ok(audio_ms)
+    }
+
+    /// Transcribe each `(input, output)` pair, up to `parallel_files` at a
+    /// time. A file that fails is reported and does not stop the others.
+    pub async fn transcribe_all(
+        &self,
+        jobs: Vec<(PathBuf, PathBuf)>,
+        parallel_files: usize,
+    ) -> Vec<TranscribedFile> {
+        stream::iter(jobs)
+            .map(|(input, output)| async move {
+                let result = self.transcribe(&input, &output).await;
+                if let Err(e) = &result {
+                    tracing::error!("Failed to transcribe {}: {:#}", input.display(), e);
+                }
+                TranscribedFile {
+                    input: input.display().to_string(),
+                    output: result.is_ok().then(|| output.display().to_string()),
+                    audio_ms: result.as_ref().map_or(0, |audio_ms| *audio_ms),
+                    error: result.err().map(|e| format!("{:#}", e)),
+                }
+            })
+            .buffered(parallel_files.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// The WAV files `path` names: the file itself, every `.wav` file in a
+/// directory, or the files matching a `*`/`?` pattern in the last
+/// component, e.g. `~/talks/day1-*.wav`. Sorted by path.
+pub fn expand_inputs(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let (dir, pattern) = if path.is_dir() {
+        (path, None)
+    } else {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        if !name.contains(['*', '?']) {
+            return Err(anyhow::anyhow!("No such file or directory: {}", path.display()));
+        }
+        (path.parent().unwrap_or(Path::new(".")), Some(glob_regex(name)?))
+    };
+
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", dir.display(), e))?
+    {
+        let input = entry?.path();
+        let name = input.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        let matches = match &pattern {
+            Some(pattern) => pattern.is_match(name),
+            None => input
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("wav")),
+        };
+        if matches && input.is_file() {
+            inputs.push(input);
+        }
+    }
+    if inputs.is_empty() {
+        return Err(anyhow::anyhow!("No WAV files found at {}", path.display()));
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Regex for a file name pattern where `*` matches any run of characters
+/// and `?` any single character.
+fn glob_regex(pattern: &str) -> Result<regex::Regex> {
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Ok(regex::Regex::new(&regex)?)
+}
+
+/// Pair each input with its transcript. A single file given by name goes
+/// to `output` itself; otherwise `output` is a directory. Without `output`,
+/// transcripts are written next to their inputs.
+pub fn plan_outputs(
+    path: &Path,
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    format: SubtitleFormat,
+) -> Vec<(PathBuf, PathBuf)> {
+    inputs
+        .into_iter()
+        .map(|input| {
+            let transcript = match &output {
+                Some(output) if path.is_file() => output.clone(),
+                Some(dir) => subtitles::default_output_path(
+                    &dir.join(input.file_name().unwrap_or_default()),
+                    format,
+                ),
+                None => subtitles::default_output_path(&input, format),
+            };
+            (input, transcript)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChunkingConfig;
+    use crate::transcription::backend::{Transcription, TranscriptionBackend};
+    use futures_util::future::BoxFuture;
+
+    fn touch(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, b"").unwrap();
+        path
+    }
+
+    fn write_wav(path: &Path) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..1600 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    struct Echo;
+
+    impl TranscriptionBackend for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+            Box::pin(async { Ok(()) })
+        }
+
+        fn transcribe<'a>(
+            &'a mut self,
+            _audio: &'a [f32],
+            _language: &'a str,
+        ) -> BoxFuture<'a, Result<Transcription>> {
+            Box::pin(async {
+                Ok(Transcription {
+                    text: "hello".to_string(),
+                    ..Transcription::default()
+                })
+            })
+        }
+
+        fn set_translate(&mut self, _translate: bool) {}
+    }
+
+    #[test]
+    fn test_expand_inputs_directory_and_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let b = touch(dir.path(), "day1-b.wav");
+        let a = touch(dir.path(), "day1-a.WAV");
+        let c = touch(dir.path(), "day2.wav");
+        touch(dir.path(), "notes.txt");
+
+        assert_eq!(expand_inputs(dir.path()).unwrap(), vec![a, b.clone(), c.clone()]);
+        assert_eq!(expand_inputs(&dir.path().join("day1-?.wav")).unwrap(), vec![b.clone()]);
+        assert_eq!(expand_inputs(&dir.path().join("*.wav")).unwrap(), vec![b, c.clone()]);
+        assert_eq!(expand_inputs(&c).unwrap(), vec![c]);
+        assert!(expand_inputs(&dir.path().join("*.mp3")).is_err());
+        assert!(expand_inputs(&dir.path().join("missing.wav")).is_err());
+    }
+
+    #[test]
+    fn test_plan_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = touch(dir.path(), "a.wav");
+        let srt = SubtitleFormat::Srt;
+
+        let single = plan_outputs(&a, vec![a.clone()], Some(PathBuf::from("/out/x.srt")), srt);
+        assert_eq!(single, vec![(a.clone(), PathBuf::from("/out/x.srt"))]);
+
+        let batch = plan_outputs(dir.path(), vec![a.clone()], Some(PathBuf::from("/out")), srt);
+        assert_eq!(batch, vec![(a.clone(), PathBuf::from("/out/a.srt"))]);
+
+        let beside = plan_outputs(dir.path(), vec![a.clone()], None, srt);
+        assert_eq!(beside, vec![(a, dir.path().join("a.srt"))]);
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_all_reports_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.wav");
+        write_wav(&good);
+        let bad = touch(dir.path(), "bad.wav");
+
+        let workers = Arc::new(WorkerPool::new(vec![Box::new(Echo), Box::new(Echo)]));
+        let chunker = Chunker::from_config(&ChunkingConfig::default(), 16000);
+        let transcriber =
+            FileTranscriber::new(workers, chunker, "en".to_string(), 16000, SubtitleFormat::Text);
+        let jobs = plan_outputs(dir.path(), vec![bad, good], None, SubtitleFormat::Text);
+        let report = transcriber.transcribe_all(jobs, 2).await;
+
+        assert!(report[0].error.is_some());
+        assert_eq!(report[0].output, None);
+        assert_eq!(report[1].error, None);
+        assert_eq!(report[1].audio_ms, 100);
+        let written = std::fs::read_to_string(dir.path().join("good.txt")).unwrap();
+        assert_eq!(written, "hello\n");
+    }
+}
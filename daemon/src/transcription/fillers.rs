@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::transcription::tokens::{normalize, trailing_punctuation};
+
+/// Built-in fillers by Whisper language code, replaced per language by
+/// `post_processing.fillers`.
+const DEFAULT_FILLERS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &["um", "umm", "uh", "uhm", "er", "erm", "ah", "hmm", "mm", "you know", "i mean"],
+    ),
+    ("es", &["eh", "em", "ehm", "mmm", "o sea"]),
+    ("de", &["äh", "ähm", "öhm", "hm", "hmm"]),
+    ("fr", &["euh", "heu", "hum", "bah"]),
+    ("it", &["ehm", "uhm"]),
+    ("pt", &["hum", "ahn"]),
+];
+
+/// Drops filler words from dictated text. See
+/// `post_processing.remove_fillers`.
+///
+/// A single-word filler is always removed. A phrase like "you know" is only
+/// removed where punctuation sets it off ("it's, you know, fine"), so "do
+/// you know him" is kept.
+#[derive(Debug, Clone, Default)]
+pub struct FillerRemover {
+    /// Lowercase words of each filler, by language.
+    fillers: HashMap<String, Vec<Vec<String>>>,
+}
+
+impl FillerRemover {
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Self {
+        let mut lists: HashMap<String, Vec<String>> = DEFAULT_FILLERS
+            .iter()
+            .map(|(language, fillers)| {
+                (
+                    language.to_string(),
+                    fillers.iter().map(|filler| filler.to_string()).collect(),
+                )
+            })
+            .collect();
+        lists.extend(overrides.clone());
+
+        let fillers = lists
+            .into_iter()
+            .map(|(language, fillers)| {
+                let mut phrases: Vec<Vec<String>> = fillers
+                    .iter()
+                    .map(|filler| filler.split_whitespace().map(normalize).collect::<Vec<_>>())
+                    .filter(|words| !words.is_empty())
+                    .collect();
+                // Longest first, so a phrase wins over a filler it starts with
+                phrases.sort_by_key(|words| std::cmp::Reverse(words.len()));
+                (language, phrases)
+            })
+            .collect();
+        Self { fillers }
+    }
+
+    pub fn apply(&self, text: &str, language: &str) -> String {
+        let Some(fillers) = self.fillers.get(language).filter(|fillers| !fillers.is_empty()) else {
+            return text.to_string();
+        };
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+        let mut capitalize_next = false;
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let matched = fillers.iter().find(|filler| {
+                let n = filler.len();
+                i + n <= tokens.len()
+                    && filler
+                        .iter()
+                        .zip(&tokens[i..i + n])
+                        .all(|(word, token)| *word == normalize(token))
+                    && (n == 1 || is_set_off(&tokens, i, n))
+            });
+            let Some(filler) = matched else {
+                let token = if capitalize_next {
+                    capitalize_first(tokens[i])
+                } else {
+                    tokens[i].to_string()
+                };
+                out.push(token);
+                capitalize_next = false;
+                i += 1;
+                continue;
+            };
+
+            let first = tokens[i];
+            let last = tokens[i + filler.len() - 1];
+            let trailing = trailing_punctuation(last);
+            if trailing.contains(['.', '!', '?']) {
+                // "I think, um." keeps its full stop on "think"
+                if let Some(previous) = out.last_mut() {
+                    let kept = previous.trim_end_matches([',', ';', ':']).len();
+                    previous.truncate(kept);
+                    previous.push_str(trailing.trim_start_matches([',', ';', ':']));
+                }
+            }
+            let sentence_start = out
+                .last()
+                .is_none_or(|previous| previous.ends_with(['.', '!', '?']));
+            capitalize_next = sentence_start && first.chars().any(char::is_uppercase);
+            i += filler.len();
+        }
+        out.join(" ")
+    }
+}
+
+/// Whether the phrase at `tokens[start..start + len]` is followed by
+/// punctuation or ends the text.
+fn is_set_off(tokens: &[&str], start: usize, len: usize) -> bool {
+    let end = start + len;
+    end == tokens.len() || !trailing_punctuation(tokens[end - 1]).is_empty()
+}
+
+fn capitalize_first(token: &str) -> String {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removes_fillers_and_their_punctuation() {
+        let remover = FillerRemover::new(&HashMap::new());
+        assert_eq!(remover.apply("Um, I think, uh, we should go.", "en"), "I think, we should go.");
+        assert_eq!(remover.apply("So I think, um.", "en"), "So I think.");
+        assert_eq!(remover.apply("Done. Uh, next one", "en"), "Done. Next one");
+    }
+
+    #[test]
+    fn test_phrases_must_be_set_off() {
+        let remover = FillerRemover::new(&HashMap::new());
+        assert_eq!(remover.apply("It's, you know, fine", "en"), "It's, fine");
+        assert_eq!(remover.apply("Do you know him?", "en"), "Do you know him?");
+    }
+
+    #[test]
+    fn test_fillers_are_per_language() {
+        let overrides = HashMap::from([
+            ("en".to_string(), vec!["like".to_string()]),
+            ("fr".to_string(), Vec::new()),
+        ]);
+        let remover = FillerRemover::new(&overrides);
+        assert_eq!(remover.apply("it was like um big", "en"), "it was um big");
+        assert_eq!(remover.apply("euh, bonjour", "fr"), "euh, bonjour");
+        assert_eq!(remover.apply("eh, hola", "es"), "hola");
+        assert_eq!(remover.apply("um, hi", "ja"), "um, hi");
+    }
+}
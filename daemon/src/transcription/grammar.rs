@@ -0,0 +1,89 @@
+use anyhow::Result;
+use std::path::Path;
+use whisper_rs::{WhisperGrammarElement, WhisperGrammarElementType};
+
+use crate::config::GrammarConfig;
+use crate::transcription::vocabulary::load_dictionary;
+
+/// Build a whisper.cpp grammar with a single rule accepting exactly one of
+/// `phrases`, i.e. `root ::= " " ("phrase one" | "phrase two" | ...)`.
+/// Whisper's tokens start with a space, so every alternative does too.
+/// Returns `None` when there are no phrases.
+pub fn phrase_grammar(phrases: &[String]) -> Option<Vec<WhisperGrammarElement>> {
+    let phrases: Vec<&str> = phrases
+        .iter()
+        .map(|phrase| phrase.trim())
+        .filter(|phrase| !phrase.is_empty())
+        .collect();
+    if phrases.is_empty() {
+        return None;
+    }
+
+    let mut rule = Vec::new();
+    for (i, phrase) in phrases.iter().enumerate() {
+        if i > 0 {
+            rule.push(WhisperGrammarElement::new(WhisperGrammarElementType::Alternate, 0));
+        }
+        for c in std::iter::once(' ').chain(phrase.chars()) {
+            rule.push(WhisperGrammarElement::new(
+                WhisperGrammarElementType::Character,
+                c as u32,
+            ));
+        }
+    }
+    rule.push(WhisperGrammarElement::new(WhisperGrammarElementType::End, 0));
+    Some(rule)
+}
+
+/// Build the grammar from `whisper.grammar.phrases` and the phrases in
+/// `whisper.grammar.phrases_file`, one per line.
+pub fn grammar_from_config(config: &GrammarConfig) -> Result<Option<Vec<WhisperGrammarElement>>> {
+    let mut phrases = config.phrases.clone();
+    if let Some(path) = &config.phrases_file {
+        phrases.extend(load_dictionary(Path::new(path))?);
+    }
+    Ok(phrase_grammar(&phrases))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn characters(rule: &[WhisperGrammarElement]) -> String {
+        rule.iter()
+            .map(|element| match element.element_type {
+                WhisperGrammarElementType::Character => char::from_u32(element.value).unwrap(),
+                WhisperGrammarElementType::Alternate => '|',
+                WhisperGrammarElementType::End => '$',
+                _ => '?',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_phrase_grammar_alternates_phrases() {
+        let phrases = vec!["save file".to_string(), " ".to_string(), "undo".to_string()];
+        let rule = phrase_grammar(&phrases).unwrap();
+        assert_eq!(characters(&rule), " save file| undo$");
+    }
+
+    #[test]
+    fn test_phrase_grammar_empty() {
+        assert!(phrase_grammar(&[]).is_none());
+        assert!(phrase_grammar(&["  ".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_grammar_from_config_reads_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("commands.txt");
+        std::fs::write(&path, "# editor\nnew tab\n").unwrap();
+        let config = GrammarConfig {
+            phrases: vec!["close tab".to_string()],
+            phrases_file: Some(path.display().to_string()),
+            ..GrammarConfig::default()
+        };
+        let rule = grammar_from_config(&config).unwrap().unwrap();
+        assert_eq!(characters(&rule), " close tab| new tab$");
+    }
+}
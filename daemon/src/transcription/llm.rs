@@ -4,6 +4,29 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::time::Duration;
 
+/// Which text `llm.input` sends to the LLM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmInput {
+    /// The transcript as Whisper produced it; post-processing runs on the
+    /// LLM's answer.
+    Raw,
+    /// The post-processed text.
+    Processed,
+}
+
+impl LlmInput {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "raw" => Ok(Self::Raw),
+            "processed" => Ok(Self::Processed),
+            other => Err(anyhow::anyhow!(
+                "Unknown llm.input '{}', expected \"raw\" or \"processed\"",
+                other
+            )),
+        }
+    }
+}
+
 pub struct LlmCleaner {
     api_url: String,
     client: reqwest::Client,
@@ -159,6 +182,7 @@ mod tests {
             model: "qwen2.5:0.5b".to_string(),
             system_prompt: "Clean up this text. Output JSON with a \"cleaned_text\" field.".to_string(),
             timeout_seconds: 10,
+            input: "processed".to_string(),
         }
     }
 
@@ -186,6 +210,13 @@ mod tests {
         assert_eq!(cleaner.api_url, "http://localhost:11434");
     }
 
+    #[test]
+    fn test_llm_input_parse() {
+        assert_eq!(LlmInput::parse("raw").unwrap(), LlmInput::Raw);
+        assert_eq!(LlmInput::parse("processed").unwrap(), LlmInput::Processed);
+        assert!(LlmInput::parse("cleaned").is_err());
+    }
+
     #[test]
     fn test_parse_cleaned_text_valid_json() {
         let content = r#"{"cleaned_text": "Hello world!"}"#;
@@ -0,0 +1,124 @@
+/// Regional formatting used by the `numbers` and `punctuation` stages. See
+/// `post_processing.locale`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+    pub open_quote: &'static str,
+    pub close_quote: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// ISO dates and a decimal point, for languages without an entry below.
+const DEFAULT: Locale = Locale {
+    decimal_separator: '.',
+    date_order: DateOrder::YearMonthDay,
+    date_separator: '-',
+    open_quote: "\u{201C}",
+    close_quote: "\u{201D}",
+};
+
+const fn locale(
+    decimal_separator: char,
+    date_order: DateOrder,
+    date_separator: char,
+    open_quote: &'static str,
+    close_quote: &'static str,
+) -> Locale {
+    Locale {
+        decimal_separator,
+        date_order,
+        date_separator,
+        open_quote,
+        close_quote,
+    }
+}
+
+impl Locale {
+    /// The locale for a `post_processing.locale` value such as "de",
+    /// "en-GB" or "pt_BR.UTF-8". "auto" follows the dictation `language`.
+    pub fn resolve(setting: &str, language: &str) -> Self {
+        if setting.eq_ignore_ascii_case("auto") {
+            Self::parse(language)
+        } else {
+            Self::parse(setting)
+        }
+    }
+
+    fn parse(tag: &str) -> Self {
+        let tag = tag.split('.').next().unwrap_or_default().replace('_', "-");
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts.next().unwrap_or_default().to_uppercase();
+
+        use DateOrder::*;
+        match (language.as_str(), region.as_str()) {
+            ("en", "GB" | "IE" | "AU" | "NZ" | "IN" | "ZA") => {
+                locale('.', DayMonthYear, '/', "\u{2018}", "\u{2019}")
+            }
+            ("en", _) => locale('.', MonthDayYear, '/', "\u{201C}", "\u{201D}"),
+            ("de", _) => locale(',', DayMonthYear, '.', "\u{201E}", "\u{201C}"),
+            ("fr", _) => locale(',', DayMonthYear, '/', "\u{AB}\u{202F}", "\u{202F}\u{BB}"),
+            ("es", "MX" | "US") => locale('.', DayMonthYear, '/', "\u{201C}", "\u{201D}"),
+            ("es" | "it", _) => locale(',', DayMonthYear, '/', "\u{AB}", "\u{BB}"),
+            ("pt", "BR") => locale(',', DayMonthYear, '/', "\u{201C}", "\u{201D}"),
+            ("pt", _) => locale(',', DayMonthYear, '/', "\u{AB}", "\u{BB}"),
+            ("nl", _) => locale(',', DayMonthYear, '-', "\u{201C}", "\u{201D}"),
+            ("ru" | "uk", _) => locale(',', DayMonthYear, '.', "\u{AB}", "\u{BB}"),
+            ("pl", _) => locale(',', DayMonthYear, '.', "\u{201E}", "\u{201D}"),
+            ("sv", _) => locale(',', YearMonthDay, '-', "\u{201D}", "\u{201D}"),
+            ("ja", _) => locale('.', YearMonthDay, '/', "\u{300C}", "\u{300D}"),
+            ("zh", _) => locale('.', YearMonthDay, '/', "\u{201C}", "\u{201D}"),
+            _ => DEFAULT,
+        }
+    }
+
+    pub fn format_date(&self, year: u32, month: u32, day: u32) -> String {
+        let sep = self.date_separator;
+        match self.date_order {
+            DateOrder::DayMonthYear => format!("{:02}{sep}{:02}{sep}{}", day, month, year),
+            DateOrder::MonthDayYear => format!("{:02}{sep}{:02}{sep}{}", month, day, year),
+            DateOrder::YearMonthDay => format!("{}{sep}{:02}{sep}{:02}", year, month, day),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_follows_language_or_setting() {
+        assert_eq!(Locale::resolve("auto", "de").decimal_separator, ',');
+        assert_eq!(Locale::resolve("auto", "en").decimal_separator, '.');
+        assert_eq!(Locale::resolve("es_MX.UTF-8", "de").decimal_separator, '.');
+        assert_eq!(Locale::resolve("auto", "xx"), DEFAULT);
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(
+            Locale::resolve("en-US", "").format_date(2024, 3, 5),
+            "03/05/2024"
+        );
+        assert_eq!(
+            Locale::resolve("en-GB", "").format_date(2024, 3, 5),
+            "05/03/2024"
+        );
+        assert_eq!(
+            Locale::resolve("de", "").format_date(2024, 3, 5),
+            "05.03.2024"
+        );
+        assert_eq!(
+            Locale::resolve("sv", "").format_date(2024, 3, 5),
+            "2024-03-05"
+        );
+    }
+}
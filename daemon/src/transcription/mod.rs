@@ -1,36 +1,64 @@
+pub mod abort;
+pub mod backend;
+pub mod brackets;
+pub mod casing;
+pub mod chunking;
+pub mod cloud;
+pub mod confidence;
+#[cfg(feature = "ctranslate2")]
+pub mod ctranslate2;
+pub mod dates;
+pub mod dictionary;
+pub mod emoji;
 pub mod engine;
+pub mod files;
+pub mod fillers;
+pub mod grammar;
 pub mod llm;
+pub mod locale;
+pub mod models;
+pub mod multipart;
+pub mod numbers;
+pub mod pool;
+pub mod post_processor;
+pub mod punctuation;
+pub mod repetition;
+pub mod replace;
+pub mod retry;
 pub mod streaming_engine;
+pub mod tokens;
+pub mod vocabulary;
+pub mod vosk;
+pub mod whisper_server;
 
+/// The built-in clean-up, as used where no `PostProcessor` is configured.
 pub fn post_process_transcription(text: &str) -> String {
-    let original = text.trim().to_string();
-    let mut text = original.clone();
+    post_process_with(text, true)
+}
 
-    let words: Vec<&str> = text.split_whitespace().collect();
-    let mut deduped_words = Vec::new();
-    for word in words {
-        if !deduped_words.last().map_or(false, |last| *last == word) {
-            deduped_words.push(word);
-        }
-    }
-    text = deduped_words.join(" ");
+/// Post-process Whisper output. `strip_brackets` removes bracketed
+/// annotations such as "[Music]"; it can be turned off when Whisper is told
+/// not to emit them, so dictated parentheses survive. Repeated words are
+/// kept; see `repetition::RepeatFilter`.
+pub fn post_process_with(text: &str, strip_brackets: bool) -> String {
+    let original = text;
+    let mut text = collapse_whitespace(text);
+    if strip_brackets {
+        text = strip_bracketed(&text);
+    }
+    tracing::debug!("Post-processed: '{}' -> '{}'", original.trim(), text);
+    text
+}
 
-    let re = regex::Regex::new(r"\s+").unwrap();
-    text = re.replace_all(&text, " ").trim().to_string();
+/// Runs of whitespace as single spaces, trimmed.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
 
+/// `text` without "[Music]", "(laughs)" and other bracketed annotations.
+pub fn strip_bracketed(text: &str) -> String {
     let re_brackets = regex::Regex::new(r"\[.*?\]|\{.*?\}|\(.*?\)").unwrap();
-    text = re_brackets.replace_all(&text, "").to_string();
-
-    if text.ends_with(&['.', '?']) {
-        text.push(' ');
-    }
-
-    let re_final = regex::Regex::new(r"\s+").unwrap();
-    text = re_final.replace_all(&text, " ").trim().to_string();
-
-    tracing::debug!("Post-processed: '{}' -> '{}'", original, text);
-
-    text
+    collapse_whitespace(&re_brackets.replace_all(text, ""))
 }
 
 #[cfg(test)]
@@ -52,10 +80,17 @@ mod tests {
     }
 
     #[test]
-    fn test_post_process_remove_duplicate_words() {
-        let input = "hello hello world world test";
+    fn test_post_process_keeps_repeated_words() {
+        let input = "she had had  enough of that that day";
         let output = post_process_transcription(input);
-        assert_eq!(output, "hello world test");
+        assert_eq!(output, "she had had enough of that that day");
+    }
+
+    #[test]
+    fn test_post_process_can_keep_brackets() {
+        let input = "call me (after lunch) today";
+        assert_eq!(post_process_with(input, false), "call me (after lunch) today");
+        assert_eq!(post_process_with(input, true), "call me today");
     }
 
     #[test]
@@ -104,7 +139,7 @@ mod tests {
     fn test_post_process_combined() {
         let input = "  hello hello [noise] world {test} (skip)  world  ";
         let output = post_process_transcription(input);
-        assert_eq!(output, "hello world world");
+        assert_eq!(output, "hello hello world world");
     }
 
     #[test]
@@ -132,7 +167,7 @@ mod tests {
     fn test_post_process_realistic_whisper_output() {
         let input = " hello [laughs] world (um) [clears throat]  test  test  ";
         let output = post_process_transcription(input);
-        assert_eq!(output, "hello world test");
+        assert_eq!(output, "hello world test test");
     }
 
     #[test]
@@ -149,13 +184,6 @@ mod tests {
         assert_eq!(output, "hello, world! test. 123");
     }
 
-    #[test]
-    fn test_post_process_multiple_consecutive_duplicates() {
-        let input = "hello hello hello world world world";
-        let output = post_process_transcription(input);
-        assert_eq!(output, "hello world");
-    }
-
     #[test]
     fn test_post_process_no_duplicates() {
         let input = "hello world test";
@@ -0,0 +1,273 @@
+use whisper_rs::DtwModelPreset;
+
+/// Where the whisper.cpp project publishes its ggml models.
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// `whisper.model` value that picks a model for the machine.
+pub const AUTO_MODEL: &str = "auto";
+
+/// Share of available memory a model may take when choosing automatically,
+/// leaving room for decoding buffers and the rest of the desktop.
+const AUTO_MEMORY_FRACTION: u64 = 2;
+
+/// Preferred models for `model = "auto"`, best first. GPU decoding is fast
+/// enough for full-precision models; on CPU quantized models keep dictation
+/// responsive.
+const AUTO_GPU_MODELS: &[&str] = &["large-v3-turbo", "medium", "small", "base", "tiny"];
+const AUTO_CPU_MODELS: &[&str] = &["small-q5_1", "base-q5_1", "tiny-q5_1"];
+
+/// A whisper.cpp model that can be selected by name instead of by URL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelInfo {
+    pub alias: &'static str,
+    pub file_name: &'static str,
+    /// Approximate download size in MiB.
+    pub size_mib: u32,
+    /// SHA-256 of the file, when it has been verified for the registry.
+    pub sha256: Option<&'static str>,
+}
+
+impl ModelInfo {
+    pub fn url(&self) -> String {
+        format!("{}/{}", MODEL_BASE_URL, self.file_name)
+    }
+
+    pub fn quantization(&self) -> Option<&'static str> {
+        quantization_of(self.file_name)
+    }
+}
+
+/// Quantization of a ggml model file such as `ggml-base-q5_1.bin`, or `None`
+/// for a full-precision model.
+pub fn quantization_of(file_name: &str) -> Option<&str> {
+    let stem = file_name
+        .strip_suffix(".bin")
+        .or_else(|| file_name.strip_suffix(".gguf"))
+        .unwrap_or(file_name);
+    let (_, suffix) = stem.rsplit_once('-')?;
+    let is_quantization = suffix.len() >= 2
+        && suffix.starts_with('q')
+        && suffix[1..].chars().all(|c| c.is_ascii_digit() || c == '_' || c == 'k');
+    is_quantization.then_some(suffix)
+}
+
+/// Alignment heads whisper.cpp uses for DTW word timestamps with a model
+/// file such as `ggml-base.en-q5_1.bin`. Only the official models have a
+/// preset; fine-tuned ones return `None`.
+pub fn dtw_preset_of(file_name: &str) -> Option<DtwModelPreset> {
+    let stem = file_name
+        .strip_suffix(".bin")
+        .or_else(|| file_name.strip_suffix(".gguf"))
+        .unwrap_or(file_name);
+    let stem = match quantization_of(file_name) {
+        Some(quantization) => stem.strip_suffix(quantization)?.strip_suffix('-')?,
+        None => stem,
+    };
+    let preset = match stem.strip_prefix("ggml-").unwrap_or(stem) {
+        "tiny" => DtwModelPreset::Tiny,
+        "tiny.en" => DtwModelPreset::TinyEn,
+        "base" => DtwModelPreset::Base,
+        "base.en" => DtwModelPreset::BaseEn,
+        "small" => DtwModelPreset::Small,
+        "small.en" => DtwModelPreset::SmallEn,
+        "medium" => DtwModelPreset::Medium,
+        "medium.en" => DtwModelPreset::MediumEn,
+        "large-v1" => DtwModelPreset::LargeV1,
+        "large-v2" => DtwModelPreset::LargeV2,
+        "large-v3" => DtwModelPreset::LargeV3,
+        "large-v3-turbo" => DtwModelPreset::LargeV3Turbo,
+        _ => return None,
+    };
+    Some(preset)
+}
+
+const fn model(alias: &'static str, file_name: &'static str, size_mib: u32) -> ModelInfo {
+    ModelInfo {
+        alias,
+        file_name,
+        size_mib,
+        sha256: None,
+    }
+}
+
+/// Built-in registry of the official whisper.cpp models.
+pub const MODELS: &[ModelInfo] = &[
+    model("tiny", "ggml-tiny.bin", 75),
+    model("tiny.en", "ggml-tiny.en.bin", 75),
+    model("base", "ggml-base.bin", 142),
+    model("base.en", "ggml-base.en.bin", 142),
+    model("small", "ggml-small.bin", 466),
+    model("small.en", "ggml-small.en.bin", 466),
+    model("medium", "ggml-medium.bin", 1500),
+    model("medium.en", "ggml-medium.en.bin", 1500),
+    model("large-v1", "ggml-large-v1.bin", 2900),
+    model("large-v2", "ggml-large-v2.bin", 2900),
+    model("large-v3", "ggml-large-v3.bin", 2900),
+    model("large-v3-turbo", "ggml-large-v3-turbo.bin", 1550),
+    model("tiny-q5_1", "ggml-tiny-q5_1.bin", 31),
+    model("tiny.en-q5_1", "ggml-tiny.en-q5_1.bin", 31),
+    model("tiny-q8_0", "ggml-tiny-q8_0.bin", 42),
+    model("base-q5_1", "ggml-base-q5_1.bin", 57),
+    model("base.en-q5_1", "ggml-base.en-q5_1.bin", 57),
+    model("base-q8_0", "ggml-base-q8_0.bin", 78),
+    model("small-q5_1", "ggml-small-q5_1.bin", 181),
+    model("small.en-q5_1", "ggml-small.en-q5_1.bin", 181),
+    model("small-q8_0", "ggml-small-q8_0.bin", 252),
+    model("medium-q5_0", "ggml-medium-q5_0.bin", 514),
+    model("medium.en-q5_0", "ggml-medium.en-q5_0.bin", 514),
+    model("medium-q8_0", "ggml-medium-q8_0.bin", 785),
+    model("large-v2-q5_0", "ggml-large-v2-q5_0.bin", 1030),
+    model("large-v2-q8_0", "ggml-large-v2-q8_0.bin", 1500),
+    model("large-v3-q5_0", "ggml-large-v3-q5_0.bin", 1030),
+    model("large-v3-turbo-q5_0", "ggml-large-v3-turbo-q5_0.bin", 547),
+    model("large-v3-turbo-q8_0", "ggml-large-v3-turbo-q8_0.bin", 834),
+];
+
+/// Look up a model alias such as "base.en".
+pub fn lookup(alias: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|model| model.alias == alias)
+}
+
+/// The q5 quantized variant of an unquantized model, which decodes much
+/// faster on CPU for a small loss in accuracy.
+pub fn quantized_alternative(model: &ModelInfo) -> Option<&'static ModelInfo> {
+    if model.quantization().is_some() {
+        return None;
+    }
+    let prefix = format!("{}-q5", model.alias);
+    MODELS.iter().find(|other| other.alias.starts_with(&prefix))
+}
+
+/// Pick a model for `model = "auto"` from the memory available (in MiB),
+/// whether Whisper runs on a GPU, and whether only English is spoken, in
+/// which case the English-only variant is preferred where one exists.
+pub fn auto_select(available_mib: u64, gpu: bool, english_only: bool) -> &'static ModelInfo {
+    let budget = available_mib / AUTO_MEMORY_FRACTION;
+    let candidates = if gpu { AUTO_GPU_MODELS } else { AUTO_CPU_MODELS };
+    let pick = |alias: &str| {
+        let english = english_only.then(|| english_variant(alias)).and_then(|alias| lookup(&alias));
+        english.or_else(|| lookup(alias))
+    };
+
+    candidates
+        .iter()
+        .filter_map(|alias| pick(alias))
+        .find(|model| model.size_mib as u64 <= budget)
+        .or_else(|| candidates.last().and_then(|alias| pick(alias)))
+        .expect("auto model candidates are in the registry")
+}
+
+/// `small-q5_1` -> `small.en-q5_1`, `base` -> `base.en`.
+fn english_variant(alias: &str) -> String {
+    match alias.split_once('-') {
+        Some((size, quantization)) if quantization.starts_with('q') => {
+            format!("{}.en-{}", size, quantization)
+        }
+        _ => format!("{}.en", alias),
+    }
+}
+
+/// Memory available to new processes, from `/proc/meminfo`.
+pub fn available_memory_mib() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_mem_available(&meminfo)
+}
+
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib / 1024)
+}
+
+/// Turn a model reference into a download URL: URLs pass through unchanged
+/// and aliases are resolved against the registry.
+pub fn resolve_url(reference: &str) -> Option<String> {
+    if reference.contains("://") {
+        return Some(reference.to_string());
+    }
+    lookup(reference).map(ModelInfo::url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_alias() {
+        let model = lookup("base.en").unwrap();
+        assert_eq!(model.file_name, "ggml-base.en.bin");
+        assert_eq!(
+            model.url(),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin"
+        );
+        assert!(lookup("huge").is_none());
+    }
+
+    #[test]
+    fn test_resolve_url_passes_urls_through() {
+        assert_eq!(
+            resolve_url("http://example.com/model.bin").as_deref(),
+            Some("http://example.com/model.bin")
+        );
+        assert!(resolve_url("tiny").unwrap().ends_with("/ggml-tiny.bin"));
+        assert_eq!(resolve_url("not-a-model"), None);
+    }
+
+    #[test]
+    fn test_quantization_of() {
+        assert_eq!(quantization_of("ggml-base-q5_1.bin"), Some("q5_1"));
+        assert_eq!(quantization_of("ggml-large-v3-turbo-q8_0.bin"), Some("q8_0"));
+        assert_eq!(quantization_of("ggml-small.en-q5_0.gguf"), Some("q5_0"));
+        assert_eq!(quantization_of("ggml-large-v3.bin"), None);
+        assert_eq!(quantization_of("ggml-base.bin"), None);
+        assert_eq!(lookup("tiny-q8_0").unwrap().quantization(), Some("q8_0"));
+    }
+
+    #[test]
+    fn test_dtw_preset_of() {
+        assert!(matches!(dtw_preset_of("ggml-base.en.bin"), Some(DtwModelPreset::BaseEn)));
+        assert!(matches!(dtw_preset_of("ggml-small-q5_1.bin"), Some(DtwModelPreset::Small)));
+        assert!(matches!(
+            dtw_preset_of("ggml-large-v3-turbo-q8_0.bin"),
+            Some(DtwModelPreset::LargeV3Turbo)
+        ));
+        assert!(dtw_preset_of("ggml-small.en-tdrz.bin").is_none());
+        assert!(dtw_preset_of("my-finetune.bin").is_none());
+    }
+
+    #[test]
+    fn test_quantized_alternative() {
+        let medium = lookup("medium").unwrap();
+        assert_eq!(quantized_alternative(medium).unwrap().alias, "medium-q5_0");
+        assert!(quantized_alternative(lookup("medium-q8_0").unwrap()).is_none());
+        assert!(quantized_alternative(lookup("large-v1").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_auto_select() {
+        // Plenty of memory: best candidate for the device
+        assert_eq!(auto_select(16_000, true, false).alias, "large-v3-turbo");
+        assert_eq!(auto_select(16_000, false, false).alias, "small-q5_1");
+        // English-only variants where they exist
+        assert_eq!(auto_select(16_000, false, true).alias, "small.en-q5_1");
+        assert_eq!(auto_select(16_000, true, true).alias, "large-v3-turbo");
+        assert_eq!(auto_select(2_000, true, true).alias, "small.en");
+        // Tight memory falls back to the smallest model
+        assert_eq!(auto_select(100, false, false).alias, "tiny-q5_1");
+        assert_eq!(auto_select(0, true, false).alias, "tiny");
+    }
+
+    #[test]
+    fn test_parse_mem_available() {
+        let meminfo = "MemTotal:       16318436 kB\nMemAvailable:    8159218 kB\n";
+        assert_eq!(parse_mem_available(meminfo), Some(7967));
+        assert_eq!(parse_mem_available("MemTotal: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn test_registry_aliases_are_unique() {
+        for (i, model) in MODELS.iter().enumerate() {
+            assert!(MODELS[i + 1..].iter().all(|other| other.alias != model.alias));
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::Cursor;
+
+/// Encode mono samples as a 16-bit PCM WAV file, which every transcription
+/// server accepts and is half the size of float WAV.
+pub fn encode_wav(audio: &[f32], sample_rate: u32) -> anyhow::Result<Vec<u8>> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::with_capacity(44 + audio.len() * 2));
+    let mut writer = hound::WavWriter::new(&mut cursor, spec)?;
+    for &sample in audio {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+    Ok(cursor.into_inner())
+}
+
+/// A `multipart/form-data` body of text fields and the WAV file, with its
+/// `Content-Type` header value. reqwest is built without its multipart
+/// feature, so the form is assembled by hand.
+pub fn form(fields: &[(&str, &str)], wav: &[u8]) -> (String, Vec<u8>) {
+    let boundary = new_boundary();
+    let body = body(&boundary, fields, wav);
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
+
+fn new_boundary() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    format!("ndict-{:x}", nanos)
+}
+
+/// Build a `multipart/form-data` body of text fields followed by the WAV file.
+fn body(boundary: &str, fields: &[(&str, &str)], wav: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(wav.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"speech.wav\"\r\nContent-Type: audio/wav\r\n\r\n",
+            boundary
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(wav);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_body_layout() {
+        let body = body("b0", &[("model", "whisper-1")], b"RIFF");
+        let expected = "--b0\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n\
+            --b0\r\nContent-Disposition: form-data; name=\"file\"; filename=\"speech.wav\"\r\n\
+            Content-Type: audio/wav\r\n\r\nRIFF\r\n--b0--\r\n";
+        assert_eq!(String::from_utf8(body).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_encode_wav_is_16_bit_mono() {
+        let wav = encode_wav(&[0.0, 0.5, -1.0, 2.0], 16000).unwrap();
+        let reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.spec().sample_rate, 16000);
+        assert_eq!(reader.spec().bits_per_sample, 16);
+        let samples: Vec<i16> = reader.into_samples().map(Result::unwrap).collect();
+        assert_eq!(samples, vec![0, 16383, -32767, 32767]);
+    }
+}
@@ -0,0 +1,433 @@
+use crate::transcription::locale::Locale;
+use crate::transcription::tokens::{leading_punctuation, normalize, trailing_punctuation};
+
+/// Rewrites spelled-out numbers as digits, e.g. "twenty three" as "23" and
+/// "three point five" as "3.5". See `post_processing.numbers`.
+///
+/// Words are read in the dictation language; languages without a word table
+/// are left alone. A lone number below ten ("one of them") stays a word.
+/// Decimals use the separator of the `Locale`.
+#[derive(Debug, Clone, Copy)]
+pub struct NumberNormalizer {
+    words: &'static NumberWords,
+    decimal_separator: char,
+}
+
+/// How a language spells numbers.
+#[derive(Debug)]
+struct NumberWords {
+    /// Words for 0–99 that are not built from smaller words.
+    values: &'static [(&'static str, u64)],
+    /// Multiples of ten that a unit may follow, "twenty" in "twenty three".
+    tens: &'static [(&'static str, u64)],
+    /// Words multiplying what comes before by 100.
+    hundred: &'static [&'static str],
+    /// Words worth a number of hundreds on their own, "doscientos".
+    hundreds: &'static [(&'static str, u64)],
+    scales: &'static [(&'static str, u64)],
+    /// A scale word may start a number, "mil" being 1000.
+    bare_scales: bool,
+    /// Joins the parts of a number, "one hundred and five".
+    conjunction: &'static [&'static str],
+    point: &'static [&'static str],
+}
+
+const ENGLISH: NumberWords = NumberWords {
+    values: &[
+        ("zero", 0),
+        ("one", 1),
+        ("two", 2),
+        ("three", 3),
+        ("four", 4),
+        ("five", 5),
+        ("six", 6),
+        ("seven", 7),
+        ("eight", 8),
+        ("nine", 9),
+        ("ten", 10),
+        ("eleven", 11),
+        ("twelve", 12),
+        ("thirteen", 13),
+        ("fourteen", 14),
+        ("fifteen", 15),
+        ("sixteen", 16),
+        ("seventeen", 17),
+        ("eighteen", 18),
+        ("nineteen", 19),
+    ],
+    tens: &[
+        ("twenty", 20),
+        ("thirty", 30),
+        ("forty", 40),
+        ("fifty", 50),
+        ("sixty", 60),
+        ("seventy", 70),
+        ("eighty", 80),
+        ("ninety", 90),
+    ],
+    hundred: &["hundred"],
+    hundreds: &[],
+    scales: &[
+        ("thousand", 1_000),
+        ("million", 1_000_000),
+        ("billion", 1_000_000_000),
+    ],
+    bare_scales: false,
+    conjunction: &["and"],
+    point: &["point"],
+};
+
+const SPANISH: NumberWords = NumberWords {
+    values: &[
+        ("cero", 0),
+        ("un", 1),
+        ("uno", 1),
+        ("una", 1),
+        ("dos", 2),
+        ("tres", 3),
+        ("cuatro", 4),
+        ("cinco", 5),
+        ("seis", 6),
+        ("siete", 7),
+        ("ocho", 8),
+        ("nueve", 9),
+        ("diez", 10),
+        ("once", 11),
+        ("doce", 12),
+        ("trece", 13),
+        ("catorce", 14),
+        ("quince", 15),
+        ("dieciséis", 16),
+        ("diecisiete", 17),
+        ("dieciocho", 18),
+        ("diecinueve", 19),
+        ("veinte", 20),
+        ("veintiuno", 21),
+        ("veintiún", 21),
+        ("veintidós", 22),
+        ("veintitrés", 23),
+        ("veinticuatro", 24),
+        ("veinticinco", 25),
+        ("veintiséis", 26),
+        ("veintisiete", 27),
+        ("veintiocho", 28),
+        ("veintinueve", 29),
+    ],
+    tens: &[
+        ("treinta", 30),
+        ("cuarenta", 40),
+        ("cincuenta", 50),
+        ("sesenta", 60),
+        ("setenta", 70),
+        ("ochenta", 80),
+        ("noventa", 90),
+    ],
+    hundred: &[],
+    hundreds: &[
+        ("cien", 100),
+        ("ciento", 100),
+        ("doscientos", 200),
+        ("doscientas", 200),
+        ("trescientos", 300),
+        ("trescientas", 300),
+        ("cuatrocientos", 400),
+        ("cuatrocientas", 400),
+        ("quinientos", 500),
+        ("quinientas", 500),
+        ("seiscientos", 600),
+        ("seiscientas", 600),
+        ("setecientos", 700),
+        ("setecientas", 700),
+        ("ochocientos", 800),
+        ("ochocientas", 800),
+        ("novecientos", 900),
+        ("novecientas", 900),
+    ],
+    scales: &[
+        ("mil", 1_000),
+        ("millón", 1_000_000),
+        ("millones", 1_000_000),
+    ],
+    bare_scales: true,
+    conjunction: &["y"],
+    point: &["coma", "punto"],
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Word {
+    Value(u64),
+    Tens(u64),
+    Hundred,
+    Hundreds(u64),
+    Scale(u64),
+    Conjunction,
+    Point,
+}
+
+impl NumberWords {
+    fn word(&self, word: &str) -> Option<Word> {
+        let find = |table: &[(&str, u64)]| {
+            table
+                .iter()
+                .find(|(name, _)| *name == word)
+                .map(|(_, value)| *value)
+        };
+        if let Some(value) = find(self.values) {
+            Some(Word::Value(value))
+        } else if let Some(value) = find(self.tens) {
+            Some(Word::Tens(value))
+        } else if self.hundred.contains(&word) {
+            Some(Word::Hundred)
+        } else if let Some(value) = find(self.hundreds) {
+            Some(Word::Hundreds(value))
+        } else if let Some(value) = find(self.scales) {
+            Some(Word::Scale(value))
+        } else if self.conjunction.contains(&word) {
+            Some(Word::Conjunction)
+        } else if self.point.contains(&word) {
+            Some(Word::Point)
+        } else {
+            None
+        }
+    }
+}
+
+/// A number read from the start of a run of words.
+struct Parsed {
+    /// Words making up the number.
+    len: usize,
+    digits: String,
+}
+
+impl NumberNormalizer {
+    /// The normalizer for a Whisper language code, `None` when numbers in
+    /// that language are not supported.
+    pub fn for_language(language: &str, locale: &Locale) -> Option<Self> {
+        let words = match language {
+            "en" => &ENGLISH,
+            "es" => &SPANISH,
+            _ => return None,
+        };
+        Some(Self {
+            words,
+            decimal_separator: locale.decimal_separator,
+        })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let tokens = self.tokenize(text);
+        let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match self.parse(&tokens[i..]) {
+                Some(parsed) => {
+                    let leading = leading_punctuation(&tokens[i]);
+                    let trailing = trailing_punctuation(&tokens[i + parsed.len - 1]);
+                    out.push(format!("{}{}{}", leading, parsed.digits, trailing));
+                    i += parsed.len;
+                }
+                None => {
+                    out.push(tokens[i].clone());
+                    i += 1;
+                }
+            }
+        }
+        out.join(" ")
+    }
+
+    /// Whitespace-separated words, with "twenty-three" split in two.
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        for token in text.split_whitespace() {
+            if let Some((tens, unit)) = token.split_once('-') {
+                let is_compound = matches!(self.words.word(&normalize(tens)), Some(Word::Tens(_)))
+                    && matches!(self.words.word(&normalize(unit)), Some(Word::Value(1..=9)));
+                if is_compound {
+                    tokens.push(tens.to_string());
+                    tokens.push(unit.to_string());
+                    continue;
+                }
+            }
+            tokens.push(token.to_string());
+        }
+        tokens
+    }
+
+    /// The number starting at the first token, if any. Words that can't
+    /// continue the number ("one two") end it; punctuation inside a run
+    /// ("twenty, three") does too.
+    fn parse(&self, tokens: &[String]) -> Option<Parsed> {
+        let mut total = 0u64;
+        // The part below the last scale word
+        let mut group = 0u64;
+        let mut last_scale = u64::MAX;
+        let mut previous: Option<Word> = None;
+        // `previous`, skipping conjunctions: "treinta y tres"
+        let mut previous_number: Option<Word> = None;
+        let mut len = 0;
+
+        while len < tokens.len() {
+            if len > 0 && !trailing_punctuation(&tokens[len - 1]).is_empty() {
+                break;
+            }
+            if len > 0 && !leading_punctuation(&tokens[len]).is_empty() {
+                break;
+            }
+            let Some(word) = self.words.word(&normalize(&tokens[len])) else {
+                break;
+            };
+            let starts_group = matches!(
+                previous,
+                None | Some(Word::Conjunction | Word::Hundred | Word::Hundreds(_) | Word::Scale(_))
+            );
+            match word {
+                Word::Value(value) => {
+                    let after_tens = matches!(previous_number, Some(Word::Tens(_))) && value < 10;
+                    let opens_group = starts_group && group.is_multiple_of(100);
+                    if !opens_group && !after_tens {
+                        break;
+                    }
+                    group += value;
+                }
+                Word::Tens(value) => {
+                    if !starts_group || !group.is_multiple_of(100) {
+                        break;
+                    }
+                    group += value;
+                }
+                Word::Hundred => {
+                    if !matches!(previous, Some(Word::Value(_) | Word::Tens(_))) || group >= 100 {
+                        break;
+                    }
+                    group *= 100;
+                }
+                Word::Hundreds(value) => {
+                    if !starts_group || group != 0 {
+                        break;
+                    }
+                    group = value;
+                }
+                Word::Scale(scale) => {
+                    let counted = previous.is_some() && previous != Some(Word::Conjunction);
+                    let bare = previous.is_none() && self.words.bare_scales;
+                    if !(counted || bare) || scale >= last_scale {
+                        break;
+                    }
+                    total += group.max(1) * scale;
+                    group = 0;
+                    last_scale = scale;
+                }
+                Word::Conjunction => {
+                    if previous.is_none() || previous == Some(Word::Conjunction) {
+                        break;
+                    }
+                }
+                Word::Point => break,
+            }
+            previous = Some(word);
+            if word != Word::Conjunction {
+                previous_number = Some(word);
+            }
+            len += 1;
+        }
+
+        // A trailing conjunction belongs to the sentence: "two and"
+        if previous == Some(Word::Conjunction) {
+            len -= 1;
+        }
+        if len == 0 {
+            return None;
+        }
+        let mut digits = (total + group).to_string();
+
+        let decimals = self.decimals(tokens, len);
+        if decimals.is_empty() && len == 1 && total + group < 10 {
+            return None;
+        }
+        if !decimals.is_empty() {
+            digits.push(self.decimal_separator);
+            digits.push_str(&decimals);
+            // The point word and one word per digit
+            len += 1 + decimals.len();
+        }
+        Some(Parsed { len, digits })
+    }
+
+    /// The digits spoken after a point word following the integer part at
+    /// `tokens[..len]`, "three point one four".
+    fn decimals(&self, tokens: &[String], len: usize) -> String {
+        let mut decimals = String::new();
+        let Some(point) = tokens.get(len) else {
+            return decimals;
+        };
+        if !trailing_punctuation(&tokens[len - 1]).is_empty()
+            || !trailing_punctuation(point).is_empty()
+            || self.words.word(&normalize(point)) != Some(Word::Point)
+        {
+            return decimals;
+        }
+        for token in &tokens[len + 1..] {
+            match self.words.word(&normalize(token)) {
+                Some(Word::Value(digit @ 0..=9)) if leading_punctuation(token).is_empty() => {
+                    decimals.push(char::from_digit(digit as u32, 10).unwrap_or('0'));
+                }
+                _ => break,
+            }
+            if !trailing_punctuation(token).is_empty() {
+                break;
+            }
+        }
+        decimals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english(text: &str) -> String {
+        NumberNormalizer::for_language("en", &Locale::resolve("auto", "en"))
+            .unwrap()
+            .apply(text)
+    }
+
+    #[test]
+    fn test_english_numbers() {
+        assert_eq!(english("twenty three"), "23");
+        assert_eq!(english("Wait twenty-five minutes."), "Wait 25 minutes.");
+        assert_eq!(english("one hundred and five people"), "105 people");
+        assert_eq!(english("two thousand three hundred forty one"), "2341");
+        assert_eq!(english("three million"), "3000000");
+        assert_eq!(english("set it to three point five"), "set it to 3.5");
+        assert_eq!(english("pi is three point one four."), "pi is 3.14.");
+    }
+
+    #[test]
+    fn test_english_words_that_stay() {
+        assert_eq!(english("one of them"), "one of them");
+        assert_eq!(english("the point is"), "the point is");
+        assert_eq!(english("five and six"), "five and six");
+        assert_eq!(english("one two three"), "one two three");
+        assert_eq!(english("twenty, thirty"), "20, 30");
+        assert_eq!(english("a thousand times"), "a thousand times");
+        assert_eq!(english("ten point"), "10 point");
+    }
+
+    #[test]
+    fn test_spanish_numbers() {
+        let spanish = NumberNormalizer::for_language("es", &Locale::resolve("auto", "es")).unwrap();
+        assert_eq!(spanish.apply("treinta y tres"), "33");
+        assert_eq!(spanish.apply("mil doscientos veintitrés"), "1223");
+        assert_eq!(spanish.apply("dos millones"), "2000000");
+        assert_eq!(spanish.apply("tres coma cinco"), "3,5");
+
+        let mexico = NumberNormalizer::for_language("es", &Locale::resolve("es-MX", "es")).unwrap();
+        assert_eq!(mexico.apply("tres punto cinco"), "3.5");
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(NumberNormalizer::for_language("ja", &Locale::resolve("auto", "ja")).is_none());
+    }
+}
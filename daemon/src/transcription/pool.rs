@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::transcription::backend::WorkerPool;
+
+/// Loaded backends that are not currently in use.
+///
+/// The active backend lives in `DaemonState::backend`; switching
+/// models parks it here and takes the requested one out, so a model only
+/// has to be loaded the first time it is selected.
+#[derive(Default)]
+pub struct EnginePool {
+    idle: HashMap<String, Arc<WorkerPool>>,
+}
+
+impl EnginePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the loaded backend for `name` out of the pool, if there is one.
+    pub fn take(&mut self, name: &str) -> Option<Arc<WorkerPool>> {
+        self.idle.remove(name)
+    }
+
+    /// Keep a loaded backend for later use.
+    pub fn park(&mut self, name: String, engine: Arc<WorkerPool>) {
+        tracing::debug!("Keeping model '{}' loaded", name);
+        self.idle.insert(name, engine);
+    }
+
+    /// Drop every parked backend, returning how many there were.
+    pub fn clear(&mut self) -> usize {
+        let parked = self.idle.len();
+        self.idle.clear();
+        parked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::engine::WhisperEngine;
+
+    fn engine() -> Arc<WorkerPool> {
+        Arc::new(WorkerPool::new(vec![Box::new(
+            WhisperEngine::new(
+                "https://example.com/ggml-tiny.bin".to_string(),
+                "cpu".to_string(),
+            )
+            .unwrap(),
+        )]))
+    }
+
+    #[test]
+    fn test_pool_parks_and_takes_engines() {
+        let mut pool = EnginePool::new();
+        assert!(pool.take("tiny").is_none());
+
+        pool.park("tiny".to_string(), engine());
+        assert!(pool.take("base").is_none());
+        assert!(pool.take("tiny").is_some());
+        assert!(pool.take("tiny").is_none());
+
+        pool.park("tiny".to_string(), engine());
+        assert_eq!(pool.clear(), 1);
+        assert!(pool.take("tiny").is_none());
+    }
+}
@@ -0,0 +1,401 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::transcription::brackets::BracketStripper;
+use crate::transcription::casing::Capitalizer;
+use crate::transcription::dates::format_dates;
+use crate::transcription::dictionary::Dictionary;
+use crate::transcription::emoji::EmojiInserter;
+use crate::transcription::fillers::FillerRemover;
+use crate::transcription::locale::Locale;
+use crate::transcription::numbers::NumberNormalizer;
+use crate::transcription::punctuation::{locale_quotes, tidy_punctuation};
+use crate::transcription::repetition::RepeatFilter;
+use crate::transcription::replace::Replacements;
+use crate::transcription::collapse_whitespace;
+
+/// Every stage by its `post_processing.stages` name, in the default order.
+pub const STAGE_NAMES: &[&str] = &[
+    "brackets",
+    "dedup",
+    "fillers",
+    "numbers",
+    "dictionary",
+    "emoji",
+    "punctuation",
+    "casing",
+    "replace",
+];
+
+/// One step of the post-processing pipeline.
+#[derive(Clone)]
+enum Stage {
+    /// Whisper may emit "[Music]"-style annotations unless told not to.
+    Brackets(BracketStripper),
+    Dedup(RepeatFilter),
+    Fillers(FillerRemover),
+    Numbers,
+    Dictionary(Arc<Mutex<Dictionary>>),
+    Emoji(EmojiInserter),
+    Punctuation {
+        smart_quotes: bool,
+    },
+    Casing(Capitalizer),
+    Replace(Replacements),
+}
+
+impl Stage {
+    /// The stage called `name`, or `None` when it is turned off by its own
+    /// setting.
+    fn from_config(name: &str, config: &Config) -> Result<Option<Self>> {
+        let post_processing = &config.post_processing;
+        let stage = match name {
+            "brackets" => (!config.whisper.decoding.suppress_non_speech)
+                .then(|| BracketStripper::from_config(&post_processing.brackets))
+                .transpose()?
+                .map(Self::Brackets),
+            "dedup" => post_processing
+                .dedup_repeats
+                .then(|| Self::Dedup(RepeatFilter::new(post_processing.dedup_compression_ratio))),
+            "fillers" => post_processing
+                .remove_fillers
+                .then(|| Self::Fillers(FillerRemover::new(&post_processing.fillers))),
+            "numbers" => post_processing.numbers.then_some(Self::Numbers),
+            "dictionary" => post_processing
+                .dictionary_file
+                .as_ref()
+                .map(Dictionary::load)
+                .transpose()?
+                .map(|dictionary| Self::Dictionary(Arc::new(Mutex::new(dictionary)))),
+            "emoji" => (post_processing.emoji && unicode_output(config, "emoji"))
+                .then(|| Self::Emoji(EmojiInserter::new())),
+            "punctuation" => Some(Self::Punctuation {
+                smart_quotes: post_processing.smart_quotes
+                    && unicode_output(config, "smart_quotes"),
+            }),
+            "casing" => post_processing
+                .capitalize
+                .then(|| Self::Casing(Capitalizer::new(&post_processing.proper_nouns))),
+            "replace" => (!post_processing.replace.is_empty())
+                .then(|| Replacements::from_config(&post_processing.replace))
+                .transpose()?
+                .map(Self::Replace),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unknown post_processing stage '{}', expected one of: {}",
+                    name,
+                    STAGE_NAMES.join(", ")
+                ))
+            }
+        };
+        Ok(stage)
+    }
+
+    fn apply(&self, text: &str, language: &str, locale: &Locale) -> String {
+        match self {
+            Self::Brackets(stripper) => stripper.apply(text),
+            Self::Dedup(filter) => filter.apply(text),
+            Self::Fillers(fillers) => fillers.apply(text, language),
+            Self::Numbers => match NumberNormalizer::for_language(language, locale) {
+                Some(normalizer) => format_dates(&normalizer.apply(text), language, locale),
+                None => text.to_string(),
+            },
+            Self::Dictionary(dictionary) => {
+                let mut dictionary = dictionary.lock().unwrap_or_else(|e| e.into_inner());
+                dictionary.reload_if_changed();
+                dictionary.apply(text)
+            }
+            Self::Emoji(inserter) => inserter.apply(text),
+            Self::Punctuation { smart_quotes } => {
+                let text = tidy_punctuation(text);
+                if *smart_quotes {
+                    locale_quotes(&text, locale)
+                } else {
+                    text
+                }
+            }
+            Self::Casing(capitalizer) => capitalizer.apply(text),
+            Self::Replace(replacements) => replacements.apply(text),
+        }
+    }
+}
+
+/// Whether the output can take the non-ASCII text a stage option produces,
+/// warning that `option` is ignored when it can't.
+fn unicode_output(config: &Config, option: &str) -> bool {
+    if !config.output.unicode {
+        tracing::warn!(
+            "Ignoring post_processing.{} because output.unicode is off",
+            option
+        );
+    }
+    config.output.unicode
+}
+
+/// Turns raw transcriptions into the text that is typed by running the
+/// stages named in `post_processing.stages`, in that order. A listed stage
+/// with a setting of its own (`numbers`, `capitalize`, ...) only runs when
+/// that setting is on.
+#[derive(Clone)]
+pub struct PostProcessor {
+    stages: Vec<Stage>,
+    /// `post_processing.locale`, resolved per utterance.
+    locale: String,
+}
+
+impl PostProcessor {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let names = &config.post_processing.stages;
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(anyhow::anyhow!(
+                    "post_processing stage '{}' is listed twice",
+                    name
+                ));
+            }
+        }
+        let mut stages = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some(stage) = Stage::from_config(name, config)? {
+                stages.push(stage);
+            }
+        }
+        Ok(Self {
+            stages,
+            locale: config.post_processing.locale.clone(),
+        })
+    }
+
+    /// `language` is the language the text was spoken in, which decides
+    /// which fillers are dropped, how numbers are read and, unless
+    /// `post_processing.locale` is set, how they are written.
+    pub fn process(&self, text: &str, language: &str) -> String {
+        let locale = Locale::resolve(&self.locale, language);
+        let mut text = collapse_whitespace(text);
+        for stage in &self.stages {
+            text = stage.apply(&text, language, &locale);
+        }
+        let text = collapse_whitespace(&text);
+        tracing::debug!("Post-processed: '{}'", text);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReplaceRule;
+
+    #[test]
+    fn test_stages_run_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dictionary.txt");
+        std::fs::write(&path, "end dict = ndict\n").unwrap();
+
+        let mut config = Config::default();
+        config.post_processing.dictionary_file = Some(path.display().to_string());
+        config.post_processing.replace = vec![ReplaceRule {
+            pattern: r"\bndict\b".to_string(),
+            replacement: "`ndict`".to_string(),
+            case_sensitive: true,
+        }];
+        let processor = PostProcessor::from_config(&config).unwrap();
+        assert_eq!(
+            processor.process(" I use end dict [Music] daily", "en"),
+            "I use `ndict` daily"
+        );
+    }
+
+    #[test]
+    fn test_capitalization_is_optional() {
+        let mut config = Config::default();
+        config.post_processing.proper_nouns = vec!["ndict".to_string()];
+        let text = "i use ndict. it works";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+
+        config.post_processing.capitalize = true;
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "I use ndict. It works"
+        );
+    }
+
+    #[test]
+    fn test_numbers_follow_the_language() {
+        let mut config = Config::default();
+        let text = "twenty three point five";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+
+        config.post_processing.numbers = true;
+        let processor = PostProcessor::from_config(&config).unwrap();
+        assert_eq!(processor.process(text, "en"), "23.5");
+        assert_eq!(processor.process("veintitrés coma cinco", "es"), "23,5");
+        assert_eq!(processor.process(text, "de"), text);
+    }
+
+    #[test]
+    fn test_filler_removal_is_optional() {
+        let mut config = Config::default();
+        let text = "Um, it takes twenty uh three minutes";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+
+        config.post_processing.remove_fillers = true;
+        config.post_processing.numbers = true;
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "It takes 23 minutes"
+        );
+    }
+
+    #[test]
+    fn test_repeats_collapsed_only_when_enabled() {
+        let mut config = Config::default();
+        let text = format!("I had had enough.{}", " Thank you.".repeat(30));
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(&text, "en"),
+            text
+        );
+
+        config.post_processing.dedup_repeats = true;
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(&text, "en"),
+            "I had had enough. Thank you."
+        );
+    }
+
+    #[test]
+    fn test_stage_order_comes_from_config() {
+        let mut config = Config::default();
+        config.post_processing.capitalize = true;
+        config.post_processing.replace = vec![ReplaceRule {
+            pattern: r"^ok\b".to_string(),
+            replacement: "okay".to_string(),
+            case_sensitive: true,
+        }];
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process("ok then", "en"),
+            "Ok then"
+        );
+
+        config.post_processing.stages = vec!["replace".to_string(), "casing".to_string()];
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process("ok then", "en"),
+            "Okay then"
+        );
+    }
+
+    #[test]
+    fn test_unlisted_stages_are_skipped() {
+        let mut config = Config::default();
+        let text = "call me (laughs) , today";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "call me, today"
+        );
+
+        config.post_processing.stages = Vec::new();
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+    }
+
+    #[test]
+    fn test_invalid_stage_lists() {
+        let mut config = Config::default();
+        config.post_processing.stages = vec!["spellcheck".to_string()];
+        assert!(PostProcessor::from_config(&config).is_err());
+
+        config.post_processing.stages = vec!["casing".to_string(), "casing".to_string()];
+        assert!(PostProcessor::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_locale_formats_numbers_dates_and_quotes() {
+        let mut config = Config::default();
+        config.post_processing.numbers = true;
+        config.post_processing.smart_quotes = true;
+        let text = "\"Ship\" version three point five on March twenty first, 2025";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "\u{201C}Ship\u{201D} version 3.5 on 03/21/2025"
+        );
+
+        config.post_processing.locale = "de-DE".to_string();
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "\u{201E}Ship\u{201C} version 3,5 on 21.03.2025"
+        );
+    }
+
+    #[test]
+    fn test_emoji_need_unicode_output() {
+        let mut config = Config::default();
+        let text = "Nice thumbs up emoji";
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+
+        config.post_processing.emoji = true;
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            "Nice \u{1F44D}"
+        );
+
+        config.output.unicode = false;
+        assert_eq!(
+            PostProcessor::from_config(&config)
+                .unwrap()
+                .process(text, "en"),
+            text
+        );
+    }
+
+    #[test]
+    fn test_missing_dictionary_is_an_error() {
+        let mut config = Config::default();
+        config.post_processing.dictionary_file = Some("/nonexistent/dictionary.txt".to_string());
+        assert!(PostProcessor::from_config(&config).is_err());
+    }
+}
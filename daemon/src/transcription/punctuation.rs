@@ -0,0 +1,188 @@
+use crate::transcription::locale::Locale;
+
+/// Tidies the spacing of punctuation, which other stages and models without
+/// punctuation support (Vosk) can leave behind: "hello , world" becomes
+/// "hello, world" and doubled commas are merged.
+pub fn tidy_punctuation(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, ',' | '.' | ';' | ':' | '!' | '?') {
+            while out.ends_with(' ') {
+                out.pop();
+            }
+            if c == ',' && out.ends_with([',', '.', ';', ':', '!', '?']) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    // A comma left at the start by a removed word
+    out.trim_start_matches([',', ' ']).trim_end().to_string()
+}
+
+/// Words that make an English sentence a question when they start it.
+const QUESTION_STARTS: &[&str] = &[
+    "what", "why", "how", "who", "whom", "whose", "where", "when", "which", "is", "are", "am",
+    "was", "were", "can", "could", "would", "should", "shall", "will", "do", "does", "did", "have",
+    "has", "may", "might",
+];
+
+/// Words set off by a comma when they open an English sentence.
+const INTRODUCTORY: &[&str] = &[
+    "well",
+    "okay",
+    "ok",
+    "anyway",
+    "actually",
+    "basically",
+    "however",
+    "yes",
+    "yeah",
+    "oh",
+    "hey",
+    "hi",
+    "hello",
+    "thanks",
+];
+
+/// "but" by language, which gets a comma before it.
+fn contrast_word(language: &str) -> Option<&'static str> {
+    match language {
+        "en" => Some("but"),
+        "es" => Some("pero"),
+        "de" => Some("aber"),
+        "fr" => Some("mais"),
+        "it" => Some("ma"),
+        "pt" => Some("mas"),
+        _ => None,
+    }
+}
+
+/// Rule-based punctuation for streaming windows, which Whisper decodes
+/// without the context it needs to punctuate. Each window is treated as a
+/// sentence: its first letter is capitalized and it gets a full stop, or a
+/// question mark for English questions. Commas go after introductory words
+/// and before "but", unless Whisper already punctuated the text. See
+/// `streaming.restore_punctuation`.
+pub fn restore_punctuation(text: &str, language: &str) -> String {
+    let mut words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    let Some(first) = words.first().map(|word| word.to_lowercase()) else {
+        return String::new();
+    };
+    let punctuated = text.contains(['.', ',', ';', ':', '!', '?']);
+    let english = language == "en";
+    let introduced = english && words.len() > 1 && INTRODUCTORY.contains(&first.as_str());
+
+    if !punctuated {
+        if let Some(contrast) = contrast_word(language) {
+            for i in 1..words.len() {
+                if words[i].eq_ignore_ascii_case(contrast) {
+                    words[i - 1].push(',');
+                }
+            }
+        }
+        if introduced {
+            words[0].push(',');
+        }
+    }
+
+    let mut sentence = words.join(" ");
+    if let Some(c) = sentence.chars().next() {
+        sentence.replace_range(..c.len_utf8(), &c.to_uppercase().to_string());
+    }
+    let sentence = sentence.trim_end_matches([',', ';', ':']);
+    if sentence.ends_with(['.', '!', '?', '\u{2026}']) {
+        return sentence.to_string();
+    }
+
+    // The question word may follow an introductory word: "okay, what now?"
+    let opener = if introduced {
+        words[1].to_lowercase()
+    } else {
+        first
+    };
+    let question = english && QUESTION_STARTS.contains(&opener.as_str());
+    format!("{}{}", sentence, if question { '?' } else { '.' })
+}
+
+/// Straight double quotes as the locale's quotation marks, "like this"
+/// becoming “like this” in English and „like this“ in German. See
+/// `post_processing.smart_quotes`.
+pub fn locale_quotes(text: &str, locale: &Locale) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        if c == '"' {
+            let opening = previous.is_none_or(|p| p.is_whitespace() || "([{".contains(p));
+            out.push_str(if opening {
+                locale.open_quote
+            } else {
+                locale.close_quote
+            });
+        } else {
+            out.push(c);
+        }
+        previous = Some(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tidy_punctuation() {
+        assert_eq!(tidy_punctuation("hello , world !"), "hello, world!");
+        assert_eq!(tidy_punctuation("one,, two., three"), "one, two. three");
+        assert_eq!(tidy_punctuation(", starts here"), "starts here");
+        assert_eq!(tidy_punctuation("at 3:30 ... ok"), "at 3:30... ok");
+    }
+
+    #[test]
+    fn test_restore_punctuation() {
+        assert_eq!(
+            restore_punctuation("well i tried but it failed", "en"),
+            "Well, i tried, but it failed."
+        );
+        assert_eq!(
+            restore_punctuation("okay what do we do now", "en"),
+            "Okay, what do we do now?"
+        );
+        assert_eq!(
+            restore_punctuation("lo intenté pero falló", "es"),
+            "Lo intenté, pero falló."
+        );
+        assert_eq!(restore_punctuation("wie geht es", "de"), "Wie geht es.");
+        assert_eq!(restore_punctuation("", "en"), "");
+    }
+
+    #[test]
+    fn test_restore_punctuation_keeps_whisper_punctuation() {
+        assert_eq!(
+            restore_punctuation("Sure, but why not?", "en"),
+            "Sure, but why not?"
+        );
+        assert_eq!(
+            restore_punctuation("first, second but third,", "en"),
+            "First, second but third."
+        );
+    }
+
+    #[test]
+    fn test_locale_quotes() {
+        let text = "he said \"hi\" (\"bye\")";
+        assert_eq!(
+            locale_quotes(text, &Locale::resolve("en-US", "")),
+            "he said \u{201C}hi\u{201D} (\u{201C}bye\u{201D})"
+        );
+        assert_eq!(
+            locale_quotes(text, &Locale::resolve("de", "")),
+            "he said \u{201E}hi\u{201C} (\u{201E}bye\u{201C})"
+        );
+        assert_eq!(
+            locale_quotes("\"oui\"", &Locale::resolve("fr", "")),
+            "\u{AB}\u{202F}oui\u{202F}\u{BB}"
+        );
+    }
+}
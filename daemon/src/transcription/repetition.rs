@@ -0,0 +1,128 @@
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+use crate::transcription::tokens::normalize;
+
+/// Longest phrase, in words, collapsed when it repeats.
+const MAX_PHRASE_WORDS: usize = 6;
+
+/// Times a phrase must repeat in a row to count as a loop; a doubled word
+/// ("had had") is usually meant.
+const MIN_REPEATS: usize = 3;
+
+/// Collapses the repetition loops Whisper falls into ("thank you thank you
+/// thank you ..."). See `post_processing.dedup_repeats`.
+///
+/// Repeats are only removed from text that compresses as well as a loop
+/// does, the same compression ratio check OpenAI's Whisper uses to reject
+/// a decoding, and only when said three or more times in a row, so
+/// ordinary text like "had had" is left alone.
+#[derive(Debug, Clone, Copy)]
+pub struct RepeatFilter {
+    min_compression_ratio: f32,
+}
+
+impl RepeatFilter {
+    pub fn new(min_compression_ratio: f32) -> Self {
+        Self {
+            min_compression_ratio,
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let ratio = compression_ratio(text);
+        if ratio <= self.min_compression_ratio {
+            return text.to_string();
+        }
+        let deduped = remove_repeats(text);
+        tracing::debug!(
+            "Compression ratio {:.2} above {:.2}, removed repeats: '{}' -> '{}'",
+            ratio,
+            self.min_compression_ratio,
+            text,
+            deduped
+        );
+        deduped
+    }
+}
+
+/// Size of `text` over its zlib-compressed size. Repetitive text compresses
+/// well and scores high; Whisper treats above 2.4 as a failed decoding.
+pub fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder
+        .write_all(text.as_bytes())
+        .and_then(|_| encoder.finish());
+    match compressed {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+/// `text` with each word or phrase said `MIN_REPEATS` or more times in a
+/// row kept once. Words match ignoring case and punctuation, so "Thank you.
+/// Thank you. Thank you." counts as a loop.
+fn remove_repeats(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let keys: Vec<String> = words.iter().map(|word| normalize(word)).collect();
+    let mut out: Vec<&str> = Vec::with_capacity(words.len());
+
+    let mut i = 0;
+    while i < words.len() {
+        let repeat = (1..=MAX_PHRASE_WORDS).find_map(|len| {
+            let phrase = keys.get(i..i + len)?;
+            let count = keys[i..]
+                .chunks_exact(len)
+                .take_while(|chunk| *chunk == phrase)
+                .count();
+            (count >= MIN_REPEATS).then_some((len, count))
+        });
+        match repeat {
+            Some((len, count)) => {
+                out.extend_from_slice(&words[i..i + len]);
+                i += len * count;
+            }
+            None => {
+                out.push(words[i]);
+                i += 1;
+            }
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_repeats_ignores_case_and_punctuation() {
+        assert_eq!(remove_repeats("the The the cat"), "the cat");
+        assert_eq!(
+            remove_repeats("Thank you. Thank you. thank you, see you"),
+            "Thank you. see you"
+        );
+        assert_eq!(remove_repeats("a b a b a b c"), "a b c");
+        assert_eq!(remove_repeats("she had had it"), "she had had it");
+    }
+
+    #[test]
+    fn test_repeats_kept_without_a_loop() {
+        let filter = RepeatFilter::new(2.4);
+        let text = "I had had enough, so that that was it.";
+        assert!(compression_ratio(text) < 2.4);
+        assert_eq!(filter.apply(text), text);
+    }
+
+    #[test]
+    fn test_loops_are_collapsed() {
+        let filter = RepeatFilter::new(2.4);
+        let text = format!("Okay.{}", " Thank you.".repeat(30));
+        assert!(compression_ratio(&text) > 2.4);
+        assert_eq!(filter.apply(&text), "Okay. Thank you.");
+    }
+}
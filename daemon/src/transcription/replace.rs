@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::config::ReplaceRule;
+
+/// The `[[post_processing.replace]]` rules, compiled, for fixing recurring
+/// misrecognitions after post-processing.
+#[derive(Debug, Clone, Default)]
+pub struct Replacements {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Replacements {
+    pub fn from_config(rules: &[ReplaceRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex = RegexBuilder::new(&rule.pattern)
+                    .case_insensitive(!rule.case_sensitive)
+                    .build()
+                    .with_context(|| {
+                        format!("Invalid post_processing.replace pattern '{}'", rule.pattern)
+                    })?;
+                Ok((regex, rule.replacement.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Apply every rule in order, each to the output of the previous one.
+    /// Replacements can refer to capture groups as `$1` or `${name}`.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (regex, replacement) in &self.rules {
+            text = regex.replace_all(&text, replacement.as_str()).into_owned();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str, case_sensitive: bool) -> ReplaceRule {
+        ReplaceRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            case_sensitive,
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let replacements = Replacements::from_config(&[
+            rule(r"\bend dict\b", "ndict", false),
+            rule(r"ndict (\w+)", "ndict-$1", true),
+        ])
+        .unwrap();
+        assert_eq!(replacements.apply("I use End Dict daily"), "I use ndict-daily");
+    }
+
+    #[test]
+    fn test_case_sensitive_rules() {
+        let replacements = Replacements::from_config(&[rule("rust", "Rust", true)]).unwrap();
+        assert_eq!(replacements.apply("rust and RUST"), "Rust and RUST");
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        let error = Replacements::from_config(&[rule("(unclosed", "", false)]).unwrap_err();
+        assert!(error.to_string().contains("post_processing.replace"));
+    }
+
+    #[test]
+    fn test_no_rules_keeps_text() {
+        assert_eq!(Replacements::default().apply("hello"), "hello");
+    }
+}
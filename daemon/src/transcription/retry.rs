@@ -0,0 +1,202 @@
+use anyhow::Result;
+use shared::ipc::Event;
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::config::Config;
+use crate::transcription::backend::{self, SharedBackend, WorkerPool};
+use crate::transcription::engine;
+
+/// An utterance kept after its first transcription so it can be
+/// transcribed again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utterance {
+    pub audio: Vec<f32>,
+    pub language: String,
+    /// What the first model heard.
+    pub text: String,
+}
+
+/// Transcribes utterances a second time with `whisper.retry.model`, either
+/// on its own when the first model was unsure or on a `Retry` command, and
+/// reports differing results as `Event::Corrected`.
+#[derive(Clone)]
+pub struct SecondPass {
+    config: Arc<Config>,
+    /// Loaded on the first retry and kept until the models are unloaded.
+    backend: SharedBackend,
+    task: Arc<Mutex<String>>,
+    last: Arc<Mutex<Option<Utterance>>>,
+    event_tx: broadcast::Sender<Event>,
+}
+
+impl SecondPass {
+    pub fn new(
+        config: Arc<Config>,
+        backend: SharedBackend,
+        task: Arc<Mutex<String>>,
+        last: Arc<Mutex<Option<Utterance>>>,
+        event_tx: broadcast::Sender<Event>,
+    ) -> Self {
+        Self {
+            config,
+            backend,
+            task,
+            last,
+            event_tx,
+        }
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.config.whisper.retry.model.as_deref()
+    }
+
+    /// Whether an utterance scored `confidence` should be retried without
+    /// being asked. Unscored results are never retried.
+    pub fn wants_retry(&self, confidence: Option<f32>) -> bool {
+        self.model().is_some()
+            && confidence.is_some_and(|confidence| confidence < self.config.whisper.retry.below_confidence)
+    }
+
+    /// Keep `utterance` for `Retry` and, if its confidence is low enough,
+    /// transcribe it again in the background.
+    pub async fn offer(&self, utterance: Utterance, confidence: Option<f32>) {
+        if self.model().is_none() {
+            return;
+        }
+        *self.last.lock().await = Some(utterance.clone());
+        if self.wants_retry(confidence) {
+            let second_pass = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = second_pass.retry(&utterance).await {
+                    tracing::error!("Second pass failed: {:#}", e);
+                }
+            });
+        }
+    }
+
+    /// The utterance a `Retry` command would transcribe again.
+    pub async fn last_utterance(&self) -> Result<Utterance> {
+        if self.model().is_none() {
+            return Err(anyhow::anyhow!("Retrying needs whisper.retry.model to be set"));
+        }
+        self.last
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No utterance to retry yet"))
+    }
+
+    /// Transcribe `utterance` with the retry model and publish the result
+    /// if it differs from the first one.
+    pub async fn retry(&self, utterance: &Utterance) -> Result<()> {
+        let workers = self.load().await?;
+        tracing::info!("Retrying '{}' with {}", utterance.text, workers.name());
+        let transcription = workers.transcribe(&utterance.audio, &utterance.language).await?;
+
+        if !differs(&utterance.text, &transcription.text) {
+            tracing::debug!("Second pass agrees with '{}'", utterance.text);
+            return Ok(());
+        }
+        tracing::info!("Second pass corrected '{}' to '{}'", utterance.text, transcription.text);
+        // No subscribers is not an error
+        let _ = self.event_tx.send(Event::Corrected {
+            original: utterance.text.clone(),
+            text: transcription.text,
+            confidence: transcription.confidence,
+        });
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Arc<WorkerPool>> {
+        let model = self
+            .model()
+            .ok_or_else(|| anyhow::anyhow!("whisper.retry.model is not set"))?;
+        let mut backend = self.backend.lock().await;
+        if let Some(workers) = backend.as_ref() {
+            return Ok(workers.clone());
+        }
+
+        tracing::info!("Loading retry model '{}'", model);
+        let translate = engine::parse_task(&self.task.lock().await)?;
+        let retry_backend = backend::create_backend(&self.config, model, translate)?;
+        let workers = Arc::new(WorkerPool::load(retry_backend, 1).await?);
+        *backend = Some(workers.clone());
+        Ok(workers)
+    }
+}
+
+/// Whether two transcriptions say different things, ignoring case,
+/// punctuation and spacing.
+fn differs(first: &str, second: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.split_whitespace()
+            .map(|word| {
+                word.chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .flat_map(char::to_lowercase)
+                    .collect::<String>()
+            })
+            .filter(|word| !word.is_empty())
+            .collect()
+    };
+    words(first) != words(second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RetryConfig;
+
+    fn second_pass(model: Option<&str>, below_confidence: f32) -> SecondPass {
+        let mut config = Config::default();
+        config.whisper.retry = RetryConfig {
+            model: model.map(str::to_string),
+            below_confidence,
+        };
+        SecondPass::new(
+            Arc::new(config),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new("transcribe".to_string())),
+            Arc::new(Mutex::new(None)),
+            broadcast::channel(4).0,
+        )
+    }
+
+    #[test]
+    fn test_wants_retry_below_threshold() {
+        let second_pass = second_pass(Some("large-v3"), 0.6);
+        assert!(second_pass.wants_retry(Some(0.4)));
+        assert!(!second_pass.wants_retry(Some(0.8)));
+        assert!(!second_pass.wants_retry(None));
+        // Without a model there is nothing to retry with
+        assert!(!self::second_pass(None, 0.6).wants_retry(Some(0.4)));
+        // A zero threshold leaves retries to the Retry command
+        assert!(!self::second_pass(Some("large-v3"), 0.0).wants_retry(Some(0.1)));
+    }
+
+    #[tokio::test]
+    async fn test_last_utterance() {
+        let utterance = Utterance {
+            audio: vec![0.0; 16],
+            language: "en".to_string(),
+            text: "hello".to_string(),
+        };
+
+        let disabled = second_pass(None, 0.0);
+        disabled.offer(utterance.clone(), Some(0.9)).await;
+        assert!(disabled.last_utterance().await.is_err());
+
+        let enabled = second_pass(Some("large-v3"), 0.0);
+        assert!(enabled.last_utterance().await.is_err());
+        enabled.offer(utterance.clone(), Some(0.9)).await;
+        assert_eq!(enabled.last_utterance().await.unwrap(), utterance);
+    }
+
+    #[test]
+    fn test_differs_ignores_case_and_punctuation() {
+        assert!(!differs("Hello, world.", "hello world"));
+        assert!(differs("Hello world", "Hello word"));
+        assert!(differs("", "hello"));
+    }
+}
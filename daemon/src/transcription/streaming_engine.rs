@@ -1,51 +1,172 @@
 use anyhow::Result;
-use tracing::{debug, info};
+
+use crate::audio::resample::{resample, WHISPER_SAMPLE_RATE};
+use crate::config::DecodingConfig;
+use crate::transcription::abort::AbortSignal;
+use crate::transcription::backend::StreamingBackend;
+use crate::transcription::engine::{apply_thresholds, uses_gpu};
+use crate::vad::speech_detector::SpeechDetector;
+use futures_util::future::BoxFuture;
+use tracing::{debug, info, warn};
 use whisper_rs::{
     FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
 };
 
 pub struct StreamingEngine {
+    model_path: String,
     context: Option<WhisperContext>,
     state: Option<WhisperState>,
     buffer: Vec<f32>,
     model_loaded: bool,
+    /// `whisper.backend`, deciding whether the model runs on the GPU.
+    backend: String,
+    gpu_device: i32,
+    /// Rate of the fed audio; windows are converted to 16kHz for Whisper.
+    sample_rate: u32,
     length_samples: usize,
     keep_samples: usize,
+    /// Decode the growing window after this much new audio, before it is
+    /// full, like whisper.cpp's `stream --step`. Fixed windows only.
+    step_samples: usize,
+    /// Audio added since the window was last decoded.
+    new_samples: usize,
     last_text: String,
     is_running: bool,
     language: String,
+    translate: bool,
+    prompt: Option<String>,
+    n_threads: i32,
+    no_speech_threshold: f32,
+    decoding: DecodingConfig,
+    /// Cuts windows at pauses in speech instead of every `length_samples`.
+    segmenter: Option<SpeechDetector>,
+    /// Decode the unfinished window again after this much new audio, for
+    /// `take_partial`; 0 disables partial results.
+    partial_samples: usize,
+    /// Length of the unfinished window when it was last decoded.
+    partial_len: usize,
+    partial: String,
+    partial_changed: bool,
+    /// Return each step's decode of the unfinished window as a draft of all
+    /// its text, see `StreamingBackend::is_draft`. Fixed windows only.
+    redraft: bool,
+    /// Text of the last full window; drafts of the next start after it.
+    window_base: String,
+    /// Draft last returned, empty once its window is final.
+    last_draft: String,
+    /// Whether the window being decoded is unfinished.
+    draft: bool,
+    abort: AbortSignal,
 }
 
 impl StreamingEngine {
     pub fn new(
-        _model_path: String,
+        model_path: String,
         language: String,
-        _step_ms: u32,
+        step_ms: u32,
         length_ms: u32,
         keep_ms: u32,
         sample_rate: u32,
     ) -> Self {
         let length_samples = (length_ms as usize * sample_rate as usize) / 1000;
         let keep_samples = (keep_ms as usize * sample_rate as usize) / 1000;
+        let step_samples = (step_ms as usize * sample_rate as usize) / 1000;
 
         Self {
+            model_path,
             context: None,
             state: None,
             buffer: Vec::with_capacity(length_samples),
             model_loaded: false,
+            backend: "cpu".to_string(),
+            gpu_device: 0,
+            sample_rate,
             length_samples,
             keep_samples,
+            step_samples,
+            new_samples: 0,
             last_text: String::new(),
             is_running: false,
             language,
+            translate: false,
+            prompt: None,
+            n_threads: 4,
+            no_speech_threshold: 1.0,
+            decoding: DecodingConfig::default(),
+            segmenter: None,
+            partial_samples: 0,
+            partial_len: 0,
+            partial: String::new(),
+            partial_changed: false,
+            redraft: false,
+            window_base: String::new(),
+            last_draft: String::new(),
+            draft: false,
+            abort: AbortSignal::new(),
+        }
+    }
+
+    /// Transcribe each stretch of speech `segmenter` finds instead of fixed
+    /// overlapping windows, so words are not split between windows. Speech
+    /// longer than a window should be capped with `with_max_segment`.
+    pub fn with_segmenter(mut self, segmenter: SpeechDetector) -> Self {
+        self.segmenter = Some(segmenter);
+        self
+    }
+
+    /// Decode the window being filled every `partial_samples` of new audio,
+    /// reporting its text through `take_partial` until the window is final.
+    pub fn with_partial_interval(mut self, partial_samples: usize) -> Self {
+        self.partial_samples = partial_samples;
+        self
+    }
+
+    /// Return the unfinished window's text as a draft every step instead of
+    /// only the words the previous step did not have, so a consumer can
+    /// correct text Whisper revises as the window grows.
+    pub fn with_redrafts(mut self, redraft: bool) -> Self {
+        self.redraft = redraft;
+        self
+    }
+
+    /// Run the model on `whisper.backend`, on the GPU for "gpu", "cuda" or
+    /// "vulkan".
+    pub fn with_backend(mut self, backend: String) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Run the model on the GPU at this index, see `whisper.gpu_device`.
+    pub fn with_gpu_device(mut self, gpu_device: i32) -> Self {
+        self.gpu_device = gpu_device;
+        self
+    }
+
+    fn context_params(&self, use_gpu: bool) -> WhisperContextParameters<'static> {
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(use_gpu);
+        if use_gpu {
+            params.gpu_device(self.gpu_device);
         }
+        params
     }
 
     pub async fn load_model(&mut self, model_path: &str) -> Result<()> {
         info!("Loading Whisper model from: {}", model_path);
 
-        let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
-            .map_err(|e| anyhow::anyhow!("Failed to load Whisper model: {}", e))?;
+        let use_gpu = uses_gpu(&self.backend);
+        if use_gpu {
+            info!("Attempting to use GPU backend for streaming on device {}", self.gpu_device);
+        }
+        let ctx = match WhisperContext::new_with_params(model_path, self.context_params(use_gpu)) {
+            Ok(ctx) => ctx,
+            Err(e) if use_gpu => {
+                warn!("GPU initialization failed: {}. Falling back to CPU backend", e);
+                WhisperContext::new_with_params(model_path, self.context_params(false))
+                    .map_err(|e| anyhow::anyhow!("Failed to load Whisper model: {}", e))?
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to load Whisper model: {}", e)),
+        };
 
         let state = ctx
             .create_state()
@@ -65,7 +186,10 @@ impl StreamingEngine {
         }
 
         self.buffer.clear();
+        self.new_samples = 0;
         self.last_text.clear();
+        self.reset_drafts();
+        self.reset_partial();
         self.is_running = true;
 
         info!("Streaming engine started");
@@ -77,34 +201,112 @@ impl StreamingEngine {
             return Ok(None);
         }
 
+        match self.take_window(audio_chunk) {
+            Some(window) => {
+                self.reset_partial();
+                // Partials resume once there is audio the window did not cover
+                self.partial_len = self.pending_audio().len();
+                self.process_window(&window)
+            }
+            None => {
+                self.update_partial()?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Audio heard since the last complete window.
+    fn pending_audio(&self) -> &[f32] {
+        match self.segmenter.as_ref() {
+            Some(segmenter) => segmenter.pending_speech(),
+            None => &self.buffer,
+        }
+    }
+
+    /// Whether enough audio arrived since the last partial decode for
+    /// another one.
+    fn partial_due(&self) -> bool {
+        self.partial_samples > 0
+            && self.pending_audio().len() >= self.partial_len + self.partial_samples
+    }
+
+    /// Decode the unfinished window for a partial result, when one is due.
+    fn update_partial(&mut self) -> Result<()> {
+        if !self.partial_due() {
+            return Ok(());
+        }
+        let audio = self.pending_audio().to_vec();
+        self.partial_len = audio.len();
+
+        let text = self.decode(&audio)?;
+        let text = if self.segmenter.is_none() {
+            new_words(&self.last_text, &text)
+        } else {
+            text
+        };
+        if text != self.partial {
+            debug!("Partial transcription: '{}'", text);
+            self.partial = text;
+            self.partial_changed = true;
+        }
+        Ok(())
+    }
+
+    fn reset_drafts(&mut self) {
+        self.window_base.clear();
+        self.last_draft.clear();
+        self.draft = false;
+    }
+
+    fn reset_partial(&mut self) {
+        self.partial_len = 0;
+        self.partial.clear();
+        self.partial_changed = false;
+    }
+
+    /// Add `audio_chunk` and return the next window to transcribe: the
+    /// window so far every `step_samples`, and the whole window once it is
+    /// full, after which only its last `keep_samples` carry over.
+    fn take_window(&mut self, audio_chunk: &[f32]) -> Option<Vec<f32>> {
+        if let Some(segmenter) = self.segmenter.as_mut() {
+            self.draft = false;
+            return segmenter.process_audio(audio_chunk);
+        }
+
         self.buffer.extend(audio_chunk);
+        self.new_samples += audio_chunk.len();
 
-        if self.buffer.len() < self.length_samples {
+        let full = self.buffer.len() >= self.length_samples;
+        let step_due = self.step_samples > 0 && self.new_samples >= self.step_samples;
+        if !full && !step_due {
             debug!(
                 "Buffer not yet full: {}/{} samples",
                 self.buffer.len(),
                 self.length_samples
             );
-            return Ok(None);
+            return None;
         }
 
-        let transcription = self.process_window()?;
-
-        self.buffer = self
-            .buffer
-            .iter()
-            .skip(self.length_samples - self.keep_samples)
-            .copied()
-            .collect();
-
-        Ok(transcription)
+        self.new_samples = 0;
+        self.draft = !full;
+        if !full {
+            return Some(self.buffer.clone());
+        }
+        let keep = self.buffer[self.length_samples - self.keep_samples..].to_vec();
+        Some(std::mem::replace(&mut self.buffer, keep))
     }
 
     pub async fn stop(&mut self) {
         info!("Stopping streaming engine");
         self.is_running = false;
         self.buffer.clear();
+        self.new_samples = 0;
         self.last_text.clear();
+        self.reset_drafts();
+        self.reset_partial();
+        if let Some(segmenter) = self.segmenter.as_mut() {
+            segmenter.discard();
+        }
     }
 
     pub fn set_language(&mut self, language: String) {
@@ -112,13 +314,82 @@ impl StreamingEngine {
         info!("Streaming engine language updated to: {}", self.language);
     }
 
-    fn process_window(&mut self) -> Result<Option<String>> {
+    pub fn set_thresholds(&mut self, no_speech_threshold: f32, decoding: DecodingConfig) {
+        self.no_speech_threshold = no_speech_threshold;
+        self.decoding = decoding;
+    }
+
+    pub fn set_threads(&mut self, n_threads: i32) {
+        self.n_threads = n_threads;
+    }
+
+    pub fn set_prompt(&mut self, prompt: Option<String>) {
+        self.prompt = prompt;
+    }
+
+    pub fn set_translate(&mut self, translate: bool) {
+        self.translate = translate;
+        info!("Streaming engine translation {}", if translate { "enabled" } else { "disabled" });
+    }
+
+    fn process_window(&mut self, window: &[f32]) -> Result<Option<String>> {
+        let trimmed = self.decode(window)?;
+        if self.redraft && self.segmenter.is_none() {
+            return Ok(self.redraft_window(trimmed));
+        }
+
+        // Fixed windows overlap, so their start repeats the previous window; segments do not
+        let text = if self.segmenter.is_none() {
+            let text = new_words(&self.last_text, &trimmed);
+            self.last_text = trimmed;
+            text
+        } else {
+            trimmed
+        };
+        if !text.is_empty() {
+            debug!("New transcription: '{}'", text);
+            return Ok(Some(text));
+        }
+
+        Ok(None)
+    }
+
+    /// The text of the window decoded as `trimmed` since the last full
+    /// window, or `None` when a draft is unchanged or there is nothing to
+    /// say.
+    fn redraft_window(&mut self, trimmed: String) -> Option<String> {
+        let text = new_words(&self.window_base, &trimmed);
+        self.last_text = trimmed;
+        if !self.draft {
+            self.window_base = self.last_text.clone();
+        }
+
+        let unchanged = if self.draft {
+            text == self.last_draft
+        } else {
+            text.is_empty() && self.last_draft.is_empty()
+        };
+        self.last_draft = if self.draft { text.clone() } else { String::new() };
+        if unchanged {
+            return None;
+        }
+        debug!(
+            "{} transcription: '{}'",
+            if self.draft { "Draft" } else { "New" },
+            text
+        );
+        Some(text)
+    }
+
+    /// Run Whisper over `window` and return its trimmed text.
+    fn decode(&mut self, window: &[f32]) -> Result<String> {
+        let window = resample(window, self.sample_rate, WHISPER_SAMPLE_RATE);
         let state = self
             .state
             .as_mut()
             .ok_or_else(|| anyhow::anyhow!("WhisperState not initialized"))?;
 
-        debug!("Processing window with {} samples", self.buffer.len());
+        debug!("Processing window with {} samples", window.len());
 
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
         params.set_print_special(false);
@@ -126,11 +397,22 @@ impl StreamingEngine {
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
         params.set_language(Some(&self.language));
+        params.set_n_threads(self.n_threads);
+        params.set_translate(self.translate);
+        apply_thresholds(&mut params, self.no_speech_threshold, &self.decoding);
+        if let Some(prompt) = &self.prompt {
+            params.set_initial_prompt(prompt);
+        }
         params.set_single_segment(true);
+        let abort = self.abort.token();
+        let callback_abort = abort.clone();
+        params.set_abort_callback_safe(move || callback_abort.is_aborted());
 
-        state
-            .full(params, &self.buffer)
-            .map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
+        let result = state.full(params, &window);
+        if abort.is_aborted() {
+            return Err(anyhow::anyhow!("Transcription aborted"));
+        }
+        result.map_err(|e| anyhow::anyhow!("Transcription failed: {}", e))?;
 
         let num_segments = state.full_n_segments();
         let mut transcription = String::new();
@@ -143,15 +425,79 @@ impl StreamingEngine {
             }
         }
 
-        let trimmed = transcription.trim().to_string();
+        Ok(transcription.trim().to_string())
+    }
+}
 
-        if !trimmed.is_empty() && trimmed != self.last_text {
-            self.last_text = trimmed.clone();
-            debug!("New transcription: '{}'", trimmed);
-            return Ok(Some(trimmed));
+/// Words of `current` that follow the longest run of words it shares with
+/// the end of `previous`, ignoring case and punctuation. The last word of
+/// `previous` may have been cut off by the window edge, so the run may also
+/// end one word early.
+fn new_words(previous: &str, current: &str) -> String {
+    let normalize = |word: &str| -> String {
+        word.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let previous: Vec<String> = previous.split_whitespace().map(normalize).collect();
+    let words: Vec<&str> = current.split_whitespace().collect();
+    let current: Vec<String> = words.iter().map(|word| normalize(word)).collect();
+
+    let mut overlap = 0;
+    for end in [previous.len(), previous.len().saturating_sub(1)] {
+        let longest = (1..=end.min(current.len()))
+            .rev()
+            .find(|&len| previous[end - len..end] == current[..len]);
+        if let Some(len) = longest {
+            overlap = overlap.max(len);
         }
+    }
+    words[overlap..].join(" ")
+}
 
-        Ok(None)
+impl StreamingBackend for StreamingEngine {
+    fn name(&self) -> &str {
+        "whisper streaming"
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let model_path = self.model_path.clone();
+            self.load_model(&model_path).await
+        })
+    }
+
+    fn start(&mut self) -> Result<()> {
+        StreamingEngine::start(self)
+    }
+
+    fn push_audio(&mut self, chunk: &[f32]) -> Result<Option<String>> {
+        self.send_audio(chunk)
+    }
+
+    fn take_partial(&mut self) -> Option<String> {
+        std::mem::take(&mut self.partial_changed).then(|| self.partial.clone())
+    }
+
+    fn is_draft(&self) -> bool {
+        self.redraft && self.draft
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(StreamingEngine::stop(self))
+    }
+
+    fn set_language(&mut self, language: String) {
+        StreamingEngine::set_language(self, language);
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        StreamingEngine::set_translate(self, translate);
+    }
+
+    fn set_abort_signal(&mut self, abort: AbortSignal) {
+        self.abort = abort;
     }
 }
 
@@ -184,6 +530,20 @@ mod tests {
         assert_eq!(engine.keep_samples, 8000);
     }
 
+    #[test]
+    fn test_gpu_device_reaches_context_params() {
+        let engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 3000, 10000, 200, 16000)
+                .with_backend("gpu".to_string())
+                .with_gpu_device(1);
+        assert!(uses_gpu(&engine.backend));
+
+        let params = engine.context_params(true);
+        assert!(params.use_gpu);
+        assert_eq!(params.gpu_device, 1);
+        assert!(!engine.context_params(false).use_gpu);
+    }
+
     #[test]
     fn test_streaming_engine_not_running() {
         let mut engine =
@@ -194,6 +554,132 @@ mod tests {
         assert!(result.unwrap().is_none());
     }
 
+    #[test]
+    fn test_streaming_engine_set_thresholds() {
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 3000, 10000, 200, 16000);
+        assert_eq!(engine.no_speech_threshold, 1.0);
+
+        let decoding = DecodingConfig {
+            suppress_blank: false,
+            ..DecodingConfig::default()
+        };
+        engine.set_thresholds(0.6, decoding.clone());
+        assert_eq!(engine.no_speech_threshold, 0.6);
+        assert_eq!(engine.decoding, decoding);
+    }
+
+    #[test]
+    fn test_fixed_windows_overlap() {
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 3000, 1000, 250, 1000);
+
+        assert!(engine.take_window(&[0.1; 600]).is_none());
+        let window = engine.take_window(&[0.2; 600]).unwrap();
+        assert_eq!(window.len(), 1200);
+        // The last 250 samples carry over, with the 200 past the window
+        assert_eq!(engine.buffer.len(), 450);
+    }
+
+    #[test]
+    fn test_fixed_windows_decoded_every_step() {
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 400, 1000, 250, 1000);
+
+        assert!(engine.take_window(&[0.1; 300]).is_none());
+        // The window so far is decoded every step without being cut
+        assert_eq!(engine.take_window(&[0.1; 300]).unwrap().len(), 600);
+        assert_eq!(engine.buffer.len(), 600);
+        assert!(engine.take_window(&[0.1; 300]).is_none());
+        // A full window is cut as before, keeping its end
+        assert_eq!(engine.take_window(&[0.1; 300]).unwrap().len(), 1200);
+        assert_eq!(engine.buffer.len(), 450);
+        assert!(engine.take_window(&[0.1; 300]).is_none());
+        assert_eq!(engine.take_window(&[0.1; 100]).unwrap().len(), 850);
+    }
+
+    #[test]
+    fn test_redrafts_replace_each_other_until_the_window_is_full() {
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 400, 1000, 250, 1000)
+                .with_redrafts(true);
+
+        assert!(engine.take_window(&[0.1; 400]).is_some());
+        assert!(engine.draft);
+        assert_eq!(engine.redraft_window("I sea".to_string()).unwrap(), "I sea");
+        assert!(engine.is_draft());
+
+        assert!(engine.take_window(&[0.1; 400]).is_some());
+        assert_eq!(engine.redraft_window("I see the".to_string()).unwrap(), "I see the");
+        // The same draft again is not repeated
+        assert!(engine.redraft_window("I see the".to_string()).is_none());
+
+        assert!(engine.take_window(&[0.1; 400]).is_some());
+        assert!(!engine.is_draft());
+        assert_eq!(engine.redraft_window("I see the sea.".to_string()).unwrap(), "I see the sea.");
+
+        // The next window's drafts leave out the overlap
+        assert!(engine.take_window(&[0.1; 400]).is_some());
+        assert_eq!(engine.redraft_window("the sea. It".to_string()).unwrap(), "It");
+    }
+
+    #[test]
+    fn test_new_words_skips_overlap() {
+        assert_eq!(new_words("", "Hello there."), "Hello there.");
+        assert_eq!(
+            new_words("I went to the", "to the shops today."),
+            "shops today."
+        );
+        // Case and punctuation differ between windows
+        assert_eq!(new_words("We met Bob.", "bob, and then left"), "and then left");
+        // The previous window cut its last word short
+        assert_eq!(new_words("see you tomor", "see you tomorrow at noon"), "tomorrow at noon");
+        // Nothing new in the window
+        assert_eq!(new_words("nice to meet you", "meet you"), "");
+        assert_eq!(new_words("one two", "three four"), "three four");
+    }
+
+    #[test]
+    fn test_partial_due_every_interval() {
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 3000, 1000, 250, 1000)
+                .with_partial_interval(300);
+
+        assert!(engine.take_window(&[0.1; 200]).is_none());
+        assert!(!engine.partial_due());
+        assert!(engine.take_window(&[0.1; 200]).is_none());
+        assert!(engine.partial_due());
+        // Decoded at 400 samples, so the next partial waits for 700
+        engine.partial_len = 400;
+        assert!(engine.take_window(&[0.1; 200]).is_none());
+        assert!(!engine.partial_due());
+
+        engine.reset_partial();
+        assert!(engine.partial_due());
+
+        // Disabled by default
+        engine.partial_samples = 0;
+        assert!(!engine.partial_due());
+    }
+
+    #[test]
+    fn test_segmenter_cuts_windows_at_silence() {
+        let segmenter = SpeechDetector::new(0.05, 0.02, 100, 1.0)
+            .unwrap()
+            .with_sample_rate(1000);
+        let mut engine =
+            StreamingEngine::new("test.bin".to_string(), "en".to_string(), 3000, 10000, 500, 1000)
+                .with_segmenter(segmenter);
+
+        assert!(engine.take_window(&[0.0; 100]).is_none());
+        for _ in 0..5 {
+            assert!(engine.take_window(&[0.3; 100]).is_none());
+        }
+        // A pause ends the window long before length_ms
+        let window = (0..5).find_map(|_| engine.take_window(&[0.0; 100])).unwrap();
+        assert!(window.len() >= 500 && window.len() < 10000);
+    }
+
     #[test]
     fn test_streaming_engine_set_language() {
         let mut engine =
@@ -1,9 +1,11 @@
 use anyhow::Result;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use whisper_rs::{
     FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
 };
 
+use crate::transcription::engine::uses_gpu;
+
 pub struct StreamingWrapper {
     context: Option<WhisperContext>,
     state: Option<WhisperState>,
@@ -12,6 +14,8 @@ pub struct StreamingWrapper {
     overlap_samples: usize,
     accumulated_text: String,
     is_active: bool,
+    backend: String,
+    gpu_device: i32,
 }
 
 impl StreamingWrapper {
@@ -30,14 +34,40 @@ impl StreamingWrapper {
             overlap_samples,
             accumulated_text: String::new(),
             is_active: false,
+            backend: "cpu".to_string(),
+            gpu_device: 0,
+        }
+    }
+
+    /// Run the model on `whisper.backend` and `whisper.gpu_device`.
+    pub fn with_gpu(mut self, backend: String, gpu_device: i32) -> Self {
+        self.backend = backend;
+        self.gpu_device = gpu_device;
+        self
+    }
+
+    fn context_params(&self, use_gpu: bool) -> WhisperContextParameters<'static> {
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(use_gpu);
+        if use_gpu {
+            params.gpu_device(self.gpu_device);
         }
+        params
     }
 
     pub async fn load_model(&mut self, model_path: &str) -> Result<()> {
         info!("Loading Whisper model for streaming: {}", model_path);
 
-        let ctx = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
-            .map_err(|e| anyhow::anyhow!("Failed to load Whisper model: {}", e))?;
+        let use_gpu = uses_gpu(&self.backend);
+        let ctx = match WhisperContext::new_with_params(model_path, self.context_params(use_gpu)) {
+            Ok(ctx) => ctx,
+            Err(e) if use_gpu => {
+                warn!("GPU initialization failed: {}. Falling back to CPU backend", e);
+                WhisperContext::new_with_params(model_path, self.context_params(false))
+                    .map_err(|e| anyhow::anyhow!("Failed to load Whisper model: {}", e))?
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to load Whisper model: {}", e)),
+        };
 
         let state = ctx
             .create_state()
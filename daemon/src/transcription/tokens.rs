@@ -0,0 +1,47 @@
+/// A word without surrounding punctuation, lowercased.
+pub fn normalize(token: &str) -> String {
+    token
+        .trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase()
+}
+
+/// The punctuation before the word in `token`, e.g. "(" in "(twenty".
+pub fn leading_punctuation(token: &str) -> &str {
+    let word_start = token
+        .find(|c: char| !c.is_ascii_punctuation())
+        .unwrap_or(token.len());
+    &token[..word_start]
+}
+
+/// The punctuation after the word in `token`, e.g. ")." in "five).".
+pub fn trailing_punctuation(token: &str) -> &str {
+    let word_end = token
+        .rfind(|c: char| !c.is_ascii_punctuation())
+        .map_or(0, |i| {
+            i + token[i..].chars().next().map_or(1, char::len_utf8)
+        });
+    &token[word_end..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("\"Hello,"), "hello");
+        assert_eq!(normalize("Über."), "über");
+        assert_eq!(normalize("..."), "");
+    }
+
+    #[test]
+    fn test_surrounding_punctuation() {
+        assert_eq!(leading_punctuation("(twenty"), "(");
+        assert_eq!(trailing_punctuation("five)."), ").");
+        assert_eq!(trailing_punctuation("café!"), "!");
+        assert_eq!(leading_punctuation("word"), "");
+        assert_eq!(trailing_punctuation("word"), "");
+        assert_eq!(leading_punctuation("?!"), "?!");
+        assert_eq!(trailing_punctuation("?!"), "?!");
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::WhisperConfig;
+
+/// Whisper only attends to roughly the last 224 prompt tokens; keeping the
+/// prompt to a few hundred characters stops the oldest terms being cut.
+const MAX_PROMPT_CHARS: usize = 800;
+
+/// Read a user dictionary: one term per line, blank lines and lines starting
+/// with `#` ignored.
+pub fn load_dictionary(path: &Path) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        anyhow::anyhow!("Failed to read vocabulary file {}: {}", path.display(), e)
+    })?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Combine the initial prompt with vocabulary terms into the text handed to
+/// Whisper as prior context. Returns `None` when there is nothing to say.
+pub fn build_prompt(initial_prompt: Option<&str>, terms: &[String]) -> Option<String> {
+    let mut prompt = initial_prompt.map(str::trim).unwrap_or_default().to_string();
+
+    if !terms.is_empty() {
+        if !prompt.is_empty() {
+            prompt.push(' ');
+        }
+        // Whisper picks up spellings from a prompt that reads like text
+        prompt.push_str("Glossary: ");
+        prompt.push_str(&terms.join(", "));
+        prompt.push('.');
+    }
+
+    if prompt.chars().count() > MAX_PROMPT_CHARS {
+        tracing::warn!(
+            "Whisper prompt is {} characters, only the end will be used",
+            prompt.chars().count()
+        );
+    }
+
+    (!prompt.is_empty()).then_some(prompt)
+}
+
+/// Build the prompt from `whisper.initial_prompt`, `whisper.vocabulary` and
+/// the terms in `whisper.vocabulary_file`.
+pub fn prompt_from_config(config: &WhisperConfig) -> Result<Option<String>> {
+    let mut terms = config.vocabulary.clone();
+    if let Some(path) = &config.vocabulary_file {
+        terms.extend(load_dictionary(Path::new(path))?);
+    }
+    let prompt = build_prompt(config.initial_prompt.as_deref(), &terms);
+    if let Some(prompt) = &prompt {
+        tracing::info!("Using Whisper prompt with {} vocabulary terms", terms.len());
+        tracing::debug!("Whisper prompt: '{}'", prompt);
+    }
+    Ok(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(build_prompt(None, &[]), None);
+        assert_eq!(build_prompt(Some("  "), &[]), None);
+        assert_eq!(
+            build_prompt(Some("Meeting notes."), &[]),
+            Some("Meeting notes.".to_string())
+        );
+
+        let terms = vec!["Kubernetes".to_string(), "ndict".to_string()];
+        assert_eq!(
+            build_prompt(None, &terms),
+            Some("Glossary: Kubernetes, ndict.".to_string())
+        );
+        assert_eq!(
+            build_prompt(Some("Meeting notes."), &terms),
+            Some("Meeting notes. Glossary: Kubernetes, ndict.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_dictionary_skips_comments_and_blanks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        std::fs::write(&path, "# team names\nAnneliese\n\n  PostgreSQL  \n").unwrap();
+
+        assert_eq!(load_dictionary(&path).unwrap(), vec!["Anneliese", "PostgreSQL"]);
+        assert!(load_dictionary(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_prompt_from_config_merges_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        std::fs::write(&path, "wrtype\n").unwrap();
+
+        let config = WhisperConfig {
+            initial_prompt: Some("Rust code review.".to_string()),
+            vocabulary: vec!["ndictd".to_string()],
+            vocabulary_file: Some(path.to_string_lossy().to_string()),
+            ..WhisperConfig::default()
+        };
+        assert_eq!(
+            prompt_from_config(&config).unwrap(),
+            Some("Rust code review. Glossary: ndictd, wrtype.".to_string())
+        );
+    }
+}
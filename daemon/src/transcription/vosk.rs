@@ -0,0 +1,550 @@
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use libloading::Library;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_float, c_int, c_void, CStr, CString};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use crate::config::VoskConfig;
+use crate::transcription::backend::{StreamingBackend, Transcription, TranscriptionBackend};
+use crate::transcription::engine::AUTO_LANGUAGE;
+
+/// Vosk takes float samples in the 16-bit integer range.
+const SAMPLE_SCALE: f32 = 32768.0;
+
+type ModelNew = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type RecognizerNew = unsafe extern "C" fn(*mut c_void, c_float) -> *mut c_void;
+type RecognizerNewGrammar =
+    unsafe extern "C" fn(*mut c_void, c_float, *const c_char) -> *mut c_void;
+type FindWord = unsafe extern "C" fn(*mut c_void, *const c_char) -> c_int;
+type AcceptWaveform = unsafe extern "C" fn(*mut c_void, *const c_float, c_int) -> c_int;
+type GetResult = unsafe extern "C" fn(*mut c_void) -> *const c_char;
+type Free = unsafe extern "C" fn(*mut c_void);
+type SetLogLevel = unsafe extern "C" fn(c_int);
+
+/// Entry points of the libvosk C API, resolved when the library is loaded.
+///
+/// The library is opened at runtime rather than linked, so ndictd builds
+/// and runs without Vosk installed unless `stt.backend = "vosk"`.
+struct VoskApi {
+    model_new: ModelNew,
+    model_free: Free,
+    model_find_word: FindWord,
+    recognizer_new: RecognizerNew,
+    recognizer_new_grammar: RecognizerNewGrammar,
+    recognizer_free: Free,
+    accept_waveform: AcceptWaveform,
+    result: GetResult,
+    partial_result: GetResult,
+    final_result: GetResult,
+    _library: Library,
+}
+
+impl VoskApi {
+    fn load(path: &str) -> Result<Self> {
+        // SAFETY: loading libvosk only runs its static initializers, and the
+        // symbols below are declared with their C API signatures.
+        unsafe {
+            let library = Library::new(path).with_context(|| {
+                format!(
+                    "Failed to load Vosk library '{}'. Install libvosk or set stt.vosk.library",
+                    path
+                )
+            })?;
+            let model_new = *library.get::<ModelNew>(b"vosk_model_new\0")?;
+            let model_free = *library.get::<Free>(b"vosk_model_free\0")?;
+            let model_find_word = *library.get::<FindWord>(b"vosk_model_find_word\0")?;
+            let recognizer_new = *library.get::<RecognizerNew>(b"vosk_recognizer_new\0")?;
+            let recognizer_new_grammar =
+                *library.get::<RecognizerNewGrammar>(b"vosk_recognizer_new_grm\0")?;
+            let recognizer_free = *library.get::<Free>(b"vosk_recognizer_free\0")?;
+            let accept_waveform =
+                *library.get::<AcceptWaveform>(b"vosk_recognizer_accept_waveform_f\0")?;
+            let result = *library.get::<GetResult>(b"vosk_recognizer_result\0")?;
+            let partial_result = *library.get::<GetResult>(b"vosk_recognizer_partial_result\0")?;
+            let final_result = *library.get::<GetResult>(b"vosk_recognizer_final_result\0")?;
+            let set_log_level = *library.get::<SetLogLevel>(b"vosk_set_log_level\0")?;
+
+            // Kaldi logs every model load and decode to stderr otherwise
+            set_log_level(-1);
+
+            Ok(Self {
+                model_new,
+                model_free,
+                model_find_word,
+                recognizer_new,
+                recognizer_new_grammar,
+                recognizer_free,
+                accept_waveform,
+                result,
+                partial_result,
+                final_result,
+                _library: library,
+            })
+        }
+    }
+}
+
+struct Model {
+    api: Arc<VoskApi>,
+    handle: *mut c_void,
+}
+
+// SAFETY: Vosk models are immutable once loaded and reference counted
+// internally, so they can be shared between threads.
+unsafe impl Send for Model {}
+unsafe impl Sync for Model {}
+
+impl Model {
+    fn load(api: &Arc<VoskApi>, dir: &str) -> Result<Self> {
+        let path = CString::new(dir)?;
+        // SAFETY: `path` is a valid C string for the duration of the call
+        let handle = unsafe { (api.model_new)(path.as_ptr()) };
+        if handle.is_null() {
+            return Err(anyhow::anyhow!("Failed to load Vosk model from {}", dir));
+        }
+        Ok(Self {
+            api: api.clone(),
+            handle,
+        })
+    }
+
+    fn has_word(&self, word: &str) -> bool {
+        let Ok(word) = CString::new(word) else {
+            return false;
+        };
+        // SAFETY: `handle` is a live model and `word` a valid C string
+        unsafe { (self.api.model_find_word)(self.handle, word.as_ptr()) >= 0 }
+    }
+}
+
+impl Drop for Model {
+    fn drop(&mut self) {
+        // SAFETY: `handle` came from `vosk_model_new` and is freed once;
+        // recognizers keep their own reference to the model.
+        unsafe { (self.api.model_free)(self.handle) }
+    }
+}
+
+struct Recognizer {
+    api: Arc<VoskApi>,
+    handle: *mut c_void,
+}
+
+// SAFETY: a recognizer is only used through `&mut self`, so from one thread
+// at a time.
+unsafe impl Send for Recognizer {}
+
+impl Recognizer {
+    /// A recognizer for `model`, only hearing the phrases in `grammar` when
+    /// one is given.
+    fn new(model: &Model, sample_rate: u32, grammar: Option<&CStr>) -> Result<Self> {
+        let rate = sample_rate as c_float;
+        // SAFETY: `model.handle` is a live model and `grammar` a valid C string
+        let handle = unsafe {
+            match grammar {
+                Some(grammar) => {
+                    (model.api.recognizer_new_grammar)(model.handle, rate, grammar.as_ptr())
+                }
+                None => (model.api.recognizer_new)(model.handle, rate),
+            }
+        };
+        if handle.is_null() {
+            return Err(anyhow::anyhow!("Failed to create Vosk recognizer"));
+        }
+        Ok(Self {
+            api: model.api.clone(),
+            handle,
+        })
+    }
+
+    /// Feed audio; returns true when Vosk has found the end of an utterance
+    /// and `result` is ready.
+    fn accept(&mut self, audio: &[f32]) -> Result<bool> {
+        let scaled: Vec<f32> = audio.iter().map(|sample| sample * SAMPLE_SCALE).collect();
+        // SAFETY: the pointer and length describe `scaled`
+        let status = unsafe {
+            (self.api.accept_waveform)(self.handle, scaled.as_ptr(), scaled.len() as c_int)
+        };
+        if status < 0 {
+            return Err(anyhow::anyhow!("Vosk failed to decode audio"));
+        }
+        Ok(status == 1)
+    }
+
+    fn read(&mut self, get: GetResult) -> Result<VoskResult> {
+        // SAFETY: Vosk returns a NUL-terminated JSON string owned by the
+        // recognizer, valid until the next call on it; it is copied here.
+        let json = unsafe { CStr::from_ptr(get(self.handle)) }.to_string_lossy().into_owned();
+        parse_result(&json)
+    }
+
+    fn result(&mut self) -> Result<String> {
+        Ok(self.read(self.api.result)?.text)
+    }
+
+    fn partial_result(&mut self) -> Result<String> {
+        Ok(self.read(self.api.partial_result)?.partial)
+    }
+
+    fn final_result(&mut self) -> Result<String> {
+        Ok(self.read(self.api.final_result)?.text)
+    }
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        // SAFETY: `handle` came from `vosk_recognizer_new` and is freed once
+        unsafe { (self.api.recognizer_free)(self.handle) }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct VoskResult {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    partial: String,
+}
+
+fn parse_result(json: &str) -> Result<VoskResult> {
+    serde_json::from_str(json).with_context(|| format!("Unexpected Vosk result: {}", json))
+}
+
+/// The `stt.vosk.models` entry to use for `language`. Vosk cannot detect
+/// the spoken language, so "auto" only works with a single model.
+fn model_dir<'a>(models: &'a HashMap<String, String>, language: &str) -> Result<(&'a str, &'a str)> {
+    if language == AUTO_LANGUAGE {
+        let mut entries = models.iter();
+        return match (entries.next(), entries.next()) {
+            (Some((language, dir)), None) => Ok((language.as_str(), dir.as_str())),
+            _ => Err(anyhow::anyhow!(
+                "Vosk cannot detect the spoken language. Set a language or configure exactly one model in [stt.vosk.models]"
+            )),
+        };
+    }
+    models
+        .get_key_value(language)
+        .map(|(language, dir)| (language.as_str(), dir.as_str()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No Vosk model for language '{}'. Add it to [stt.vosk.models]",
+                language
+            )
+        })
+}
+
+/// Lowercase words of `phrase` as they appear in a Vosk vocabulary.
+fn phrase_words(phrase: &str) -> Vec<String> {
+    phrase
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Vosk grammar hearing only `phrases`; anything else comes out as
+/// "[unk]" instead of being forced onto one of them.
+fn grammar_json(phrases: &[String]) -> Result<String> {
+    let mut entries: Vec<&str> = phrases.iter().map(String::as_str).collect();
+    entries.push("[unk]");
+    Ok(serde_json::to_string(&entries)?)
+}
+
+/// Kaldi-based recognizer with small per-language models, for machines too
+/// slow for Whisper. Works both per utterance and streaming, where it also
+/// reports partial results.
+pub struct VoskEngine {
+    config: VoskConfig,
+    sample_rate: u32,
+    api: Option<Arc<VoskApi>>,
+    /// Loaded models by language code.
+    models: HashMap<String, Arc<Model>>,
+    /// Language of the streaming recognizer.
+    language: String,
+    recognizer: Option<Recognizer>,
+    partial: String,
+    partial_changed: bool,
+    /// Phrases to spot, see `with_phrases`.
+    phrases: Vec<String>,
+    /// Grammar of `phrases`, once the model is loaded and knows their words.
+    grammar: Option<CString>,
+}
+
+impl VoskEngine {
+    pub fn new(config: &VoskConfig, language: &str, sample_rate: u32) -> Self {
+        Self {
+            config: config.clone(),
+            sample_rate,
+            api: None,
+            models: HashMap::new(),
+            language: language.to_string(),
+            recognizer: None,
+            partial: String::new(),
+            partial_changed: false,
+            phrases: Vec::new(),
+            grammar: None,
+        }
+    }
+
+    /// Listen only for `phrases`, as a keyword spotter: recognition is
+    /// limited to a grammar of them, which is cheap and rarely mistakes
+    /// other speech for one. Phrases with words the model does not know,
+    /// like an invented name, are recognized with the full vocabulary.
+    pub fn with_phrases(mut self, phrases: &[&str]) -> Self {
+        self.phrases = phrases
+            .iter()
+            .map(|phrase| phrase_words(phrase).join(" "))
+            .filter(|phrase| !phrase.is_empty())
+            .collect();
+        self
+    }
+
+    fn phrase_grammar(&self, model: &Model) -> Result<Option<CString>> {
+        if self.phrases.is_empty() {
+            return Ok(None);
+        }
+        let missing: Vec<&str> = self
+            .phrases
+            .iter()
+            .flat_map(|phrase| phrase.split_whitespace())
+            .filter(|word| !model.has_word(word))
+            .collect();
+        if !missing.is_empty() {
+            info!(
+                "'{}' not in the Vosk vocabulary, spotting phrases without a grammar",
+                missing.join("', '")
+            );
+            return Ok(None);
+        }
+        Ok(Some(CString::new(grammar_json(&self.phrases)?)?))
+    }
+
+    fn model_for(&mut self, language: &str) -> Result<Arc<Model>> {
+        let api = self
+            .api
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Vosk library not loaded"))?;
+        let (language, dir) = model_dir(&self.config.models, language)?;
+        if let Some(model) = self.models.get(language) {
+            return Ok(model.clone());
+        }
+
+        info!("Loading Vosk model for '{}' from {}", language, dir);
+        let model = Arc::new(Model::load(&api, dir)?);
+        self.models.insert(language.to_string(), model.clone());
+        Ok(model)
+    }
+
+    fn load_library(&mut self) -> Result<()> {
+        if self.api.is_none() {
+            self.api = Some(Arc::new(VoskApi::load(&self.config.library)?));
+            debug!("Loaded {}", self.config.library);
+        }
+        let language = self.language.clone();
+        let model = self.model_for(&language)?;
+        self.grammar = self.phrase_grammar(&model)?;
+        Ok(())
+    }
+
+    fn new_recognizer(&mut self) -> Result<Recognizer> {
+        let language = self.language.clone();
+        let model = self.model_for(&language)?;
+        Recognizer::new(&model, self.sample_rate, self.grammar.as_deref())
+    }
+
+    fn transcribe_utterance(&mut self, audio: &[f32], language: &str) -> Result<String> {
+        let model = self.model_for(language)?;
+        let mut recognizer = Recognizer::new(&model, self.sample_rate, self.grammar.as_deref())?;
+        recognizer.accept(audio)?;
+        let text = recognizer.final_result()?;
+        debug!("Vosk transcription: '{}'", text);
+        Ok(text)
+    }
+
+    fn set_partial(&mut self, partial: String) {
+        if partial != self.partial {
+            self.partial = partial;
+            self.partial_changed = true;
+        }
+    }
+}
+
+impl TranscriptionBackend for VoskEngine {
+    fn name(&self) -> &str {
+        "vosk"
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.load_library() })
+    }
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, Result<Transcription>> {
+        Box::pin(async move {
+            Ok(Transcription {
+                text: self.transcribe_utterance(audio, language)?,
+                ..Transcription::default()
+            })
+        })
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        if translate {
+            warn!("Vosk cannot translate, transcribing speech as spoken");
+        }
+    }
+
+    /// Vosk models can be shared between recognizers, so forks only add a
+    /// recognizer of their own.
+    fn fork(&self) -> Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(Some(Box::new(Self {
+            api: self.api.clone(),
+            models: self.models.clone(),
+            phrases: self.phrases.clone(),
+            grammar: self.grammar.clone(),
+            ..Self::new(&self.config, &self.language, self.sample_rate)
+        })))
+    }
+}
+
+impl StreamingBackend for VoskEngine {
+    fn name(&self) -> &str {
+        "vosk"
+    }
+
+    fn load(&mut self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.load_library() })
+    }
+
+    fn start(&mut self) -> Result<()> {
+        self.recognizer = Some(self.new_recognizer()?);
+        self.partial.clear();
+        self.partial_changed = false;
+        info!("Vosk streaming started");
+        Ok(())
+    }
+
+    fn push_audio(&mut self, chunk: &[f32]) -> Result<Option<String>> {
+        let Some(recognizer) = self.recognizer.as_mut() else {
+            return Ok(None);
+        };
+
+        if recognizer.accept(chunk)? {
+            let text = recognizer.result()?;
+            self.set_partial(String::new());
+            return Ok((!text.is_empty()).then_some(text));
+        }
+        let partial = recognizer.partial_result()?;
+        self.set_partial(partial);
+        Ok(None)
+    }
+
+    fn take_partial(&mut self) -> Option<String> {
+        std::mem::take(&mut self.partial_changed).then(|| self.partial.clone())
+    }
+
+    fn stop(&mut self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.recognizer = None;
+            self.partial.clear();
+            info!("Vosk streaming stopped");
+        })
+    }
+
+    fn set_language(&mut self, language: String) {
+        self.language = language;
+        if self.recognizer.is_some() {
+            match self.new_recognizer() {
+                Ok(recognizer) => self.recognizer = Some(recognizer),
+                Err(e) => warn!("Keeping the current Vosk model: {}", e),
+            }
+        }
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        TranscriptionBackend::set_translate(self, translate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(language, dir)| (language.to_string(), dir.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_model_dir_by_language() {
+        let models = models(&[("en", "/vosk/en"), ("de", "/vosk/de")]);
+        assert_eq!(model_dir(&models, "de").unwrap(), ("de", "/vosk/de"));
+        assert!(model_dir(&models, "fr").is_err());
+        // Several models and no language to choose by
+        assert!(model_dir(&models, "auto").is_err());
+    }
+
+    #[test]
+    fn test_model_dir_auto_with_single_model() {
+        let models = models(&[("en", "/vosk/en")]);
+        assert_eq!(model_dir(&models, "auto").unwrap(), ("en", "/vosk/en"));
+        assert!(model_dir(&HashMap::new(), "auto").is_err());
+    }
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result(r#"{"text" : "hello world"}"#).unwrap().text, "hello world");
+        assert_eq!(parse_result(r#"{"partial" : "hel"}"#).unwrap().partial, "hel");
+        assert_eq!(parse_result(r#"{"partial" : ""}"#).unwrap().text, "");
+        assert!(parse_result("not json").is_err());
+    }
+
+    #[test]
+    fn test_phrase_grammar() {
+        assert_eq!(phrase_words("Hey, Computer!"), ["hey", "computer"]);
+        assert_eq!(phrase_words("don't stop"), ["don't", "stop"]);
+
+        let engine = VoskEngine::new(&VoskConfig::default(), "en", 16000)
+            .with_phrases(&["Hey computer", "stop dictation.", "?"]);
+        assert_eq!(engine.phrases, ["hey computer", "stop dictation"]);
+        assert_eq!(
+            grammar_json(&engine.phrases).unwrap(),
+            r#"["hey computer","stop dictation","[unk]"]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_reports_missing_library() {
+        let config = VoskConfig {
+            library: "/nonexistent/libvosk.so".to_string(),
+            ..VoskConfig::default()
+        };
+        let mut engine = VoskEngine::new(&config, "en", 16000);
+        let error = TranscriptionBackend::load(&mut engine).await.unwrap_err();
+        assert!(error.to_string().contains("stt.vosk.library"));
+    }
+
+    #[test]
+    fn test_partials_are_reported_once() {
+        let mut engine = VoskEngine::new(&VoskConfig::default(), "en", 16000);
+        assert_eq!(engine.take_partial(), None);
+        engine.set_partial("hello".to_string());
+        assert_eq!(engine.take_partial().as_deref(), Some("hello"));
+        assert_eq!(engine.take_partial(), None);
+        engine.set_partial("hello".to_string());
+        assert_eq!(engine.take_partial(), None);
+    }
+}
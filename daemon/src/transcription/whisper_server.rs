@@ -0,0 +1,293 @@
+use anyhow::Context;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::WhisperServerConfig;
+use crate::transcription::backend::{Transcription, TranscriptionBackend};
+use crate::transcription::multipart;
+
+/// Transcribes utterances with a whisper.cpp `server` over HTTP, so one
+/// machine can serve several daemons.
+///
+/// When the server stops answering, utterances fail immediately instead of
+/// each waiting for a timeout, and the connection is retried with backoff.
+#[derive(Clone)]
+pub struct WhisperServerBackend {
+    url: String,
+    client: reqwest::Client,
+    sample_rate: u32,
+    translate: bool,
+    prompt: Option<String>,
+    reconnect: Reconnect,
+}
+
+impl WhisperServerBackend {
+    pub fn new(config: &WhisperServerConfig, sample_rate: u32) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .connect_timeout(Duration::from_secs(5))
+            .build()
+            .expect("Failed to build reqwest client for whisper server");
+
+        Self {
+            url: config.url.trim_end_matches('/').to_string(),
+            client,
+            sample_rate,
+            translate: false,
+            prompt: None,
+            reconnect: Reconnect::new(
+                Duration::from_secs(config.retry_seconds),
+                Duration::from_secs(config.max_retry_seconds),
+            ),
+        }
+    }
+
+    /// Text sent as the `prompt` field, see `vocabulary::prompt_from_config`.
+    pub fn with_prompt(mut self, prompt: Option<String>) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Whether the server answers. Servers without `/health` answer 404,
+    /// which still shows they are up; 503 means a model is still loading.
+    async fn check_health(&self) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .get(format!("{}/health", self.url))
+            .send()
+            .await
+            .with_context(|| format!("Whisper server at {} is unreachable", self.url))?;
+        if response.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(anyhow::anyhow!("Whisper server at {} is still loading", self.url));
+        }
+        Ok(())
+    }
+
+    /// Record that the server could not be reached and pass the error on.
+    fn lost(&mut self, error: anyhow::Error) -> anyhow::Error {
+        let delay = self.reconnect.failed(Instant::now());
+        warn!(
+            "Lost whisper server at {}, retrying in {}s: {:#}",
+            self.url,
+            delay.as_secs(),
+            error
+        );
+        error
+    }
+
+    async fn transcribe_text(&mut self, audio: &[f32], language: &str) -> anyhow::Result<String> {
+        if let Some(wait) = self.reconnect.wait(Instant::now()) {
+            return Err(anyhow::anyhow!(
+                "Whisper server at {} is unreachable, retrying in {}s",
+                self.url,
+                wait.as_secs().max(1)
+            ));
+        }
+        if self.reconnect.is_down() {
+            // Due for a retry: make sure the server is back before uploading audio
+            if let Err(e) = self.check_health().await {
+                return Err(self.lost(e));
+            }
+            self.reconnect.succeeded();
+            info!("Reconnected to whisper server at {}", self.url);
+        }
+
+        let wav = multipart::encode_wav(audio, self.sample_rate)?;
+        let translate = self.translate.to_string();
+        let mut fields = vec![
+            ("response_format", "json"),
+            ("language", language),
+            ("translate", translate.as_str()),
+        ];
+        if let Some(prompt) = &self.prompt {
+            fields.push(("prompt", prompt));
+        }
+        let (content_type, body) = multipart::form(&fields, &wav);
+
+        let url = format!("{}/inference", self.url);
+        debug!("Sending {} samples to {}", audio.len(), url);
+        let sent = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await;
+        let response = match sent {
+            Ok(response) => response,
+            Err(e) => {
+                let error = anyhow::Error::new(e).context("Failed to send audio to whisper server");
+                return Err(self.lost(error));
+            }
+        };
+
+        let status = response.status();
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(self.lost(anyhow::anyhow!("Whisper server is still loading")));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Whisper server returned error {}: {}",
+                status.as_u16(),
+                body
+            ));
+        }
+
+        let raw_body = response
+            .text()
+            .await
+            .context("Failed to read whisper server response body")?;
+        parse_inference(&raw_body)
+    }
+}
+
+impl TranscriptionBackend for WhisperServerBackend {
+    fn name(&self) -> &str {
+        "whisper server"
+    }
+
+    /// Check the server is up. A server that is down does not stop the
+    /// daemon from starting; it is retried when speech arrives.
+    fn load(&mut self) -> BoxFuture<'_, anyhow::Result<()>> {
+        Box::pin(async move {
+            match self.check_health().await {
+                Ok(()) => info!("Connected to whisper server at {}", self.url),
+                Err(e) => {
+                    self.lost(e);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn transcribe<'a>(
+        &'a mut self,
+        audio: &'a [f32],
+        language: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<Transcription>> {
+        Box::pin(async move {
+            Ok(Transcription {
+                text: self.transcribe_text(audio, language).await?,
+                ..Transcription::default()
+            })
+        })
+    }
+
+    fn set_translate(&mut self, translate: bool) {
+        self.translate = translate;
+    }
+
+    /// The server queues requests itself, so forks only add connections.
+    fn fork(&self) -> anyhow::Result<Option<Box<dyn TranscriptionBackend>>> {
+        Ok(Some(Box::new(self.clone())))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InferenceResponse {
+    text: String,
+}
+
+fn parse_inference(body: &str) -> anyhow::Result<String> {
+    let response: InferenceResponse = serde_json::from_str(body)
+        .with_context(|| format!("Failed to parse whisper server response: {}", body))?;
+    Ok(response.text.trim().to_string())
+}
+
+/// Exponential backoff between attempts to reach the server.
+#[derive(Debug, Clone)]
+struct Reconnect {
+    initial: Duration,
+    max: Duration,
+    delay: Duration,
+    retry_at: Option<Instant>,
+}
+
+impl Reconnect {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max: max.max(initial),
+            delay: initial,
+            retry_at: None,
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        self.retry_at.is_some()
+    }
+
+    /// Time left before the next attempt, or `None` if one may be made now.
+    fn wait(&self, now: Instant) -> Option<Duration> {
+        self.retry_at
+            .filter(|retry_at| *retry_at > now)
+            .map(|retry_at| retry_at - now)
+    }
+
+    /// Schedule the next attempt, returning how long until it.
+    fn failed(&mut self, now: Instant) -> Duration {
+        self.delay = if self.is_down() {
+            (self.delay * 2).min(self.max)
+        } else {
+            self.initial
+        };
+        self.retry_at = Some(now + self.delay);
+        self.delay
+    }
+
+    fn succeeded(&mut self) {
+        self.retry_at = None;
+        self.delay = self.initial;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_backs_off_exponentially() {
+        let mut reconnect = Reconnect::new(Duration::from_secs(2), Duration::from_secs(10));
+        let now = Instant::now();
+        assert!(!reconnect.is_down());
+        assert_eq!(reconnect.wait(now), None);
+
+        assert_eq!(reconnect.failed(now), Duration::from_secs(2));
+        assert_eq!(reconnect.wait(now), Some(Duration::from_secs(2)));
+        assert_eq!(reconnect.wait(now + Duration::from_secs(2)), None);
+        assert_eq!(reconnect.failed(now), Duration::from_secs(4));
+        assert_eq!(reconnect.failed(now), Duration::from_secs(8));
+        assert_eq!(reconnect.failed(now), Duration::from_secs(10));
+
+        reconnect.succeeded();
+        assert!(!reconnect.is_down());
+        assert_eq!(reconnect.failed(now), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_parse_inference() {
+        assert_eq!(parse_inference("{\"text\":\" Hello there.\\n\"}").unwrap(), "Hello there.");
+        assert!(parse_inference("<html>Not found</html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_fails_fast_after_load() {
+        let config = WhisperServerConfig {
+            // Nothing listens on the discard port
+            url: "http://127.0.0.1:9/".to_string(),
+            retry_seconds: 60,
+            ..WhisperServerConfig::default()
+        };
+        let mut backend = WhisperServerBackend::new(&config, 16000);
+        assert_eq!(backend.url, "http://127.0.0.1:9");
+
+        backend.load().await.unwrap();
+        assert!(backend.reconnect.is_down());
+
+        let error = backend.transcribe(&[0.0; 160], "en").await.unwrap_err();
+        assert!(error.to_string().contains("retrying in"));
+    }
+}
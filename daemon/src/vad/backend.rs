@@ -0,0 +1,97 @@
+use anyhow::Result;
+
+use super::detector::{EnergyVad, VADResult};
+use super::features::SpectralFeatures;
+use super::noise_floor::NoiseFloor;
+use super::silero::{self, SileroVad};
+use crate::config::VadConfig;
+
+/// A speech/non-speech classifier for audio chunks.
+///
+/// `SpeechDetector` runs the segmenting state machine and only asks the
+/// backend whether each chunk is speech. Adding a detector means
+/// implementing this trait and registering a name in `create_vad`.
+pub trait Vad: Send {
+    /// Short human readable name used in logs.
+    fn name(&self) -> &str;
+
+    /// Classify one chunk. `is_speaking` is true while an utterance is in
+    /// progress, so implementations can apply hysteresis.
+    fn detect(&mut self, samples: &[f32], is_speaking: bool) -> VADResult;
+
+    /// Take the ambient RMS level measured at startup into account.
+    /// Detectors that do not track the noise level ignore it.
+    fn calibrate(&mut self, _ambient_level: f32) {}
+}
+
+/// Build the detector selected by `vad.backend`.
+///
+/// # Arguments
+/// * `config` - VAD configuration section
+/// * `sample_rate` - Sample rate of the audio the detector will see
+///
+/// # Returns
+/// * `Ok(Box<dyn Vad>)` - Detector ready to use
+/// * `Err` - Unknown backend name or the backend failed to initialize
+pub fn create_vad(config: &VadConfig, sample_rate: u32) -> Result<Box<dyn Vad>> {
+    match config.backend.as_str() {
+        "energy" | "rms" => {
+            let (threshold_start, threshold_stop) = config.effective_thresholds();
+            let mut vad = EnergyVad::new(threshold_start, threshold_stop)?;
+            if config.adaptive_thresholds {
+                vad = vad.with_noise_floor(NoiseFloor::new(
+                    sample_rate,
+                    threshold_start,
+                    threshold_stop,
+                    config.noise_start_ratio,
+                    config.noise_stop_ratio,
+                ));
+            }
+            if config.spectral_features {
+                vad = vad.with_spectral_features(SpectralFeatures::new(
+                    config.min_spectral_flatness,
+                    config.min_zero_crossing_rate,
+                ));
+            }
+            Ok(Box::new(vad))
+        }
+        "silero" => {
+            let model_path = match &config.silero_model_path {
+                Some(path) => std::path::PathBuf::from(path),
+                None => silero::default_model_path()?,
+            };
+            Ok(Box::new(SileroVad::new(
+                &model_path,
+                sample_rate,
+                1,
+                config.silero_threshold,
+            )?))
+        }
+        other => Err(anyhow::anyhow!(
+            "Unknown VAD backend '{}'. Expected one of: energy, silero",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_create_energy_vad() {
+        let vad = create_vad(&Config::default().vad, 16000).unwrap();
+        assert_eq!(vad.name(), "energy");
+    }
+
+    #[test]
+    fn test_create_unknown_vad_fails() {
+        let config = VadConfig {
+            backend: "webrtc".to_string(),
+            ..Config::default().vad
+        };
+        let result = create_vad(&config, 16000);
+        assert!(result.err().unwrap().to_string().contains("Unknown VAD backend"));
+    }
+}
@@ -0,0 +1,70 @@
+use shared::ipc::amplitude_to_dbfs;
+
+/// Ambient noise must sit at least this factor below `threshold_start`, or
+/// ordinary room noise will keep triggering the detector.
+const MIN_HEADROOM: f32 = 2.0;
+
+/// Measures the ambient noise level over the first stretch of audio after
+/// `Start`, before any of it reaches the speech detector.
+pub struct AmbientCalibration {
+    target_samples: usize,
+    sum_squares: f64,
+    count: usize,
+}
+
+impl AmbientCalibration {
+    pub fn new(sample_rate: u32, duration_ms: u32) -> Self {
+        let target_samples = (sample_rate as u64 * duration_ms as u64 / 1000).max(1) as usize;
+        tracing::info!("Calibrating ambient noise for {}ms, please stay quiet", duration_ms);
+        Self {
+            target_samples,
+            sum_squares: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Feed a chunk; returns the ambient RMS level once enough audio has
+    /// been heard.
+    pub fn push(&mut self, samples: &[f32]) -> Option<f32> {
+        self.sum_squares += samples.iter().map(|&s| (s * s) as f64).sum::<f64>();
+        self.count += samples.len();
+        if self.count < self.target_samples {
+            return None;
+        }
+        Some((self.sum_squares / self.count as f64).sqrt() as f32)
+    }
+}
+
+/// A user-facing warning when the measured ambient `level` leaves too little
+/// headroom below `threshold_start`.
+pub fn ambient_warning(level: f32, threshold_start: f32) -> Option<String> {
+    if level * MIN_HEADROOM < threshold_start {
+        return None;
+    }
+    Some(format!(
+        "Ambient noise is {:.1} dBFS, too loud for vad.threshold_start ({:.1} dBFS); \
+        raise the threshold or enable vad.adaptive_thresholds",
+        amplitude_to_dbfs(level),
+        amplitude_to_dbfs(threshold_start)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibration_reports_after_duration() {
+        // 10ms at 1kHz
+        let mut calibration = AmbientCalibration::new(1000, 10);
+        assert_eq!(calibration.push(&[0.1; 6]), None);
+        let level = calibration.push(&[0.1; 6]).unwrap();
+        assert!((level - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ambient_warning_needs_headroom() {
+        assert!(ambient_warning(0.002, 0.02).is_none());
+        assert!(ambient_warning(0.015, 0.02).unwrap().contains("too loud"));
+    }
+}
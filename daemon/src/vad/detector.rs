@@ -1,6 +1,11 @@
 use anyhow::Result;
+use shared::ipc::amplitude_to_dbfs;
 use tracing::info;
 
+use super::backend::Vad;
+use super::features::SpectralFeatures;
+use super::noise_floor::NoiseFloor;
+
 pub struct VoiceActivityDetector {
     threshold_start: f32,
     threshold_stop: f32,
@@ -9,8 +14,11 @@ pub struct VoiceActivityDetector {
 impl VoiceActivityDetector {
     pub fn new(threshold_start: f32, threshold_stop: f32) -> Result<Self> {
         info!(
-            "VAD initialized with threshold_start: {}, threshold_stop: {}",
-            threshold_start, threshold_stop
+            "VAD initialized with threshold_start: {} ({:.1} dBFS), threshold_stop: {} ({:.1} dBFS)",
+            threshold_start,
+            amplitude_to_dbfs(threshold_start),
+            threshold_stop,
+            amplitude_to_dbfs(threshold_stop)
         );
 
         Ok(Self {
@@ -19,6 +27,11 @@ impl VoiceActivityDetector {
         })
     }
 
+    pub fn set_thresholds(&mut self, threshold_start: f32, threshold_stop: f32) {
+        self.threshold_start = threshold_start;
+        self.threshold_stop = threshold_stop;
+    }
+
     pub fn detect(&self, audio_level: f32, is_speaking: bool) -> VADResult {
         let is_speech = if is_speaking {
             audio_level >= self.threshold_stop
@@ -27,10 +40,13 @@ impl VoiceActivityDetector {
         };
 
         tracing::debug!(
-            "Audio level: {:.4}, threshold_start: {:.4}, threshold_stop: {:.4}, is_speaking: {}, is_speech: {}",
+            "Audio level: {:.4} ({:.1} dBFS), threshold_start: {:.4} ({:.1} dBFS), threshold_stop: {:.4} ({:.1} dBFS), is_speaking: {}, is_speech: {}",
             audio_level,
+            amplitude_to_dbfs(audio_level),
             self.threshold_start,
+            amplitude_to_dbfs(self.threshold_start),
             self.threshold_stop,
+            amplitude_to_dbfs(self.threshold_stop),
             is_speaking,
             is_speech
         );
@@ -47,9 +63,83 @@ impl VoiceActivityDetector {
         }
 
         let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
-        let rms = (sum_squares / samples.len() as f32).sqrt();
+        (sum_squares / samples.len() as f32).sqrt()
+    }
+}
 
-        rms
+/// RMS level detector with optional adaptive thresholds and tonal noise
+/// rejection, selected by `vad.backend = "energy"`.
+pub struct EnergyVad {
+    detector: VoiceActivityDetector,
+    noise_floor: Option<NoiseFloor>,
+    spectral: Option<SpectralFeatures>,
+}
+
+impl EnergyVad {
+    pub fn new(threshold_start: f32, threshold_stop: f32) -> Result<Self> {
+        Ok(Self {
+            detector: VoiceActivityDetector::new(threshold_start, threshold_stop)?,
+            noise_floor: None,
+            spectral: None,
+        })
+    }
+
+    /// Derive the thresholds from a running noise floor estimate instead of
+    /// keeping them fixed.
+    pub fn with_noise_floor(mut self, noise_floor: NoiseFloor) -> Self {
+        self.noise_floor = Some(noise_floor);
+        self
+    }
+
+    /// Treat chunks that look like tonal background noise as silence, even
+    /// when they are loud enough to pass the threshold.
+    pub fn with_spectral_features(mut self, spectral: SpectralFeatures) -> Self {
+        self.spectral = Some(spectral);
+        self
+    }
+}
+
+impl Vad for EnergyVad {
+    fn name(&self) -> &str {
+        "energy"
+    }
+
+    fn detect(&mut self, samples: &[f32], is_speaking: bool) -> VADResult {
+        let is_tonal_noise = self
+            .spectral
+            .as_mut()
+            .is_some_and(|spectral| spectral.is_tonal_noise(samples));
+        let level = if is_tonal_noise {
+            tracing::debug!("Chunk looks like tonal noise, ignoring its level");
+            0.0
+        } else {
+            self.detector.calculate_audio_level(samples)
+        };
+
+        if let Some(noise_floor) = self.noise_floor.as_mut() {
+            // Only background audio may move the floor
+            if !is_speaking {
+                noise_floor.update(level, samples.len());
+            }
+            let (threshold_start, threshold_stop) = noise_floor.thresholds();
+            tracing::debug!(
+                "Noise floor {:.4}: threshold_start={:.4}, threshold_stop={:.4}",
+                noise_floor.level().unwrap_or(0.0),
+                threshold_start,
+                threshold_stop
+            );
+            self.detector.set_thresholds(threshold_start, threshold_stop);
+        }
+
+        self.detector.detect(level, is_speaking)
+    }
+
+    fn calibrate(&mut self, ambient_level: f32) {
+        if let Some(noise_floor) = self.noise_floor.as_mut() {
+            noise_floor.seed(ambient_level);
+            let (threshold_start, threshold_stop) = noise_floor.thresholds();
+            self.detector.set_thresholds(threshold_start, threshold_stop);
+        }
     }
 }
 
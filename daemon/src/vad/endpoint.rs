@@ -0,0 +1,119 @@
+/// Trailing silence this much quieter than recent speech means the speaker
+/// clearly stopped (a sharp energy decay).
+const SHARP_DECAY_RATIO: f32 = 0.1;
+
+/// Trailing audio at least this loud relative to recent speech is treated as
+/// a hesitation: breathing, a drawn-out "umm", a trailing-off voice.
+const HESITATION_RATIO: f32 = 0.4;
+
+/// Weight of each new speech frame in the recent speech level.
+const SPEECH_LEVEL_SMOOTHING: f32 = 0.2;
+
+/// Picks the end-of-speech silence timeout from how the audio decays after
+/// the last speech, instead of always waiting `min_silence_duration_ms`.
+pub struct Endpointer {
+    min_ms: u32,
+    max_ms: u32,
+    speech_level: Option<f32>,
+    silence_sum_squares: f64,
+    silence_count: usize,
+}
+
+impl Endpointer {
+    pub fn new(min_ms: u32, max_ms: u32) -> Self {
+        Self {
+            min_ms,
+            max_ms: max_ms.max(min_ms),
+            speech_level: None,
+            silence_sum_squares: 0.0,
+            silence_count: 0,
+        }
+    }
+
+    /// Record a frame classified as speech.
+    pub fn observe_speech(&mut self, samples: &[f32]) {
+        let level = rms(samples);
+        self.speech_level = Some(match self.speech_level {
+            Some(current) => current + (level - current) * SPEECH_LEVEL_SMOOTHING,
+            None => level,
+        });
+    }
+
+    /// Record a frame heard while waiting for the end of speech.
+    pub fn observe_silence(&mut self, samples: &[f32]) {
+        self.silence_sum_squares += samples.iter().map(|&s| (s * s) as f64).sum::<f64>();
+        self.silence_count += samples.len();
+    }
+
+    /// Forget the current pause, e.g. when speech resumes.
+    pub fn reset_silence(&mut self) {
+        self.silence_sum_squares = 0.0;
+        self.silence_count = 0;
+    }
+
+    /// Forget everything at the end of an utterance.
+    pub fn reset(&mut self) {
+        self.speech_level = None;
+        self.reset_silence();
+    }
+
+    /// Silence timeout for the current pause, given the configured one.
+    pub fn timeout_ms(&self, base_ms: u32) -> u32 {
+        let speech_level = match self.speech_level {
+            Some(level) if level > 0.0 && self.silence_count > 0 => level,
+            _ => return base_ms,
+        };
+        let silence_level = (self.silence_sum_squares / self.silence_count as f64).sqrt() as f32;
+        let ratio = silence_level / speech_level;
+
+        if ratio <= SHARP_DECAY_RATIO {
+            self.min_ms.min(base_ms)
+        } else if ratio >= HESITATION_RATIO {
+            self.max_ms.max(base_ms)
+        } else {
+            base_ms
+        }
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = samples.iter().map(|s| s * s).sum();
+    (sum_squares / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sharp_decay_shortens_timeout() {
+        let mut endpointer = Endpointer::new(300, 1500);
+        endpointer.observe_speech(&[0.3; 160]);
+        endpointer.observe_silence(&[0.001; 160]);
+        assert_eq!(endpointer.timeout_ms(1000), 300);
+    }
+
+    #[test]
+    fn test_hesitation_lengthens_timeout() {
+        let mut endpointer = Endpointer::new(300, 1500);
+        endpointer.observe_speech(&[0.1; 160]);
+        endpointer.observe_silence(&[0.05; 160]);
+        assert_eq!(endpointer.timeout_ms(1000), 1500);
+    }
+
+    #[test]
+    fn test_unclear_decay_keeps_base_timeout() {
+        let mut endpointer = Endpointer::new(300, 1500);
+        assert_eq!(endpointer.timeout_ms(1000), 1000);
+
+        endpointer.observe_speech(&[0.1; 160]);
+        endpointer.observe_silence(&[0.02; 160]);
+        assert_eq!(endpointer.timeout_ms(1000), 1000);
+
+        endpointer.reset();
+        assert_eq!(endpointer.timeout_ms(1000), 1000);
+    }
+}
@@ -0,0 +1,127 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Added to every power bin so silent bins don't send the log to -inf.
+const POWER_EPSILON: f32 = 1e-10;
+
+/// Fraction of adjacent sample pairs that change sign.
+pub fn zero_crossing_rate(samples: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+        .count();
+    crossings as f32 / (samples.len() - 1) as f32
+}
+
+/// Spectral feature checks that complement the RMS detector.
+///
+/// Sustained tonal noise such as mains hum or a fan motor can be louder than
+/// `threshold_start`, but its energy sits in a few low-frequency lines: the
+/// spectrum is very peaky (low flatness) and the waveform crosses zero
+/// rarely. Speech has a much richer spectrum and crosses zero far more often,
+/// so a chunk is only rejected when both features look tonal.
+pub struct SpectralFeatures {
+    planner: FftPlanner<f32>,
+    min_flatness: f32,
+    min_zero_crossing_rate: f32,
+}
+
+impl SpectralFeatures {
+    pub fn new(min_flatness: f32, min_zero_crossing_rate: f32) -> Self {
+        Self {
+            planner: FftPlanner::new(),
+            min_flatness,
+            min_zero_crossing_rate,
+        }
+    }
+
+    /// Spectral flatness of a Hann-windowed chunk: the geometric over the
+    /// arithmetic mean of the power spectrum, from near 0 (pure tone) to 1
+    /// (white noise).
+    pub fn spectral_flatness(&mut self, samples: &[f32]) -> f32 {
+        let len = samples.len();
+        if len < 4 {
+            return 1.0;
+        }
+
+        let mut buffer: Vec<Complex<f32>> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                let hann = 0.5
+                    - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (len - 1) as f32).cos();
+                Complex::new(s * hann, 0.0)
+            })
+            .collect();
+        self.planner.plan_fft_forward(len).process(&mut buffer);
+
+        // Positive frequencies without DC
+        let powers: Vec<f32> = buffer[1..len / 2]
+            .iter()
+            .map(|c| c.norm_sqr() + POWER_EPSILON)
+            .collect();
+        let log_mean = powers.iter().map(|p| p.ln()).sum::<f32>() / powers.len() as f32;
+        let mean = powers.iter().sum::<f32>() / powers.len() as f32;
+        (log_mean.exp() / mean).clamp(0.0, 1.0)
+    }
+
+    /// Whether `samples` look like tonal background noise rather than speech.
+    pub fn is_tonal_noise(&mut self, samples: &[f32]) -> bool {
+        zero_crossing_rate(samples) < self.min_zero_crossing_rate
+            && self.spectral_flatness(samples) < self.min_flatness
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * frequency * i as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    fn noise(len: usize) -> Vec<f32> {
+        // Deterministic LCG so the test is stable
+        let mut state: u32 = 12345;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as f32 / 32768.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zero_crossing_rate() {
+        assert_eq!(zero_crossing_rate(&[1.0, -1.0, 1.0, -1.0, 1.0]), 1.0);
+        assert_eq!(zero_crossing_rate(&[0.5; 10]), 0.0);
+        assert_eq!(zero_crossing_rate(&[]), 0.0);
+
+        // 60Hz at 16kHz crosses zero 120 times a second
+        let zcr = zero_crossing_rate(&sine(60.0, 16000));
+        assert!((zcr - 120.0 / 16000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_flatness_separates_tone_from_noise() {
+        let mut features = SpectralFeatures::new(0.05, 0.02);
+        let tone = features.spectral_flatness(&sine(1000.0, 512));
+        let white = features.spectral_flatness(&noise(512));
+        assert!(tone < 0.05, "tone flatness {}", tone);
+        assert!(white > 0.3, "noise flatness {}", white);
+    }
+
+    #[test]
+    fn test_hum_is_tonal_noise() {
+        let mut features = SpectralFeatures::new(0.05, 0.02);
+        assert!(features.is_tonal_noise(&sine(60.0, 512)));
+        assert!(!features.is_tonal_noise(&noise(512)));
+        // High-pitched content crosses zero too often to be called hum
+        assert!(!features.is_tonal_noise(&sine(1000.0, 512)));
+    }
+}
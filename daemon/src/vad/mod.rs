@@ -1,2 +1,8 @@
+pub mod backend;
+pub mod calibration;
 pub mod detector;
+pub mod endpoint;
+pub mod features;
+pub mod noise_floor;
+pub mod silero;
 pub mod speech_detector;
@@ -0,0 +1,126 @@
+/// Time constant for the estimate to follow a rising noise level. Slow, so
+/// speech the detector misses does not drag the floor up with it.
+const RISE_TIME_SECS: f32 = 4.0;
+
+/// Time constant for following a falling noise level.
+const FALL_TIME_SECS: f32 = 0.5;
+
+/// Running estimate of the background noise level, used to keep the energy
+/// thresholds a fixed ratio above whatever the room currently sounds like.
+///
+/// Only audio outside of speech should be fed in. The configured thresholds
+/// stay in effect as a lower bound, so a quiet room behaves as before.
+pub struct NoiseFloor {
+    sample_rate: u32,
+    level: Option<f32>,
+    min_start: f32,
+    min_stop: f32,
+    start_ratio: f32,
+    stop_ratio: f32,
+}
+
+impl NoiseFloor {
+    pub fn new(
+        sample_rate: u32,
+        min_start: f32,
+        min_stop: f32,
+        start_ratio: f32,
+        stop_ratio: f32,
+    ) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            level: None,
+            min_start,
+            min_stop,
+            start_ratio,
+            stop_ratio,
+        }
+    }
+
+    /// Fold the RMS `level` of `samples` non-speech samples into the estimate.
+    pub fn update(&mut self, level: f32, samples: usize) {
+        let current = match self.level {
+            Some(current) => current,
+            None => {
+                self.level = Some(level);
+                return;
+            }
+        };
+
+        let time_constant = if level > current {
+            RISE_TIME_SECS
+        } else {
+            FALL_TIME_SECS
+        };
+        let elapsed = samples as f32 / self.sample_rate as f32;
+        let alpha = (elapsed / time_constant).min(1.0);
+        self.level = Some(current + (level - current) * alpha);
+    }
+
+    /// Start the estimate from a measured ambient level instead of the first
+    /// chunk that happens to arrive.
+    pub fn seed(&mut self, level: f32) {
+        self.level = Some(level);
+    }
+
+    pub fn level(&self) -> Option<f32> {
+        self.level
+    }
+
+    /// Current `(threshold_start, threshold_stop)`.
+    pub fn thresholds(&self) -> (f32, f32) {
+        let floor = self.level.unwrap_or(0.0);
+        (
+            self.min_start.max(floor * self.start_ratio),
+            self.min_stop.max(floor * self.stop_ratio),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor() -> NoiseFloor {
+        NoiseFloor::new(1000, 0.02, 0.01, 3.0, 2.0)
+    }
+
+    #[test]
+    fn test_seed_sets_floor() {
+        let mut noise = floor();
+        noise.seed(0.02);
+        assert_eq!(noise.level(), Some(0.02));
+        assert_eq!(noise.thresholds(), (0.06, 0.04));
+    }
+
+    #[test]
+    fn test_quiet_room_keeps_configured_thresholds() {
+        let mut noise = floor();
+        assert_eq!(noise.thresholds(), (0.02, 0.01));
+        noise.update(0.001, 1000);
+        assert_eq!(noise.thresholds(), (0.02, 0.01));
+    }
+
+    #[test]
+    fn test_thresholds_follow_loud_noise() {
+        let mut noise = floor();
+        noise.update(0.02, 1000);
+        let (start, stop) = noise.thresholds();
+        assert!((start - 0.06).abs() < 1e-6);
+        assert!((stop - 0.04).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_floor_rises_slowly_and_falls_quickly() {
+        let mut noise = floor();
+        noise.update(0.01, 1000);
+
+        // One second of louder noise moves a quarter of the way up
+        noise.update(0.05, 1000);
+        assert!((noise.level().unwrap() - 0.02).abs() < 1e-6);
+
+        // Half a second of quiet brings it all the way down
+        noise.update(0.0, 500);
+        assert_eq!(noise.level(), Some(0.0));
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use whisper_rs::{WhisperVadContext, WhisperVadContextParams};
+
+use super::backend::Vad;
+use super::detector::{VADResult, VoiceActivityDetector};
+use crate::audio::resample::resample;
+use crate::model::find_model_path;
+
+/// Where the ggml conversion of the Silero model is published.
+pub const SILERO_MODEL_URL: &str =
+    "https://huggingface.co/ggml-org/whisper-vad/resolve/main/ggml-silero-v5.1.2.bin";
+
+/// The only rate Silero works at.
+const SILERO_SAMPLE_RATE: u32 = 16000;
+
+/// Samples per Silero window at 16kHz.
+const WINDOW_SAMPLES: usize = 512;
+
+/// Offset below the start threshold at which speech is considered over,
+/// matching Silero's own reference implementation.
+const STOP_THRESHOLD_OFFSET: f32 = 0.15;
+
+/// Neural speech detector using Silero VAD through whisper.cpp.
+///
+/// Produces a speech probability in `0.0..=1.0` per chunk, which is far more
+/// robust against steady background noise than RMS level. Audio at other
+/// rates is converted to the 16kHz Silero needs, and each window is scored
+/// once, when it is complete. whisper.cpp clears the model's recurrent state
+/// on every call, so it cannot be carried over from one chunk to the next.
+pub struct SileroVad {
+    context: WhisperVadContext,
+    /// Rate of the audio passed to `detect`.
+    sample_rate: u32,
+    /// 16kHz audio not yet making up a whole window.
+    pending: Vec<f32>,
+    /// Probability of the newest scored window, kept for chunks that do not
+    /// complete one.
+    probability: f32,
+    hysteresis: VoiceActivityDetector,
+}
+
+impl SileroVad {
+    /// Load the model for audio at `sample_rate`; chunks scoring at least
+    /// `threshold` start speech.
+    pub fn new(
+        model_path: &Path,
+        sample_rate: u32,
+        n_threads: i32,
+        threshold: f32,
+    ) -> Result<Self> {
+        if !model_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Silero VAD model not found at {}. Download it from {}",
+                model_path.display(),
+                SILERO_MODEL_URL
+            ));
+        }
+
+        let mut params = WhisperVadContextParams::new();
+        params.set_n_threads(n_threads);
+        params.set_use_gpu(false);
+
+        let path = model_path.to_string_lossy();
+        let context = WhisperVadContext::new(&path, params)
+            .map_err(|e| anyhow::anyhow!("Failed to load Silero VAD model {}: {:?}", path, e))?;
+
+        tracing::info!("Silero VAD loaded from {}", model_path.display());
+        Ok(Self {
+            context,
+            sample_rate,
+            pending: Vec::with_capacity(WINDOW_SAMPLES * 2),
+            probability: 0.0,
+            hysteresis: VoiceActivityDetector::new(
+                threshold,
+                (threshold - STOP_THRESHOLD_OFFSET).max(0.0),
+            )?,
+        })
+    }
+
+    /// Speech probability for the newest audio in `samples`. Only the
+    /// windows `samples` completes are run through the model.
+    pub fn speech_probability(&mut self, samples: &[f32]) -> Result<f32> {
+        self.pending
+            .extend(resample(samples, self.sample_rate, SILERO_SAMPLE_RATE));
+        let windows = take_windows(&mut self.pending);
+        if windows.is_empty() {
+            return Ok(self.probability);
+        }
+
+        self.context
+            .detect_speech(&windows)
+            .map_err(|e| anyhow::anyhow!("Silero VAD failed: {:?}", e))?;
+
+        self.probability =
+            latest_probability(self.context.probabilities(), windows.len() / WINDOW_SAMPLES);
+        Ok(self.probability)
+    }
+}
+
+impl Vad for SileroVad {
+    fn name(&self) -> &str {
+        "silero"
+    }
+
+    fn detect(&mut self, samples: &[f32], is_speaking: bool) -> VADResult {
+        let probability = self.speech_probability(samples).unwrap_or_else(|e| {
+            tracing::warn!("{}", e);
+            0.0
+        });
+        self.hysteresis.detect(probability, is_speaking)
+    }
+}
+
+/// Default model location, alongside the Whisper models.
+pub fn default_model_path() -> Result<PathBuf> {
+    find_model_path(SILERO_MODEL_URL)
+}
+
+/// Remove and return the whole windows at the start of `pending`, leaving
+/// the rest for the next chunk.
+fn take_windows(pending: &mut Vec<f32>) -> Vec<f32> {
+    let whole = pending.len() / WINDOW_SAMPLES * WINDOW_SAMPLES;
+    pending.drain(..whole).collect()
+}
+
+/// Highest probability among the last `windows` entries, i.e. the windows
+/// just scored.
+fn latest_probability(probabilities: &[f32], windows: usize) -> f32 {
+    let start = probabilities.len().saturating_sub(windows);
+    probabilities[start..].iter().copied().fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_windows_keeps_the_remainder() {
+        let mut pending = vec![0.0; WINDOW_SAMPLES - 1];
+        assert!(take_windows(&mut pending).is_empty());
+        assert_eq!(pending.len(), WINDOW_SAMPLES - 1);
+
+        pending.extend(vec![0.0; WINDOW_SAMPLES + 10]);
+        assert_eq!(take_windows(&mut pending).len(), WINDOW_SAMPLES * 2);
+        assert_eq!(pending.len(), 9);
+    }
+
+    #[test]
+    fn test_latest_probability_uses_newest_windows() {
+        let probabilities = [0.9, 0.1, 0.3, 0.2];
+        assert_eq!(latest_probability(&probabilities, 2), 0.3);
+        assert_eq!(latest_probability(&probabilities, 10), 0.9);
+        assert_eq!(latest_probability(&[], 1), 0.0);
+    }
+
+    #[test]
+    fn test_missing_model_is_reported() {
+        let result = SileroVad::new(Path::new("/nonexistent/ggml-silero.bin"), 16000, 1, 0.5);
+        let message = result.err().unwrap().to_string();
+        assert!(message.contains("not found"));
+        assert!(message.contains(SILERO_MODEL_URL));
+    }
+}
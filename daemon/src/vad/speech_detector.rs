@@ -1,7 +1,22 @@
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::time::Instant;
 use tracing::{debug, info, warn};
 
-use super::detector::VoiceActivityDetector;
+use super::backend::{self, Vad};
+use super::detector::EnergyVad;
+use super::endpoint::Endpointer;
+use crate::audio::rebatch::FrameRebatcher;
+use crate::config::VadConfig;
+
+/// Sample rate assumed until `with_sample_rate` says otherwise.
+const DEFAULT_SAMPLE_RATE: u32 = 16000;
+
+/// Window used to measure levels when trimming segment edges (10ms at 16kHz).
+const TRIM_WINDOW_SAMPLES: usize = 160;
+
+/// Fraction of an over-long segment, counted from its end, searched for a
+/// quiet point to split at.
+const SPLIT_SEARCH_FRACTION: usize = 4;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SpeechState {
@@ -12,12 +27,27 @@ pub enum SpeechState {
 
 pub struct SpeechDetector {
     state: SpeechState,
-    vad: VoiceActivityDetector,
+    vad: Box<dyn Vad>,
     speech_start_time: Option<Instant>,
     silence_start_time: Option<Instant>,
     speech_buffer: Vec<f32>,
     silence_duration_ms: u32,
     gain: f32,
+    trim_margin_samples: Option<usize>,
+    trim_threshold: f32,
+    pre_roll: Vec<f32>,
+    pre_roll_samples: usize,
+    onset: Vec<f32>,
+    speech_start_frames: u32,
+    speech_stop_frames: u32,
+    speech_run: u32,
+    silence_run: u32,
+    max_segment_samples: Option<usize>,
+    sample_rate: u32,
+    silence_samples: usize,
+    framer: Option<FrameRebatcher>,
+    pending_segments: VecDeque<Vec<f32>>,
+    endpointer: Option<Endpointer>,
 }
 
 impl SpeechDetector {
@@ -27,16 +57,31 @@ impl SpeechDetector {
         silence_duration_ms: u32,
         gain: f32,
     ) -> anyhow::Result<Self> {
-        let vad = VoiceActivityDetector::new(threshold_start, threshold_stop)?;
-        tracing::info!(
-            "SpeechDetector initialized: threshold_start={:.4}, threshold_stop={:.4}, silence_duration_ms={}, gain={:.2}",
-            threshold_start,
+        let vad = EnergyVad::new(threshold_start, threshold_stop)?;
+        Ok(Self::with_vad(
+            Box::new(vad),
             threshold_stop,
             silence_duration_ms,
+            gain,
+        ))
+    }
+
+    /// Segment speech using an arbitrary detector. `trim_threshold` is the
+    /// RMS level below which segment edges count as silence when trimming.
+    pub fn with_vad(
+        vad: Box<dyn Vad>,
+        trim_threshold: f32,
+        silence_duration_ms: u32,
+        gain: f32,
+    ) -> Self {
+        tracing::info!(
+            "SpeechDetector initialized: vad={}, silence_duration_ms={}, gain={:.2}",
+            vad.name(),
+            silence_duration_ms,
             gain
         );
 
-        Ok(Self {
+        Self {
             state: SpeechState::Idle,
             vad,
             speech_start_time: None,
@@ -44,27 +89,195 @@ impl SpeechDetector {
             speech_buffer: Vec::new(),
             silence_duration_ms,
             gain,
-        })
+            trim_margin_samples: None,
+            trim_threshold,
+            pre_roll: Vec::new(),
+            pre_roll_samples: 0,
+            onset: Vec::new(),
+            speech_start_frames: 1,
+            speech_stop_frames: 1,
+            speech_run: 0,
+            silence_run: 0,
+            max_segment_samples: None,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            silence_samples: 0,
+            framer: None,
+            pending_segments: VecDeque::new(),
+            endpointer: None,
+        }
+    }
+
+    /// Build a detector from the `[vad]` section using the detector selected
+    /// by `vad.backend`, enabling edge trimming when `vad.trim_silence` is set.
+    pub fn from_config(config: &VadConfig, gain: f32, sample_rate: u32) -> anyhow::Result<Self> {
+        let vad = backend::create_vad(config, sample_rate)?;
+        // Trimming always works on signal level, whatever the detector
+        let detector = Self::with_vad(
+            vad,
+            config.effective_thresholds().1,
+            config.min_silence_duration_ms,
+            gain,
+        )
+        .with_sample_rate(sample_rate);
+
+        let detector = if config.frame_ms > 0 {
+            let frame_samples = (sample_rate as u64 * config.frame_ms as u64 / 1000) as usize;
+            detector.with_frame_size(frame_samples)
+        } else {
+            detector
+        };
+
+        let padding_samples =
+            (sample_rate as u64 * config.pre_speech_padding_ms as u64 / 1000) as usize;
+        let detector = detector
+            .with_pre_speech_padding(padding_samples)
+            .with_hangover(config.speech_start_frames, config.speech_stop_frames);
+
+        let detector = if config.smart_endpointing {
+            detector.with_endpointer(Endpointer::new(
+                config.min_endpoint_ms,
+                config.max_endpoint_ms,
+            ))
+        } else {
+            detector
+        };
+
+        let detector = if config.max_segment_ms > 0 {
+            let max_samples = (sample_rate as u64 * config.max_segment_ms as u64 / 1000) as usize;
+            detector.with_max_segment(max_samples)
+        } else {
+            detector
+        };
+
+        if config.trim_silence {
+            let margin_samples = (sample_rate as u64 * config.trim_margin_ms as u64 / 1000) as usize;
+            Ok(detector.with_trim_margin(margin_samples))
+        } else {
+            Ok(detector)
+        }
     }
 
+    /// Sample rate of the incoming audio, used to turn millisecond settings
+    /// into sample counts.
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate.max(1);
+        self
+    }
+
+    /// Analyze fixed frames of `frame_samples` assembled from the incoming
+    /// chunks instead of the chunks themselves, so hangover counts and
+    /// detector behaviour do not depend on the capture callback size.
+    pub fn with_frame_size(mut self, frame_samples: usize) -> Self {
+        self.framer = Some(FrameRebatcher::new(frame_samples));
+        self
+    }
+
+    /// Keep the last `padding_samples` of audio heard before the speech
+    /// trigger and start each segment with it, so quiet word-initial
+    /// consonants are not cut off.
+    pub fn with_pre_speech_padding(mut self, padding_samples: usize) -> Self {
+        self.pre_roll_samples = padding_samples;
+        self.pre_roll = Vec::with_capacity(padding_samples);
+        self
+    }
+
+    /// Require `start_frames` consecutive speech chunks before speech starts
+    /// (or resumes after a pause) and `stop_frames` consecutive silent chunks
+    /// before the end-of-speech timer starts. Values below 1 are treated as 1.
+    pub fn with_hangover(mut self, start_frames: u32, stop_frames: u32) -> Self {
+        self.speech_start_frames = start_frames.max(1);
+        self.speech_stop_frames = stop_frames.max(1);
+        self
+    }
+
+    /// Adapt the end-of-speech silence timeout to how sharply the audio
+    /// decays after the last word.
+    pub fn with_endpointer(mut self, endpointer: Endpointer) -> Self {
+        self.endpointer = Some(endpointer);
+        self
+    }
+
+    /// Emit an intermediate segment whenever continuous speech reaches
+    /// `max_samples`, split at the quietest point near its end, so long
+    /// monologues are transcribed progressively.
+    pub fn with_max_segment(mut self, max_samples: usize) -> Self {
+        let min_samples = TRIM_WINDOW_SAMPLES * SPLIT_SEARCH_FRACTION;
+        self.max_segment_samples = Some(max_samples.max(min_samples));
+        self
+    }
+
+    /// Trim sub-threshold audio from both ends of each completed segment,
+    /// keeping `margin_samples` of context around the detected speech.
+    pub fn with_trim_margin(mut self, margin_samples: usize) -> Self {
+        self.trim_margin_samples = Some(margin_samples);
+        self
+    }
+
+    /// Hand the ambient level measured at startup to the detector.
+    pub fn calibrate(&mut self, ambient_level: f32) {
+        self.vad.calibrate(ambient_level);
+    }
+
+    /// Feed captured audio; returns a completed speech segment when one ends.
     pub fn process_audio(&mut self, samples: &[f32]) -> Option<Vec<f32>> {
-        let audio_level = self.vad.calculate_audio_level(samples);
+        let Some(mut framer) = self.framer.take() else {
+            return self.process_frame(samples);
+        };
+
+        let mut frames = Vec::new();
+        framer.push(samples, |frame| frames.push(frame));
+        self.framer = Some(framer);
+        for frame in frames {
+            if let Some(segment) = self.process_frame(&frame) {
+                self.pending_segments.push_back(segment);
+            }
+        }
+        // A huge chunk can finish more than one segment; hand them out in order
+        self.pending_segments.pop_front()
+    }
+
+    fn process_frame(&mut self, samples: &[f32]) -> Option<Vec<f32>> {
         let is_speaking = self.state == SpeechState::Speaking;
-        let vad_result = self.vad.detect(audio_level, is_speaking);
+        let vad_result = self.vad.detect(samples, is_speaking);
+        if vad_result.is_speech {
+            self.speech_run += 1;
+            self.silence_run = 0;
+            if let Some(endpointer) = self.endpointer.as_mut() {
+                endpointer.observe_speech(samples);
+            }
+        } else {
+            self.silence_run += 1;
+            self.speech_run = 0;
+        }
 
         match self.state {
             SpeechState::Idle => {
-                if vad_result.is_speech {
+                if self.speech_run >= self.speech_start_frames {
                     self.transition_to_speaking();
+                    self.speech_buffer.append(&mut self.pre_roll);
+                    self.speech_buffer.append(&mut self.onset);
                     self.speech_buffer.extend_from_slice(samples);
                     info!("State transition: Idle → Speaking");
                     debug!("Speech detected, buffer size: {}", self.speech_buffer.len());
+                } else if vad_result.is_speech {
+                    // Hold on to the onset until enough speech chunks confirm it
+                    self.onset.extend_from_slice(samples);
+                } else if self.pre_roll_samples > 0 {
+                    // An unconfirmed onset is just more pre-speech audio
+                    self.pre_roll.append(&mut self.onset);
+                    self.pre_roll.extend_from_slice(samples);
+                    if self.pre_roll.len() > self.pre_roll_samples {
+                        let excess = self.pre_roll.len() - self.pre_roll_samples;
+                        self.pre_roll.drain(..excess);
+                    }
+                } else {
+                    self.onset.clear();
                 }
             }
             SpeechState::Speaking => {
                 self.speech_buffer.extend_from_slice(samples);
 
-                if !vad_result.is_speech {
+                if self.silence_run >= self.speech_stop_frames {
                     self.transition_to_silence_detected();
                     warn!("State transition: Speaking → SilenceDetected");
                     debug!(
@@ -72,11 +285,19 @@ impl SpeechDetector {
                         self.silence_duration_ms
                     );
                 }
+
+                if let Some(segment) = self.take_intermediate_segment() {
+                    return Some(segment);
+                }
             }
             SpeechState::SilenceDetected => {
                 self.speech_buffer.extend_from_slice(samples);
+                self.silence_samples += samples.len();
+                if let Some(endpointer) = self.endpointer.as_mut() {
+                    endpointer.observe_silence(samples);
+                }
 
-                if vad_result.is_speech {
+                if self.speech_run >= self.speech_start_frames {
                     self.transition_to_speaking();
                     info!("State transition: SilenceDetected → Speaking (false alarm)");
                     debug!(
@@ -84,8 +305,17 @@ impl SpeechDetector {
                         self.speech_buffer.len()
                     );
                 } else if self.silence_duration_exceeded() {
-                    let speech = std::mem::take(&mut self.speech_buffer);
+                    let mut speech = std::mem::take(&mut self.speech_buffer);
                     self.reset();
+                    if let Some(margin) = self.trim_margin_samples {
+                        let original_len = speech.len();
+                        speech = self.trim_edges(&speech, margin).to_vec();
+                        debug!(
+                            "Trimmed segment from {} to {} samples",
+                            original_len,
+                            speech.len()
+                        );
+                    }
                     let duration_ms = self.calculate_duration_ms(&speech);
                     info!("State transition: SilenceDetected → Idle");
                     info!(
@@ -104,6 +334,26 @@ impl SpeechDetector {
         None
     }
 
+    /// Split off the head of the speech buffer once it reaches the maximum
+    /// segment length, keeping the rest as the start of the next segment.
+    fn take_intermediate_segment(&mut self) -> Option<Vec<f32>> {
+        let max_samples = self.max_segment_samples?;
+        if self.speech_buffer.len() < max_samples {
+            return None;
+        }
+
+        let len = self.speech_buffer.len();
+        let search_from = len - len / SPLIT_SEARCH_FRACTION;
+        let split = quietest_split(&self.speech_buffer, TRIM_WINDOW_SAMPLES, search_from);
+        let rest = self.speech_buffer.split_off(split);
+        let head = std::mem::replace(&mut self.speech_buffer, rest);
+        info!(
+            "Long utterance: emitting {} ms intermediate segment",
+            self.calculate_duration_ms(&head)
+        );
+        Some(head.iter().map(|&s| s * self.gain).collect())
+    }
+
     fn transition_to_speaking(&mut self) {
         self.state = SpeechState::Speaking;
         self.speech_start_time = Some(Instant::now());
@@ -113,31 +363,116 @@ impl SpeechDetector {
     fn transition_to_silence_detected(&mut self) {
         self.state = SpeechState::SilenceDetected;
         self.silence_start_time = Some(Instant::now());
+        self.silence_samples = 0;
+        if let Some(endpointer) = self.endpointer.as_mut() {
+            endpointer.reset_silence();
+        }
     }
 
+    /// Silence is measured in audio samples rather than wall-clock time, so
+    /// the timeout is exact however the audio arrives.
     fn silence_duration_exceeded(&self) -> bool {
-        self.silence_start_time
-            .map(|t| t.elapsed() >= Duration::from_millis(self.silence_duration_ms as u64))
-            .unwrap_or(false)
+        let timeout_ms = match &self.endpointer {
+            Some(endpointer) => endpointer.timeout_ms(self.silence_duration_ms),
+            None => self.silence_duration_ms,
+        };
+        let required = self.sample_rate as u64 * timeout_ms as u64 / 1000;
+        self.silence_start_time.is_some() && self.silence_samples as u64 >= required
     }
 
     fn calculate_duration_ms(&self, samples: &[f32]) -> u32 {
-        let sample_count = samples.len();
-        let sample_rate = 16000u32;
-        let duration_ms = (sample_count as u32 * 1000) / sample_rate;
-        duration_ms
+        (samples.len() as u64 * 1000 / self.sample_rate as u64) as u32
+    }
+
+    fn trim_edges<'a>(&self, samples: &'a [f32], margin: usize) -> &'a [f32] {
+        // Never trim into the pre-speech padding that was asked for
+        let lead_margin = margin.max(self.pre_roll_samples);
+        trim_silence(samples, self.trim_threshold, TRIM_WINDOW_SAMPLES, lead_margin, margin)
+    }
+
+    /// Audio of the segment still being spoken, before gain and trimming,
+    /// or nothing between segments.
+    pub fn pending_speech(&self) -> &[f32] {
+        match self.state {
+            SpeechState::Idle => &[],
+            SpeechState::Speaking | SpeechState::SilenceDetected => &self.speech_buffer,
+        }
+    }
+
+    /// Drop any speech heard so far, e.g. when capture stops mid-utterance.
+    pub fn discard(&mut self) {
+        self.speech_buffer.clear();
+        self.pre_roll.clear();
+        self.onset.clear();
+        self.pending_segments.clear();
+        self.speech_run = 0;
+        self.silence_run = 0;
+        self.reset();
     }
 
     fn reset(&mut self) {
         self.state = SpeechState::Idle;
         self.speech_start_time = None;
         self.silence_start_time = None;
+        self.silence_samples = 0;
+        if let Some(endpointer) = self.endpointer.as_mut() {
+            endpointer.reset();
+        }
+    }
+}
+
+/// Return the part of `samples` between the first and last `window`-sized
+/// block whose RMS reaches `threshold`, widened by `lead_margin` samples
+/// before and `tail_margin` samples after. Audio with no block above the
+/// threshold is returned unchanged.
+pub fn trim_silence(
+    samples: &[f32],
+    threshold: f32,
+    window: usize,
+    lead_margin: usize,
+    tail_margin: usize,
+) -> &[f32] {
+    let window = window.max(1);
+    let is_loud = |block: &[f32]| {
+        let sum_squares: f32 = block.iter().map(|s| s * s).sum();
+        (sum_squares / block.len() as f32).sqrt() >= threshold
+    };
+
+    let first = samples.chunks(window).position(is_loud);
+    let last = samples.chunks(window).rposition(is_loud);
+
+    match (first, last) {
+        (Some(first), Some(last)) => {
+            let start = (first * window).saturating_sub(lead_margin);
+            let end = ((last + 1) * window + tail_margin).min(samples.len());
+            &samples[start..end]
+        }
+        _ => samples,
+    }
+}
+
+/// Index of the middle of the quietest `window`-sized block starting at or
+/// after `search_from`, i.e. the best place to cut continuous speech.
+fn quietest_split(samples: &[f32], window: usize, search_from: usize) -> usize {
+    let window = window.max(1);
+    let mut best = (f32::MAX, samples.len());
+    let mut start = search_from.min(samples.len());
+    while start + window <= samples.len() {
+        let block = &samples[start..start + window];
+        let energy: f32 = block.iter().map(|s| s * s).sum();
+        if energy < best.0 {
+            best = (energy, start + window / 2);
+        }
+        start += window;
     }
+    best.1
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vad::features::SpectralFeatures;
+    use crate::vad::noise_floor::NoiseFloor;
 
     #[test]
     fn test_speech_detector_new() {
@@ -237,6 +572,251 @@ mod tests {
         assert_eq!(detector.state, SpeechState::Idle);
     }
 
+    #[test]
+    fn test_trim_silence_keeps_margin() {
+        let mut samples = vec![0.0f32; 1000];
+        samples.extend(vec![0.5f32; 500]);
+        samples.extend(vec![0.0f32; 1000]);
+
+        let trimmed = trim_silence(&samples, 0.1, 100, 50, 50);
+        assert_eq!(trimmed.len(), 600);
+        assert_eq!(trimmed[0], 0.0);
+        assert_eq!(trimmed[50], 0.5);
+    }
+
+    #[test]
+    fn test_trim_silence_without_speech_is_unchanged() {
+        let samples = vec![0.001f32; 480];
+        assert_eq!(trim_silence(&samples, 0.1, 160, 0, 0).len(), 480);
+        assert!(trim_silence(&[], 0.1, 160, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_trim_silence_margin_is_clamped_to_bounds() {
+        let samples = vec![0.5f32; 300];
+        assert_eq!(trim_silence(&samples, 0.1, 100, 1000, 1000).len(), 300);
+    }
+
+    #[test]
+    fn test_trim_silence_separate_margins() {
+        let mut samples = vec![0.0f32; 1000];
+        samples.extend(vec![0.5f32; 100]);
+        samples.extend(vec![0.0f32; 1000]);
+
+        let trimmed = trim_silence(&samples, 0.1, 100, 300, 0);
+        assert_eq!(trimmed.len(), 400);
+    }
+
+    #[test]
+    fn test_pre_speech_padding_is_prepended() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_pre_speech_padding(4);
+
+        detector.process_audio(&[0.001, 0.002, 0.003]);
+        detector.process_audio(&[0.004, 0.005]);
+        detector.process_audio(&[0.5, 0.5]);
+
+        assert_eq!(detector.state, SpeechState::Speaking);
+        assert_eq!(
+            detector.speech_buffer,
+            vec![0.002, 0.003, 0.004, 0.005, 0.5, 0.5]
+        );
+        assert!(detector.pre_roll.is_empty());
+    }
+
+    #[test]
+    fn test_completed_segment_is_trimmed() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 0, 1.0)
+            .unwrap()
+            .with_trim_margin(0);
+
+        detector.process_audio(&vec![0.05; 320]);
+        detector.process_audio(&vec![0.0; 1600]);
+        let speech = detector.process_audio(&vec![0.0; 1600]).unwrap();
+
+        assert_eq!(speech.len(), 320);
+    }
+
+    #[test]
+    fn test_hangover_ignores_single_chunk_click() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_hangover(2, 2);
+
+        detector.process_audio(&[0.5, 0.5]);
+        assert_eq!(detector.state, SpeechState::Idle);
+        detector.process_audio(&[0.001, 0.001]);
+        assert_eq!(detector.state, SpeechState::Idle);
+        assert!(detector.onset.is_empty());
+    }
+
+    #[test]
+    fn test_hangover_keeps_onset_audio() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_hangover(2, 2);
+
+        detector.process_audio(&[0.3, 0.3]);
+        detector.process_audio(&[0.4, 0.4]);
+        assert_eq!(detector.state, SpeechState::Speaking);
+        assert_eq!(detector.speech_buffer, vec![0.3, 0.3, 0.4, 0.4]);
+    }
+
+    #[test]
+    fn test_hangover_requires_consecutive_silence() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_hangover(1, 2);
+
+        detector.process_audio(&[0.3, 0.3]);
+        detector.process_audio(&[0.001, 0.001]);
+        assert_eq!(detector.state, SpeechState::Speaking);
+        detector.process_audio(&[0.3, 0.3]);
+        detector.process_audio(&[0.001, 0.001]);
+        assert_eq!(detector.state, SpeechState::Speaking);
+        detector.process_audio(&[0.001, 0.001]);
+        assert_eq!(detector.state, SpeechState::SilenceDetected);
+    }
+
+    #[test]
+    fn test_quietest_split_finds_energy_minimum() {
+        let mut samples = vec![0.5f32; 400];
+        samples.extend(vec![0.01f32; 100]);
+        samples.extend(vec![0.5f32; 300]);
+
+        assert_eq!(quietest_split(&samples, 100, 200), 450);
+        // Nothing to search returns the end
+        assert_eq!(quietest_split(&samples, 100, 800), 800);
+    }
+
+    #[test]
+    fn test_long_speech_emits_intermediate_segment() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_max_segment(2000);
+
+        // A soft stretch that is still speech, where the split should land
+        let mut speech = vec![0.5f32; 1900];
+        speech[1650..1810].fill(0.05);
+        assert!(detector.process_audio(&speech).is_none());
+
+        let segment = detector.process_audio(&vec![0.5f32; 300]).unwrap();
+        assert_eq!(detector.state, SpeechState::Speaking);
+        assert_eq!(segment.len(), 1730);
+        assert_eq!(detector.speech_buffer.len(), 470);
+    }
+
+    #[test]
+    fn test_noise_floor_raises_start_threshold() {
+        let vad = EnergyVad::new(0.02, 0.01)
+            .unwrap()
+            .with_noise_floor(NoiseFloor::new(16000, 0.02, 0.01, 3.0, 2.0));
+        let mut detector = SpeechDetector::with_vad(Box::new(vad), 0.01, 1000, 1.0);
+
+        // Steady 0.03 hum would trigger the fixed threshold, but becomes the floor
+        detector.process_audio(&vec![0.03; 512]);
+        assert_eq!(detector.state, SpeechState::Idle);
+
+        detector.process_audio(&vec![0.5; 512]);
+        assert_eq!(detector.state, SpeechState::Speaking);
+    }
+
+    #[test]
+    fn test_calibration_seeds_noise_floor() {
+        let vad = EnergyVad::new(0.02, 0.01)
+            .unwrap()
+            .with_noise_floor(NoiseFloor::new(16000, 0.02, 0.01, 3.0, 2.0));
+        let mut detector = SpeechDetector::with_vad(Box::new(vad), 0.01, 1000, 1.0);
+        detector.calibrate(0.05);
+
+        // Well above the configured threshold but within the calibrated floor
+        detector.process_audio(&vec![0.1; 512]);
+        assert_eq!(detector.state, SpeechState::Idle);
+    }
+
+    #[test]
+    fn test_spectral_features_ignore_hum() {
+        let vad = EnergyVad::new(0.02, 0.01)
+            .unwrap()
+            .with_spectral_features(SpectralFeatures::new(0.05, 0.02));
+        let mut detector = SpeechDetector::with_vad(Box::new(vad), 0.01, 1000, 1.0);
+
+        // Loud 50Hz mains hum
+        let hum: Vec<f32> = (0..512)
+            .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 50.0 * i as f32 / 16000.0).sin())
+            .collect();
+        detector.process_audio(&hum);
+        assert_eq!(detector.state, SpeechState::Idle);
+    }
+
+    #[test]
+    fn test_from_config_rejects_unknown_backend() {
+        let config = VadConfig {
+            backend: "webrtc".to_string(),
+            ..crate::config::Config::default().vad
+        };
+        let result = SpeechDetector::from_config(&config, 1.0, 16000);
+        assert!(result.err().unwrap().to_string().contains("Unknown VAD backend"));
+    }
+
+    #[test]
+    fn test_from_config_silero_requires_model() {
+        let config = VadConfig {
+            backend: "silero".to_string(),
+            silero_model_path: Some("/nonexistent/ggml-silero.bin".to_string()),
+            ..crate::config::Config::default().vad
+        };
+        let result = SpeechDetector::from_config(&config, 1.0, 16000);
+        assert!(result.err().unwrap().to_string().contains("Silero VAD model not found"));
+    }
+
+    #[test]
+    fn test_silence_timeout_counts_samples() {
+        // 100ms of silence at 16kHz is 1600 samples, however it is chunked
+        let mut detector = SpeechDetector::new(0.02, 0.01, 100, 1.0).unwrap();
+        detector.process_audio(&[0.5; 160]);
+        detector.process_audio(&[0.0; 160]);
+        assert_eq!(detector.state, SpeechState::SilenceDetected);
+
+        for _ in 0..9 {
+            assert!(detector.process_audio(&[0.0; 160]).is_none());
+        }
+        assert!(detector.process_audio(&[0.0; 160]).is_some());
+    }
+
+    #[test]
+    fn test_smart_endpointing_ends_clean_stop_early() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_endpointer(Endpointer::new(100, 1500));
+        detector.process_audio(&[0.3; 1600]);
+        detector.process_audio(&[0.0; 160]);
+        assert_eq!(detector.state, SpeechState::SilenceDetected);
+
+        // Digital silence after loud speech: 100ms instead of 1000ms
+        let mut ended = None;
+        for _ in 0..10 {
+            ended = ended.or(detector.process_audio(&[0.0; 160]));
+        }
+        assert!(ended.is_some());
+    }
+
+    #[test]
+    fn test_frames_are_independent_of_chunk_size() {
+        let mut detector = SpeechDetector::new(0.02, 0.01, 1000, 1.0)
+            .unwrap()
+            .with_frame_size(480)
+            .with_hangover(2, 2);
+
+        // One 960-sample callback is two 30ms frames, enough to start speech
+        detector.process_audio(&[0.5; 900]);
+        assert_eq!(detector.state, SpeechState::Idle);
+        detector.process_audio(&[0.5; 60]);
+        assert_eq!(detector.state, SpeechState::Speaking);
+        assert_eq!(detector.speech_buffer.len(), 960);
+    }
+
     #[test]
     fn test_duration_calculation() {
         let detector = SpeechDetector::new(0.02, 0.01, 100, 1.0).unwrap();
@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::config::WakeConfig;
+use crate::transcription::backend::SharedBackend;
+
+/// Language the wake phrase is spotted in; the default models are English-only.
+pub const WAKE_LANGUAGE: &str = "en";
+
+/// Fuzzy matcher for a short spoken phrase in the wake spotter's
+/// transcription.
+///
+/// Speech models rarely spell an invented word like "ndict" the same way
+/// twice ("n dict", "endict", "and dict"), so words are compared with spaces and
+/// punctuation removed and a few character edits are tolerated.
+#[derive(Debug, Clone)]
+pub struct PhraseMatcher {
+    squashed: String,
+    word_count: usize,
+    max_edits: usize,
+}
+
+impl PhraseMatcher {
+    pub fn new(phrase: &str) -> Self {
+        let words: Vec<String> = phrase.split_whitespace().map(normalize_word).collect();
+        let squashed: String = words.concat();
+        Self {
+            max_edits: squashed.chars().count() / 4,
+            word_count: words.iter().filter(|w| !w.is_empty()).count().max(1),
+            squashed,
+        }
+    }
+
+    /// Index of the first whitespace-separated token of `text` where the
+    /// phrase starts, if it occurs.
+    pub fn find(&self, text: &str) -> Option<usize> {
+        if self.squashed.is_empty() {
+            return None;
+        }
+
+        let words: Vec<String> = text.split_whitespace().map(normalize_word).collect();
+        // Whisper may split the phrase into more words or merge it into fewer
+        let max_window = self.word_count + 2;
+        (0..words.len()).find(|&start| {
+            (1..=max_window)
+                .take_while(|len| start + len <= words.len())
+                .any(|len| {
+                    let candidate = words[start..start + len].concat();
+                    !candidate.is_empty()
+                        && edit_distance(&candidate, &self.squashed) <= self.max_edits
+                })
+        })
+    }
+}
+
+/// Tracks whether dictation is on in wake-word mode.
+///
+/// While dictation is off, short speech segments only go to a keyword
+/// spotter listening for the wake phrase, see `wake.spotter`; nothing is
+/// typed.
+/// Once it is on, segments go through the normal pipeline until the stop
+/// phrase is heard.
+#[derive(Debug, Clone)]
+pub struct WakeWord {
+    wake: PhraseMatcher,
+    stop: PhraseMatcher,
+    max_phrase_samples: usize,
+    dictating: Arc<AtomicBool>,
+}
+
+impl WakeWord {
+    pub fn from_config(config: &WakeConfig, sample_rate: u32) -> Self {
+        tracing::info!(
+            "Wake-word mode: say '{}' to start and '{}' to stop dictation",
+            config.wake_phrase,
+            config.stop_phrase
+        );
+        Self {
+            wake: PhraseMatcher::new(&config.wake_phrase),
+            stop: PhraseMatcher::new(&config.stop_phrase),
+            max_phrase_samples: (sample_rate as u64 * config.max_phrase_ms as u64 / 1000) as usize,
+            dictating: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_dictating(&self) -> bool {
+        self.dictating.load(Ordering::Acquire)
+    }
+
+    /// Check a speech segment heard while dictation is off for the wake
+    /// phrase. Segments too long to be the phrase are skipped without
+    /// running the model.
+    pub async fn listen(&self, engine: &SharedBackend, audio: &[f32]) {
+        if audio.len() > self.max_phrase_samples {
+            tracing::debug!("Ignoring {} samples of speech while asleep", audio.len());
+            return;
+        }
+
+        let engine = engine.lock().await.clone();
+        let result = match engine {
+            Some(engine) => engine.transcribe(audio, WAKE_LANGUAGE).await.map(|t| t.text),
+            None => Err(anyhow::anyhow!("Wake-word engine not available")),
+        };
+        match result {
+            Ok(text) => {
+                tracing::debug!("Wake-word check heard: '{}'", text);
+                self.check_wake(&text);
+            }
+            Err(e) => tracing::warn!("Wake-word transcription failed: {}", e),
+        }
+    }
+
+    /// Turn dictation on if `text` contains the wake phrase.
+    pub fn check_wake(&self, text: &str) -> bool {
+        let woke = self.wake.find(text).is_some();
+        if woke {
+            self.dictating.store(true, Ordering::Release);
+            tracing::info!("Wake phrase heard, dictation on");
+        }
+        woke
+    }
+
+    /// Cut the stop phrase, and anything said after it, from dictated text,
+    /// turning dictation off when it is found.
+    pub fn filter_dictated(&self, text: &str) -> String {
+        match self.stop.find(text) {
+            Some(index) => {
+                self.dictating.store(false, Ordering::Release);
+                tracing::info!("Stop phrase heard, dictation off");
+                text.split_whitespace()
+                    .take(index)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            None => text.to_string(),
+        }
+    }
+}
+
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wake_word() -> WakeWord {
+        WakeWord::from_config(&WakeConfig::default(), 16000)
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("heyndict", "heyndict"), 0);
+        assert_eq!(edit_distance("heyendict", "heyndict"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_phrase_matcher_tolerates_spelling() {
+        let matcher = PhraseMatcher::new("hey ndict");
+        assert_eq!(matcher.find("Hey, ndict."), Some(0));
+        assert_eq!(matcher.find("Hey n dict!"), Some(0));
+        assert_eq!(matcher.find("okay hey endict"), Some(1));
+        assert_eq!(matcher.find("hello there"), None);
+        assert_eq!(matcher.find(""), None);
+    }
+
+    #[test]
+    fn test_wake_phrase_turns_dictation_on() {
+        let wake = wake_word();
+        assert!(!wake.is_dictating());
+        assert!(!wake.check_wake("what's for dinner"));
+        assert!(!wake.is_dictating());
+        assert!(wake.check_wake(" Hey Ndict."));
+        assert!(wake.is_dictating());
+    }
+
+    #[test]
+    fn test_stop_phrase_is_removed_and_turns_dictation_off() {
+        let wake = wake_word();
+        wake.check_wake("hey ndict");
+
+        assert_eq!(wake.filter_dictated("Send the report."), "Send the report.");
+        assert!(wake.is_dictating());
+
+        assert_eq!(
+            wake.filter_dictated("See you tomorrow. Stop dictation. Bye"),
+            "See you tomorrow."
+        );
+        assert!(!wake.is_dictating());
+    }
+}
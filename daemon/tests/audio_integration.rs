@@ -18,6 +18,7 @@ mod tests {
     use crate::common::print_info;
     use crate::common::print_success;
     use ndictd::audio::capture::AudioCapture;
+    use ndictd::audio::source::AudioSource;
     use std::time::Duration;
     use tokio::sync::broadcast;
 
@@ -73,7 +74,7 @@ mod tests {
         })
         .await; // Timeout error is expected here, we just ignore it to stop the loop
 
-        capture.stop().await.expect("Failed to stop capture");
+        capture.stop().expect("Failed to stop capture");
 
         if audio_detected {
             print_error(&format!(
@@ -132,7 +133,7 @@ mod tests {
         })
         .await;
 
-        capture.stop().await.expect("Failed to stop capture");
+        capture.stop().expect("Failed to stop capture");
 
         if !speech_detected {
             print_error("FAILURE: No significant audio detected during speech.");
@@ -181,7 +182,7 @@ mod tests {
         })
         .await;
 
-        capture.stop().await.expect("Failed to stop capture");
+        capture.stop().expect("Failed to stop capture");
 
         if chunk_count == 0 {
             print_error("FAILURE: No audio chunks received.");
@@ -17,7 +17,7 @@ mod tests {
         print_info("This test verifies virtual keyboard can type to active Wayland window.");
         print_info("Prerequisites:");
         print_info("  - Running Wayland session");
-        print_info("  - ndictd daemon has CAP_SYS_INPUT capability");
+        print_info("  - Compositor with the virtual keyboard protocol (Sway, Hyprland, niri)");
         print_info("  - An active text input window (terminal, editor, etc.)");
 
         if !confirm_action("Ready to test keyboard typing? (y/n)") {
@@ -48,7 +48,7 @@ mod tests {
                 print_info("Possible causes:");
                 print_info("  - No active Wayland session");
                 print_info("  - No focused text input window");
-                print_info("  - Missing CAP_SYS_INPUT capability");
+                print_info("  - Compositor without the virtual keyboard protocol");
                 panic!("Test failed: {}", e);
             }
         }
@@ -6,6 +6,7 @@ mod tests {
         confirm_action, print_error, print_header, print_info, print_success, wait_for_user,
     };
     use ndictd::audio::capture::AudioCapture;
+    use ndictd::audio::source::AudioSource;
     use ndictd::vad::detector::VoiceActivityDetector;
     use std::io::{self, Write};
     use std::sync::Mutex;
@@ -174,7 +175,7 @@ mod tests {
                 });
 
                 let (max_lvl, speech, total) = collect_task.await.unwrap();
-                capture.stop().await.unwrap();
+                capture.stop().unwrap();
 
                 // Analysis of this sample
                 print_info(&format!("   Peak Level: {:.5}", max_lvl));
@@ -267,7 +268,7 @@ mod tests {
             });
 
             let flips = analysis_task.await.unwrap();
-            capture.stop().await.unwrap();
+            capture.stop().unwrap();
 
             println!("\n------------------------------------------");
             println!("RESULTS: {} state changes in 5 seconds", flips);
@@ -372,7 +373,7 @@ mod tests {
             });
 
             let (speech_seen, measured) = task.await.unwrap();
-            capture.stop().await.unwrap();
+            capture.stop().unwrap();
 
             println!("\n------------------------------------------");
             if !speech_seen {
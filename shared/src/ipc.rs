@@ -9,11 +9,41 @@ pub enum Command {
     Resume,
     Status,
     SetLanguage(String),
+    /// Transcribe only the next utterance in this language, then return to
+    /// the `SetLanguage` one. Not available in streaming mode.
+    SetNextLanguage(String),
+    /// Whisper task: "transcribe", or "translate" to dictate any language as English.
+    SetTask(String),
+    /// Switch to a model from `whisper.models` ("default" for `model_url`).
+    SetModel(String),
     Toggle,
     MStart,
     MComplete,
     MCompleteRaw,
     MStop,
+    /// Keep the connection open and receive newline-delimited `Event`s.
+    Subscribe,
+    /// Write the current session's transcriptions as "text" or "markdown",
+    /// to `path` or a file under the data directory.
+    Export { format: String, path: Option<String> },
+    /// Type the text held back while paused with `output.buffer_while_paused`
+    /// and stay paused.
+    Flush,
+    /// Transcribe the last utterance again with `whisper.retry.model`; the
+    /// result arrives as `Event::Corrected`.
+    Retry,
+    /// List the model files the daemon has downloaded or verified.
+    ListModels,
+    /// Transcribe the WAV file at `path`, every WAV file in a directory or
+    /// the files matching a `*`/`?` pattern, as "text", "srt", "vtt" or
+    /// "json". Transcripts go next to their inputs with the format's
+    /// extension, or to `output`: a file for a single input, otherwise a
+    /// directory.
+    TranscribeFile {
+        path: String,
+        format: String,
+        output: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -21,6 +51,32 @@ pub enum Response {
     Ok,
     Error(String),
     Status(StatusInfo),
+    /// Path of the file an `Export` was written to.
+    Exported(String),
+    Models(Vec<InstalledModel>),
+    /// One entry per input of a `TranscribeFile`, in input order.
+    Transcribed(Vec<TranscribedFile>),
+}
+
+/// The outcome of transcribing one file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TranscribedFile {
+    pub input: String,
+    /// Where the transcript was written, unless transcription failed.
+    pub output: Option<String>,
+    /// Length of the audio in milliseconds.
+    pub audio_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A model file recorded in the daemon's model manifest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstalledModel {
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    /// Local time the checksum was last verified.
+    pub last_verified: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -28,6 +84,78 @@ pub struct StatusInfo {
     pub is_running: bool,
     pub is_active: bool,
     pub language: String,
+    /// Most recent input level, present while audio is being captured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_level: Option<AudioLevel>,
+    /// Language Whisper last detected when `language` is "auto".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<String>,
+}
+
+/// Input level measured over a short window of captured audio.
+/// Both values are linear amplitudes in the range 0.0..=1.0; see
+/// `rms_dbfs`/`peak_dbfs` for the same levels in dBFS.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct AudioLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+impl AudioLevel {
+    pub fn rms_dbfs(&self) -> f32 {
+        amplitude_to_dbfs(self.rms)
+    }
+
+    pub fn peak_dbfs(&self) -> f32 {
+        amplitude_to_dbfs(self.peak)
+    }
+}
+
+/// Quietest level reported in dBFS; digital silence is clamped to this.
+pub const MIN_DBFS: f32 = -120.0;
+
+/// Convert a linear amplitude (1.0 = full scale) to dBFS.
+pub fn amplitude_to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * amplitude.log10()).max(MIN_DBFS)
+}
+
+/// Convert dBFS back to a linear amplitude, e.g. -40 dBFS is 0.01.
+pub fn dbfs_to_amplitude(dbfs: f32) -> f32 {
+    10f32.powf(dbfs / 20.0)
+}
+
+/// Notifications pushed to subscribed clients, one JSON object per line.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Event {
+    AudioLevel(AudioLevel),
+    /// Something the user should act on, e.g. a clipping input.
+    Warning(String),
+    /// The audio stream went silent and was restarted by the watchdog.
+    AudioRestarted,
+    /// The named output kept failing to write and was set up again.
+    OutputRestarted(String),
+    /// Whisper detected a different spoken language while `language` is "auto".
+    LanguageDetected(String),
+    /// Text produced from an utterance, with Whisper's confidence in it
+    /// (mean token probability) when known.
+    Transcribed { text: String, confidence: Option<f32> },
+    /// An utterance scored below `whisper.min_confidence`; `dropped` tells
+    /// whether it was discarded or typed anyway.
+    LowConfidence { text: String, confidence: f32, dropped: bool },
+    /// Text of the utterance still being spoken, from streaming backends
+    /// that report partial results. Not final: it may still change, and the
+    /// `Transcribed` event that follows replaces it.
+    Partial(String),
+    /// The `whisper.retry` model heard an utterance differently: `original`
+    /// is the text already delivered, `text` the second pass.
+    Corrected {
+        original: String,
+        text: String,
+        confidence: Option<f32>,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -43,6 +171,9 @@ pub enum IpcError {
 
     #[error("Connection timeout")]
     Timeout,
+
+    #[error("Daemon rejected request: {0}")]
+    Rejected(String),
 }
 
 #[cfg(test)]
@@ -50,6 +181,22 @@ mod tests {
     use super::*;
     use serde_json;
 
+    #[test]
+    fn test_dbfs_conversions() {
+        assert_eq!(amplitude_to_dbfs(1.0), 0.0);
+        assert!((amplitude_to_dbfs(0.01) + 40.0).abs() < 1e-4);
+        assert_eq!(amplitude_to_dbfs(0.0), MIN_DBFS);
+        assert!((dbfs_to_amplitude(-40.0) - 0.01).abs() < 1e-6);
+        assert!((dbfs_to_amplitude(amplitude_to_dbfs(0.25)) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_audio_level_dbfs() {
+        let level = AudioLevel { rms: 0.1, peak: 1.0 };
+        assert!((level.rms_dbfs() + 20.0).abs() < 1e-4);
+        assert_eq!(level.peak_dbfs(), 0.0);
+    }
+
     #[test]
     fn test_command_serialization_start() {
         let cmd = Command::Start;
@@ -64,6 +211,13 @@ mod tests {
         assert_eq!(json, r#"{"SetLanguage":"en"}"#);
     }
 
+    #[test]
+    fn test_command_serialization_set_task() {
+        let cmd = Command::SetTask("translate".to_string());
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"SetTask":"translate"}"#);
+    }
+
     #[test]
     fn test_command_round_trip_all_variants() {
         let commands = vec![
@@ -73,11 +227,27 @@ mod tests {
             Command::Resume,
             Command::Status,
             Command::SetLanguage("test".to_string()),
+            Command::SetNextLanguage("de".to_string()),
+            Command::SetTask("translate".to_string()),
+            Command::SetModel("tiny".to_string()),
             Command::Toggle,
             Command::MStart,
             Command::MComplete,
             Command::MCompleteRaw,
             Command::MStop,
+            Command::Subscribe,
+            Command::Export {
+                format: "markdown".to_string(),
+                path: Some("/tmp/session.md".to_string()),
+            },
+            Command::Flush,
+            Command::Retry,
+            Command::ListModels,
+            Command::TranscribeFile {
+                path: "/tmp/talk.wav".to_string(),
+                format: "srt".to_string(),
+                output: None,
+            },
         ];
         for cmd in commands {
             let json = serde_json::to_string(&cmd).unwrap();
@@ -106,6 +276,8 @@ mod tests {
             is_running: true,
             is_active: false,
             language: "en".to_string(),
+            audio_level: None,
+            detected_language: None,
         };
         let resp = Response::Status(info.clone());
         let json = serde_json::to_string(&resp).unwrap();
@@ -124,7 +296,30 @@ mod tests {
                 is_running: true,
                 is_active: false,
                 language: "test".to_string(),
+                audio_level: None,
+                detected_language: None,
             }),
+            Response::Exported("/tmp/session.md".to_string()),
+            Response::Models(vec![InstalledModel {
+                path: "/home/user/.local/share/ndict/ggml-base.bin".to_string(),
+                size_bytes: 147_951_465,
+                sha256: "60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe".to_string(),
+                last_verified: "2026-10-15 09:30:00".to_string(),
+            }]),
+            Response::Transcribed(vec![
+                TranscribedFile {
+                    input: "/talks/a.wav".to_string(),
+                    output: Some("/talks/a.srt".to_string()),
+                    audio_ms: 61_000,
+                    error: None,
+                },
+                TranscribedFile {
+                    input: "/talks/b.wav".to_string(),
+                    output: None,
+                    audio_ms: 0,
+                    error: Some("Failed to open WAV file".to_string()),
+                },
+            ]),
         ];
         for resp in responses {
             let json = serde_json::to_string(&resp).unwrap();
@@ -139,6 +334,8 @@ mod tests {
             is_running: true,
             is_active: true,
             language: "en".to_string(),
+            audio_level: None,
+            detected_language: None,
         };
         let json = serde_json::to_string(&info).unwrap();
         assert!(json.contains("is_running"));
@@ -159,6 +356,8 @@ mod tests {
                 is_running: running,
                 is_active: active,
                 language: lang.to_string(),
+                audio_level: None,
+                detected_language: None,
             };
             let json = serde_json::to_string(&info).unwrap();
             let deserialized: StatusInfo = serde_json::from_str(&json).unwrap();
@@ -187,6 +386,131 @@ mod tests {
         assert!(err.to_string().contains("Connection refused"));
     }
 
+    #[test]
+    fn test_status_info_with_audio_level() {
+        let info = StatusInfo {
+            is_running: true,
+            is_active: true,
+            language: "en".to_string(),
+            audio_level: Some(AudioLevel { rms: 0.25, peak: 0.5 }),
+            detected_language: None,
+        };
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""audio_level":{"rms":0.25,"peak":0.5}"#));
+        let deserialized: StatusInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(info, deserialized);
+    }
+
+    #[test]
+    fn test_status_info_without_audio_level_field() {
+        let json = r#"{"is_running":true,"is_active":false,"language":"en"}"#;
+        let info: StatusInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.audio_level, None);
+    }
+
+    #[test]
+    fn test_event_serialization_audio_level() {
+        let event = Event::AudioLevel(AudioLevel { rms: 0.125, peak: 1.0 });
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"AudioLevel":{"rms":0.125,"peak":1.0}}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(event, deserialized);
+    }
+
+    #[test]
+    fn test_event_serialization_warning() {
+        let event = Event::Warning("Input is clipping".to_string());
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"Warning":"Input is clipping"}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(event, deserialized);
+    }
+
+    #[test]
+    fn test_event_serialization_audio_restarted() {
+        let json = serde_json::to_string(&Event::AudioRestarted).unwrap();
+        assert_eq!(json, r#""AudioRestarted""#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, Event::AudioRestarted);
+    }
+
+    #[test]
+    fn test_event_serialization_output_restarted() {
+        let event = Event::OutputRestarted("keyboard".to_string());
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"OutputRestarted":"keyboard"}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(event, deserialized);
+    }
+
+    #[test]
+    fn test_event_language_detected_serialization() {
+        let event = Event::LanguageDetected("de".to_string());
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"LanguageDetected":"de"}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, event);
+    }
+
+    #[test]
+    fn test_event_transcribed_serialization() {
+        let event = Event::Transcribed {
+            text: "hello".to_string(),
+            confidence: Some(0.5),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"Transcribed":{"text":"hello","confidence":0.5}}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, event);
+    }
+
+    #[test]
+    fn test_event_partial_serialization() {
+        let event = Event::Partial("hello wor".to_string());
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"Partial":"hello wor"}"#);
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, event);
+    }
+
+    #[test]
+    fn test_event_corrected_serialization() {
+        let event = Event::Corrected {
+            original: "wreck a nice beach".to_string(),
+            text: "recognize speech".to_string(),
+            confidence: Some(0.9),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Corrected":{"original":"wreck a nice beach","text":"recognize speech","confidence":0.9}}"#
+        );
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, event);
+    }
+
+    #[test]
+    fn test_status_info_omits_detected_language_when_unknown() {
+        let mut info = StatusInfo {
+            is_running: true,
+            is_active: true,
+            language: "auto".to_string(),
+            audio_level: None,
+            detected_language: None,
+        };
+        assert!(!serde_json::to_string(&info).unwrap().contains("detected_language"));
+
+        info.detected_language = Some("fr".to_string());
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains(r#""detected_language":"fr""#));
+    }
+
+    #[test]
+    fn test_ipc_error_display_rejected() {
+        let err = IpcError::Rejected("busy".to_string());
+        assert!(err.to_string().contains("busy"));
+    }
+
     #[test]
     fn test_ipc_error_display_timeout() {
         let err = IpcError::Timeout;